@@ -0,0 +1,179 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! The graph editor drawn from a Bevy system with `bevy_egui`. The editor
+//! state lives in a Bevy resource, which requires it to be `Send + Sync`.
+
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use egui_graph_edit::*;
+
+/// The nodes of this example only have inputs and outputs.
+#[derive(Clone, Debug)]
+pub struct NodeData;
+
+/// Any output can be connected to any input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataType;
+
+/// The constant of an unconnected input.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValueType(f32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeTemplate {
+    Number,
+    Add,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Response;
+
+/// Whole-graph state, stored in its own resource.
+#[derive(Resource, Default)]
+pub struct GraphState;
+
+impl DataTypeTrait<GraphState> for DataType {
+    fn data_type_color(&self, _user_state: &mut GraphState) -> egui::Color32 {
+        egui::Color32::from_rgb(38, 109, 211)
+    }
+
+    fn name(&self) -> Cow<'_, str> {
+        "number".into()
+    }
+}
+
+impl NodeTemplateTrait for NodeTemplate {
+    type NodeData = NodeData;
+    type DataType = DataType;
+    type ValueType = ValueType;
+    type UserState = GraphState;
+    type CategoryType = &'static str;
+
+    fn node_finder_label(&self, _user_state: &mut GraphState) -> Cow<'_, str> {
+        match self {
+            NodeTemplate::Number => "Number".into(),
+            NodeTemplate::Add => "Add".into(),
+        }
+    }
+
+    fn node_graph_label(&self, user_state: &mut GraphState) -> String {
+        self.node_finder_label(user_state).into()
+    }
+
+    fn user_data(&self, _user_state: &mut GraphState) -> NodeData {
+        NodeData
+    }
+
+    fn build_node(
+        &self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        _user_state: &mut GraphState,
+        node_id: NodeId,
+    ) {
+        let mut input = |name: &str, kind| {
+            graph.add_input_param(
+                node_id,
+                name.to_owned(),
+                DataType,
+                ValueType::default(),
+                kind,
+                true,
+            );
+        };
+        match self {
+            NodeTemplate::Number => input("value", InputParamKind::ConstantOnly),
+            NodeTemplate::Add => {
+                input("a", InputParamKind::ConnectionOrConstant);
+                input("b", InputParamKind::ConnectionOrConstant);
+            }
+        }
+        graph.add_output_param(node_id, "out".to_owned(), DataType);
+    }
+}
+
+pub struct AllNodeTemplates;
+
+impl NodeTemplateIter for AllNodeTemplates {
+    type Item = NodeTemplate;
+
+    fn all_kinds(&self) -> Vec<NodeTemplate> {
+        vec![NodeTemplate::Number, NodeTemplate::Add]
+    }
+}
+
+impl WidgetValueTrait for ValueType {
+    type Response = Response;
+    type UserState = GraphState;
+    type NodeData = NodeData;
+
+    fn value_widget(
+        &mut self,
+        param_name: &str,
+        _node_id: NodeId,
+        ui: &mut egui::Ui,
+        _user_state: &mut GraphState,
+        _node_data: &NodeData,
+    ) -> Vec<Response> {
+        ui.horizontal(|ui| {
+            ui.label(param_name);
+            ui.add(egui::DragValue::new(&mut self.0));
+        });
+        Vec::new()
+    }
+}
+
+impl UserResponseTrait for Response {}
+
+impl NodeDataTrait for NodeData {
+    type Response = Response;
+    type UserState = GraphState;
+    type DataType = DataType;
+    type ValueType = ValueType;
+
+    fn bottom_ui(
+        &self,
+        _ui: &mut egui::Ui,
+        _node_id: NodeId,
+        _graph: &Graph<NodeData, DataType, ValueType>,
+        _user_state: &mut GraphState,
+    ) -> Vec<NodeResponse<Response, NodeData>> {
+        Vec::new()
+    }
+}
+
+type EditorState = GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, GraphState>;
+
+/// The editor state, kept across frames like any other Bevy resource.
+#[derive(Resource, Default)]
+struct GraphEditor(EditorState);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(EguiPlugin)
+        .init_resource::<GraphEditor>()
+        .init_resource::<GraphState>()
+        .add_systems(Update, graph_editor_system)
+        .run();
+}
+
+fn graph_editor_system(
+    mut contexts: EguiContexts,
+    mut editor: ResMut<GraphEditor>,
+    mut user_state: ResMut<GraphState>,
+) {
+    egui::TopBottomPanel::top("top").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!(
+            "{} nodes, right click to add more",
+            editor.0.graph.nodes.len()
+        ));
+    });
+    egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
+        let _ = editor
+            .0
+            .draw_graph_editor(ui, AllNodeTemplates, &mut user_state, Vec::default());
+    });
+}
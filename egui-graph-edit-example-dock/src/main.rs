@@ -0,0 +1,238 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! The graphs of a workspace hosted in the tabs of an `egui_dock` layout,
+//! which can be split, moved around and undocked into windows.
+
+use std::borrow::Cow;
+
+use eframe::egui;
+use egui_dock::{DockArea, DockState, NodeIndex, Style, TabViewer};
+use egui_graph_edit::*;
+
+/// The nodes of this example only have inputs and outputs.
+#[derive(Clone, Debug)]
+pub struct NodeData;
+
+/// Any output can be connected to any input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataType;
+
+/// The constant of an unconnected input.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValueType(f32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeTemplate {
+    Number,
+    Add,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Response;
+
+/// Whole-graph state, shared by the graphs of the workspace.
+#[derive(Default)]
+pub struct GraphState;
+
+impl DataTypeTrait<GraphState> for DataType {
+    fn data_type_color(&self, _user_state: &mut GraphState) -> egui::Color32 {
+        egui::Color32::from_rgb(38, 109, 211)
+    }
+
+    fn name(&self) -> Cow<'_, str> {
+        "number".into()
+    }
+}
+
+impl NodeTemplateTrait for NodeTemplate {
+    type NodeData = NodeData;
+    type DataType = DataType;
+    type ValueType = ValueType;
+    type UserState = GraphState;
+    type CategoryType = &'static str;
+
+    fn node_finder_label(&self, _user_state: &mut GraphState) -> Cow<'_, str> {
+        match self {
+            NodeTemplate::Number => "Number".into(),
+            NodeTemplate::Add => "Add".into(),
+        }
+    }
+
+    fn node_graph_label(&self, user_state: &mut GraphState) -> String {
+        self.node_finder_label(user_state).into()
+    }
+
+    fn user_data(&self, _user_state: &mut GraphState) -> NodeData {
+        NodeData
+    }
+
+    fn build_node(
+        &self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        _user_state: &mut GraphState,
+        node_id: NodeId,
+    ) {
+        let mut input = |name: &str, kind| {
+            graph.add_input_param(
+                node_id,
+                name.to_owned(),
+                DataType,
+                ValueType::default(),
+                kind,
+                true,
+            );
+        };
+        match self {
+            NodeTemplate::Number => input("value", InputParamKind::ConstantOnly),
+            NodeTemplate::Add => {
+                input("a", InputParamKind::ConnectionOrConstant);
+                input("b", InputParamKind::ConnectionOrConstant);
+            }
+        }
+        graph.add_output_param(node_id, "out".to_owned(), DataType);
+    }
+}
+
+pub struct AllNodeTemplates;
+
+impl NodeTemplateIter for AllNodeTemplates {
+    type Item = NodeTemplate;
+
+    fn all_kinds(&self) -> Vec<NodeTemplate> {
+        vec![NodeTemplate::Number, NodeTemplate::Add]
+    }
+}
+
+impl WidgetValueTrait for ValueType {
+    type Response = Response;
+    type UserState = GraphState;
+    type NodeData = NodeData;
+
+    fn value_widget(
+        &mut self,
+        param_name: &str,
+        _node_id: NodeId,
+        ui: &mut egui::Ui,
+        _user_state: &mut GraphState,
+        _node_data: &NodeData,
+    ) -> Vec<Response> {
+        ui.horizontal(|ui| {
+            ui.label(param_name);
+            ui.add(egui::DragValue::new(&mut self.0));
+        });
+        Vec::new()
+    }
+}
+
+impl UserResponseTrait for Response {}
+
+impl NodeDataTrait for NodeData {
+    type Response = Response;
+    type UserState = GraphState;
+    type DataType = DataType;
+    type ValueType = ValueType;
+
+    fn bottom_ui(
+        &self,
+        _ui: &mut egui::Ui,
+        _node_id: NodeId,
+        _graph: &Graph<NodeData, DataType, ValueType>,
+        _user_state: &mut GraphState,
+    ) -> Vec<NodeResponse<Response, NodeData>> {
+        Vec::new()
+    }
+}
+
+type Workspace = GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, GraphState>;
+
+/// Draws the graphs of the workspace, the tabs being their indices.
+struct GraphTabs<'a> {
+    workspace: &'a mut Workspace,
+    user_state: &'a mut GraphState,
+}
+
+impl TabViewer for GraphTabs<'_> {
+    type Tab = usize;
+
+    fn title(&mut self, tab: &mut usize) -> egui::WidgetText {
+        self.workspace.graphs[*tab].name.as_str().into()
+    }
+
+    fn id(&mut self, tab: &mut usize) -> egui::Id {
+        egui::Id::new(("graph_tab", *tab))
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut usize) {
+        let _ =
+            self.workspace
+                .show_dock_tab(ui, *tab, AllNodeTemplates, self.user_state, Vec::new());
+    }
+
+    // Closing a graph would shift the indices of the other tabs
+    fn closeable(&mut self, _tab: &mut usize) -> bool {
+        false
+    }
+}
+
+struct DockExample {
+    workspace: Workspace,
+    user_state: GraphState,
+    dock_state: DockState<usize>,
+}
+
+impl Default for DockExample {
+    fn default() -> Self {
+        let mut workspace = Workspace::new("Graph 1", GraphEditorState::default());
+        workspace.add_graph("Graph 2", GraphEditorState::default());
+        let mut dock_state = DockState::new(vec![0]);
+        dock_state
+            .main_surface_mut()
+            .split_right(NodeIndex::root(), 0.5, vec![1]);
+        Self {
+            workspace,
+            user_state: GraphState,
+            dock_state,
+        }
+    }
+}
+
+impl eframe::App for DockExample {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::top("top").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                if ui.button("New graph").clicked() {
+                    let name = format!("Graph {}", self.workspace.graphs.len() + 1);
+                    let index = self.workspace.add_graph(name, GraphEditorState::default());
+                    self.dock_state.push_to_focused_leaf(index);
+                }
+                // Copy and paste go to the tab last clicked
+                if ui.button("Copy").clicked() {
+                    self.workspace.copy_selection();
+                }
+                if ui.button("Paste").clicked() {
+                    let _ = self.workspace.paste(egui::Pos2::ZERO);
+                }
+                ui.label(format!("Active: {}", self.workspace.active_graph().name));
+            });
+        });
+        DockArea::new(&mut self.dock_state)
+            .style(Style::from_egui(ctx.style().as_ref()))
+            .show(
+                ctx,
+                &mut GraphTabs {
+                    workspace: &mut self.workspace,
+                    user_state: &mut self.user_state,
+                },
+            );
+    }
+}
+
+fn main() {
+    eframe::run_native(
+        "Egui Graph Edit dock example",
+        eframe::NativeOptions::default(),
+        Box::new(|_cc| Ok(Box::<DockExample>::default())),
+    )
+    .expect("Failed to run native example");
+}
@@ -186,6 +186,13 @@ impl eframe::App for NodeGraphExampleSimple {
                     // Recalculate the line to display above the graph
                     self.cached_text_graph_description = self.calculate_result();
                 }
+                // Lay out the expressions computed by the selected nodes
+                if ui.button("Arrange selected as trees").clicked() {
+                    self.state.run_layout(&TreeLayout {
+                        roots: self.state.selected_nodes.clone(),
+                        ..Default::default()
+                    });
+                }
             });
         });
         // Add a panel where textual representation of the graph will be displayed
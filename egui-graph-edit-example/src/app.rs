@@ -8,6 +8,7 @@ use egui_graph_edit::*;
 /// The NodeData holds a custom data struct inside each node. It's useful to
 /// store additional information that doesn't live in parameters. For this
 /// example, the node data stores the template (i.e. the "type") of the node.
+#[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct MyNodeData {
     template: MyNodeTemplate,
@@ -16,7 +17,7 @@ pub struct MyNodeData {
 /// `DataType`s are what defines the possible range of connections when
 /// attaching two ports together. The graph UI will make sure to not allow
 /// attaching incompatible datatypes.
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum MyDataType {
     Scalar,
@@ -30,7 +31,7 @@ pub enum MyDataType {
 /// this library makes no attempt to check this consistency. For instance, it is
 /// up to the user code in this example to make sure no parameter is created
 /// with a DataType of Scalar and a ValueType of Vec2.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum MyValueType {
     Vec2 { value: egui::Vec2 },
@@ -116,6 +117,13 @@ impl DataTypeTrait<MyGraphState> for MyDataType {
             MyDataType::Vec2 => Cow::Borrowed("2d vector"),
         }
     }
+
+    fn tooltip_ui(&self, ui: &mut egui::Ui, _param_id: AnyParameterId, _: &mut MyGraphState) {
+        ui.weak(self.name());
+        if *self == MyDataType::Vec2 {
+            ui.label("Two scalars, x and y");
+        }
+    }
 }
 
 // A trait for the node kinds, which tells the library how to build new nodes
@@ -366,6 +374,22 @@ impl NodeDataTrait for MyNodeData {
 
         responses
     }
+    // Tints the body of the active node, so it can be found at a glance.
+    fn body_color(
+        &self,
+        ui: &egui::Ui,
+        node_id: NodeId,
+        _graph: &Graph<MyNodeData, MyDataType, MyValueType>,
+        user_state: &mut Self::UserState,
+    ) -> Option<egui::Color32> {
+        let background = if ui.visuals().dark_mode {
+            egui::Color32::from_rgb(0x3f, 0x3f, 0x3f)
+        } else {
+            egui::Color32::WHITE
+        };
+        (user_state.active_node == Some(node_id))
+            .then(|| background.lerp_to_gamma(egui::Color32::GOLD, 0.15))
+    }
 }
 
 type MyGraph = Graph<MyNodeData, MyDataType, MyValueType>;
@@ -379,6 +403,9 @@ pub struct NodeGraphExample {
     state: MyEditorState,
 
     user_state: MyGraphState,
+
+    /// Text searched in the node labels, the matching nodes are selected.
+    search: String,
 }
 
 #[cfg(feature = "persistence")]
@@ -396,6 +423,7 @@ impl NodeGraphExample {
         Self {
             state,
             user_state: MyGraphState::default(),
+            search: String::new(),
         }
     }
 }
@@ -410,9 +438,87 @@ impl eframe::App for NodeGraphExample {
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // The history isn't persisted, it starts over with each session
+        if self.state.undo_history.is_none() {
+            self.state.enable_undo(100);
+            // Copying and pasting nodes goes through egui's clipboard
+            // events, which also work in the browser.
+            #[cfg(feature = "persistence")]
+            self.state.enable_system_clipboard_shortcuts();
+            #[cfg(not(feature = "persistence"))]
+            self.state.enable_clipboard_shortcuts();
+        }
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_theme_preference_switch(ui);
+                let history = self.state.undo_history.as_ref();
+                let (can_undo, can_redo) = history.map_or((false, false), |history| {
+                    (history.can_undo(), history.can_redo())
+                });
+                if ui
+                    .add_enabled(can_undo, egui::Button::new("Undo"))
+                    .clicked()
+                {
+                    self.state.undo();
+                }
+                if ui
+                    .add_enabled(can_redo, egui::Button::new("Redo"))
+                    .clicked()
+                {
+                    self.state.redo();
+                }
+                ui.menu_button("Palette", |ui| {
+                    for (name, style) in GraphEditorStyle::palettes() {
+                        if ui.button(name).clicked() {
+                            self.state.set_style(style);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                if ui.button("Auto layout").clicked() {
+                    self.state.auto_layout();
+                }
+                ui.menu_button("Connections", |ui| {
+                    for (name, style) in [
+                        ("Curves", ConnectionStyle::Bezier),
+                        ("Straight", ConnectionStyle::Straight),
+                        ("Orthogonal", ConnectionStyle::Orthogonal),
+                    ] {
+                        if ui.button(name).clicked() {
+                            self.state.connection_style = style;
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.checkbox(&mut self.state.dependency_focus, "Dependency focus");
+                let mut acyclic = self.state.cycle_policy == CyclePolicy::Reject;
+                if ui.checkbox(&mut acyclic, "Acyclic").changed() {
+                    self.state.cycle_policy = if acyclic {
+                        CyclePolicy::Reject
+                    } else {
+                        CyclePolicy::Allow
+                    };
+                }
+                let mut minimap = self.state.minimap.is_some();
+                if ui.checkbox(&mut minimap, "Minimap").changed() {
+                    self.state.minimap = minimap.then(Minimap::default);
+                }
+                let mut grid = self.state.background_grid.is_some();
+                if ui.checkbox(&mut grid, "Grid").changed() {
+                    self.state.background_grid = grid.then(BackgroundGrid::default);
+                }
+                ui.add_enabled(
+                    grid,
+                    egui::Checkbox::new(&mut self.state.snap_to_grid, "Snap"),
+                );
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut self.search)
+                        .hint_text("Search nodes")
+                        .desired_width(120.0),
+                );
+                if search.changed() && !self.search.is_empty() {
+                    self.state.selected_nodes = self.state.graph.find_nodes_by_label(&self.search);
+                }
             });
         });
         let graph_response = egui::CentralPanel::default()
@@ -437,6 +543,24 @@ impl eframe::App for NodeGraphExample {
             }
         }
 
+        // Probes show the value of the output they are attached to
+        let mut outputs_cache = HashMap::new();
+        let probed: Vec<OutputId> = self.state.probes.keys().collect();
+        for output in probed {
+            let node = self.state.graph[output].node;
+            let value = match evaluate_node(&self.state.graph, node, &mut outputs_cache) {
+                Ok(_) => match outputs_cache.get(&output) {
+                    Some(MyValueType::Scalar { value }) => ProbeValue::Number(*value as f64),
+                    Some(MyValueType::Vec2 { value }) => {
+                        ProbeValue::Text(format!("({:.3}, {:.3})", value.x, value.y))
+                    }
+                    None => ProbeValue::Empty,
+                },
+                Err(err) => ProbeValue::Text(err.to_string()),
+            };
+            self.state.probes[output].value = value;
+        }
+
         if let Some(node) = self.user_state.active_node {
             if self.state.graph.nodes.contains_key(node) {
                 let text = match evaluate_node(&self.state.graph, node, &mut HashMap::new()) {
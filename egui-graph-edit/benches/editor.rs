@@ -0,0 +1,103 @@
+//! Benchmarks of the editor loop on generated graphs. Run with
+//! `cargo bench -p egui-graph-edit --features synthetic,persistence`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use egui_graph_edit::*;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn options(shape: SyntheticShape, nodes: usize) -> SyntheticGraphOptions {
+    SyntheticGraphOptions {
+        shape,
+        nodes,
+        connections: nodes,
+        ..Default::default()
+    }
+}
+
+fn draw_frame(ctx: &egui::Context, state: &mut SyntheticEditorState) {
+    let raw_input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(1920.0, 1080.0),
+        )),
+        ..Default::default()
+    };
+    let template = SyntheticTemplate {
+        inputs: 2,
+        outputs: 1,
+    };
+    let output = ctx.run(raw_input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            std::hint::black_box(state.draw_graph_editor(ui, template, &mut (), Vec::new()));
+        });
+    });
+    std::hint::black_box(ctx.tessellate(output.shapes, output.pixels_per_point));
+}
+
+fn draw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("draw");
+    for nodes in SIZES {
+        for (name, overview_zoom) in [("full", 0.0), ("overview", 2.0)] {
+            let mut state = options(SyntheticShape::Grid, nodes).build_editor_state();
+            state.overview_zoom = overview_zoom;
+            let ctx = egui::Context::default();
+            // The first frame sets up fonts and memory
+            draw_frame(&ctx, &mut state);
+            group.bench_function(BenchmarkId::new(name, nodes), |b| {
+                b.iter(|| draw_frame(&ctx, &mut state))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    for nodes in SIZES {
+        group.bench_function(BenchmarkId::new("random", nodes), |b| {
+            b.iter(|| options(SyntheticShape::Random, nodes).build_graph())
+        });
+    }
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialization");
+    for nodes in SIZES {
+        let state = options(SyntheticShape::Random, nodes).build_editor_state();
+        let json = serde_json::to_string(&state).unwrap();
+        group.bench_function(BenchmarkId::new("serialize", nodes), |b| {
+            b.iter(|| serde_json::to_string(&state).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("deserialize", nodes), |b| {
+            b.iter(|| serde_json::from_str::<SyntheticEditorState>(&json).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn compaction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compact");
+    for nodes in SIZES {
+        let mut state = options(SyntheticShape::Random, nodes * 2).build_editor_state();
+        // Leave every other slot empty
+        for node_id in state.node_order.to_vec().into_iter().step_by(2) {
+            state.graph.remove_node(node_id);
+            state.node_order.remove(node_id);
+            state.node_positions.remove(node_id);
+            state.node_orientations.remove(node_id);
+        }
+        group.bench_function(BenchmarkId::from_parameter(nodes), |b| {
+            b.iter_batched(
+                || state.clone(),
+                |mut state| state.compact(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, draw, generate, serialization, compaction);
+criterion_main!(benches);
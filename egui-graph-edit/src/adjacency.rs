@@ -0,0 +1,287 @@
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::Deserialize;
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// All connections, as input-output pairs. Multi-inputs appear once per
+    /// connection.
+    pub fn iter_connections(&self) -> impl Iterator<Item = (InputId, OutputId)> + '_ {
+        self.connections
+            .iter()
+            .flat_map(|(input, outputs)| outputs.iter().map(move |output| (input, *output)))
+    }
+
+    /// The output connected to `input`, the first one for multi-inputs.
+    pub fn connection(&self, input: InputId) -> Option<OutputId> {
+        self.connections(input).first().copied()
+    }
+
+    /// The outputs connected to `input`, in the order the connections were
+    /// made.
+    pub fn connections(&self, input: InputId) -> &[OutputId] {
+        self.connections
+            .get(input)
+            .map(|outputs| outputs.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The number of connections, counting each connection of multi-inputs.
+    pub fn connection_count(&self) -> usize {
+        self.connections.values().map(|outputs| outputs.len()).sum()
+    }
+
+    /// The inputs connected to `output`, in the order the connections were
+    /// made. This is a lookup, it doesn't scan the connections of the graph.
+    pub fn output_targets(&self, output: OutputId) -> &[InputId] {
+        self.output_targets
+            .get(output)
+            .map(|targets| targets.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The connections going into or out of `node`, as input-output pairs:
+    /// first those of its inputs, then those of its outputs. This only looks
+    /// at the parameters of `node`, so it stays fast on large graphs.
+    pub fn connections_of(&self, node: NodeId) -> impl Iterator<Item = (InputId, OutputId)> + '_ {
+        let node = self.nodes.get(node);
+        let incoming = node
+            .into_iter()
+            .flat_map(|node| node.input_ids())
+            .flat_map(|input| {
+                self.connections(input)
+                    .iter()
+                    .map(move |output| (input, *output))
+            });
+        let outgoing = node
+            .into_iter()
+            .flat_map(|node| node.output_ids())
+            .flat_map(|output| {
+                self.output_targets(output)
+                    .iter()
+                    .map(move |input| (*input, output))
+            })
+            // Connections between two ports of the node were listed already
+            .filter(move |(input, _)| {
+                self.inputs.get(*input).map(|param| param.node) != node.map(|node| node.id)
+            });
+        incoming.chain(outgoing)
+    }
+
+    /// Recomputes the lookup tables used by [`Graph::output_targets`] and
+    /// [`Graph::connections_of`] from `connections`. Only needed after
+    /// modifying `connections` directly rather than through
+    /// [`Graph::add_connection`] and [`Graph::remove_connection`].
+    pub fn rebuild_connection_index(&mut self) {
+        self.output_targets.clear();
+        for (input, outputs) in &self.connections {
+            for output in outputs {
+                if let Some(entry) = self.output_targets.entry(*output) {
+                    entry.or_default().push(input);
+                }
+            }
+        }
+    }
+
+    /// Whether the lookup tables agree with `connections`.
+    pub(crate) fn connection_index_is_valid(&self) -> bool {
+        let indexed: usize = self.output_targets.values().map(|t| t.len()).sum();
+        indexed == self.connection_count()
+            && self
+                .iter_connections()
+                .all(|(input, output)| self.output_targets(output).contains(&input))
+    }
+
+    /// Removes the connection from `output` to `input`. Returns false when
+    /// they weren't connected.
+    pub fn disconnect(&mut self, output: OutputId, input: InputId) -> bool {
+        let Some(outputs) = self.connections.get_mut(input) else {
+            return false;
+        };
+        let Some(index) = outputs.iter().position(|o| *o == output) else {
+            return false;
+        };
+        outputs.remove(index);
+        if outputs.is_empty() {
+            self.connections.remove(input);
+        }
+        self.unlink_target(output, input);
+        true
+    }
+
+    pub(crate) fn unlink_target(&mut self, output: OutputId, input: InputId) {
+        if let Some(targets) = self.output_targets.get_mut(output) {
+            targets.retain(|target| *target != input);
+            if targets.is_empty() {
+                self.output_targets.remove(output);
+            }
+        }
+    }
+}
+
+/// The serialized fields of a [`Graph`]. Deserializing goes through this so
+/// the connection index can be rebuilt.
+#[cfg(feature = "persistence")]
+#[derive(Deserialize)]
+pub(crate) struct GraphData<NodeData, DataType, ValueType> {
+    nodes: SlotMap<NodeId, Node<NodeData>>,
+    inputs: SlotMap<InputId, InputParam<DataType, ValueType>>,
+    outputs: SlotMap<OutputId, OutputParam<DataType>>,
+    connections: SecondaryMap<InputId, SavedConnections>,
+    #[serde(default)]
+    stable_ids: StableIds,
+}
+
+/// The connections of an input. Graphs saved before multi-inputs were added
+/// have a single output per input.
+#[cfg(feature = "persistence")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SavedConnections {
+    One(OutputId),
+    Many(SVec<OutputId>),
+}
+
+#[cfg(feature = "persistence")]
+impl SavedConnections {
+    pub(crate) fn into_outputs(self) -> SVec<OutputId> {
+        match self {
+            SavedConnections::One(output) => smallvec::smallvec![output],
+            SavedConnections::Many(outputs) => outputs,
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl<NodeData, DataType, ValueType> From<GraphData<NodeData, DataType, ValueType>>
+    for Graph<NodeData, DataType, ValueType>
+{
+    fn from(data: GraphData<NodeData, DataType, ValueType>) -> Self {
+        let mut graph = Graph {
+            nodes: data.nodes,
+            inputs: data.inputs,
+            outputs: data.outputs,
+            connections: data
+                .connections
+                .into_iter()
+                .map(|(input, connections)| (input, connections.into_outputs()))
+                .collect(),
+            stable_ids: data.stable_ids,
+            output_targets: SecondaryMap::default(),
+        };
+        graph.rebuild_connection_index();
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_node(graph: &mut Graph<(), (), ()>) -> (NodeId, InputId, OutputId) {
+        let node = graph.add_node("node".into(), (), |_, _| {});
+        let input = graph.add_input_param(
+            node,
+            "in".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        let output = graph.add_output_param(node, "out".into(), ());
+        (node, input, output)
+    }
+
+    #[test]
+    fn test_connection_index() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (a, a_in, a_out) = add_node(&mut graph);
+        let (b, b_in, b_out) = add_node(&mut graph);
+        let (c, c_in, _) = add_node(&mut graph);
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(a_out, c_in);
+        graph.add_connection(b_out, a_in);
+        assert_eq!(graph.output_targets(a_out), &[b_in, c_in]);
+
+        let connections: Vec<_> = graph.connections_of(a).collect();
+        assert_eq!(
+            connections,
+            vec![(a_in, b_out), (b_in, a_out), (c_in, a_out)]
+        );
+
+        // Replacing the connection of an input updates both outputs
+        graph.add_connection(b_out, c_in);
+        assert_eq!(graph.output_targets(a_out), &[b_in]);
+        assert_eq!(graph.output_targets(b_out), &[a_in, c_in]);
+
+        graph.remove_node(b);
+        assert!(graph.output_targets(a_out).is_empty());
+        assert_eq!(graph.connections_of(c).count(), 0);
+        assert!(graph.connection_index_is_valid());
+
+        graph.connections.insert(a_in, smallvec::smallvec![a_out]);
+        assert!(!graph.connection_index_is_valid());
+        graph.rebuild_connection_index();
+        // A connection from a node to itself is only listed once
+        assert_eq!(graph.connections_of(a).count(), 1);
+    }
+
+    #[test]
+    fn test_multi_input() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (_, _, a_out) = add_node(&mut graph);
+        let (_, _, b_out) = add_node(&mut graph);
+        let (c, c_in, _) = add_node(&mut graph);
+        let sum = graph.add_multi_input_param(
+            c,
+            "sum".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        graph.add_connection(a_out, sum);
+        graph.add_connection(b_out, sum);
+        graph.add_connection(a_out, sum);
+        // Other inputs still replace their connection
+        graph.add_connection(a_out, c_in);
+        graph.add_connection(b_out, c_in);
+
+        assert_eq!(graph.connections(sum), &[a_out, b_out]);
+        assert_eq!(graph.connection(sum), Some(a_out));
+        assert_eq!(graph.connections(c_in), &[b_out]);
+        assert_eq!(graph.connection_count(), 3);
+        let connections: Vec<_> = graph.connections_of(c).collect();
+        assert_eq!(connections, vec![(c_in, b_out), (sum, a_out), (sum, b_out)]);
+        assert_eq!(graph.output_targets(b_out), &[sum, c_in]);
+
+        assert_eq!(graph.remove_connection(sum), Some(b_out));
+        assert_eq!(graph.connections(sum), &[a_out]);
+        assert!(graph.disconnect(a_out, sum));
+        assert!(!graph.disconnect(a_out, sum));
+        assert!(graph.connections(sum).is_empty());
+        assert!(graph.connection_index_is_valid());
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_load_single_connections() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (_, _, a_out) = add_node(&mut graph);
+        let (_, b_in, _) = add_node(&mut graph);
+        graph.add_connection(a_out, b_in);
+
+        // Graphs saved before multi-inputs map each input to one output
+        let mut saved = serde_json::to_value(&graph).unwrap();
+        let connections = &mut saved["connections"];
+        *connections = serde_json::to_value(
+            graph
+                .iter_connections()
+                .collect::<SecondaryMap<InputId, OutputId>>(),
+        )
+        .unwrap();
+        let loaded: Graph<(), (), ()> = serde_json::from_value(saved).unwrap();
+        assert_eq!(loaded.connections(b_in), &[a_out]);
+        assert_eq!(loaded.output_targets(a_out), &[b_in]);
+    }
+}
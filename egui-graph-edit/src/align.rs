@@ -0,0 +1,275 @@
+use super::*;
+use egui::{Pos2, Rect, Ui, Vec2};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Which part of the selected nodes to line up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum NodeAlignment {
+    Left,
+    /// Centers the nodes on the same vertical line.
+    CenterX,
+    Right,
+    Top,
+    /// Centers the nodes on the same horizontal line.
+    CenterY,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum DistributeAxis {
+    Horizontal,
+    Vertical,
+}
+
+/// A way to arrange the selected nodes, see
+/// [`GraphEditorState::arrange_selection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum ArrangeAction {
+    /// Moves the nodes so that the given edges or centers line up with the
+    /// outermost one.
+    Align(NodeAlignment),
+    /// Moves the nodes between the first and the last one along the axis so
+    /// that the gaps between them are equal.
+    Distribute(DistributeAxis),
+}
+
+impl ArrangeAction {
+    /// The actions in the order they appear in the selection menu, with their
+    /// labels. Their shortcuts are [`EditorKeybindings::arrange`].
+    pub const ALL: [(ArrangeAction, &'static str); 8] = [
+        (Self::Align(NodeAlignment::Left), "Align left edges"),
+        (
+            Self::Align(NodeAlignment::CenterX),
+            "Align vertical centers",
+        ),
+        (Self::Align(NodeAlignment::Right), "Align right edges"),
+        (Self::Align(NodeAlignment::Top), "Align top edges"),
+        (
+            Self::Align(NodeAlignment::CenterY),
+            "Align horizontal centers",
+        ),
+        (Self::Align(NodeAlignment::Bottom), "Align bottom edges"),
+        (
+            Self::Distribute(DistributeAxis::Horizontal),
+            "Distribute horizontally",
+        ),
+        (
+            Self::Distribute(DistributeAxis::Vertical),
+            "Distribute vertically",
+        ),
+    ];
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// The [`NodeResponse::MoveNode`] responses that would perform `action`
+    /// on the selected nodes, based on their rects as of the last frame.
+    /// Nodes that don't need to move are left out.
+    pub fn arrange_moves<UserResponse>(
+        &self,
+        action: ArrangeAction,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>>
+    where
+        UserResponse: UserResponseTrait,
+        NodeData: NodeDataTrait,
+    {
+        let rects: Vec<(NodeId, Rect)> = self
+            .selected_nodes
+            .iter()
+            .filter_map(|node_id| Some((*node_id, *self.node_rects.get(node_id)?)))
+            .collect();
+        let deltas = match action {
+            ArrangeAction::Align(alignment) => align(&rects, alignment),
+            ArrangeAction::Distribute(axis) => distribute(&rects, axis),
+        };
+        // The rects are on the screen, the nodes move in graph coordinates
+        deltas
+            .into_iter()
+            .filter(|(_, delta)| *delta != Vec2::ZERO)
+            .map(|(node, delta)| NodeResponse::MoveNode {
+                node,
+                drag_delta: delta / self.pan_zoom.zoom,
+            })
+            .collect()
+    }
+
+    /// Performs `action` on the selected nodes, and returns the
+    /// [`NodeResponse::MoveNode`] responses for the nodes that moved.
+    pub fn arrange_selection<UserResponse>(
+        &mut self,
+        action: ArrangeAction,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>>
+    where
+        UserResponse: UserResponseTrait,
+        NodeData: NodeDataTrait,
+    {
+        let moves = self.arrange_moves(action);
+        let targets: Vec<(NodeId, Pos2)> = moves
+            .iter()
+            .filter_map(|response| match response {
+                NodeResponse::MoveNode { node, drag_delta } => {
+                    Some((*node, self.node_positions[*node] + *drag_delta))
+                }
+                _ => None,
+            })
+            .collect();
+        self.move_nodes_animated(targets);
+        moves
+    }
+}
+
+fn align(rects: &[(NodeId, Rect)], alignment: NodeAlignment) -> Vec<(NodeId, Vec2)> {
+    let coordinate = |rect: &Rect| match alignment {
+        NodeAlignment::Left => rect.left(),
+        NodeAlignment::CenterX => rect.center().x,
+        NodeAlignment::Right => rect.right(),
+        NodeAlignment::Top => rect.top(),
+        NodeAlignment::CenterY => rect.center().y,
+        NodeAlignment::Bottom => rect.bottom(),
+    };
+    let coordinates = rects.iter().map(|(_, rect)| coordinate(rect));
+    let target = match alignment {
+        NodeAlignment::Left | NodeAlignment::Top => coordinates.fold(f32::INFINITY, f32::min),
+        NodeAlignment::Right | NodeAlignment::Bottom => {
+            coordinates.fold(f32::NEG_INFINITY, f32::max)
+        }
+        // The middle of the outermost centers
+        NodeAlignment::CenterX | NodeAlignment::CenterY => {
+            let (min, max) = coordinates
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), c| {
+                    (min.min(c), max.max(c))
+                });
+            (min + max) / 2.0
+        }
+    };
+    let horizontal = matches!(
+        alignment,
+        NodeAlignment::Left | NodeAlignment::CenterX | NodeAlignment::Right
+    );
+    rects
+        .iter()
+        .map(|(node_id, rect)| {
+            let offset = target - coordinate(rect);
+            let delta = if horizontal {
+                Vec2::new(offset, 0.0)
+            } else {
+                Vec2::new(0.0, offset)
+            };
+            (*node_id, delta)
+        })
+        .collect()
+}
+
+fn distribute(rects: &[(NodeId, Rect)], axis: DistributeAxis) -> Vec<(NodeId, Vec2)> {
+    if rects.len() < 3 {
+        return Vec::new();
+    }
+    let range = |rect: &Rect| match axis {
+        DistributeAxis::Horizontal => rect.x_range(),
+        DistributeAxis::Vertical => rect.y_range(),
+    };
+    let mut sorted = rects.to_vec();
+    sorted.sort_by(|(_, a), (_, b)| range(a).min.total_cmp(&range(b).min));
+
+    let first = range(&sorted[0].1).min;
+    let end = sorted
+        .iter()
+        .map(|(_, rect)| range(rect).max)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let total_size: f32 = sorted.iter().map(|(_, rect)| range(rect).span()).sum();
+    let gap = (end - first - total_size) / (sorted.len() - 1) as f32;
+
+    let mut next = first;
+    sorted
+        .iter()
+        .map(|(node_id, rect)| {
+            let offset = next - range(rect).min;
+            next += range(rect).span() + gap;
+            let delta = match axis {
+                DistributeAxis::Horizontal => Vec2::new(offset, 0.0),
+                DistributeAxis::Vertical => Vec2::new(0.0, offset),
+            };
+            (*node_id, delta)
+        })
+        .collect()
+}
+
+/// Shows the menu arranging the selected nodes at `pos`. Returns the
+/// picked action, and whether the pointer is over the menu.
+pub(crate) fn show_selection_menu(
+    ui: &Ui,
+    pos: Pos2,
+    keybindings: &EditorKeybindings,
+) -> (Option<ArrangeAction>, Rect) {
+    let mut picked = None;
+    let response = egui::Area::new(ui.id().with("selection_menu"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(pos)
+        .show(ui.ctx(), |ui| {
+            egui::Frame::menu(ui.style()).show(ui, |ui| {
+                for ((action, label), shortcut) in
+                    ArrangeAction::ALL.iter().zip(keybindings.arrange)
+                {
+                    if let ArrangeAction::Distribute(DistributeAxis::Horizontal) = action {
+                        ui.separator();
+                    }
+                    let shortcut = shortcut
+                        .map(|shortcut| ui.ctx().format_shortcut(&shortcut))
+                        .unwrap_or_default();
+                    if ui
+                        .add(egui::Button::new(*label).shortcut_text(shortcut))
+                        .clicked()
+                    {
+                        picked = Some(*action);
+                    }
+                }
+            });
+        });
+    (picked, response.response.rect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_arrange() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b, c] = ["a", "b", "c"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+        let rects = [
+            (a, Rect::from_min_size(pos2(0.0, 0.0), vec2(100.0, 50.0))),
+            (b, Rect::from_min_size(pos2(300.0, 20.0), vec2(50.0, 50.0))),
+            (
+                c,
+                Rect::from_min_size(pos2(120.0, 100.0), vec2(100.0, 10.0)),
+            ),
+        ];
+
+        let deltas = align(&rects, NodeAlignment::Right);
+        assert_eq!(
+            deltas,
+            vec![
+                (a, vec2(250.0, 0.0)),
+                (b, Vec2::ZERO),
+                (c, vec2(130.0, 0.0))
+            ]
+        );
+        let deltas = align(&rects, NodeAlignment::CenterY);
+        assert_eq!(deltas[0], (a, vec2(0.0, 40.0)));
+
+        // 350 wide, with 250 taken by the nodes
+        let deltas = distribute(&rects, DistributeAxis::Horizontal);
+        assert_eq!(
+            deltas,
+            vec![(a, Vec2::ZERO), (c, vec2(30.0, 0.0)), (b, Vec2::ZERO)]
+        );
+        assert!(distribute(&rects[..2], DistributeAxis::Vertical).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+use egui::{emath::TSTransform, Color32, Painter, Pos2, Rect, Stroke};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// A grid drawn behind the nodes, following the pan and zoom of the editor.
+/// Nodes can snap to it, see [`GraphEditorState::snap_to_grid`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct BackgroundGrid {
+    /// The spacing of the grid, in graph coordinates.
+    pub spacing: f32,
+    pub pattern: GridPattern,
+    /// The color of the grid. Defaults to a faint version of the text color
+    /// of the style.
+    pub color: Option<Color32>,
+}
+
+impl Default for BackgroundGrid {
+    fn default() -> Self {
+        Self {
+            spacing: 20.0,
+            pattern: GridPattern::Dots,
+            color: None,
+        }
+    }
+}
+
+/// How a [`BackgroundGrid`] is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum GridPattern {
+    /// A dot at each intersection of the grid.
+    #[default]
+    Dots,
+    /// Horizontal and vertical lines.
+    Lines,
+}
+
+/// Below this spacing on the screen, only every other line of the grid is
+/// drawn, so zooming out doesn't fill the editor with dots.
+const MIN_SCREEN_SPACING: f32 = 12.0;
+
+impl BackgroundGrid {
+    /// The nearest intersection of the grid to `pos`, in graph coordinates.
+    pub fn snap(&self, pos: Pos2) -> Pos2 {
+        if self.spacing > 0.0 {
+            (pos / self.spacing).round() * self.spacing
+        } else {
+            pos
+        }
+    }
+
+    /// Paints the part of the grid inside `editor_rect`.
+    pub(crate) fn draw(
+        &self,
+        painter: &Painter,
+        transform: TSTransform,
+        editor_rect: Rect,
+        style: &GraphEditorStyle,
+    ) {
+        if self.spacing <= 0.0 {
+            return;
+        }
+        let mut spacing = self.spacing;
+        while spacing * transform.scaling < MIN_SCREEN_SPACING {
+            spacing *= 2.0;
+        }
+        let color = self
+            .color
+            .unwrap_or_else(|| style.text.gamma_multiply(0.15));
+        let visible = transform.inverse().mul_rect(editor_rect);
+        let first = (visible.min / spacing).floor() * spacing;
+        let xs = (0..)
+            .map(|i| first.x + i as f32 * spacing)
+            .take_while(|x| *x <= visible.max.x);
+        let ys = || {
+            (0..)
+                .map(|i| first.y + i as f32 * spacing)
+                .take_while(|y| *y <= visible.max.y)
+        };
+
+        let painter = painter.with_clip_rect(editor_rect);
+        match self.pattern {
+            GridPattern::Dots => {
+                let radius = transform.scaling.clamp(0.5, 1.5);
+                for x in xs {
+                    for y in ys() {
+                        painter.circle_filled(transform * Pos2::new(x, y), radius, color);
+                    }
+                }
+            }
+            GridPattern::Lines => {
+                let stroke = Stroke::new(1.0, color);
+                for x in xs {
+                    let x = (transform * Pos2::new(x, 0.0)).x;
+                    painter.vline(x, editor_rect.y_range(), stroke);
+                }
+                for y in ys() {
+                    let y = (transform * Pos2::new(0.0, y)).y;
+                    painter.hline(editor_rect.x_range(), y, stroke);
+                }
+            }
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// The grid dragged and created nodes snap to, when
+    /// [`Self::snap_to_grid`] is enabled.
+    pub(crate) fn snapping_grid(&self) -> Option<BackgroundGrid> {
+        self.background_grid.filter(|_| self.snap_to_grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snap() {
+        let grid = BackgroundGrid::default();
+        assert_eq!(grid.snap(Pos2::new(29.0, -31.0)), Pos2::new(20.0, -40.0));
+        assert_eq!(grid.snap(Pos2::new(31.0, 9.0)), Pos2::new(40.0, 0.0));
+        let disabled = BackgroundGrid {
+            spacing: 0.0,
+            ..grid
+        };
+        assert_eq!(disabled.snap(Pos2::new(1.5, 2.5)), Pos2::new(1.5, 2.5));
+    }
+}
@@ -0,0 +1,517 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc, Arc,
+};
+
+use super::*;
+use crate::adjacency::SavedConnections;
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use slotmap::{Key, KeyData};
+
+/// How far a [`ChunkedLoader`] got, in nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl LoadProgress {
+    /// The loaded part of the graph, between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+}
+
+type LoadResult<NodeData, DataType, ValueType, NodeTemplate, UserState> = Result<
+    (
+        GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+        IdMapping,
+        LoadReport,
+    ),
+    LoadReport,
+>;
+
+/// Loads a saved editor state a few nodes at a time, so that huge graphs can
+/// be loaded over several frames without freezing the UI. Call
+/// [`ChunkedLoader::step`] every frame until [`ChunkedLoader::is_done`], then
+/// [`ChunkedLoader::finish`]. To load on a worker thread instead, see
+/// [`ChunkedLoader::spawn`].
+///
+/// This reports invalid elements like
+/// [`GraphEditorState::from_json_str_with_report`] does. Nodes and parameters
+/// get new ids as they are inserted: the [`IdMapping`] returned by `finish`
+/// relates the ids in the document to them. Node data referring to other
+/// nodes by id must be translated with it.
+pub struct ChunkedLoader<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    mode: LoadMode,
+    /// The editor data, already loaded. The graph is filled in step by step.
+    state: GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    /// The remaining slots of the saved graph. Slots are taken out as their
+    /// elements are loaded.
+    nodes: Vec<Value>,
+    inputs: Vec<Value>,
+    outputs: Vec<Value>,
+    connections: Value,
+    stable_ids: Value,
+    next_node: usize,
+    progress: LoadProgress,
+    mapping: IdMapping,
+    report: LoadReport,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    ChunkedLoader<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: DeserializeOwned,
+    DataType: DeserializeOwned + PartialEq,
+    ValueType: DeserializeOwned,
+    NodeTemplate: DeserializeOwned,
+{
+    /// Parses the document and loads the editor data, but none of the nodes
+    /// yet. Parsing takes a while for huge documents, use
+    /// [`ChunkedLoader::spawn`] to keep it off the UI thread too.
+    pub fn new(json: &str, mode: LoadMode) -> Result<Self, LoadReport> {
+        match serde_json::from_str(json) {
+            Ok(value) => Self::from_value(value, mode),
+            Err(err) => {
+                let mut report = LoadReport::default();
+                report.push(LoadElement::Document, "", err.to_string());
+                Err(report)
+            }
+        }
+    }
+
+    /// Same as [`ChunkedLoader::new`], for already parsed JSON.
+    pub fn from_value(mut value: Value, mode: LoadMode) -> Result<Self, LoadReport> {
+        let mut report = LoadReport::default();
+        let Some(mut graph) = value.get_mut("graph").map(Value::take) else {
+            report.push(LoadElement::Document, "graph", "missing field");
+            return Err(report);
+        };
+        let mut slots = |field: &str| match graph.get_mut(field).map(Value::take) {
+            Some(Value::Array(slots)) => slots,
+            _ => {
+                report.push(
+                    LoadElement::Document,
+                    join_path("graph", field),
+                    "expected a list of slots",
+                );
+                Vec::new()
+            }
+        };
+        let (nodes, inputs, outputs) = (slots("nodes"), slots("inputs"), slots("outputs"));
+        let connections = graph
+            .get_mut("connections")
+            .map_or(Value::Null, Value::take);
+        let stable_ids = graph.get_mut("stable_ids").map_or(Value::Null, Value::take);
+
+        value["graph"] = serde_json::to_value(Graph::<(), (), ()>::new())
+            .expect("An empty graph should always serialize");
+        let state = match serde_json::from_value(value) {
+            Ok(state) => state,
+            Err(err) => {
+                report.push(LoadElement::EditorState, "", err.to_string());
+                return Err(report);
+            }
+        };
+        if report
+            .issues
+            .iter()
+            .any(|issue| issue.path.starts_with("graph"))
+        {
+            return Err(report);
+        }
+
+        let total = nodes.iter().filter(|slot| occupied(slot)).count();
+        Ok(Self {
+            mode,
+            state,
+            nodes,
+            inputs,
+            outputs,
+            connections,
+            stable_ids,
+            next_node: 0,
+            progress: LoadProgress { loaded: 0, total },
+            mapping: IdMapping::default(),
+            report,
+        })
+    }
+
+    pub fn progress(&self) -> LoadProgress {
+        self.progress
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_node >= self.nodes.len()
+    }
+
+    /// Loads up to `max_nodes` more nodes, along with their parameters.
+    pub fn step(&mut self, max_nodes: usize) -> LoadProgress {
+        let mut loaded = 0;
+        while loaded < max_nodes && !self.is_done() {
+            let index = self.next_node;
+            self.next_node += 1;
+            let slot = self.nodes[index].take();
+            if let Some(key) = slot_key::<NodeId>(&slot, index) {
+                self.load_node(key, index, slot);
+                loaded += 1;
+            }
+        }
+        self.progress.loaded += loaded;
+        self.progress
+    }
+
+    /// Loads the remaining nodes and the connections between them, and
+    /// returns the loaded state. The draw order, positions and selection are
+    /// translated to the new ids, and the state is repaired as in
+    /// [`GraphEditorState::from_json_str_with_report`].
+    pub fn finish(mut self) -> LoadResult<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        self.step(usize::MAX);
+
+        let mut report = self.report;
+        let orphan = "parameter isn't listed by the node it belongs to";
+        for (index, slot) in self.inputs.iter().enumerate() {
+            if let Some(input) = slot_key(slot, index) {
+                let path = format!("graph.inputs[{index}]");
+                report.push(LoadElement::Input(input), path, orphan);
+            }
+        }
+        for (index, slot) in self.outputs.iter().enumerate() {
+            if let Some(output) = slot_key(slot, index) {
+                let path = format!("graph.outputs[{index}]");
+                report.push(LoadElement::Output(output), path, orphan);
+            }
+        }
+
+        let graph = &mut self.state.graph;
+        let mut connections = serde_json::json!({ "connections": self.connections });
+        check_slots::<InputId, SavedConnections>(
+            &mut connections,
+            "connections",
+            "graph",
+            LoadElement::Connection,
+            &mut report,
+        );
+        let connections: SecondaryMap<InputId, SavedConnections> =
+            serde_json::from_value(connections["connections"].take()).unwrap_or_default();
+        let connections = connections.into_iter().flat_map(|(input, connections)| {
+            connections
+                .into_outputs()
+                .into_iter()
+                .map(move |output| (input, output))
+        });
+        for (input, output) in connections {
+            match (
+                self.mapping.inputs.get(input),
+                self.mapping.outputs.get(output),
+            ) {
+                (Some(input), Some(output)) => graph.add_connection(*output, *input),
+                _ if report.mentions(LoadElement::Input(input)) => {}
+                _ => report.push(
+                    LoadElement::Connection(input),
+                    format!("graph.connections[{}]", input.data().as_ffi() as u32),
+                    IntegrityIssue::DanglingConnection { input, output }.to_string(),
+                ),
+            }
+        }
+
+        if !self.stable_ids.is_null() {
+            match serde_json::from_value::<StableIds>(self.stable_ids) {
+                Ok(stable_ids) => graph.stable_ids = stable_ids.remap(&self.mapping),
+                Err(err) => report.push(LoadElement::Document, "graph.stable_ids", err.to_string()),
+            }
+        }
+
+        self.state.remap_editor_data(&self.mapping);
+        report_repairs(self.state.repair(), "", &mut report);
+        report
+            .finish(self.state, self.mode)
+            .map(|(state, report)| (state, self.mapping, report))
+    }
+
+    fn load_node(&mut self, old_id: NodeId, index: usize, mut slot: Value) {
+        let path = format!("graph.nodes[{index}]");
+        let value = slot["value"].take();
+        let node = match Node::<NodeData>::deserialize(&value) {
+            Ok(node) => node,
+            Err(err) => {
+                self.report
+                    .push(LoadElement::Node(old_id), path, err.to_string());
+                // The parameters of the node are left out along with it
+                let params = |field: &str| {
+                    Vec::<(String, KeyData)>::deserialize(&value[field]).unwrap_or_default()
+                };
+                for (_, key) in params("inputs") {
+                    discard_slot(&mut self.inputs, key);
+                }
+                for (_, key) in params("outputs") {
+                    discard_slot(&mut self.outputs, key);
+                }
+                return;
+            }
+        };
+
+        let graph = &mut self.state.graph;
+        let new_id = graph.nodes.insert_with_key(|node_id| Node {
+            id: node_id,
+            label: node.label,
+            inputs: Vec::with_capacity(node.inputs.len()),
+            outputs: Vec::with_capacity(node.outputs.len()),
+            user_data: node.user_data,
+        });
+        self.mapping.nodes.insert(old_id, new_id);
+
+        for (name, old_input) in node.inputs {
+            let param = take_param::<InputParam<DataType, ValueType>>(
+                &mut self.inputs,
+                old_input.data(),
+                "inputs",
+                LoadElement::Input(old_input),
+                &mut self.report,
+            );
+            let Some(param) = param.filter(|param| param.node == old_id) else {
+                continue;
+            };
+            let new_input = graph.inputs.insert_with_key(|input_id| InputParam {
+                id: input_id,
+                node: new_id,
+                ..param
+            });
+            graph.nodes[new_id].inputs.push((name, new_input));
+            self.mapping.inputs.insert(old_input, new_input);
+        }
+
+        for (name, old_output) in node.outputs {
+            let param = take_param::<OutputParam<DataType>>(
+                &mut self.outputs,
+                old_output.data(),
+                "outputs",
+                LoadElement::Output(old_output),
+                &mut self.report,
+            );
+            let Some(param) = param.filter(|param| param.node == old_id) else {
+                continue;
+            };
+            let new_output = graph.outputs.insert_with_key(|output_id| OutputParam {
+                id: output_id,
+                node: new_id,
+                ..param
+            });
+            graph.nodes[new_id].outputs.push((name, new_output));
+            self.mapping.outputs.insert(old_output, new_output);
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    ChunkedLoader<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: DeserializeOwned + Send + 'static,
+    DataType: DeserializeOwned + PartialEq + Send + 'static,
+    ValueType: DeserializeOwned + Send + 'static,
+    NodeTemplate: DeserializeOwned + Send + 'static,
+    UserState: 'static,
+{
+    /// Parses and loads the document on a new thread, repainting `ctx` as it
+    /// progresses and once it's done. Poll the returned [`LoadingThread`]
+    /// every frame to show the progress and pick up the result.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn(
+        json: String,
+        mode: LoadMode,
+        ctx: egui::Context,
+    ) -> LoadingThread<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        const BATCH_SIZE: usize = 1024;
+
+        let progress = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+        let (sender, receiver) = mpsc::channel();
+        let thread_progress = progress.clone();
+        std::thread::spawn(move || {
+            let loader = Self::new(&json, mode);
+            drop(json);
+            let result = loader.and_then(|mut loader| {
+                while !loader.is_done() {
+                    let LoadProgress { loaded, total } = loader.step(BATCH_SIZE);
+                    thread_progress.0.store(loaded, Ordering::Relaxed);
+                    thread_progress.1.store(total, Ordering::Relaxed);
+                    ctx.request_repaint();
+                }
+                loader.finish()
+            });
+            // The receiving end may have been dropped to cancel the load
+            let _ = sender.send(result);
+            ctx.request_repaint();
+        });
+
+        LoadingThread { progress, receiver }
+    }
+}
+
+/// A document being loaded on another thread, see [`ChunkedLoader::spawn`].
+/// Dropping it discards the result.
+pub struct LoadingThread<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    progress: Arc<(AtomicUsize, AtomicUsize)>,
+    receiver: mpsc::Receiver<LoadResult<NodeData, DataType, ValueType, NodeTemplate, UserState>>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    LoadingThread<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// The progress as of the last loaded batch. The total is zero until the
+    /// document is parsed.
+    pub fn progress(&self) -> LoadProgress {
+        LoadProgress {
+            loaded: self.progress.0.load(Ordering::Relaxed),
+            total: self.progress.1.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The result of the load once it's done. Returns `None` while loading,
+    /// and after the result has been taken.
+    pub fn poll(
+        &self,
+    ) -> Option<LoadResult<NodeData, DataType, ValueType, NodeTemplate, UserState>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Whether a saved slotmap slot holds a value. Occupied slots have odd
+/// versions.
+fn occupied(slot: &Value) -> bool {
+    slot.get("version")
+        .and_then(Value::as_u64)
+        .is_some_and(|version| version % 2 == 1)
+}
+
+/// The key of an occupied slot. The first slot is a sentinel and never
+/// holds a value.
+fn slot_key<K: Key>(slot: &Value, index: usize) -> Option<K> {
+    let version = slot.get("version")?.as_u64()?;
+    (index > 0 && occupied(slot))
+        .then(|| K::from(KeyData::from_ffi((version << 32) | index as u64)))
+}
+
+fn discard_slot(slots: &mut [Value], key: KeyData) {
+    let index = key.as_ffi() as u32 as usize;
+    if let Some(slot) = slots.get_mut(index) {
+        if slot_key::<slotmap::DefaultKey>(slot, index).is_some_and(|slot| slot.data() == key) {
+            *slot = Value::Null;
+        }
+    }
+}
+
+/// Takes the parameter with the given key out of its slots, reporting why
+/// it can't be loaded if needed.
+fn take_param<T: DeserializeOwned>(
+    slots: &mut [Value],
+    key: KeyData,
+    field: &str,
+    element: LoadElement,
+    report: &mut LoadReport,
+) -> Option<T> {
+    let ffi = key.as_ffi();
+    let index = ffi as u32 as usize;
+    let path = format!("graph.{field}[{index}]");
+    let Some(slot) = slots.get_mut(index).filter(|slot| {
+        slot_key::<slotmap::DefaultKey>(slot, index).is_some_and(|slot| slot.data() == key)
+    }) else {
+        report.push(element, path, "parameter doesn't exist");
+        return None;
+    };
+
+    let value = slot["value"].take();
+    *slot = Value::Null;
+    match serde_json::from_value(value) {
+        Ok(param) => Some(param),
+        Err(err) => {
+            report.push(element, path, err.to_string());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type State = GraphEditorState<u32, (), u32, (), ()>;
+
+    #[test]
+    fn test_chunked_load() {
+        let mut state = State::default();
+        let mut previous = None;
+        for index in 0..10 {
+            let node = state.graph.add_node(format!("{index}"), index, |_, _| {});
+            let input = state.graph.add_input_param(
+                node,
+                "in".into(),
+                (),
+                index,
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            let output = state.graph.add_output_param(node, "out".into(), ());
+            if let Some(previous) = previous {
+                state.graph.add_connection(previous, input);
+            }
+            previous = Some(output);
+            state.node_order.push(node);
+            state
+                .node_positions
+                .insert(node, egui::pos2(index as f32, 0.0));
+            state
+                .node_orientations
+                .insert(node, NodeOrientation::LeftToRight);
+        }
+        let first = state.node_order.iter().next().unwrap();
+        state.graph.remove_node(first);
+        state.node_order.remove(first);
+        state.node_positions.remove(first);
+        state.node_orientations.remove(first);
+        let last = state.node_order.top().unwrap();
+        state.selected_nodes = vec![last];
+        state.graph.set_node_stable_id(last, "last").unwrap();
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut loader =
+            ChunkedLoader::<u32, (), u32, (), ()>::new(&json, LoadMode::Strict).unwrap();
+        assert_eq!(
+            loader.progress(),
+            LoadProgress {
+                loaded: 0,
+                total: 9
+            }
+        );
+        assert_eq!(loader.step(4).loaded, 4);
+        assert_eq!(loader.step(4).loaded, 8);
+        assert!(!loader.is_done());
+        let (loaded, mapping, report) = loader.finish().unwrap();
+
+        assert!(report.is_clean());
+        assert_eq!(loaded.graph.nodes.len(), 9);
+        assert_eq!(loaded.graph.connections.len(), 8);
+        let last = mapping.nodes[last];
+        assert_eq!(loaded.selected_nodes, vec![last]);
+        assert_eq!(loaded.graph.node_by_stable_id("last"), Some(last));
+        assert_eq!(loaded.graph[last].user_data, 9);
+        assert_eq!(loaded.node_positions[last], egui::pos2(9.0, 0.0));
+        assert!(loaded.validate_integrity().is_ok());
+
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        let slot = state.graph[last].inputs[0].1.data().as_ffi() as u32 as usize;
+        value["graph"]["inputs"][slot]["value"]["value"] = "not a number".into();
+        let report = ChunkedLoader::<u32, (), u32, (), ()>::from_value(value, LoadMode::Strict)
+            .unwrap()
+            .finish()
+            .err()
+            .unwrap();
+        assert_eq!(report.issues[0].path, format!("graph.inputs[{slot}]"));
+    }
+}
@@ -0,0 +1,185 @@
+use super::*;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Prefix of the clipboard text for copied nodes, so that pasting any other
+/// text is ignored.
+const CLIPBOARD_PREFIX: &str = "egui-graph-edit:";
+
+impl<NodeData, DataType, ValueType> GraphFragment<NodeData, DataType, ValueType>
+where
+    NodeData: Serialize + DeserializeOwned,
+    DataType: Serialize + DeserializeOwned,
+    ValueType: Serialize + DeserializeOwned,
+{
+    /// Serializes the fragment into text suitable for the system clipboard.
+    /// Fails if the node data or the values can't be written as JSON, e.g.
+    /// maps whose keys aren't strings.
+    pub fn to_clipboard_text(&self) -> serde_json::Result<String> {
+        Ok(format!(
+            "{CLIPBOARD_PREFIX}{}",
+            serde_json::to_string(self)?
+        ))
+    }
+
+    /// Parses text produced by [`GraphFragment::to_clipboard_text`]. Returns
+    /// `None` for any other text.
+    pub fn from_clipboard_text(text: &str) -> Option<Self> {
+        let json = text.trim().strip_prefix(CLIPBOARD_PREFIX)?;
+        serde_json::from_str(json).ok()
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<Response = UserResponse> + Clone + Serialize + DeserializeOwned,
+    UserResponse: UserResponseTrait,
+    DataType: PartialEq + Clone + Serialize + DeserializeOwned,
+    ValueType: Clone + Serialize + DeserializeOwned,
+{
+    /// Like [`GraphEditorState::enable_clipboard_shortcuts`], going through
+    /// the system clipboard: copied nodes are put there as text, see
+    /// [`GraphFragment::to_clipboard_text`], so they can be pasted into
+    /// other editors or instances of the application. Pasting text that
+    /// isn't a copy of nodes does nothing.
+    ///
+    /// This reacts to egui's copy, cut and paste events rather than to key
+    /// presses, so it works wherever egui integrates with the clipboard,
+    /// including web browsers where the clipboard can only be accessed
+    /// asynchronously.
+    pub fn enable_system_clipboard_shortcuts(&mut self) {
+        self.enable_clipboard_shortcuts();
+        let Some(hooks) = &mut self.clipboard_hooks else {
+            return;
+        };
+        hooks.copy = |state: &mut Self, ctx| {
+            if state.selected_nodes.is_empty() {
+                return false;
+            }
+            let fragment = state.copy_fragment(&state.selected_nodes);
+            // Nothing is cut when the nodes can't be copied
+            let Ok(text) = fragment.to_clipboard_text() else {
+                return false;
+            };
+            ctx.copy_text(text);
+            state.clipboard = Some(fragment);
+            true
+        };
+        hooks.paste = |state: &mut Self, position, text| {
+            let Some(mut fragment) = text.and_then(GraphFragment::from_clipboard_text) else {
+                return Vec::new();
+            };
+            fragment.source_position = None;
+            state.paste_fragment(fragment, position);
+            state.selected_nodes.clone()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_clipboard_text() {
+        let mut graph = Graph::<u32, (), f32>::new();
+        let node = graph.add_node("node".into(), 7, |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "x".into(),
+                (),
+                1.5,
+                InputParamKind::ConstantOnly,
+                true,
+            );
+        });
+        let mut node_positions = SecondaryMap::new();
+        node_positions.insert(node, egui::Pos2::ZERO);
+        let fragment = GraphFragment {
+            graph,
+            node_positions,
+            node_orientations: SecondaryMap::new(),
+            source_position: None,
+        };
+
+        let text = fragment.to_clipboard_text().unwrap();
+        let parsed = GraphFragment::<u32, (), f32>::from_clipboard_text(&text).unwrap();
+        assert_eq!(parsed.graph[node].user_data, 7);
+        assert_eq!(*parsed.graph.inputs.values().next().unwrap().value(), 1.5);
+        assert!(GraphFragment::<u32, (), f32>::from_clipboard_text("hello").is_none());
+
+        // JSON maps only have string keys
+        let mut graph = Graph::<HashMap<(u8, u8), u8>, (), ()>::new();
+        graph.add_node("node".into(), HashMap::from([((0, 0), 0)]), |_, _| {});
+        let fragment = GraphFragment {
+            graph,
+            node_positions: SecondaryMap::new(),
+            node_orientations: SecondaryMap::new(),
+            source_position: None,
+        };
+        assert!(fragment.to_clipboard_text().is_err());
+    }
+
+    #[cfg(feature = "synthetic")]
+    #[test]
+    fn test_system_clipboard_shortcuts() {
+        use egui::{pos2, Event, OutputCommand, RawInput};
+
+        let mut state = SyntheticGraphOptions {
+            nodes: 1,
+            ..Default::default()
+        }
+        .build_editor_state();
+        state.enable_system_clipboard_shortcuts();
+        let node = state.node_order.to_vec()[0];
+        state.selected_nodes = vec![node];
+
+        let ctx = egui::Context::default();
+        let frame = |state: &mut SyntheticEditorState, event: Event| {
+            let input = RawInput {
+                events: vec![Event::PointerMoved(pos2(500.0, 400.0)), event],
+                ..Default::default()
+            };
+            crate::synthetic::run_editor_frame(&ctx, state, input)
+        };
+
+        let (_, output) = frame(&mut state, Event::Copy);
+        let text = output
+            .platform_output
+            .commands
+            .iter()
+            .find_map(|command| match command {
+                OutputCommand::CopyText(text) => Some(text.clone()),
+                _ => None,
+            })
+            .expect("Copying should fill the clipboard");
+        assert!(
+            GraphFragment::<SyntheticNode, SyntheticType, SyntheticValue>::from_clipboard_text(
+                &text
+            )
+            .is_some()
+        );
+
+        // Other text isn't pasted
+        let (response, _) = frame(&mut state, Event::Paste("hello".into()));
+        assert!(response.node_responses.is_empty());
+        assert_eq!(state.graph.nodes.len(), 1);
+
+        let (response, _) = frame(&mut state, Event::Paste(text));
+        assert_eq!(state.graph.nodes.len(), 2);
+        let [NodeResponse::CreatedNode(pasted)] = response.node_responses[..] else {
+            panic!("expected a CreatedNode response");
+        };
+        assert_eq!(state.selected_nodes, vec![pasted]);
+
+        let (response, _) = frame(&mut state, Event::Cut);
+        assert_eq!(state.graph.nodes.len(), 1);
+        assert!(!state.graph.nodes.contains_key(pasted));
+        assert!(response
+            .node_responses
+            .iter()
+            .any(|response| matches!(response, NodeResponse::DeleteNodeFull { node_id, .. } if *node_id == pasted)));
+    }
+}
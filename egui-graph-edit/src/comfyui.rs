@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+
+use super::*;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// A workflow in the JSON format saved by the [ComfyUI](https://github.com/comfyanonymous/ComfyUI)
+/// frontend. Fields that this crate doesn't use are kept as raw JSON so they
+/// survive a round trip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComfyWorkflow {
+    #[serde(default)]
+    pub last_node_id: u64,
+    #[serde(default)]
+    pub last_link_id: u64,
+    #[serde(default)]
+    pub nodes: Vec<ComfyNode>,
+    #[serde(default)]
+    pub links: Vec<ComfyLink>,
+    #[serde(default)]
+    pub groups: Vec<Value>,
+    #[serde(default)]
+    pub config: Value,
+    #[serde(default)]
+    pub extra: Value,
+    #[serde(default = "default_version")]
+    pub version: f32,
+}
+
+fn default_version() -> f32 {
+    0.4
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComfyNode {
+    pub id: u64,
+    /// The ComfyUI node class, e.g. `KSampler`.
+    #[serde(rename = "type")]
+    pub class_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(deserialize_with = "deserialize_vec2")]
+    pub pos: [f32; 2],
+    #[serde(default, deserialize_with = "deserialize_vec2")]
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub flags: Value,
+    #[serde(default)]
+    pub order: u64,
+    #[serde(default)]
+    pub mode: u64,
+    #[serde(default)]
+    pub inputs: Vec<ComfyInput>,
+    #[serde(default)]
+    pub outputs: Vec<ComfyOutput>,
+    #[serde(default)]
+    pub properties: Value,
+    /// The values of the node widgets, i.e. its constant inputs, in the order
+    /// defined by the ComfyUI node class.
+    #[serde(default)]
+    pub widgets_values: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComfyInput {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub link: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub widget: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComfyOutput {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub links: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot_index: Option<usize>,
+}
+
+/// A connection between two ComfyUI nodes. Serialized as the array
+/// `[id, origin_id, origin_slot, target_id, target_slot, type]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    from = "(u64, u64, usize, u64, usize, Value)",
+    into = "(u64, u64, usize, u64, usize, Value)"
+)]
+pub struct ComfyLink {
+    pub id: u64,
+    pub origin_id: u64,
+    pub origin_slot: usize,
+    pub target_id: u64,
+    pub target_slot: usize,
+    pub data_type: Value,
+}
+
+impl From<(u64, u64, usize, u64, usize, Value)> for ComfyLink {
+    fn from(
+        (id, origin_id, origin_slot, target_id, target_slot, data_type): (
+            u64,
+            u64,
+            usize,
+            u64,
+            usize,
+            Value,
+        ),
+    ) -> Self {
+        Self {
+            id,
+            origin_id,
+            origin_slot,
+            target_id,
+            target_slot,
+            data_type,
+        }
+    }
+}
+
+impl From<ComfyLink> for (u64, u64, usize, u64, usize, Value) {
+    fn from(link: ComfyLink) -> Self {
+        (
+            link.id,
+            link.origin_id,
+            link.origin_slot,
+            link.target_id,
+            link.target_slot,
+            link.data_type,
+        )
+    }
+}
+
+/// Older ComfyUI versions store positions and sizes as `{"0": x, "1": y}`
+/// instead of `[x, y]`.
+fn deserialize_vec2<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[f32; 2], D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Vec2Repr {
+        Array([f32; 2]),
+        Object {
+            #[serde(rename = "0")]
+            x: f32,
+            #[serde(rename = "1")]
+            y: f32,
+        },
+    }
+
+    Ok(match Vec2Repr::deserialize(deserializer)? {
+        Vec2Repr::Array(array) => array,
+        Vec2Repr::Object { x, y } => [x, y],
+    })
+}
+
+impl ComfyWorkflow {
+    pub fn from_json_str(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Describes how the nodes of the application correspond to ComfyUI nodes.
+/// Implemented by the user to convert graphs with
+/// [`GraphEditorState::to_comfy_workflow`] and
+/// [`GraphEditorState::import_comfy_workflow`].
+///
+/// ComfyUI identifies the ports of a node by their index, or slot. By
+/// default, output slots are the node outputs in order, and input slots are
+/// the node inputs accepting connections, in order.
+pub trait ComfyNodeMapping<NodeData, DataType, ValueType> {
+    /// The ComfyUI class of the node, e.g. `KSampler`.
+    fn comfy_type(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+    ) -> String;
+
+    /// The ComfyUI name of a data type, e.g. `MODEL` or `LATENT`.
+    fn comfy_data_type(&mut self, data_type: &DataType) -> String;
+
+    /// The widget values of the node, in the order ComfyUI expects them for
+    /// the node class. Defaults to no values.
+    fn widgets_values(
+        &mut self,
+        _graph: &Graph<NodeData, DataType, ValueType>,
+        _node_id: NodeId,
+    ) -> Vec<Value> {
+        Vec::new()
+    }
+
+    /// The size of the node written to the workflow.
+    fn node_size(
+        &mut self,
+        _graph: &Graph<NodeData, DataType, ValueType>,
+        _node_id: NodeId,
+    ) -> egui::Vec2 {
+        egui::vec2(200.0, 100.0)
+    }
+
+    /// Adds a node corresponding to `node`, including its parameters, to the
+    /// graph. The widget values of the ComfyUI node should be used to set the
+    /// values of the constant inputs. Returns `None` for unsupported node
+    /// classes, which are skipped along with their links.
+    fn build_node(
+        &mut self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        node: &ComfyNode,
+    ) -> Option<NodeId>;
+
+    fn input_slot(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+        slot: usize,
+    ) -> Option<InputId> {
+        connectable_inputs(graph, node_id).nth(slot)
+    }
+
+    fn output_slot(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+        slot: usize,
+    ) -> Option<OutputId> {
+        graph[node_id].outputs.get(slot).map(|(_, id)| *id)
+    }
+}
+
+fn connectable_inputs<NodeData, DataType, ValueType>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    node_id: NodeId,
+) -> impl Iterator<Item = InputId> + '_ {
+    graph[node_id]
+        .inputs
+        .iter()
+        .map(|(_, id)| *id)
+        .filter(|id| !matches!(graph[*id].kind, InputParamKind::ConstantOnly))
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Converts the graph into a ComfyUI workflow. Nodes are numbered from 1,
+    /// in drawing order.
+    pub fn to_comfy_workflow(
+        &self,
+        mapping: &mut impl ComfyNodeMapping<NodeData, DataType, ValueType>,
+    ) -> ComfyWorkflow {
+        let graph = &self.graph;
+        let comfy_ids: HashMap<NodeId, u64> = self
+            .node_order
+            .iter()
+            .enumerate()
+            .map(|(index, node_id)| (node_id, index as u64 + 1))
+            .collect();
+
+        let mut input_slots = HashMap::new();
+        let mut output_slots = HashMap::new();
+        for node_id in &self.node_order {
+            for (slot, input) in connectable_inputs(graph, node_id).enumerate() {
+                input_slots.insert(input, slot);
+            }
+            for (slot, (_, output)) in graph[node_id].outputs.iter().enumerate() {
+                output_slots.insert(*output, slot);
+            }
+        }
+
+        let mut links = Vec::new();
+        let mut input_links = HashMap::new();
+        let mut output_links = HashMap::<OutputId, Vec<u64>>::new();
+        for (input, output) in graph.iter_connections() {
+            let id = links.len() as u64 + 1;
+            let (Some(input_slot), Some(output_slot)) =
+                (input_slots.get(&input), output_slots.get(&output))
+            else {
+                continue;
+            };
+            links.push(ComfyLink {
+                id,
+                origin_id: comfy_ids[&graph[output].node],
+                origin_slot: *output_slot,
+                target_id: comfy_ids[&graph[input].node],
+                target_slot: *input_slot,
+                data_type: Value::String(mapping.comfy_data_type(&graph[output].typ)),
+            });
+            input_links.insert(input, id);
+            output_links.entry(output).or_default().push(id);
+        }
+
+        let nodes = self
+            .node_order
+            .iter()
+            .enumerate()
+            .map(|(order, node_id)| {
+                let node = &graph[node_id];
+                let class_type = mapping.comfy_type(graph, node_id);
+                let pos = self
+                    .node_positions
+                    .get(node_id)
+                    .copied()
+                    .unwrap_or(egui::Pos2::ZERO);
+                let size = mapping.node_size(graph, node_id);
+
+                ComfyNode {
+                    id: comfy_ids[&node_id],
+                    title: (node.label != class_type).then(|| node.label.clone()),
+                    class_type,
+                    pos: [pos.x, pos.y],
+                    size: [size.x, size.y],
+                    flags: Value::Object(Default::default()),
+                    order: order as u64,
+                    mode: 0,
+                    inputs: connectable_inputs(graph, node_id)
+                        .map(|input| ComfyInput {
+                            name: param_name(&node.inputs, input),
+                            data_type: mapping.comfy_data_type(&graph[input].typ),
+                            link: input_links.get(&input).copied(),
+                            widget: None,
+                        })
+                        .collect(),
+                    outputs: node
+                        .outputs
+                        .iter()
+                        .enumerate()
+                        .map(|(slot, (name, output))| ComfyOutput {
+                            name: name.clone(),
+                            data_type: mapping.comfy_data_type(&graph[*output].typ),
+                            links: Some(output_links.remove(output).unwrap_or_default()),
+                            slot_index: Some(slot),
+                        })
+                        .collect(),
+                    properties: Value::Object(Default::default()),
+                    widgets_values: Value::Array(mapping.widgets_values(graph, node_id)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        ComfyWorkflow {
+            last_node_id: nodes.len() as u64,
+            last_link_id: links.len() as u64,
+            nodes,
+            links,
+            groups: Vec::new(),
+            config: Value::Object(Default::default()),
+            extra: Value::Object(Default::default()),
+            version: default_version(),
+        }
+    }
+
+    /// Adds the nodes and links of a ComfyUI workflow to the graph, keeping
+    /// their positions. Returns the id given to each imported ComfyUI node.
+    pub fn import_comfy_workflow(
+        &mut self,
+        workflow: &ComfyWorkflow,
+        mapping: &mut impl ComfyNodeMapping<NodeData, DataType, ValueType>,
+    ) -> HashMap<u64, NodeId> {
+        let mut node_ids = HashMap::new();
+        for comfy_node in &workflow.nodes {
+            let Some(node_id) = mapping.build_node(&mut self.graph, comfy_node) else {
+                continue;
+            };
+            if let Some(title) = &comfy_node.title {
+                self.graph[node_id].label = title.clone();
+            }
+            self.node_positions
+                .insert(node_id, egui::pos2(comfy_node.pos[0], comfy_node.pos[1]));
+            self.node_orientations
+                .insert(node_id, NodeOrientation::LeftToRight);
+            self.node_order.push(node_id);
+            node_ids.insert(comfy_node.id, node_id);
+        }
+
+        for link in &workflow.links {
+            let (Some(origin), Some(target)) =
+                (node_ids.get(&link.origin_id), node_ids.get(&link.target_id))
+            else {
+                continue;
+            };
+            let output = mapping.output_slot(&self.graph, *origin, link.origin_slot);
+            let input = mapping.input_slot(&self.graph, *target, link.target_slot);
+            if let (Some(output), Some(input)) = (output, input) {
+                self.graph.add_connection(output, input);
+            }
+        }
+
+        node_ids
+    }
+}
+
+fn param_name<Id: PartialEq>(params: &[(String, Id)], id: Id) -> String {
+    params
+        .iter()
+        .find(|(_, param_id)| *param_id == id)
+        .map(|(name, _)| name.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = r#"{
+        "last_node_id": 2,
+        "last_link_id": 1,
+        "nodes": [
+            {"id": 1, "type": "Seed", "pos": [10, 20], "size": {"0": 200, "1": 80},
+             "outputs": [{"name": "SEED", "type": "INT", "links": [1], "slot_index": 0}],
+             "widgets_values": [42]},
+            {"id": 2, "type": "Print", "pos": [300, 20], "size": [200, 80],
+             "inputs": [{"name": "value", "type": "INT", "link": 1}]}
+        ],
+        "links": [[1, 1, 0, 2, 0, "INT"]],
+        "groups": [],
+        "config": {},
+        "extra": {},
+        "version": 0.4
+    }"#;
+
+    struct Mapping;
+
+    impl ComfyNodeMapping<String, (), i64> for Mapping {
+        fn comfy_type(&mut self, graph: &Graph<String, (), i64>, node_id: NodeId) -> String {
+            graph[node_id].user_data.clone()
+        }
+
+        fn comfy_data_type(&mut self, _data_type: &()) -> String {
+            "INT".into()
+        }
+
+        fn widgets_values(
+            &mut self,
+            graph: &Graph<String, (), i64>,
+            node_id: NodeId,
+        ) -> Vec<Value> {
+            graph[node_id]
+                .inputs(graph)
+                .filter(|input| matches!(input.kind, InputParamKind::ConstantOnly))
+                .map(|input| input.value.into())
+                .collect()
+        }
+
+        fn build_node(
+            &mut self,
+            graph: &mut Graph<String, (), i64>,
+            node: &ComfyNode,
+        ) -> Option<NodeId> {
+            let class_type = node.class_type.clone();
+            let seed = node.widgets_values.get(0).and_then(Value::as_i64);
+            Some(
+                graph.add_node(class_type.clone(), class_type, |graph, node_id| match node
+                    .class_type
+                    .as_str()
+                {
+                    "Seed" => {
+                        graph.add_input_param(
+                            node_id,
+                            "seed".into(),
+                            (),
+                            seed.unwrap_or_default(),
+                            InputParamKind::ConstantOnly,
+                            true,
+                        );
+                        graph.add_output_param(node_id, "SEED".into(), ());
+                    }
+                    _ => {
+                        graph.add_input_param(
+                            node_id,
+                            "value".into(),
+                            (),
+                            0,
+                            InputParamKind::ConnectionOnly,
+                            true,
+                        );
+                    }
+                }),
+            )
+        }
+    }
+
+    #[test]
+    fn test_comfy_roundtrip() {
+        let workflow = ComfyWorkflow::from_json_str(WORKFLOW).unwrap();
+        let mut state = GraphEditorState::<String, (), i64, (), ()>::default();
+        let ids = state.import_comfy_workflow(&workflow, &mut Mapping);
+        assert_eq!(state.graph.nodes.len(), 2);
+        assert_eq!(state.graph.connections.len(), 1);
+        assert_eq!(state.node_positions[ids[&2]], egui::pos2(300.0, 20.0));
+
+        let exported = state.to_comfy_workflow(&mut Mapping);
+        assert_eq!(exported.links.len(), 1);
+        assert_eq!(exported.links[0].origin_slot, 0);
+        assert_eq!(exported.links[0].target_slot, 0);
+        assert_eq!(exported.nodes[0].widgets_values, serde_json::json!([42]));
+        assert_eq!(exported.nodes[1].inputs[0].link, Some(exported.links[0].id));
+
+        let json = exported.to_json_string().unwrap();
+        assert!(json.contains(r#""type": "Seed""#));
+    }
+}
@@ -0,0 +1,404 @@
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// An edit that can be performed on a [`GraphEditorState`] without going
+/// through the UI, see [`GraphEditorState::apply`]. Each command matches an
+/// action of interactive editing.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum GraphCommand<NodeTemplate, ValueType> {
+    /// Creates a node at `position`, in graph coordinates, as picking it in
+    /// the node finder does.
+    CreateNodeFromTemplate {
+        template: NodeTemplate,
+        position: egui::Pos2,
+    },
+    /// Connects an output to an input, replacing the previous connection of
+    /// the input.
+    Connect {
+        output: OutputId,
+        input: InputId,
+    },
+    Disconnect {
+        output: OutputId,
+        input: InputId,
+    },
+    /// Deletes a node along with its connections.
+    Delete(NodeId),
+    /// Drags a node by `delta`. When the node is part of a selection of
+    /// several nodes, the whole selection moves and a
+    /// [`NodeResponse::MoveSelection`] is emitted.
+    Move {
+        node: NodeId,
+        delta: egui::Vec2,
+    },
+    /// Sets the constant value of an input, as its inline widget does.
+    SetValue {
+        input: InputId,
+        value: ValueType,
+    },
+    /// Selects a single node, as clicking it does.
+    Select(NodeId),
+    /// Replaces the selection, as box selection does.
+    SetSelection(Vec<NodeId>),
+    /// Draws a node on top of the others.
+    Raise(NodeId),
+    /// Selects a node and all the nodes connected to it, as the
+    /// [`EditorKeybindings::select_islands`] shortcut does.
+    SelectIsland(NodeId),
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<Response = UserResponse>,
+    UserResponse: UserResponseTrait,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+    >,
+    DataType: PartialEq,
+{
+    /// Performs `command` the same way the editor UI does, and returns the
+    /// responses the UI would have emitted for it. Commands referring to
+    /// missing nodes or parameters, and connections the UI wouldn't allow,
+    /// are rejected without changing the state.
+    ///
+    /// Pass the returned responses on to the code handling the responses of
+    /// [`GraphEditorState::draw_graph_editor`], so that both stay in sync.
+    pub fn apply(
+        &mut self,
+        command: GraphCommand<NodeTemplate, ValueType>,
+        user_state: &mut UserState,
+    ) -> Result<Vec<NodeResponse<UserResponse, NodeData>>, EguiGraphError> {
+        let response = match command {
+            GraphCommand::CreateNodeFromTemplate { template, position } => {
+                let node_id = self.add_node_from_template(&template, position, user_state);
+                NodeResponse::CreatedNode(node_id)
+            }
+            GraphCommand::Connect { output, input } => {
+                self.check_connection(output, input)?;
+                NodeResponse::ConnectEventEnded { output, input }
+            }
+            GraphCommand::Disconnect { output, input } => {
+                // Unlike in the UI, the connection isn't picked up to be
+                // dropped elsewhere.
+                if !self.graph.disconnect(output, input) {
+                    return Err(EguiGraphError::InvalidParameterId(input.into()));
+                }
+                return Ok(vec![NodeResponse::DisconnectEvent { output, input }]);
+            }
+            GraphCommand::Delete(node_id) => {
+                self.check_node(node_id)?;
+                let mut responses = vec![NodeResponse::DeleteNodeUi(node_id)];
+                responses.extend(self.delete_node(node_id));
+                return Ok(responses);
+            }
+            GraphCommand::Move { node, delta } => {
+                self.check_node(node)?;
+                self.move_response(node, delta)
+            }
+            GraphCommand::SetValue { input, value } => {
+                let param = self
+                    .graph
+                    .inputs
+                    .get_mut(input)
+                    .ok_or(EguiGraphError::InvalidParameterId(input.into()))?;
+                param.value = value;
+                return Ok(Vec::new());
+            }
+            GraphCommand::Select(node_id) => {
+                self.check_node(node_id)?;
+                NodeResponse::SelectNode(node_id)
+            }
+            GraphCommand::SetSelection(nodes) => {
+                for node_id in &nodes {
+                    self.check_node(*node_id)?;
+                }
+                self.selected_nodes = nodes;
+                return Ok(Vec::new());
+            }
+            GraphCommand::Raise(node_id) => {
+                self.check_node(node_id)?;
+                NodeResponse::RaiseNode(node_id)
+            }
+            GraphCommand::SelectIsland(node_id) => {
+                self.check_node(node_id)?;
+                self.select_islands(&[node_id]);
+                return Ok(Vec::new());
+            }
+        };
+
+        let mut responses = self.handle_node_response(&response);
+        responses.insert(0, response);
+        Ok(responses)
+    }
+
+    fn check_node(&self, node_id: NodeId) -> Result<(), EguiGraphError> {
+        if self.graph.nodes.contains_key(node_id) && self.node_positions.contains_key(node_id) {
+            Ok(())
+        } else {
+            Err(EguiGraphError::InvalidNodeId(node_id))
+        }
+    }
+
+    /// Applies the rules of the UI: ports must have the same type and belong
+    /// to different nodes, the input must have a port, and the connection
+    /// must not close a cycle under [`CyclePolicy::Reject`].
+    fn check_connection(&self, output: OutputId, input: InputId) -> Result<(), EguiGraphError> {
+        let output_param = self
+            .graph
+            .outputs
+            .get(output)
+            .ok_or(EguiGraphError::InvalidParameterId(output.into()))?;
+        let input_param = self
+            .graph
+            .inputs
+            .get(input)
+            .ok_or(EguiGraphError::InvalidParameterId(input.into()))?;
+        if output_param.typ != input_param.typ
+            || output_param.node == input_param.node
+            || matches!(input_param.kind, InputParamKind::ConstantOnly)
+            || (self.cycle_policy == CyclePolicy::Reject
+                && self.graph.would_create_cycle(output, input))
+        {
+            return Err(EguiGraphError::InvalidConnection { output, input });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[derive(Clone, Debug)]
+    struct Data;
+
+    impl NodeDataTrait for Data {
+        type Response = EmptyResponse;
+        type UserState = ();
+        type DataType = u8;
+        type ValueType = i32;
+
+        fn bottom_ui(
+            &self,
+            _ui: &mut egui::Ui,
+            _node_id: NodeId,
+            _graph: &Graph<Self, u8, i32>,
+            _user_state: &mut (),
+        ) -> Vec<NodeResponse<EmptyResponse, Self>> {
+            Vec::new()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct EmptyResponse;
+
+    impl UserResponseTrait for EmptyResponse {}
+
+    #[derive(Clone, Debug)]
+    struct Template;
+
+    impl NodeTemplateTrait for Template {
+        type NodeData = Data;
+        type DataType = u8;
+        type ValueType = i32;
+        type UserState = ();
+        type CategoryType = ();
+
+        fn node_finder_label(&self, _user_state: &mut ()) -> Cow<'_, str> {
+            "node".into()
+        }
+
+        fn node_graph_label(&self, _user_state: &mut ()) -> String {
+            "node".into()
+        }
+
+        fn user_data(&self, _user_state: &mut ()) -> Data {
+            Data
+        }
+
+        fn build_node(
+            &self,
+            graph: &mut Graph<Data, u8, i32>,
+            _user_state: &mut (),
+            node_id: NodeId,
+        ) {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                0,
+                0,
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), 0);
+        }
+    }
+
+    type State = GraphEditorState<Data, u8, i32, Template, ()>;
+
+    fn create(state: &mut State, x: f32) -> NodeId {
+        let command = GraphCommand::CreateNodeFromTemplate {
+            template: Template,
+            position: egui::pos2(x, 0.0),
+        };
+        match state.apply(command, &mut ()).unwrap()[..] {
+            [NodeResponse::CreatedNode(node_id)] => node_id,
+            _ => panic!("expected a single CreatedNode response"),
+        }
+    }
+
+    #[test]
+    fn test_apply_commands() {
+        let mut state = State::default();
+        let a = create(&mut state, 0.0);
+        let b = create(&mut state, 300.0);
+        let output = state.graph[a].get_output("out").unwrap();
+        let input = state.graph[b].get_input("in").unwrap();
+
+        let self_loop = GraphCommand::Connect {
+            output,
+            input: state.graph[a].get_input("in").unwrap(),
+        };
+        assert!(state.apply(self_loop, &mut ()).is_err());
+
+        let responses = state
+            .apply(GraphCommand::Connect { output, input }, &mut ())
+            .unwrap();
+        assert!(matches!(
+            responses[..],
+            [NodeResponse::ConnectEventEnded { .. }]
+        ));
+        assert_eq!(state.graph.connection(input), Some(output));
+
+        state
+            .apply(GraphCommand::SetSelection(vec![a, b]), &mut ())
+            .unwrap();
+        let delta = egui::vec2(5.0, 10.0);
+        let responses = state
+            .apply(GraphCommand::Move { node: a, delta }, &mut ())
+            .unwrap();
+        assert!(matches!(
+            &responses[..],
+            [NodeResponse::MoveSelection { nodes, .. }] if nodes == &[a, b]
+        ));
+        assert_eq!(state.node_positions[b], egui::pos2(305.0, 10.0));
+
+        let c = create(&mut state, 600.0);
+        state.apply(GraphCommand::SelectIsland(b), &mut ()).unwrap();
+        assert_eq!(state.selected_nodes, vec![a, b]);
+        state.apply(GraphCommand::Delete(c), &mut ()).unwrap();
+
+        let responses = state.apply(GraphCommand::Delete(a), &mut ()).unwrap();
+        assert!(matches!(
+            responses[..],
+            [
+                NodeResponse::DeleteNodeUi(_),
+                NodeResponse::DisconnectEvent { .. },
+                NodeResponse::DeleteNodeFull { .. }
+            ]
+        ));
+        assert_eq!(state.selected_nodes, vec![b]);
+        assert!(state.apply(GraphCommand::Select(a), &mut ()).is_err());
+        assert!(state.validate_integrity().is_ok());
+    }
+
+    #[test]
+    fn test_apply_reject_cycles() {
+        let mut state = State::default();
+        let a = create(&mut state, 0.0);
+        let b = create(&mut state, 300.0);
+        let a_out = state.graph[a].get_output("out").unwrap();
+        let b_in = state.graph[b].get_input("in").unwrap();
+        let b_out = state.graph[b].get_output("out").unwrap();
+        let a_in = state.graph[a].get_input("in").unwrap();
+        state
+            .apply(
+                GraphCommand::Connect {
+                    output: a_out,
+                    input: b_in,
+                },
+                &mut (),
+            )
+            .unwrap();
+
+        state.cycle_policy = CyclePolicy::Reject;
+        let cycle = GraphCommand::Connect {
+            output: b_out,
+            input: a_in,
+        };
+        assert!(matches!(
+            state.apply(cycle.clone(), &mut ()),
+            Err(EguiGraphError::InvalidConnection { .. })
+        ));
+        assert_eq!(state.graph.connection(a_in), None);
+
+        state.cycle_policy = CyclePolicy::Allow;
+        state.apply(cycle, &mut ()).unwrap();
+        assert_eq!(state.graph.connection(a_in), Some(b_out));
+    }
+
+    #[cfg(feature = "synthetic")]
+    #[test]
+    fn test_drag_reject_cycles() {
+        use crate::synthetic::{
+            run_editor_frame, SyntheticEditorState, SyntheticGraphOptions, SyntheticShape,
+        };
+        use egui::{Event, Modifiers, PointerButton, Pos2, RawInput};
+
+        // Drags a connection from the output of the second node of a chain
+        // to the first input of the first node.
+        let drag = |policy| {
+            let mut state = SyntheticGraphOptions {
+                shape: SyntheticShape::Chain,
+                nodes: 2,
+                ..Default::default()
+            }
+            .build_editor_state();
+            state.cycle_policy = policy;
+            let nodes = state.node_order.to_vec();
+            let output = state.graph[nodes[1]].outputs[0].1;
+            let input = state.graph[nodes[0]].inputs[0].1;
+
+            let ctx = egui::Context::default();
+            let frame = |state: &mut SyntheticEditorState, events| {
+                let input = RawInput {
+                    events,
+                    ..Default::default()
+                };
+                run_editor_frame(&ctx, state, input).0.node_responses
+            };
+            let button = |pos: Pos2, pressed| Event::PointerButton {
+                pos,
+                button: PointerButton::Primary,
+                pressed,
+                modifiers: Modifiers::NONE,
+            };
+            frame(&mut state, Vec::new());
+            let from = state.port_locations[&output.into()];
+            let to = state.port_locations[&input.into()];
+            frame(
+                &mut state,
+                vec![Event::PointerMoved(from), button(from, true)],
+            );
+            frame(&mut state, vec![Event::PointerMoved(from.lerp(to, 0.5))]);
+            frame(&mut state, vec![Event::PointerMoved(to)]);
+            let responses = frame(&mut state, vec![button(to, false)]);
+            let connected = responses.iter().any(|response| {
+                matches!(response, NodeResponse::ConnectEventEnded { input: i, .. } if *i == input)
+            });
+            assert_eq!(connected, state.graph.connection(input) == Some(output));
+            connected
+        };
+
+        assert!(drag(CyclePolicy::Allow));
+        assert!(!drag(CyclePolicy::Reject));
+    }
+}
@@ -0,0 +1,197 @@
+use super::*;
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Rebuilds the storage of the graph so that it only takes as much memory
+    /// as its current elements need. Slotmaps never shrink, and removed
+    /// elements leave holes in them and in every map indexed by their ids, so
+    /// sessions that create and delete many nodes keep growing otherwise.
+    ///
+    /// Every node and parameter gets a new id, in the same order as before.
+    /// The returned [`IdMapping`] relates the old ids to the new ones, and
+    /// must be used to update any id stored outside of the graph: new ids may
+    /// be equal to old ids of unrelated elements. Parameters that aren't
+    /// listed by their node, and connections between missing parameters, are
+    /// dropped.
+    pub fn compact(&mut self) -> IdMapping {
+        let mut nodes = SlotMap::with_capacity_and_key(self.nodes.len());
+        let mut inputs = SlotMap::with_capacity_and_key(self.inputs.len());
+        let mut outputs = SlotMap::with_capacity_and_key(self.outputs.len());
+        let mut old_inputs = std::mem::take(&mut self.inputs);
+        let mut old_outputs = std::mem::take(&mut self.outputs);
+        let mut mapping = IdMapping::default();
+
+        for (old_node_id, node) in std::mem::take(&mut self.nodes) {
+            let new_node_id = nodes.insert_with_key(|node_id| Node {
+                id: node_id,
+                label: node.label,
+                inputs: Vec::with_capacity(node.inputs.len()),
+                outputs: Vec::with_capacity(node.outputs.len()),
+                user_data: node.user_data,
+            });
+            mapping.nodes.insert(old_node_id, new_node_id);
+
+            for (name, old_input_id) in node.inputs {
+                let Some(param) = old_inputs.remove(old_input_id) else {
+                    continue;
+                };
+                let new_input_id = inputs.insert_with_key(|input_id| InputParam {
+                    id: input_id,
+                    node: new_node_id,
+                    ..param
+                });
+                nodes[new_node_id].inputs.push((name, new_input_id));
+                mapping.inputs.insert(old_input_id, new_input_id);
+            }
+
+            for (name, old_output_id) in node.outputs {
+                let Some(param) = old_outputs.remove(old_output_id) else {
+                    continue;
+                };
+                let new_output_id = outputs.insert_with_key(|output_id| OutputParam {
+                    id: output_id,
+                    node: new_node_id,
+                    ..param
+                });
+                nodes[new_node_id].outputs.push((name, new_output_id));
+                mapping.outputs.insert(old_output_id, new_output_id);
+            }
+        }
+
+        let mut connections = SecondaryMap::with_capacity(inputs.len());
+        for (input, outputs) in std::mem::take(&mut self.connections) {
+            let Some(input) = mapping.inputs.get(input) else {
+                continue;
+            };
+            let outputs: SVec<OutputId> = outputs
+                .iter()
+                .filter_map(|output| mapping.outputs.get(*output).copied())
+                .collect();
+            if !outputs.is_empty() {
+                connections.insert(*input, outputs);
+            }
+        }
+
+        self.nodes = nodes;
+        self.inputs = inputs;
+        self.outputs = outputs;
+        self.connections = connections;
+        self.stable_ids = std::mem::take(&mut self.stable_ids).remap(&mapping);
+        self.rebuild_connection_index();
+        mapping
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Compacts the graph with [`Graph::compact`], and translates the editor
+    /// data (draw order, selection, positions and orientations) to the new
+    /// ids. The caches filled in while drawing are cleared, so the next frame
+    /// lays out every node again.
+    ///
+    /// The ids in `remote_participants` aren't translated, as they are
+    /// expected to be filled in again by the application.
+    pub fn compact(&mut self) -> IdMapping {
+        let mapping = self.graph.compact();
+        self.remap_editor_data(&mapping);
+        mapping
+    }
+
+    /// Translates the editor data to the ids of `mapping`, dropping the nodes
+    /// that aren't in it, and clears the caches filled in while drawing.
+    pub(crate) fn remap_editor_data(&mut self, mapping: &IdMapping) {
+        let node = |id: &NodeId| mapping.nodes.get(*id).copied();
+
+        self.node_order = self.node_order.iter().filter_map(|id| node(&id)).collect();
+        self.selected_nodes = self.selected_nodes.iter().filter_map(node).collect();
+        self.node_positions = remap_nodes(&self.node_positions, mapping);
+        self.node_orientations = remap_nodes(&self.node_orientations, mapping);
+        self.node_sizes = remap_nodes(&self.node_sizes, mapping);
+        self.collapsed_nodes = remap_nodes(&self.collapsed_nodes, mapping);
+        self.connection_in_progress = self
+            .connection_in_progress
+            .and_then(|(id, param)| Some((node(&id)?, mapping.param(param)?)));
+        self.node_drag = None;
+        self.layout_transition = Default::default();
+        self.frame_budget_state.active_node =
+            self.frame_budget_state.active_node.and_then(|id| node(&id));
+        self.focused_node = self.focused_node.and_then(|id| node(&id));
+
+        self.port_locations = Default::default();
+        self.node_rects = Default::default();
+        self.node_layouts = Default::default();
+        self.node_extents = Default::default();
+        self.node_rect_index = Default::default();
+        self.node_shapes = Default::default();
+    }
+}
+
+#[cfg(feature = "ui")]
+fn remap_nodes<T: Copy>(
+    map: &SecondaryMap<NodeId, T>,
+    mapping: &IdMapping,
+) -> SecondaryMap<NodeId, T> {
+    map.iter()
+        .filter_map(|(id, value)| Some((*mapping.nodes.get(id)?, *value)))
+        .collect()
+}
+
+#[cfg(all(test, feature = "ui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let add_node = |state: &mut GraphEditorState<(), (), (), (), ()>, x: f32| {
+            let node = state.graph.add_node("node".into(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    (),
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), ());
+            });
+            state.node_order.push(node);
+            state.node_positions.insert(node, egui::pos2(x, 0.0));
+            state
+                .node_orientations
+                .insert(node, NodeOrientation::LeftToRight);
+            node
+        };
+
+        let nodes: Vec<_> = (0..100).map(|i| add_node(&mut state, i as f32)).collect();
+        let a = add_node(&mut state, -1.0);
+        let b = add_node(&mut state, -2.0);
+        for node in nodes {
+            state.graph.remove_node(node);
+            state.node_order.remove(node);
+            state.node_positions.remove(node);
+            state.node_orientations.remove(node);
+        }
+        let a_out = state.graph[a].get_output("out").unwrap();
+        let b_in = state.graph[b].get_input("in").unwrap();
+        state.graph.add_connection(a_out, b_in);
+        state.graph.set_node_stable_id(b, "b").unwrap();
+        state.selected_nodes = vec![b];
+
+        let mapping = state.compact();
+        let (a, b) = (mapping.nodes[a], mapping.nodes[b]);
+        assert_eq!(state.node_order.to_vec(), vec![a, b]);
+        assert_eq!(state.selected_nodes, vec![b]);
+        assert_eq!(state.node_positions[b], egui::pos2(-2.0, 0.0));
+        assert_eq!(state.graph.node_by_stable_id("b"), Some(b));
+        assert_eq!(
+            state
+                .graph
+                .connection(state.graph[b].get_input("in").unwrap()),
+            state.graph[a].get_output("out").ok()
+        );
+        assert!(state.graph.nodes.capacity() < 100);
+        assert!(state.validate_integrity().is_ok());
+    }
+}
@@ -0,0 +1,329 @@
+use super::*;
+
+/// Node data that knows how to compute the outputs of its node from its
+/// inputs, so whole graphs can be run by a [`Scheduler`]. Values are looked
+/// up and set by port name, and converted to and from `Value` with `TryFrom`
+/// and `Into`:
+///
+/// ```
+/// # use egui_graph_edit::*;
+/// struct Double;
+///
+/// impl ComputeNode<f32> for Double {
+///     type Error = EguiGraphError;
+///
+///     fn compute(
+///         &self,
+///         inputs: &ComputeInputs<f32>,
+///         outputs: &mut ComputeOutputs<f32>,
+///     ) -> Result<(), EguiGraphError> {
+///         let x: f32 = inputs.get("x")?;
+///         outputs.set("out", x * 2.0);
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait ComputeNode<Value> {
+    type Error: From<EguiGraphError>;
+
+    fn compute(
+        &self,
+        inputs: &ComputeInputs<Value>,
+        outputs: &mut ComputeOutputs<Value>,
+    ) -> Result<(), Self::Error>;
+}
+
+/// The values of the inputs of a node being computed, by name. Multi-inputs
+/// have one value per connection.
+pub struct ComputeInputs<'a, Value> {
+    pub node_id: NodeId,
+    values: Vec<(&'a str, Value)>,
+}
+
+impl<Value> ComputeInputs<'_, Value> {
+    /// The value of the input named `name`: the value of the output
+    /// connected to it, or its constant otherwise.
+    pub fn value(&self, name: &str) -> Result<&Value, EguiGraphError> {
+        self.values
+            .iter()
+            .find(|(input, _)| *input == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| EguiGraphError::NoParameterNamed(self.node_id, name.into()))
+    }
+
+    /// The values of all outputs connected to the input named `name`, in
+    /// the order the connections were made, e.g. for multi-inputs. Gives its
+    /// constant when nothing is connected.
+    pub fn values<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'b Value> + 'b {
+        self.values
+            .iter()
+            .filter(move |(input, _)| *input == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Like [`Self::value`], converted to `T`.
+    pub fn get<T>(&self, name: &str) -> Result<T, EguiGraphError>
+    where
+        Value: Clone,
+        T: TryFrom<Value>,
+    {
+        T::try_from(self.value(name)?.clone())
+            .map_err(|_| EguiGraphError::WrongValueType(self.node_id, name.into()))
+    }
+}
+
+/// The values a node being computed sets on its outputs, by name.
+pub struct ComputeOutputs<Value> {
+    values: Vec<(String, Value)>,
+}
+
+impl<Value> ComputeOutputs<Value> {
+    pub fn set(&mut self, name: &str, value: impl Into<Value>) -> &mut Self {
+        let value = value.into();
+        match self.values.iter_mut().find(|(output, _)| output == name) {
+            Some((_, old)) => *old = value,
+            None => self.values.push((name.into(), value)),
+        }
+        self
+    }
+}
+
+/// Runs the nodes of a graph implementing [`ComputeNode`], each one after
+/// the nodes it depends on, and keeps the values of their outputs.
+/// Unconnected inputs take their constant, converted with `From`.
+///
+/// Unlike an [`Evaluator`], the scheduler always runs the whole graph. With
+/// the `parallel_evaluator` feature, [`Scheduler::run_parallel`] computes
+/// the nodes that don't depend on each other on several threads.
+#[derive(Clone, Debug)]
+pub struct Scheduler<Value> {
+    outputs: SecondaryMap<OutputId, Value>,
+}
+
+impl<Value> Default for Scheduler<Value> {
+    fn default() -> Self {
+        Self {
+            outputs: SecondaryMap::new(),
+        }
+    }
+}
+
+impl<Value> Scheduler<Value> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value of `output` as of the last run.
+    pub fn output(&self, output: OutputId) -> Option<&Value> {
+        self.outputs.get(output)
+    }
+
+    /// Computes all nodes of the graph, one after the other. Fails with
+    /// [`EguiGraphError::Cycle`] when the graph has cycles, or with the
+    /// first error returned by a node.
+    pub fn run<NodeData, DataType, ValueType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+    ) -> Result<(), NodeData::Error>
+    where
+        NodeData: ComputeNode<Value>,
+        ValueType: Clone,
+        Value: From<ValueType> + Clone,
+    {
+        self.outputs.clear();
+        for level in Self::levels(graph)? {
+            for node_id in level {
+                let outputs = self.compute(graph, node_id)?;
+                self.store(graph, node_id, outputs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::run`], computing the nodes that don't depend on each
+    /// other on the rayon thread pool.
+    #[cfg(feature = "parallel_evaluator")]
+    pub fn run_parallel<NodeData, DataType, ValueType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+    ) -> Result<(), NodeData::Error>
+    where
+        NodeData: ComputeNode<Value> + Sync,
+        NodeData::Error: Send,
+        DataType: Sync,
+        ValueType: Clone + Sync,
+        Value: From<ValueType> + Clone + Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        self.outputs.clear();
+        for level in Self::levels(graph)? {
+            let results: Vec<_> = level
+                .par_iter()
+                .map(|node_id| self.compute(graph, *node_id))
+                .collect();
+            for (node_id, outputs) in level.into_iter().zip(results) {
+                self.store(graph, node_id, outputs?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The nodes of the graph grouped by depth: each group only depends on
+    /// the groups before it.
+    fn levels<NodeData, DataType, ValueType>(
+        graph: &Graph<NodeData, DataType, ValueType>,
+    ) -> Result<Vec<Vec<NodeId>>, EguiGraphError> {
+        let mut depths: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        let mut levels: Vec<Vec<NodeId>> = Vec::new();
+        for node_id in graph.topological_order()? {
+            let depth = graph
+                .upstream_nodes(node_id)
+                .map(|upstream| depths[upstream] + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(node_id, depth);
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(node_id);
+        }
+        Ok(levels)
+    }
+
+    fn compute<NodeData, DataType, ValueType>(
+        &self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+    ) -> Result<ComputeOutputs<Value>, NodeData::Error>
+    where
+        NodeData: ComputeNode<Value>,
+        ValueType: Clone,
+        Value: From<ValueType> + Clone,
+    {
+        let node = &graph[node_id];
+        let mut values = Vec::with_capacity(node.inputs.len());
+        for (name, input) in &node.inputs {
+            let outputs = graph.connections(*input);
+            if outputs.is_empty() {
+                values.push((name.as_str(), graph[*input].value.clone().into()));
+            }
+            for output in outputs {
+                let value = self
+                    .outputs
+                    .get(*output)
+                    .cloned()
+                    .ok_or(EguiGraphError::MissingOutputValue(*output))?;
+                values.push((name.as_str(), value));
+            }
+        }
+        let inputs = ComputeInputs { node_id, values };
+        let mut outputs = ComputeOutputs { values: Vec::new() };
+        node.user_data.compute(&inputs, &mut outputs)?;
+        Ok(outputs)
+    }
+
+    fn store<NodeData, DataType, ValueType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+        outputs: ComputeOutputs<Value>,
+    ) -> Result<(), EguiGraphError> {
+        let node = &graph[node_id];
+        for (name, value) in outputs.values {
+            let output = node.get_output(&name)?;
+            self.outputs.insert(output, value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Value {
+        Number(f32),
+        Flag(bool),
+    }
+
+    impl From<f32> for Value {
+        fn from(value: f32) -> Self {
+            Self::Number(value)
+        }
+    }
+
+    impl TryFrom<Value> for f32 {
+        type Error = ();
+
+        fn try_from(value: Value) -> Result<Self, ()> {
+            match value {
+                Value::Number(value) => Ok(value),
+                Value::Flag(_) => Err(()),
+            }
+        }
+    }
+
+    /// Sums its inputs `a` and `b`.
+    struct Sum;
+
+    impl ComputeNode<Value> for Sum {
+        type Error = EguiGraphError;
+
+        fn compute(
+            &self,
+            inputs: &ComputeInputs<Value>,
+            outputs: &mut ComputeOutputs<Value>,
+        ) -> Result<(), EguiGraphError> {
+            let sum = inputs.get::<f32>("a")? + inputs.get::<f32>("b")?;
+            outputs.set("out", sum);
+            Ok(())
+        }
+    }
+
+    fn add_sum(graph: &mut Graph<Sum, (), f32>, a: f32, b: f32) -> (NodeId, OutputId) {
+        let node = graph.add_node("sum".into(), Sum, |graph, node| {
+            for (name, value) in [("a", a), ("b", b)] {
+                graph.add_input_param(
+                    node,
+                    name.into(),
+                    (),
+                    value,
+                    InputParamKind::ConnectionOrConstant,
+                    true,
+                );
+            }
+        });
+        (node, graph.add_output_param(node, "out".into(), ()))
+    }
+
+    #[test]
+    fn test_run() {
+        let mut graph = Graph::<Sum, (), f32>::new();
+        let (a, a_out) = add_sum(&mut graph, 1.0, 2.0);
+        let (_, b_out) = add_sum(&mut graph, 3.0, 4.0);
+        let (c, c_out) = add_sum(&mut graph, 0.0, 0.0);
+        graph.add_connection(a_out, graph[c].get_input("a").unwrap());
+        graph.add_connection(b_out, graph[c].get_input("b").unwrap());
+
+        let mut scheduler = Scheduler::new();
+        scheduler.run(&graph).unwrap();
+        assert_eq!(scheduler.output(c_out), Some(&Value::Number(10.0)));
+        #[cfg(feature = "parallel_evaluator")]
+        {
+            let mut parallel = Scheduler::new();
+            parallel.run_parallel(&graph).unwrap();
+            assert_eq!(parallel.output(c_out), Some(&Value::Number(10.0)));
+        }
+
+        let inputs = ComputeInputs {
+            node_id: a,
+            values: vec![("a", Value::Flag(true))],
+        };
+        assert!(matches!(
+            inputs.get::<f32>("a"),
+            Err(EguiGraphError::WrongValueType(node_id, _)) if node_id == a
+        ));
+    }
+}
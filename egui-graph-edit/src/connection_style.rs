@@ -0,0 +1,109 @@
+use egui::{Pos2, Vec2};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The spacing of the grid [`ConnectionStyle::Orthogonal`] connections turn
+/// on, in graph coordinates, when [`GraphEditorState::connection_grid`] isn't
+/// set.
+///
+/// [`GraphEditorState::connection_grid`]: crate::GraphEditorState::connection_grid
+pub const ORTHOGONAL_CONNECTION_SPACING: f32 = 10.0;
+
+/// How connections are drawn between their ports, see
+/// [`GraphEditorState::connection_style`].
+///
+/// [`GraphEditorState::connection_style`]: crate::GraphEditorState::connection_style
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(
+    feature = "persistence",
+    derive(Serialize, Deserialize),
+    serde(from = "SavedConnectionStyle", into = "SavedConnectionStyle")
+)]
+pub enum ConnectionStyle {
+    /// Curves leaving and entering the ports horizontally.
+    #[default]
+    Bezier,
+    /// Straight lines from port to port.
+    Straight,
+    /// Horizontal and vertical segments, turning on a fine grid.
+    Orthogonal,
+    /// The points returned by the function, in screen coordinates, from the
+    /// start to the end of the segment. Functions can't be serialized, so
+    /// this is saved as [`ConnectionStyle::Bezier`].
+    Custom(fn(&ConnectionSegment) -> Vec<Pos2>),
+}
+
+/// The [`ConnectionStyle`]s that can be saved.
+#[cfg(feature = "persistence")]
+#[derive(Serialize, Deserialize)]
+enum SavedConnectionStyle {
+    Bezier,
+    Straight,
+    Orthogonal,
+}
+
+#[cfg(feature = "persistence")]
+impl From<ConnectionStyle> for SavedConnectionStyle {
+    fn from(style: ConnectionStyle) -> Self {
+        match style {
+            ConnectionStyle::Bezier | ConnectionStyle::Custom(_) => Self::Bezier,
+            ConnectionStyle::Straight => Self::Straight,
+            ConnectionStyle::Orthogonal => Self::Orthogonal,
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl From<SavedConnectionStyle> for ConnectionStyle {
+    fn from(style: SavedConnectionStyle) -> Self {
+        match style {
+            SavedConnectionStyle::Bezier => Self::Bezier,
+            SavedConnectionStyle::Straight => Self::Straight,
+            SavedConnectionStyle::Orthogonal => Self::Orthogonal,
+        }
+    }
+}
+
+/// A segment of a connection, between two ports or the waypoints of the
+/// connection, passed to [`ConnectionStyle::Custom`]. Everything is in
+/// screen coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConnectionSegment {
+    pub src_pos: Pos2,
+    /// The unit direction the connection leaves `src_pos` in.
+    pub src_dir: Vec2,
+    pub dst_pos: Pos2,
+    /// The unit direction the connection enters `dst_pos` from, pointing
+    /// away from it.
+    pub dst_dir: Vec2,
+    /// The zoom of the editor, to scale the shape of the connection along.
+    pub scaling: f32,
+}
+
+#[cfg(all(test, feature = "persistence"))]
+mod tests {
+    use super::*;
+    use crate::GraphEditorState;
+
+    fn direct_route(segment: &ConnectionSegment) -> Vec<Pos2> {
+        vec![segment.src_pos, segment.dst_pos]
+    }
+
+    #[test]
+    fn test_save_connection_style() {
+        let saved = serde_json::to_string(&ConnectionStyle::Orthogonal).unwrap();
+        assert_eq!(saved, "\"Orthogonal\"");
+        let loaded: ConnectionStyle = serde_json::from_str(&saved).unwrap();
+        assert!(matches!(loaded, ConnectionStyle::Orthogonal));
+
+        let state = GraphEditorState::<(), (), (), (), ()> {
+            connection_style: ConnectionStyle::Custom(direct_route),
+            ..Default::default()
+        };
+        let saved = serde_json::to_value(&state).unwrap();
+        assert_eq!(saved["connection_style"], "Bezier");
+        let loaded: GraphEditorState<(), (), (), (), ()> = serde_json::from_value(saved).unwrap();
+        assert!(matches!(loaded.connection_style, ConnectionStyle::Bezier));
+    }
+}
@@ -0,0 +1,127 @@
+use super::*;
+
+/// Finds the nodes affected by the edits made to a graph, so applications
+/// can recompute only the invalidated part of an expensive pipeline. Like
+/// [`OperationRecorder`](crate::OperationRecorder), the tracker keeps a
+/// shadow copy of the graph and compares it with the current one, so it
+/// catches every edit no matter its origin: responses of the editor, inline
+/// value widgets or changes made by user code.
+///
+/// A node is dirty when it was added, when the value or the connection of
+/// one of its inputs changed, or when it was marked with
+/// [`DirtyTracker::mark_dirty`]. The nodes connected to a removed node are
+/// dirty, as their inputs lost their connection. Use
+/// [`Graph::downstream_closure`] to find all the nodes depending on the
+/// dirty ones.
+#[derive(Debug, Clone)]
+pub struct DirtyTracker<ValueType> {
+    nodes: SecondaryMap<NodeId, ()>,
+    connections: SecondaryMap<InputId, SVec<OutputId>>,
+    values: SecondaryMap<InputId, ValueType>,
+    marked: Vec<NodeId>,
+}
+
+impl<ValueType> Default for DirtyTracker<ValueType> {
+    fn default() -> Self {
+        Self {
+            nodes: Default::default(),
+            connections: Default::default(),
+            values: Default::default(),
+            marked: Vec::new(),
+        }
+    }
+}
+
+impl<ValueType: Clone + PartialEq> DirtyTracker<ValueType> {
+    /// Creates a tracker which considers all nodes of `graph` as clean.
+    pub fn new<NodeData, DataType>(graph: &Graph<NodeData, DataType, ValueType>) -> Self {
+        let mut tracker = Self::default();
+        tracker.reset(graph);
+        tracker
+    }
+
+    /// Considers all nodes of `graph` as clean.
+    pub fn reset<NodeData, DataType>(&mut self, graph: &Graph<NodeData, DataType, ValueType>) {
+        self.nodes = graph.nodes.keys().map(|node_id| (node_id, ())).collect();
+        self.connections = graph.connections.clone();
+        self.values = graph
+            .inputs
+            .iter()
+            .map(|(id, param)| (id, param.value.clone()))
+            .collect();
+        self.marked.clear();
+    }
+
+    /// Marks a node as dirty after a change the tracker can't see, e.g. to
+    /// its `NodeData`.
+    pub fn mark_dirty(&mut self, node_id: NodeId) {
+        self.marked.push(node_id);
+    }
+
+    /// Returns the nodes made dirty since the last call, in the order of
+    /// [`Graph::nodes`], and considers all nodes as clean again.
+    pub fn take_dirty_nodes<NodeData, DataType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+    ) -> Vec<NodeId> {
+        let mut dirty: SecondaryMap<NodeId, ()> =
+            self.marked.drain(..).map(|node_id| (node_id, ())).collect();
+        for (input, param) in &graph.inputs {
+            if graph.connections.get(input) != self.connections.get(input)
+                || self.values.get(input) != Some(&param.value)
+            {
+                dirty.insert(param.node, ());
+            }
+        }
+        let nodes = graph
+            .nodes
+            .keys()
+            .filter(|node_id| !self.nodes.contains_key(*node_id) || dirty.contains_key(*node_id))
+            .collect();
+        self.reset(graph);
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_node(graph: &mut Graph<(), (), f32>) -> (NodeId, InputId, OutputId) {
+        let node = graph.add_node("node".into(), (), |_, _| {});
+        let input = graph.add_input_param(
+            node,
+            "in".into(),
+            (),
+            0.0,
+            InputParamKind::ConnectionOrConstant,
+            true,
+        );
+        let output = graph.add_output_param(node, "out".into(), ());
+        (node, input, output)
+    }
+
+    #[test]
+    fn test_take_dirty_nodes() {
+        let mut graph = Graph::<(), (), f32>::new();
+        let (a, _, a_out) = add_node(&mut graph);
+        let (b, b_in, b_out) = add_node(&mut graph);
+        let (c, c_in, _) = add_node(&mut graph);
+        graph.add_connection(b_out, c_in);
+        let mut tracker = DirtyTracker::new(&graph);
+        assert!(tracker.take_dirty_nodes(&graph).is_empty());
+
+        graph[b_in].value = 1.0;
+        assert_eq!(tracker.take_dirty_nodes(&graph), vec![b]);
+        assert_eq!(graph.downstream_closure([b]), vec![b, c]);
+
+        graph.add_connection(a_out, b_in);
+        let (d, _, _) = add_node(&mut graph);
+        tracker.mark_dirty(a);
+        assert_eq!(tracker.take_dirty_nodes(&graph), vec![a, b, d]);
+
+        // Removing b disconnects c
+        graph.remove_node(b);
+        assert_eq!(tracker.take_dirty_nodes(&graph), vec![c]);
+    }
+}
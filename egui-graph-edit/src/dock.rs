@@ -0,0 +1,155 @@
+use super::*;
+use egui::Ui;
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
+    GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+        Response = UserResponse,
+        UserState = UserState,
+        DataType = DataType,
+        ValueType = ValueType,
+    >,
+    UserResponse: UserResponseTrait,
+    ValueType:
+        WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
+    CategoryType: CategoryTrait,
+{
+    /// Draws the graph at `index` as the content of a tab of a docking
+    /// layout, e.g. from `TabViewer::ui` with `egui_dock` or from
+    /// `Behavior::pane_ui` with `egui_tiles`, using graph indices as the tabs.
+    /// Returns `None` when there is no graph at `index`.
+    ///
+    /// Unlike calling `draw_graph_editor` on the graph directly, this:
+    /// - gives each tab its own widget ids, so that the nodes and node
+    ///   finders of tabs shown side by side don't clash;
+    /// - makes the graph the active one when the pointer is pressed over its
+    ///   tab, so that [`GraphWorkspace::copy_selection`] and
+    ///   [`GraphWorkspace::paste`] go to the tab being worked in;
+    /// - drops the drags and the node finder left open in a tab that was
+    ///   hidden, e.g. behind another tab, instead of resuming them once it's
+    ///   shown again.
+    ///
+    /// The node finder is drawn in the foreground, above the docking layout
+    /// and its floating windows, and kept inside the tab.
+    pub fn show_dock_tab(
+        &mut self,
+        ui: &mut Ui,
+        index: usize,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        user_state: &mut UserState,
+        prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
+    ) -> Option<GraphResponse<UserResponse, NodeData>> {
+        let state = &mut self.graphs.get_mut(index)?.state;
+        let response = ui
+            .push_id(("graph_dock_tab", index), |ui| {
+                // Docking crates skip the callbacks of hidden tabs, which
+                // shows as a gap in the passes the tab was drawn in
+                let id = ui.id();
+                let pass = ui.ctx().cumulative_pass_nr();
+                let last_pass =
+                    ui.data_mut(|data| std::mem::replace(data.get_temp_mut_or(id, pass), pass));
+                if last_pass + 1 < pass {
+                    state.connection_in_progress = None;
+                    state.node_drag = None;
+                    state.frame_drag = None;
+                    state.node_finder = None;
+                    state.selection_menu = None;
+                }
+                state.draw_graph_editor(ui, all_kinds, user_state, prepend_responses)
+            })
+            .inner;
+        if response.cursor_in_editor && ui.input(|input| input.pointer.any_pressed()) {
+            self.active = index;
+        }
+        Some(response)
+    }
+}
+
+#[cfg(all(test, feature = "synthetic"))]
+mod tests {
+    use super::*;
+    use crate::synthetic::SyntheticGraphOptions;
+    use egui::{pos2, Event, PointerButton, RawInput};
+
+    #[test]
+    fn test_dock_tabs() {
+        let options = SyntheticGraphOptions {
+            nodes: 2,
+            ..Default::default()
+        };
+        let mut workspace = GraphWorkspace::new("a", options.build_editor_state());
+        workspace.add_graph("b", options.build_editor_state());
+
+        // The tabs side by side, each in a 500x800 column
+        let ctx = egui::Context::default();
+        let frame = |workspace: &mut GraphWorkspace<_, _, _, _, _>, shown: &[usize], events| {
+            let input = RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    pos2(0.0, 0.0),
+                    egui::vec2(1000.0, 800.0),
+                )),
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.columns(2, |columns| {
+                        for (index, column) in columns.iter_mut().enumerate() {
+                            if shown.contains(&index) {
+                                let templates = options.template();
+                                let response = workspace.show_dock_tab(
+                                    column,
+                                    index,
+                                    templates,
+                                    &mut (),
+                                    Vec::new(),
+                                );
+                                assert!(response.is_some());
+                            }
+                        }
+                    });
+                    assert!(workspace
+                        .show_dock_tab(ui, 2, options.template(), &mut (), Vec::new())
+                        .is_none());
+                });
+            });
+        };
+
+        // Pressing in a tab makes its graph active
+        frame(&mut workspace, &[0, 1], Vec::new());
+        assert_eq!(workspace.active, 0);
+        let press = |pos, pressed| {
+            vec![
+                Event::PointerMoved(pos),
+                Event::PointerButton {
+                    pos,
+                    button: PointerButton::Primary,
+                    pressed,
+                    modifiers: Default::default(),
+                },
+            ]
+        };
+        frame(&mut workspace, &[0, 1], press(pos2(990.0, 790.0), true));
+        frame(&mut workspace, &[0, 1], press(pos2(990.0, 790.0), false));
+        assert_eq!(workspace.active, 1);
+
+        // The finder of a hidden tab is closed, not that of a shown one
+        for graph in &mut workspace.graphs {
+            graph.state.node_finder = Some(NodeFinder::new_at(pos2(100.0, 100.0)));
+        }
+        frame(&mut workspace, &[1], Vec::new());
+        frame(&mut workspace, &[0, 1], Vec::new());
+        assert!(workspace.graphs[0].state.node_finder.is_none());
+        assert!(workspace.graphs[1].state.node_finder.is_some());
+        assert_eq!(workspace.active, 1);
+    }
+}
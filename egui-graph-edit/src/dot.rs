@@ -0,0 +1,117 @@
+use std::fmt::Write;
+
+use super::*;
+
+/// Attributes of a node in a DOT description, as `(name, value)` pairs, see
+/// [`Graph::to_dot_with`].
+pub type DotAttributes = Vec<(String, String)>;
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Describes the graph in the DOT language of Graphviz, e.g. to render
+    /// it with `dot -Tsvg`. Nodes are labeled with their label, and each
+    /// connection is an edge from the node of its output to the node of its
+    /// input, labeled with the names of both parameters.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(|_, _| Vec::new())
+    }
+
+    /// Same as [`Graph::to_dot`], adding the attributes returned by
+    /// `node_attributes` to each node, e.g. `("color", "red")`. A `label`
+    /// attribute replaces the label of the node. Values are quoted, so they
+    /// can be any text.
+    pub fn to_dot_with(
+        &self,
+        mut node_attributes: impl FnMut(NodeId, &Node<NodeData>) -> DotAttributes,
+    ) -> String {
+        let mut dot_ids: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        let mut dot = String::from("digraph {\n");
+        for (index, (node_id, node)) in self.nodes.iter().enumerate() {
+            dot_ids.insert(node_id, index);
+            let mut attributes = node_attributes(node_id, node);
+            if !attributes.iter().any(|(name, _)| name == "label") {
+                attributes.insert(0, ("label".into(), node.label.clone()));
+            }
+            let attributes: Vec<String> = attributes
+                .iter()
+                .map(|(name, value)| format!("{name}={}", quote(value)))
+                .collect();
+            writeln!(dot, "    n{index} [{}];", attributes.join(", ")).unwrap();
+        }
+        for (input, output) in self.iter_connections() {
+            let (Some(input), Some(output)) = (self.inputs.get(input), self.outputs.get(output))
+            else {
+                continue;
+            };
+            let input_name = param_name(&self.nodes[input.node].inputs, input.id);
+            let output_name = param_name(&self.nodes[output.node].outputs, output.id);
+            writeln!(
+                dot,
+                "    n{} -> n{} [taillabel={}, headlabel={}];",
+                dot_ids[output.node],
+                dot_ids[input.node],
+                quote(output_name),
+                quote(input_name),
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The name of the parameter `id` among `params`.
+fn param_name<Id: PartialEq>(params: &[(String, Id)], id: Id) -> &str {
+    params
+        .iter()
+        .find(|(_, param)| *param == id)
+        .map_or("", |(name, _)| name)
+}
+
+/// `text` as a quoted DOT string.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let a = graph.add_node("Make \"a\"".into(), (), |_, _| {});
+        let b = graph.add_node("Sum".into(), (), |_, _| {});
+        let out = graph.add_output_param(a, "out".into(), ());
+        let input =
+            graph.add_input_param(b, "in".into(), (), (), InputParamKind::ConnectionOnly, true);
+        graph.add_connection(out, input);
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph {\n    n0 [label=\"Make \\\"a\\\"\"];\n    n1 [label=\"Sum\"];\n    \
+             n0 -> n1 [taillabel=\"out\", headlabel=\"in\"];\n}\n"
+        );
+        let dot = graph.to_dot_with(|node_id, _| {
+            if node_id == b {
+                vec![
+                    ("label".into(), "Total".into()),
+                    ("color".into(), "red".into()),
+                ]
+            } else {
+                Vec::new()
+            }
+        });
+        assert!(dot.contains("n1 [label=\"Total\", color=\"red\"];"));
+    }
+}
@@ -1,16 +1,177 @@
-use std::collections::HashSet;
-
-use crate::color_hex_utils::*;
-use crate::utils::ColorUtils;
-
 use super::*;
-use egui::epaint::{CubicBezierShape, RectShape};
+use egui::epaint::{CircleShape, CubicBezierShape, PathShape, RectShape, Tessellator};
 use egui::*;
 
 pub type PortLocations = std::collections::HashMap<AnyParameterId, Pos2>;
 pub type NodeRects = std::collections::HashMap<NodeId, Rect>;
 
-const DISTANCE_TO_CONNECT: f32 = 10.0;
+/// The measured size of a node and the location of its ports, relative to its
+/// top-left corner, in graph coordinates. Nodes that are out of view and haven't changed since they
+/// were measured are not laid out again; this is used instead.
+#[derive(Clone, Debug)]
+pub struct NodeLayout {
+    pub size: Vec2,
+    pub ports: Vec<(AnyParameterId, Vec2)>,
+    /// Summarizes what the layout depends on, see [`LayoutKeyInput::key`].
+    pub(crate) key: u64,
+}
+
+/// How far a node extends, as measured the last time it was drawn in full.
+/// Rects are relative to the top-left corner of the node, in graph
+/// coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct NodeExtents {
+    /// The node along with its margins, where it can be clicked and dragged.
+    pub outer_rect: Rect,
+    /// The size of the background of the node.
+    pub size: Vec2,
+}
+
+/// A node being dragged with the pointer, see
+/// [`GraphEditorState::node_drag`].
+#[derive(Clone, Copy, Debug)]
+pub struct NodeDrag {
+    pub node: NodeId,
+    /// The position of the node when the drag started.
+    pub start: Pos2,
+    /// How far the pointer moved the node since then, before applying
+    /// constraints.
+    pub offset: Vec2,
+}
+
+/// A port about to be painted, see [`NodeDataTrait::draw_port`].
+#[derive(Clone, Copy, Debug)]
+pub struct PortDrawInfo {
+    pub param_id: AnyParameterId,
+    /// The center of the port, in screen coordinates.
+    pub center: Pos2,
+    /// See [`GraphEditorStyle::port_radius`], enlarged by
+    /// [`HoverStyle::port_scale`] when hovered.
+    pub radius: f32,
+    /// The color the editor paints the port with: the color of its data
+    /// type, or [`GraphEditorStyle::port_hover`] when hovered.
+    pub color: Color32,
+    /// See [`DataTypeTrait::data_type_color`].
+    pub data_type_color: Color32,
+    /// The pointer is close enough to drag a connection from the port, or
+    /// drop one onto it.
+    pub hovered: bool,
+    pub connected: bool,
+}
+
+/// Where a node shows its title, see [`NodeDataTrait::title_placement`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TitlePlacement {
+    /// In a title bar above the contents of the node.
+    #[default]
+    Top,
+    /// In a title bar below the contents of the node.
+    Bottom,
+    /// Above the contents of the node, without a title bar.
+    Body,
+    /// Nowhere. Such compact nodes, e.g. constants or reroutes, don't have
+    /// buttons to delete or flip them either.
+    Hidden,
+}
+
+/// The sizes a node can be resized to, in graph coordinates, see
+/// [`NodeDataTrait::size_constraints`]. Sizes include the margins of the
+/// node, and contents bigger than the size grow the node past it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeSizeConstraints {
+    /// The size of the node until it is resized.
+    pub default: Vec2,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for NodeSizeConstraints {
+    fn default() -> Self {
+        Self {
+            // The space nodes that can't be resized are laid out in
+            default: vec2(200.0, 200.0),
+            min: vec2(80.0, 40.0),
+            max: Vec2::INFINITY,
+        }
+    }
+}
+
+impl NodeSizeConstraints {
+    /// `size` within the minimum and maximum sizes.
+    pub fn clamp(&self, size: Vec2) -> Vec2 {
+        size.max(self.min).min(self.max.max(self.min))
+    }
+}
+
+/// The size of the handle in the bottom right corner of resizable nodes, on
+/// the screen.
+const NODE_RESIZE_HANDLE_SIZE: f32 = 10.0;
+
+/// The shapes painted for a node the last time it was drawn, see
+/// [`GraphEditorState::cache_node_shapes`].
+#[derive(Clone, Debug)]
+pub struct NodeShapes {
+    /// Top-left corner of the node when the shapes were painted.
+    origin: Pos2,
+    pub(crate) key: u64,
+    /// Shapes along with their clip rect, unless they were clipped to the
+    /// editor only.
+    shapes: Vec<(Option<Rect>, Shape)>,
+}
+
+/// The color of each data type drawn during the current frame, see
+/// [`GraphEditorState::cache_data_type_colors`].
+#[derive(Clone, Debug, Default)]
+pub struct DataTypeColors {
+    enabled: bool,
+    /// A parameter of each data type met so far, and the color of that type.
+    /// Types are compared through these parameters, so that they don't need
+    /// to be cloned or hashed.
+    colors: Vec<(AnyParameterId, Color32)>,
+}
+
+impl DataTypeColors {
+    /// Forgets the colors of the previous frame.
+    pub fn reset(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.colors.clear();
+    }
+
+    /// The color of the data type of `param`, asking the data type only the
+    /// first time this type is met.
+    pub fn get<NodeData, DataType, ValueType, UserState>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        param: AnyParameterId,
+        user_state: &mut UserState,
+    ) -> Color32
+    where
+        DataType: DataTypeTrait<UserState>,
+    {
+        let data_type = graph.any_param_type(param).unwrap();
+        if !self.enabled {
+            return data_type.data_type_color(user_state);
+        }
+        let cached = self.colors.iter().find(|(known, _)| {
+            graph
+                .any_param_type(*known)
+                .is_ok_and(|known| known == data_type)
+        });
+        if let Some((_, color)) = cached {
+            return *color;
+        }
+        let color = data_type.data_type_color(user_state);
+        self.colors.push((param, color));
+        color
+    }
+}
+
+/// Maximum distance, in physical pixels, between a connection curve and the
+/// line segments it is drawn with.
+const CONNECTION_TOLERANCE: f32 = 0.25;
+/// How much the zoom changes per point scrolled with the wheel: scrolling by
+/// this many points doubles or halves it.
+const SCROLL_ZOOM_DOUBLING: f32 = 200.0;
 
 /// Nodes communicate certain events to the parent graph when drawn. There is
 /// one special `User` variant which can be used by users as the return value
@@ -40,10 +201,57 @@ pub enum NodeResponse<UserResponse: UserResponseTrait, NodeData: NodeDataTrait>
     },
     /// Emitted when a node is interacted with, and should be raised
     RaiseNode(NodeId),
+    /// Emitted when a node is dragged on its own. The delta is in graph
+    /// coordinates, whatever the zoom.
     MoveNode {
         node: NodeId,
         drag_delta: Vec2,
     },
+    /// Emitted instead of [`NodeResponse::MoveNode`] when the dragged node is
+    /// part of a selection of several nodes, which all move by `drag_delta`.
+    MoveSelection {
+        nodes: Vec<NodeId>,
+        drag_delta: Vec2,
+    },
+    /// Emitted when probing an output is picked from the context menu of its
+    /// port. The editor adds a [`Probe`] for the application to feed.
+    AddProbe(OutputId),
+    /// Emitted when a frame was put around the selected nodes with
+    /// [`EditorKeybindings::frame_selection`].
+    CreatedFrame(FrameId),
+    /// Emitted when the title of a frame was edited.
+    RenameFrame {
+        frame: FrameId,
+        title: String,
+    },
+    /// Emitted when a frame is dragged by its title bar, along with the
+    /// nodes inside it.
+    MoveFrame {
+        frame: FrameId,
+        nodes: Vec<NodeId>,
+        drag_delta: Vec2,
+    },
+    /// Emitted when a frame is resized with the handle in its bottom right
+    /// corner.
+    ResizeFrame {
+        frame: FrameId,
+        drag_delta: Vec2,
+    },
+    /// Emitted when deleting a frame is picked from its context menu. The
+    /// nodes inside it are kept.
+    DeleteFrame(FrameId),
+    /// Emitted when a node is resized with the handle in its bottom right
+    /// corner, with its new size, see [`GraphEditorState::node_sizes`].
+    ResizeNode {
+        node: NodeId,
+        size: Vec2,
+    },
+    /// Emitted when the collapse button of a node is clicked, see
+    /// [`GraphEditorState::set_node_collapsed`].
+    CollapseNode {
+        node: NodeId,
+        collapsed: bool,
+    },
     User(UserResponse),
 }
 
@@ -60,6 +268,17 @@ pub struct GraphResponse<UserResponse: UserResponseTrait, NodeData: NodeDataTrai
     pub cursor_in_editor: bool,
     /// Is the mouse currently hovering the node finder?
     pub cursor_in_finder: bool,
+    /// The mouse position in graph coordinates, the same space as
+    /// `node_positions`. Useful to share the cursor with remote participants.
+    pub cursor_graph_pos: Option<Pos2>,
+    /// Whether anything drawn may differ from the previous frame: the graph
+    /// or view changed, the pointer moved or clicked over the editor, or an
+    /// interaction is ongoing. When false, apps that repaint reactively can
+    /// wait for the next input event before drawing again.
+    ///
+    /// While nodes, connections or box selections are being dragged, or
+    /// nodes glide to new positions, the editor requests repaints itself.
+    pub changed: bool,
 }
 
 impl<UserResponse: UserResponseTrait, NodeData: NodeDataTrait> Default
@@ -70,6 +289,8 @@ impl<UserResponse: UserResponseTrait, NodeData: NodeDataTrait> Default
             node_responses: Default::default(),
             cursor_in_editor: false,
             cursor_in_finder: false,
+            cursor_graph_pos: None,
+            changed: false,
         }
     }
 }
@@ -80,10 +301,25 @@ pub struct GraphNodeWidget<'a, NodeData, DataType, ValueType> {
     pub graph: &'a mut Graph<NodeData, DataType, ValueType>,
     pub port_locations: &'a mut PortLocations,
     pub node_rects: &'a mut NodeRects,
+    pub node_extents: &'a mut SecondaryMap<NodeId, NodeExtents>,
+    pub data_type_colors: &'a mut DataTypeColors,
     pub node_id: NodeId,
     pub ongoing_drag: Option<(NodeId, AnyParameterId)>,
     pub selected: bool,
-    pub pan: egui::Vec2,
+    /// From graph to screen coordinates, see [`PanZoom::transform`].
+    pub transform: emath::TSTransform,
+    pub style: &'a GraphEditorStyle,
+    /// Set to this node when it holds the keyboard focus.
+    pub focused_node: &'a mut Option<NodeId>,
+    /// Draw the node as a plain box with its title, see
+    /// [`GraphEditorState::overview_zoom`].
+    pub overview: bool,
+    /// The size the node was resized to, if it was, see
+    /// [`GraphEditorState::node_sizes`].
+    pub size: Option<Vec2>,
+    /// Draw only the title bar of the node, see
+    /// [`GraphEditorState::collapsed_nodes`].
+    pub collapsed: bool,
 }
 
 impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
@@ -108,6 +344,66 @@ where
     DataType: DataTypeTrait<UserState>,
     CategoryType: CategoryTrait,
 {
+    /// Records the rect and port locations of a node that isn't drawn in
+    /// full, from its last layout.
+    fn place_node(&mut self, node_id: NodeId, min: Pos2, zoom: f32) {
+        let layout = &self.node_layouts[node_id];
+        self.node_rects
+            .insert(node_id, Rect::from_min_size(min, layout.size * zoom));
+        for (param, offset) in &layout.ports {
+            self.port_locations.insert(*param, min + *offset * zoom);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn show_node(
+        &mut self,
+        ui: &mut Ui,
+        user_state: &mut UserState,
+        node_id: NodeId,
+        selected: bool,
+        dimmed: bool,
+        transform: emath::TSTransform,
+        overview: bool,
+        style: &GraphEditorStyle,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) {
+        let opacity = ui.opacity();
+        if dimmed {
+            ui.multiply_opacity(style.path_highlight.dim_opacity);
+        }
+        GraphNodeWidget {
+            position: self.node_positions.get_mut(node_id).unwrap(),
+            orientation: self.node_orientations.get_mut(node_id).unwrap(),
+            graph: &mut self.graph,
+            port_locations: &mut self.port_locations,
+            node_rects: &mut self.node_rects,
+            node_extents: &mut self.node_extents,
+            data_type_colors: &mut self.data_type_colors,
+            node_id,
+            ongoing_drag: self.connection_in_progress,
+            selected,
+            transform,
+            style,
+            focused_node: &mut self.focused_node,
+            overview,
+            size: self.node_sizes.get(node_id).copied(),
+            collapsed: self.collapsed_nodes.contains_key(node_id),
+        }
+        .show_into(ui, user_state, responses);
+        ui.set_opacity(opacity);
+    }
+
+    /// Draws the graph editor, filling the space available in `ui`.
+    ///
+    /// The editor only reacts to the pointer where it isn't covered by other
+    /// layers, and derives its widget ids from `ui`, so several editors can be
+    /// shown at once. To host editors in docking layouts such as `egui_dock` or
+    /// `egui_tiles`, keep one [`GraphEditorState`] per tab (see
+    /// [`GraphWorkspace`]) and call this from the tab's UI callback, or use
+    /// `GraphWorkspace::show_dock_tab` with the `dock` feature. Tabs that are
+    /// not given distinct ids by the docking crate should be wrapped in
+    /// [`Ui::push_id`].
     #[must_use]
     pub fn draw_graph_editor(
         &mut self,
@@ -115,6 +411,48 @@ where
         all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
         user_state: &mut UserState,
         prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
+    ) -> GraphResponse<UserResponse, NodeData> {
+        self.draw_graph_editor_with_overlay(ui, all_kinds, user_state, prepend_responses, |_, _| {})
+    }
+
+    /// Same as [`GraphEditorState::draw_graph_editor`], calling `overlay`
+    /// once the nodes and connections are drawn, to paint annotations,
+    /// rulers or debug information above them.
+    #[must_use]
+    pub fn draw_graph_editor_with_overlay(
+        &mut self,
+        ui: &mut Ui,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        user_state: &mut UserState,
+        prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
+        overlay: impl FnOnce(&EditorOverlay, &mut UserState),
+    ) -> GraphResponse<UserResponse, NodeData> {
+        let Some(theme) = self.theme else {
+            return self.draw_graph_editor_impl(
+                ui,
+                all_kinds,
+                user_state,
+                prepend_responses,
+                overlay,
+            );
+        };
+        let visuals = theme.default_visuals();
+        ui.painter()
+            .rect_filled(ui.max_rect(), 0.0, visuals.extreme_bg_color);
+        let app_visuals = std::mem::replace(ui.visuals_mut(), visuals);
+        let response =
+            self.draw_graph_editor_impl(ui, all_kinds, user_state, prepend_responses, overlay);
+        *ui.visuals_mut() = app_visuals;
+        response
+    }
+
+    fn draw_graph_editor_impl(
+        &mut self,
+        ui: &mut Ui,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        user_state: &mut UserState,
+        prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
+        overlay: impl FnOnce(&EditorOverlay, &mut UserState),
     ) -> GraphResponse<UserResponse, NodeData> {
         // This causes the graph editor to use as much free space as it can.
         // (so for windows it will use up to the resizeably set limit
@@ -125,27 +463,35 @@ where
         let cursor_pos = ui
             .ctx()
             .input(|i| i.pointer.hover_pos().unwrap_or(Pos2::ZERO));
-        let mut cursor_in_editor = resp.rect.contains(cursor_pos);
+        // Unlike checking the rect alone, this takes into account windows or
+        // docked panels covering the editor.
+        let mut cursor_in_editor = ui.rect_contains_pointer(resp.rect);
         let mut cursor_in_finder = false;
+        let pan_zoom_before = self.pan_zoom;
+        let had_finder = self.node_finder.is_some();
+        let style = self.current_style(ui.visuals()).at_zoom(self.pan_zoom.zoom);
 
-        // Gets filled with the node metrics as they are drawn
-        let mut port_locations = PortLocations::new();
-        let mut node_rects = NodeRects::new();
+        // Gets filled with the node metrics as they are drawn. The maps are
+        // kept between frames so they don't need to be reallocated.
+        self.port_locations.clear();
+        self.node_rects.clear();
+        self.data_type_colors.reset(self.cache_data_type_colors);
+        self.focused_node = None;
 
         // The responses returned from node drawing have side effects that are best
         // executed at the end of this function.
         let mut delayed_responses: Vec<NodeResponse<UserResponse, NodeData>> = prepend_responses;
+        let prepended_responses = delayed_responses.len();
 
         // Used to detect drag events in the background
         let mut drag_started_on_background = false;
         let mut drag_released_on_background = false;
 
-        debug_assert_eq!(
-            self.node_order.iter().copied().collect::<HashSet<_>>(),
-            self.graph.iter_nodes().collect::<HashSet<_>>(),
-            "The node_order field of the GraphEditorself was left in an \
-        inconsistent self. It has either more or less values than the graph."
-        );
+        // User code may have modified the graph without updating the editor
+        // data. Fix that instead of failing to draw the nodes.
+        if self.needs_repair() {
+            self.repair();
+        }
 
         // Allocate rect before the nodes, otherwise this will block the interaction
         // with the nodes.
@@ -156,49 +502,256 @@ where
             drag_released_on_background = true;
         }
 
+        let time = ui.input(|i| i.time);
+        self.begin_layout_transition_frame(time);
+
+        if let Some(grid) = &self.background_grid {
+            let transform = self.pan_zoom.transform(editor_rect);
+            grid.draw(ui.painter(), transform, editor_rect, &style);
+        }
+
         /* Draw nodes */
-        for node_id in self.node_order.iter().copied() {
-            let responses = GraphNodeWidget {
-                position: self.node_positions.get_mut(node_id).unwrap(),
-                orientation: self.node_orientations.get_mut(node_id).unwrap(),
-                graph: &mut self.graph,
-                port_locations: &mut port_locations,
-                node_rects: &mut node_rects,
-                node_id,
-                ongoing_drag: self.connection_in_progress,
-                selected: self
-                    .selected_nodes
-                    .iter()
-                    .any(|selected| *selected == node_id),
-                pan: self.pan_zoom.pan + editor_rect.min.to_vec2(),
+        let overview = self.pan_zoom.zoom < self.overview_zoom;
+        // Budgets rely on timers, which aren't available on the web
+        let budget = self
+            .frame_budget
+            .filter(|_| cfg!(not(target_arch = "wasm32")));
+        let frame_start = budget.map(|_| std::time::Instant::now());
+        let over_budget = || {
+            frame_start
+                .zip(budget)
+                .is_some_and(|(start, budget)| start.elapsed() > budget)
+        };
+        // Cached shapes are only used for nodes nothing can interact with:
+        // the cursor is away from them, and no text field could be editing
+        // their values.
+        let cache_shapes = self.cache_node_shapes || budget.is_some();
+        let can_replay = cache_shapes
+            && self.connection_in_progress.is_none()
+            && ui.ctx().memory(|mem| mem.focused().is_none());
+        let transform = self.pan_zoom.transform(editor_rect);
+        // The fonts and the widgets of the nodes follow the zoom
+        let editor_style = ui.style().clone();
+        let text_factor = style.zoom_scaling.text.factor(self.pan_zoom.zoom);
+        if text_factor != 1.0 {
+            for font_id in ui.style_mut().text_styles.values_mut() {
+                font_id.size *= text_factor;
+            }
+            scale_spacing(ui.spacing_mut(), text_factor);
+        }
+        let frame_responses = self.draw_frames(ui, transform, &style);
+
+        // Nothing in the loop changes the order, so it is moved out to be
+        // iterated while the rest of the state is borrowed.
+        let node_order = std::mem::take(&mut self.node_order);
+        let highlighted_nodes = self.highlighted_nodes();
+        let mut plans = {
+            let inputs: Vec<LayoutKeyInput> = node_order
+                .iter()
+                .map(|node_id| {
+                    LayoutKeyInput::new(
+                        &self.graph,
+                        &self.node_sizes,
+                        &self.collapsed_nodes,
+                        node_id,
+                    )
+                })
+                .collect();
+            NodePlanner {
+                node_positions: &self.node_positions,
+                node_orientations: &self.node_orientations,
+                node_layouts: &self.node_layouts,
+                node_extents: &self.node_extents,
+                node_shapes: &self.node_shapes,
+                selected_nodes: &self.selected_nodes,
+                connections: &self.graph.connections,
+                highlighted_nodes: highlighted_nodes.as_ref(),
+                overview,
+                transform,
+                port_hit_radius: style.port_hit_radius,
+                clip_rect: ui.clip_rect(),
+                cursor_pos,
+                can_replay,
+                style_key: style_key(ui, &style),
+            }
+            .plan_nodes(&inputs)
+        };
+        if let (Some(budget), Some(frame_start)) = (budget, frame_start) {
+            self.defer_node_updates(&mut plans, budget.saturating_sub(frame_start.elapsed()));
+        }
+        let mut deferred = 0;
+        for plan in &plans {
+            let node_id = plan.node_id;
+            let mut draw = plan.draw;
+            // Estimates can be off, so keep an eye on the actual time
+            if draw == NodeDraw::Full && !self.is_critical(plan) && over_budget() {
+                draw = NodeDraw::Deferred;
+            }
+            let min = plan.rect.min;
+
+            match draw {
+                NodeDraw::Hidden => {
+                    self.place_node(node_id, min, transform.scaling);
+                    continue;
+                }
+                NodeDraw::Replay => {
+                    replay_node_shapes(ui, &self.node_shapes[node_id], min);
+                    self.place_node(node_id, min, transform.scaling);
+                    continue;
+                }
+                NodeDraw::Deferred => {
+                    deferred += 1;
+                    // Show the node as it was last drawn, or as a plain box
+                    // when it never was.
+                    if self.node_layouts.contains_key(node_id) {
+                        if !plan.visible {
+                            self.place_node(node_id, min, transform.scaling);
+                            continue;
+                        }
+                        if let Some(cached) = self.node_shapes.get(node_id) {
+                            replay_node_shapes(ui, cached, min);
+                            self.place_node(node_id, min, transform.scaling);
+                            continue;
+                        }
+                    }
+                    self.show_node(
+                        ui,
+                        user_state,
+                        node_id,
+                        plan.selected,
+                        plan.dimmed,
+                        transform,
+                        true,
+                        &style,
+                        &mut delayed_responses,
+                    );
+                    let key = self.node_layout_key(node_id, true);
+                    self.record_node_layout(node_id, key, transform);
+                    continue;
+                }
+                NodeDraw::Full => {}
             }
-            .show(ui, user_state);
 
-            // Actions executed later
-            delayed_responses.extend(responses);
+            let first_shape = ui
+                .ctx()
+                .graphics(|graphics| graphics.get(ui.layer_id()).map_or(0, |l| l.next_idx().0));
+            let node_start = frame_start.map(|_| std::time::Instant::now());
+
+            // Actions are executed later
+            self.show_node(
+                ui,
+                user_state,
+                node_id,
+                plan.selected,
+                plan.dimmed,
+                transform,
+                overview,
+                &style,
+                &mut delayed_responses,
+            );
+            self.record_node_layout(node_id, plan.layout_key, transform);
+            if let Some(node_start) = node_start {
+                self.record_node_cost(node_start.elapsed());
+            }
+            // The hover feedback and the focus outline go away with the
+            // pointer and the focus
+            if cache_shapes && !plan.hovered && self.focused_node != Some(node_id) {
+                let shapes = ui.ctx().graphics(|graphics| {
+                    let Some(list) = graphics.get(ui.layer_id()) else {
+                        return Vec::new();
+                    };
+                    list.all_entries()
+                        .skip(first_shape)
+                        .map(|clipped| {
+                            let clip = Some(clipped.clip_rect).filter(|c| *c != ui.clip_rect());
+                            (clip, clipped.shape.clone())
+                        })
+                        .collect()
+                });
+                self.node_shapes.insert(
+                    node_id,
+                    NodeShapes {
+                        origin: transform * self.node_positions[node_id],
+                        key: plan.shapes_key,
+                        shapes,
+                    },
+                );
+            }
+        }
+        self.node_order = node_order;
+        ui.set_style(editor_style);
+        if self.cycle_policy == CyclePolicy::Reject {
+            let graph = &self.graph;
+            delayed_responses.retain(|response| match response {
+                NodeResponse::ConnectEventEnded { input, output } => {
+                    !graph.would_create_cycle(*output, *input)
+                }
+                _ => true,
+            });
+        }
+        let node_responses_end = delayed_responses.len();
+        delayed_responses.extend(frame_responses);
+        for (node_id, rect) in &self.node_rects {
+            let min = self.node_positions[*node_id];
+            self.node_rect_index.insert(
+                *node_id,
+                Rect::from_min_size(min, rect.size() / transform.scaling),
+            );
+        }
+        // Nodes may have been removed from code
+        if self.node_rect_index.len() > self.graph.nodes.len() {
+            let nodes = &self.graph.nodes;
+            self.node_rect_index
+                .retain(|node_id| nodes.contains_key(node_id));
+        }
+        self.frame_budget_state.deferred = deferred;
+        if deferred > 0 {
+            // Catch up on the next frames
+            ui.ctx().request_repaint();
         }
 
         /* Draw the node finder, if open */
         let mut should_close_node_finder = false;
+        let snapping_grid = self.snapping_grid();
         if let Some(ref mut node_finder) = self.node_finder {
-            let mut node_finder_area = Area::new(Id::from("node_finder")).order(Order::Foreground);
+            // The finder is scoped to this editor, so that several editors
+            // (e.g. in the tabs of a docking layout) each get their own.
+            let mut node_finder_area = Area::new(ui.id().with("node_finder"))
+                .order(Order::Foreground)
+                .constrain_to(editor_rect);
             if let Some(pos) = node_finder.position {
                 node_finder_area = node_finder_area.current_pos(pos);
             }
+            let visuals = self.theme.map(|_| ui.visuals().clone());
             node_finder_area.show(ui.ctx(), |ui| {
-                if let Some(node_kind) = node_finder.show(ui, all_kinds, user_state) {
-                    let new_node = self.graph.add_node(
-                        node_kind.node_graph_label(user_state),
-                        node_kind.user_data(user_state),
-                        |graph, node_id| node_kind.build_node(graph, user_state, node_id),
-                    );
-                    self.node_positions.insert(
-                        new_node,
-                        cursor_pos - self.pan_zoom.pan - editor_rect.min.to_vec2(),
+                if let Some(visuals) = visuals {
+                    *ui.visuals_mut() = visuals;
+                }
+                if let Some(node_kind) = node_finder.show_with_history(
+                    ui,
+                    all_kinds,
+                    user_state,
+                    &style,
+                    &mut self.template_history,
+                ) {
+                    let mut position = transform.inverse() * cursor_pos;
+                    if let Some(grid) = snapping_grid {
+                        position = grid.snap(position);
+                    }
+                    let new_node = Self::add_node_from_template_impl(
+                        &mut self.graph,
+                        &mut self.node_positions,
+                        &mut self.node_orientations,
+                        &mut self.node_order,
+                        self.avoid_overlaps.then_some(&mut self.node_rect_index),
+                        &node_kind,
+                        position,
+                        user_state,
                     );
-                    self.node_orientations
-                        .insert(new_node, NodeOrientation::LeftToRight);
-                    self.node_order.push(new_node);
+                    // Moving away from other nodes may have left the grid
+                    if let Some(grid) = snapping_grid {
+                        self.node_positions[new_node] = grid.snap(self.node_positions[new_node]);
+                    }
 
                     should_close_node_finder = true;
                     delayed_responses.push(NodeResponse::CreatedNode(new_node));
@@ -216,7 +769,56 @@ where
             self.node_finder = None;
         }
 
+        /* Draw the menu arranging the selection, if open */
+        let mut cursor_in_menu = false;
+        let mut arranged: Vec<NodeResponse<UserResponse, NodeData>> = Vec::new();
+        if let Some(pos) = self.selection_menu {
+            let (action, menu_rect) = show_selection_menu(ui, pos, &self.keybindings);
+            cursor_in_menu = menu_rect.contains(cursor_pos);
+            if let Some(action) = action {
+                arranged.extend(self.arrange_selection(action));
+                self.selection_menu = None;
+            }
+        }
+
+        // The minimap is painted above the connections, but takes the
+        // pointer before the background does. Like menus, it keeps clicks
+        // from deselecting nodes.
+        let minimap_view = self.minimap_view(editor_rect);
+        if let Some(view) = &minimap_view {
+            let response = self.interact_with_minimap(ui, view, editor_rect);
+            cursor_in_menu |= response.hovered() || response.dragged();
+        }
+        if self.selected_nodes.len() > 1
+            && cursor_in_editor
+            && ui.ctx().memory(|mem| mem.focused().is_none())
+        {
+            let action = ArrangeAction::ALL
+                .iter()
+                .zip(self.keybindings.arrange)
+                .find(|(_, shortcut)| consume_shortcut(ui, *shortcut))
+                .map(|((action, _), _)| *action);
+            if let Some(action) = action {
+                arranged.extend(self.arrange_selection(action));
+            }
+        }
+
         /* Draw connections */
+        let mut connection_mesh = ConnectionMesh::new(
+            ui.painter(),
+            style.connection_width,
+            style.connection_outline,
+        );
+        connection_mesh.style = self.connection_style;
+        connection_mesh.scaling = transform.scaling;
+        let connection_grid = match self.connection_style {
+            ConnectionStyle::Orthogonal => {
+                self.connection_grid.or(Some(ORTHOGONAL_CONNECTION_SPACING))
+            }
+            _ => self.connection_grid,
+        };
+        connection_mesh.grid =
+            connection_grid.map(|grid| (grid * transform.scaling, transform.translation.to_pos2()));
         fn port_control(param_id: &AnyParameterId, orientation: NodeOrientation) -> Vec2 {
             match (param_id, orientation) {
                 (AnyParameterId::Input(_), NodeOrientation::LeftToRight) => -Vec2::X,
@@ -227,11 +829,15 @@ where
         }
 
         if let Some((_, ref locator)) = self.connection_in_progress {
+            if let Some(cursor) = style.hover.connecting_cursor {
+                ui.ctx().set_cursor_icon(cursor);
+            }
             let port_type = self.graph.any_param_type(*locator).unwrap();
-            let connection_color = port_type.data_type_color(user_state);
-            let start_pos = port_locations[locator];
+            let connection_color = self.data_type_colors.get(&self.graph, *locator, user_state);
+            let start_pos = self.port_locations[locator];
 
             // Find a port to connect to
+            #[allow(clippy::too_many_arguments)]
             fn snap_to_ports<
                 NodeData,
                 UserState,
@@ -241,42 +847,48 @@ where
                 Value,
             >(
                 graph: &Graph<NodeData, DataType, ValueType>,
+                src: AnyParameterId,
+                src_node_id: NodeId,
+                reject_cycles: bool,
                 port_type: &DataType,
                 ports: &SlotMap<Key, Value>,
                 port_locations: &PortLocations,
                 node_orientations: &SecondaryMap<NodeId, NodeOrientation>,
                 cursor_pos: Pos2,
+                hit_radius: f32,
                 default_control: Vec2,
-            ) -> (Pos2, Vec2) {
-                ports
-                    .iter()
-                    .find_map(|(port_id, _)| {
-                        let compatible_ports = graph
-                            .any_param_type(port_id.into())
-                            .map(|other| other == port_type)
-                            .unwrap_or(false);
-
-                        if compatible_ports {
-                            port_locations.get(&port_id.into()).and_then(|port_pos| {
-                                if port_pos.distance(cursor_pos) < DISTANCE_TO_CONNECT {
-                                    let param_id: AnyParameterId = port_id.into();
-                                    let dst_node_id = match param_id {
-                                        AnyParameterId::Output(id) => graph.get_output(id).node,
-                                        AnyParameterId::Input(id) => graph.get_input(id).node,
-                                    };
-                                    let dst_orientation = node_orientations[dst_node_id];
-                                    let dst_control = port_control(&param_id, dst_orientation);
-
-                                    Some((*port_pos, dst_control))
-                                } else {
-                                    None
-                                }
-                            })
-                        } else {
-                            None
+            ) -> (Pos2, Vec2, PreviewTarget) {
+                let hovered = ports.iter().find_map(|(port_id, _)| {
+                    let param_id: AnyParameterId = port_id.into();
+                    let port_pos = port_locations.get(&param_id)?;
+                    (port_pos.distance(cursor_pos) < hit_radius).then_some((param_id, *port_pos))
+                });
+                let Some((param_id, port_pos)) = hovered else {
+                    return (cursor_pos, default_control, PreviewTarget::Free);
+                };
+                let dst_node_id = match param_id {
+                    AnyParameterId::Output(id) => graph.get_output(id).node,
+                    AnyParameterId::Input(id) => graph.get_input(id).node,
+                };
+                // Self-loops aren't allowed
+                let compatible = dst_node_id != src_node_id
+                    && graph
+                        .any_param_type(param_id)
+                        .is_ok_and(|other| other == port_type);
+                let cycle = reject_cycles
+                    && match (src, param_id) {
+                        (AnyParameterId::Output(output), AnyParameterId::Input(input))
+                        | (AnyParameterId::Input(input), AnyParameterId::Output(output)) => {
+                            graph.would_create_cycle(output, input)
                         }
-                    })
-                    .unwrap_or((cursor_pos, default_control))
+                        _ => false,
+                    };
+                if compatible && !cycle {
+                    let dst_control = port_control(&param_id, node_orientations[dst_node_id]);
+                    (port_pos, dst_control, PreviewTarget::Valid)
+                } else {
+                    (cursor_pos, default_control, PreviewTarget::Invalid)
+                }
             }
 
             // Figure out where source connection should point to
@@ -288,159 +900,293 @@ where
             let src_control = port_control(locator, src_orientation);
 
             // Figure out where destination connection should point to
-            let (dst_pos, dst_control) = match locator {
+            let (dst_pos, dst_control, target) = match locator {
                 AnyParameterId::Output(_) => snap_to_ports(
                     &self.graph,
+                    *locator,
+                    src_node_id,
+                    self.cycle_policy == CyclePolicy::Reject,
                     port_type,
                     &self.graph.inputs,
-                    &port_locations,
+                    &self.port_locations,
                     &self.node_orientations,
                     cursor_pos,
+                    style.port_hit_radius,
                     -src_control,
                 ),
 
                 AnyParameterId::Input(_) => snap_to_ports(
                     &self.graph,
+                    *locator,
+                    src_node_id,
+                    self.cycle_policy == CyclePolicy::Reject,
                     port_type,
                     &self.graph.outputs,
-                    &port_locations,
+                    &self.port_locations,
                     &self.node_orientations,
                     cursor_pos,
+                    style.port_hit_radius,
                     -src_control,
                 ),
             };
-            draw_connection(
-                ui.painter(),
-                start_pos,
-                src_control,
-                dst_pos,
-                dst_control,
-                connection_color,
-            );
+            let preview = &style.connection_preview;
+            let color = match target {
+                PreviewTarget::Free => None,
+                PreviewTarget::Valid => preview.valid_color,
+                PreviewTarget::Invalid => preview.invalid_color,
+            }
+            .unwrap_or(connection_color);
+            if let Some(points) =
+                connection_mesh.connection_points(start_pos, src_control, dst_pos, dst_control)
+            {
+                match preview.dash {
+                    Some((dash, gap)) => {
+                        for dash in dash_path(&points, dash, gap) {
+                            connection_mesh.add_line(dash, color);
+                        }
+                    }
+                    None => connection_mesh.add_line(points, color),
+                }
+            }
+            if let (PreviewTarget::Valid, Some(ring)) = (target, preview.snap_ring) {
+                connection_mesh.add_ring(dst_pos, style.port_radius + 3.0, ring);
+            }
         }
 
+        // Connections on the highlighted path are drawn last, on top
+        let mut highlighted_connections = Vec::new();
+        // Double clicking a connection adds a waypoint to the segment under
+        // the cursor
+        let mut new_waypoint = None;
+        let mut waypoint_handles = Vec::new();
+        self.prune_waypoints();
         for (input, output) in self.graph.iter_connections() {
-            let port_type = self
-                .graph
-                .any_param_type(AnyParameterId::Output(output))
-                .unwrap();
-            let connection_color = port_type.data_type_color(user_state);
-            let src_pos = port_locations[&AnyParameterId::Output(output)];
-            let dst_pos = port_locations[&AnyParameterId::Input(input)];
+            let mut connection_color =
+                self.data_type_colors
+                    .get(&self.graph, AnyParameterId::Output(output), user_state);
+            let src_pos = self.port_locations[&AnyParameterId::Output(output)];
+            let mut dst_pos = self.port_locations[&AnyParameterId::Input(input)];
+            // The wires of multi-inputs fan out over the height of their port,
+            // in the order they were connected
+            let fan = self.graph.connections(input);
+            if fan.len() > 1 {
+                let index = fan.iter().position(|o| *o == output).unwrap_or(0) as f32;
+                let middle = (fan.len() - 1) as f32 / 2.0;
+                let spacing = (style.port_radius / middle).min(style.port_radius);
+                dst_pos.y += (index - middle) * spacing;
+            }
             let src_id = self.graph.get_output(output).node;
             let dst_id = self.graph.get_input(input).node;
             let src_orientation = self.node_orientations[src_id];
             let dst_orientation = self.node_orientations[dst_id];
             let src_control = port_control(&output.into(), src_orientation);
             let dst_control = port_control(&input.into(), dst_orientation);
-            draw_connection(
-                ui.painter(),
+            let waypoints: Vec<Pos2> = connection_waypoints(&self.waypoints, input, output)
+                .iter()
+                .map(|pos| transform * *pos)
+                .collect();
+            for (index, pos) in waypoints.iter().enumerate() {
+                waypoint_handles.push((input, output, index, *pos, connection_color));
+            }
+            if new_waypoint.is_none() && r.double_clicked() {
+                let hit = connection_mesh
+                    .segments(src_pos, src_control, &waypoints, dst_pos, dst_control)
+                    .iter()
+                    .position(|points| {
+                        points.as_ref().is_some_and(|points| {
+                            polyline_distance(points, cursor_pos) < style.port_hit_radius / 2.0
+                        })
+                    });
+                new_waypoint = hit.map(|index| (input, output, index));
+            }
+            let on_path = self
+                .highlighted_path
+                .as_ref()
+                .is_some_and(|path| path.connections.contains(&(input, output)));
+            if on_path {
+                highlighted_connections.push((
+                    src_pos,
+                    src_control,
+                    waypoints,
+                    dst_pos,
+                    dst_control,
+                    connection_color,
+                ));
+                continue;
+            }
+            let dimmed = highlighted_nodes
+                .as_ref()
+                .is_some_and(|nodes| !nodes.contains_key(src_id) || !nodes.contains_key(dst_id));
+            if dimmed {
+                connection_color =
+                    connection_color.gamma_multiply(style.path_highlight.dim_opacity);
+            }
+            connection_mesh.add_connection(
                 src_pos,
                 src_control,
+                &waypoints,
                 dst_pos,
                 dst_control,
                 connection_color,
             );
         }
+        connection_mesh.width *= style.path_highlight.width_factor;
+        for (src_pos, src_control, waypoints, dst_pos, dst_control, color) in
+            highlighted_connections
+        {
+            connection_mesh.add_connection(
+                src_pos,
+                src_control,
+                &waypoints,
+                dst_pos,
+                dst_control,
+                color,
+            );
+        }
+
+        connection_mesh.paint(ui.painter());
+
+        for (input, output, index, pos, color) in waypoint_handles {
+            let rect = Rect::from_center_size(pos, Vec2::splat(style.port_hit_radius));
+            let response = ui.interact(
+                rect,
+                ui.id().with(("waypoint", input, output, index)),
+                Sense::click_and_drag(),
+            );
+            let mut remove = response.double_clicked();
+            response.context_menu(|ui| {
+                if ui.button("Remove waypoint").clicked() {
+                    remove = true;
+                    ui.close_menu();
+                }
+            });
+            if remove {
+                self.remove_waypoint(input, output, index);
+                continue;
+            }
+            if response.dragged() {
+                let drag_delta = response.drag_delta() / transform.scaling;
+                if let Some(point) = self
+                    .waypoints
+                    .get_mut(input)
+                    .and_then(|connections| connections.iter_mut().find(|c| c.output == output))
+                    .and_then(|c| c.points.get_mut(index))
+                {
+                    *point += drag_delta;
+                }
+            }
+            let color = if response.hovered() || response.dragged() {
+                style.port_hover
+            } else {
+                color
+            };
+            ui.painter()
+                .circle_filled(pos, style.connection_width * 2.0, color);
+        }
+        if let Some((input, output, index)) = new_waypoint {
+            self.insert_waypoint(input, output, index, transform.inverse() * cursor_pos);
+        }
+
+        let removed_probes = draw_probes(
+            ui,
+            &self.probes,
+            &self.graph,
+            &self.port_locations,
+            &mut self.data_type_colors,
+            &style,
+            user_state,
+        );
+        for output in removed_probes {
+            self.probes.remove(output);
+        }
+
+        draw_diagnostic_badges(
+            ui,
+            &self.diagnostics,
+            &self.node_rects,
+            &self.port_locations,
+            &style.diagnostics,
+        );
+
+        draw_remote_participants(
+            ui.painter(),
+            &self.remote_participants,
+            &self.node_rects,
+            transform,
+        );
+
+        if let Some(view) = &minimap_view {
+            self.draw_minimap(ui.painter(), view, editor_rect, &style);
+        }
+
+        overlay(
+            &EditorOverlay {
+                painter: &ui.painter().with_clip_rect(editor_rect),
+                editor_rect,
+                transform,
+                node_rects: &self.node_rects,
+                port_locations: &self.port_locations,
+                selected_nodes: &self.selected_nodes,
+            },
+            user_state,
+        );
 
         /* Handle responses from drawing nodes */
 
-        // Some responses generate additional responses when processed. These
-        // are stored here to report them back to the user.
-        let mut extra_responses: Vec<NodeResponse<UserResponse, NodeData>> = Vec::new();
+        // Some responses generate additional responses when processed, and
+        // arranging the selection already moved nodes. These are stored here
+        // to report them back to the user.
+        let mut extra_responses: Vec<NodeResponse<UserResponse, NodeData>> = arranged;
+
+        // Nodes are drawn without knowing about the selection or modifiers,
+        // so constrain their drags, and turn drags of selected nodes into
+        // drags of the whole selection.
+        let modifiers = ui.input(|i| i.modifiers);
+        for response in &mut delayed_responses[prepended_responses..node_responses_end] {
+            if let NodeResponse::MoveNode { node, drag_delta } = *response {
+                let drag_delta = self.constrain_drag(node, drag_delta, modifiers);
+                *response = self.move_response(node, drag_delta);
+            }
+        }
+        if !ui.input(|i| i.pointer.any_down()) {
+            self.node_drag = None;
+        }
 
         for response in delayed_responses.iter() {
-            match response {
-                NodeResponse::ConnectEventStarted(node_id, port) => {
-                    self.connection_in_progress = Some((*node_id, *port));
-                }
-                NodeResponse::ConnectEventEnded { input, output } => {
-                    self.graph.add_connection(*output, *input)
-                }
-                NodeResponse::CreatedNode(_) => {
-                    //Convenience NodeResponse for users
-                }
-                NodeResponse::SelectNode(node_id) => {
-                    self.selected_nodes = Vec::from([*node_id]);
-                }
-                NodeResponse::DeleteNodeUi(node_id) => {
-                    let (node, disc_events) = self.graph.remove_node(*node_id);
-                    // Pass the disconnection responses first so user code can perform cleanup
-                    // before node removal response.
-                    extra_responses.extend(
-                        disc_events
-                            .into_iter()
-                            .map(|(input, output)| NodeResponse::DisconnectEvent { input, output }),
-                    );
-                    // Pass the full node as a response so library users can
-                    // listen for it and get their user data.
-                    extra_responses.push(NodeResponse::DeleteNodeFull {
-                        node_id: *node_id,
-                        node,
-                    });
-                    self.node_positions.remove(*node_id);
-                    // Make sure to not leave references to old nodes hanging
-                    self.selected_nodes.retain(|id| *id != *node_id);
-                    self.node_order.retain(|id| *id != *node_id);
-                }
-                NodeResponse::DisconnectEvent { input, output } => {
-                    let other_node = self.graph.get_output(*output).node;
-                    self.graph.remove_connection(*input);
-                    self.connection_in_progress =
-                        Some((other_node, AnyParameterId::Output(*output)));
-                }
-                NodeResponse::RaiseNode(node_id) => {
-                    let old_pos = self
-                        .node_order
-                        .iter()
-                        .position(|id| *id == *node_id)
-                        .expect("Node to be raised should be in `node_order`");
-                    self.node_order.remove(old_pos);
-                    self.node_order.push(*node_id);
-                }
-                NodeResponse::MoveNode { node, drag_delta } => {
-                    self.node_positions[*node] += *drag_delta;
-                    // Handle multi-node selection movement
-                    if self.selected_nodes.contains(node) && self.selected_nodes.len() > 1 {
-                        for n in self.selected_nodes.iter().copied() {
-                            if n != *node {
-                                self.node_positions[n] += *drag_delta;
-                            }
-                        }
-                    }
-                }
-                NodeResponse::User(_) => {
-                    // These are handled by the user code.
-                }
-                NodeResponse::DeleteNodeFull { .. } => {
-                    unreachable!("The UI should never produce a DeleteNodeFull event.")
-                }
+            extra_responses.extend(self.handle_node_response(response));
+        }
+        if self.push_aside {
+            let dragged: Vec<NodeId> = delayed_responses[prepended_responses..node_responses_end]
+                .iter()
+                .flat_map(|response| match response {
+                    NodeResponse::MoveNode { node, .. } => std::slice::from_ref(node),
+                    NodeResponse::MoveSelection { nodes, .. } => nodes.as_slice(),
+                    _ => &[],
+                })
+                .copied()
+                .collect();
+            if !dragged.is_empty() {
+                extra_responses.extend(self.push_nodes_aside(&dragged));
             }
         }
 
         // Handle box selection
         if let Some(box_start) = self.ongoing_box_selection {
             let selection_rect = Rect::from_two_pos(cursor_pos, box_start);
-            let bg_color = Color32::from_rgba_unmultiplied(200, 200, 200, 20);
-            let stroke_color = Color32::from_rgba_unmultiplied(200, 200, 200, 180);
             ui.painter().rect(
                 selection_rect,
                 2.0,
-                bg_color,
-                Stroke::new(3.0, stroke_color),
+                style.box_selection_fill,
+                Stroke::new(3.0, style.box_selection_stroke),
                 StrokeKind::Outside,
             );
 
-            self.selected_nodes = node_rects
-                .into_iter()
-                .filter_map(|(node_id, rect)| {
-                    if selection_rect.intersects(rect) {
-                        Some(node_id)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+            // Only select nodes that were drawn on this frame
+            let mut selected_nodes = self
+                .node_rect_index
+                .query(transform.inverse().mul_rect(selection_rect));
+            selected_nodes.retain(|node_id| self.node_rects.contains_key(node_id));
+            self.selected_nodes = selected_nodes;
         }
 
         // Push any responses that were generated during response handling.
@@ -453,89 +1199,931 @@ where
         // This locks the context, so don't hold on to it for too long.
         let mouse = &ui.ctx().input(|i| i.pointer.clone());
 
+        if mouse.any_pressed() && budget.is_some() {
+            self.frame_budget_state.active_node = self.node_order.iter().rev().find(|node_id| {
+                self.node_rects
+                    .get(node_id)
+                    .is_some_and(|rect| rect.contains(cursor_pos))
+            });
+        }
+
         if mouse.any_released() && self.connection_in_progress.is_some() {
             self.connection_in_progress = None;
         }
 
-        if mouse.secondary_released() && !cursor_in_finder {
-            self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+        // Right clicking one of several selected nodes opens the selection
+        // menu instead of the node finder.
+        let top_node = self.node_order.iter().rev().find(|node_id| {
+            self.node_rects
+                .get(node_id)
+                .is_some_and(|rect| rect.contains(cursor_pos))
+        });
+        let over_selection = self.selected_nodes.len() > 1
+            && top_node.is_some_and(|node_id| self.selected_nodes.contains(&node_id));
+
+        // Long presses stand in for right clicks on touch screens.
+        if (mouse.secondary_released() || r.long_touched())
+            && cursor_in_editor
+            && !cursor_in_finder
+            && !cursor_in_menu
+        {
+            if over_selection {
+                self.selection_menu = Some(cursor_pos);
+            } else {
+                self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+            }
         }
         if ui.ctx().input(|i| i.key_pressed(Key::Escape)) {
             self.node_finder = None;
+            self.selection_menu = None;
+            self.highlighted_path = None;
+        }
+        if cursor_in_editor && !cursor_in_finder && ui.ctx().memory(|mem| mem.focused().is_none()) {
+            delayed_responses.extend(self.handle_keybindings(ui, user_state));
+        }
+        if cursor_in_editor
+            && ui.ctx().memory(|mem| mem.focused().is_none())
+            && consume_shortcut(ui, self.keybindings.highlight_path)
+        {
+            match self.selected_nodes[..] {
+                [first, second] => self.highlight_path(first, second),
+                _ => self.highlighted_path = None,
+            }
+        }
+        if cursor_in_editor
+            && ui.ctx().memory(|mem| mem.focused().is_none())
+            && consume_shortcut(ui, self.keybindings.select_islands)
+        {
+            match top_node {
+                Some(node_id) => self.select_islands(&[node_id]),
+                None => self.select_islands(&self.selected_nodes.clone()),
+            }
+        }
+
+        if cursor_in_editor
+            && !self.selected_nodes.is_empty()
+            && ui.ctx().memory(|mem| mem.focused().is_none())
+            && consume_shortcut(ui, self.keybindings.frame_selection)
+        {
+            let frame = self.add_frame("Frame", &self.selected_nodes.clone());
+            self.start_frame_renaming(frame);
+            delayed_responses.push(NodeResponse::CreatedFrame(frame));
+        }
+
+        if r.dragged() && ui.ctx().input(|i| i.pointer.middle_down()) {
+            self.pan_zoom.pan += ui.ctx().input(|i| i.pointer.delta());
+        }
+
+        // Two finger drags pan the view on touch screens. The first finger
+        // also acts as the pointer, so it must not start a box selection.
+        if cursor_in_editor {
+            if let Some(touch) = ui.ctx().input(|i| i.multi_touch()) {
+                self.pan_zoom.pan += touch.translation_delta;
+                self.ongoing_box_selection = None;
+                drag_started_on_background = false;
+            }
+        }
+
+        // Scrolling zooms around the cursor, as pinching does on touch
+        // screens and touchpads
+        if cursor_in_editor && !cursor_in_finder && !cursor_in_menu {
+            let factor = ui.ctx().input(|i| {
+                i.zoom_delta() * (i.smooth_scroll_delta.y / SCROLL_ZOOM_DOUBLING).exp2()
+            });
+            if factor != 1.0 {
+                self.pan_zoom
+                    .zoom_around(factor, cursor_pos - editor_rect.min, self.zoom_range);
+            }
+        }
+
+        // Deselect and deactivate finder if the editor backround is clicked,
+        // *or* if the the mouse clicks off the ui
+        if mouse.any_pressed() && !cursor_in_finder && !cursor_in_menu {
+            let opening_menu = mouse.secondary_pressed() && over_selection;
+            if cursor_in_editor && !opening_menu {
+                self.selected_nodes = Vec::new();
+            }
+            self.node_finder = None;
+            self.selection_menu = None;
+        }
+
+        if drag_started_on_background && mouse.primary_down() {
+            self.ongoing_box_selection = Some(cursor_pos);
+        }
+        if mouse.primary_released() || drag_released_on_background {
+            self.ongoing_box_selection = None;
+        }
+
+        // Copy, cut and paste events are sent for the keyboard shortcuts,
+        // unless a text field has the focus
+        if let Some(hooks) = self.clipboard_hooks {
+            let unfocused = ui.ctx().memory(|mem| mem.focused().is_none());
+            if cursor_in_editor && !cursor_in_finder && unfocused {
+                let mut pasted = false;
+                for event in ui.ctx().input(|i| i.events.clone()) {
+                    let text = match event {
+                        Event::Copy => {
+                            (hooks.copy)(self, ui.ctx());
+                            continue;
+                        }
+                        Event::Cut => {
+                            if (hooks.copy)(self, ui.ctx()) {
+                                delayed_responses.extend(self.delete_selection(user_state));
+                            }
+                            continue;
+                        }
+                        Event::Paste(text) => Some(text),
+                        // Some integrations only send a paste event when the
+                        // system clipboard holds text, which the editor's own
+                        // clipboard doesn't need.
+                        Event::Key {
+                            key,
+                            pressed: true,
+                            modifiers,
+                            ..
+                        } if key == Key::Paste || (key == Key::V && modifiers.command) => None,
+                        _ => continue,
+                    };
+                    // Integrations sending both the key and the event paste once
+                    if !pasted {
+                        let position = transform.inverse() * cursor_pos;
+                        let nodes = (hooks.paste)(self, position, text.as_deref());
+                        pasted = !nodes.is_empty();
+                        delayed_responses.extend(nodes.into_iter().map(NodeResponse::CreatedNode));
+                    }
+                }
+            }
+        }
+
+        let transitioning = self.layout_transition.is_running();
+        self.end_layout_transition_frame();
+
+        let input_changed = ui.ctx().input(|i| {
+            let pointer_changed = i.pointer.delta() != Vec2::ZERO
+                || i.pointer.any_pressed()
+                || i.pointer.any_released();
+            let typed = i
+                .events
+                .iter()
+                .any(|event| matches!(event, Event::Key { .. } | Event::Text(_)));
+            (pointer_changed && (cursor_in_editor || cursor_in_finder)) || typed
+        });
+
+        // Edits are recorded once the pointer and the keyboard are done with
+        // them, so that dragging a node or typing a value makes a single edit.
+        // Nodes gliding to a layout are back at their target by now, so the
+        // layout makes a single edit too. Only frames with input or responses
+        // can edit the graph, the others don't compare it with the history.
+        let mut stepped_history = false;
+        if let Some(hooks) = self.undo_hooks {
+            let mut unrecorded = false;
+            if let Some(history) = &mut self.undo_history {
+                history.unrecorded |= input_changed || !delayed_responses.is_empty();
+                unrecorded = history.unrecorded;
+            }
+            let unfocused = ui.ctx().memory(|mem| mem.focused().is_none());
+            if unrecorded && unfocused && !mouse.any_down() {
+                (hooks.record)(self);
+            }
+            let shortcuts = self
+                .undo_history
+                .as_ref()
+                .is_some_and(|history| history.shortcuts);
+            if shortcuts && cursor_in_editor && unfocused {
+                // The undo shortcut matches the redo one too, so redo comes first
+                if consume_shortcut(ui, self.keybindings.redo) {
+                    stepped_history = (hooks.redo)(self);
+                } else if consume_shortcut(ui, self.keybindings.undo) {
+                    stepped_history = (hooks.undo)(self);
+                }
+            }
+        }
+
+        let cursor_graph_pos = ui
+            .ctx()
+            .input(|i| i.pointer.hover_pos())
+            .map(|pos| transform.inverse() * pos);
+
+        let interacting = self.is_interacting()
+            || self.pan_zoom.pan != pan_zoom_before.pan
+            || self.pan_zoom.zoom != pan_zoom_before.zoom
+            || delayed_responses.iter().any(|response| {
+                matches!(
+                    response,
+                    NodeResponse::MoveNode { .. }
+                        | NodeResponse::MoveSelection { .. }
+                        | NodeResponse::MoveFrame { .. }
+                        | NodeResponse::ResizeFrame { .. }
+                        | NodeResponse::ResizeNode { .. }
+                )
+            });
+        if interacting || transitioning {
+            ui.ctx().request_repaint();
+        }
+        let changed = interacting
+            || transitioning
+            || input_changed
+            || self.frame_budget_state.deferred > 0
+            || !delayed_responses.is_empty()
+            || stepped_history
+            || self.node_finder.is_some() != had_finder;
+        self.revalidate(changed);
+
+        GraphResponse {
+            node_responses: delayed_responses,
+            cursor_in_editor,
+            cursor_in_finder,
+            cursor_graph_pos,
+            changed,
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+    >,
+    DataType: PartialEq,
+{
+    /// Creates a node from `template` at `position`, in graph coordinates,
+    /// and places it on top of the other nodes. Returns the id of the new
+    /// node. Note that, unlike nodes created by the node finder, no
+    /// [`NodeResponse::CreatedNode`] is emitted.
+    ///
+    /// If another node is in the way, the node is placed nearby instead,
+    /// see [`GraphEditorState::avoid_overlaps`].
+    pub fn add_node_from_template(
+        &mut self,
+        template: &NodeTemplate,
+        position: Pos2,
+        user_state: &mut UserState,
+    ) -> NodeId {
+        let avoid_overlaps = self.avoid_overlaps;
+        Self::add_node_from_template_impl(
+            &mut self.graph,
+            &mut self.node_positions,
+            &mut self.node_orientations,
+            &mut self.node_order,
+            avoid_overlaps.then_some(&mut self.node_rect_index),
+            template,
+            position,
+            user_state,
+        )
+    }
+
+    // NOTE: Takes the fields separately so it can be used while the node
+    // finder is borrowed. The node is moved to a free spot when given the
+    // rect index.
+    #[allow(clippy::too_many_arguments)]
+    fn add_node_from_template_impl(
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        node_positions: &mut SecondaryMap<NodeId, Pos2>,
+        node_orientations: &mut SecondaryMap<NodeId, NodeOrientation>,
+        node_order: &mut NodeOrder,
+        node_rect_index: Option<&mut NodeRectIndex>,
+        template: &NodeTemplate,
+        mut position: Pos2,
+        user_state: &mut UserState,
+    ) -> NodeId {
+        let new_node = graph.add_node(
+            template.node_graph_label(user_state),
+            template.user_data(user_state),
+            |graph, node_id| template.build_node(graph, user_state, node_id),
+        );
+        if let Some(index) = node_rect_index {
+            position = index.free_position(position, UNMEASURED_NODE_SIZE);
+            // Until it is drawn and measured, so that nodes created in a
+            // row don't end up on top of each other either.
+            index.insert(
+                new_node,
+                Rect::from_min_size(position, UNMEASURED_NODE_SIZE),
+            );
+        }
+        node_positions.insert(new_node, position);
+        node_orientations.insert(new_node, NodeOrientation::LeftToRight);
+        node_order.push(new_node);
+        new_node
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Removes the editor data of `node`, which was just removed from the
+    /// graph, so that no references to it are left hanging.
+    pub(crate) fn forget_node(&mut self, node_id: NodeId, node: &Node<NodeData>) {
+        self.node_positions.remove(node_id);
+        self.node_orientations.remove(node_id);
+        self.node_layouts.remove(node_id);
+        self.node_extents.remove(node_id);
+        self.node_sizes.remove(node_id);
+        self.collapsed_nodes.remove(node_id);
+        self.node_rect_index.remove(node_id);
+        self.node_shapes.remove(node_id);
+        for output in node.output_ids() {
+            self.probes.remove(output);
+        }
+        self.selected_nodes.retain(|id| *id != node_id);
+        self.node_order.remove(node_id);
+        if matches!(self.connection_in_progress, Some((id, _)) if id == node_id) {
+            self.connection_in_progress = None;
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<Response = UserResponse>,
+    UserResponse: UserResponseTrait,
+    DataType: PartialEq,
+{
+    /// Whether only the title bar of a node is drawn.
+    pub fn is_node_collapsed(&self, node_id: NodeId) -> bool {
+        self.collapsed_nodes.contains_key(node_id)
+    }
+
+    /// Collapses a node to its title bar, with its ports along the sides of
+    /// the title bar, or expands it back.
+    pub fn set_node_collapsed(&mut self, node_id: NodeId, collapsed: bool) {
+        if !collapsed {
+            self.collapsed_nodes.remove(node_id);
+        } else if self.graph.nodes.contains_key(node_id) {
+            self.collapsed_nodes.insert(node_id, ());
+        }
+    }
+
+    /// Removes a node along with its connections and editor data, as the
+    /// node's close button does. Returns a [`NodeResponse::DisconnectEvent`]
+    /// for each removed connection followed by a
+    /// [`NodeResponse::DeleteNodeFull`] carrying the node.
+    pub fn delete_node(&mut self, node_id: NodeId) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let (node, disc_events) = self.graph.remove_node(node_id);
+        // Pass the disconnection responses first so user code can perform cleanup
+        // before node removal response.
+        let mut responses: Vec<_> = disc_events
+            .into_iter()
+            .map(|(input, output)| NodeResponse::DisconnectEvent { input, output })
+            .collect();
+        // Pass the full node as a response so library users can
+        // listen for it and get their user data.
+        self.forget_node(node_id, &node);
+        responses.push(NodeResponse::DeleteNodeFull { node_id, node });
+        responses
+    }
+
+    /// Forgets the measured layout of a node, so that it is laid out again on
+    /// the next frame even if it is out of view. Only needed when changing
+    /// something that affects the size of a node from code, other than its
+    /// parameters and connections, e.g. an input value shown inline.
+    pub fn mark_node_dirty(&mut self, node_id: NodeId) {
+        self.node_layouts.remove(node_id);
+        self.node_shapes.remove(node_id);
+    }
+
+    /// Emphasizes the nodes and connections data flows through between `a`
+    /// and `b`, in either direction, and dims the rest of the graph. Clears
+    /// the highlight when neither node depends on the other. See
+    /// [`Graph::path_between`].
+    pub fn highlight_path(&mut self, a: NodeId, b: NodeId) {
+        self.highlighted_path = self
+            .graph
+            .path_between(a, b)
+            .or_else(|| self.graph.path_between(b, a));
+    }
+
+    /// Selects `nodes` along with all the nodes connected to them, directly
+    /// or through other nodes, so whole independent clusters can be moved or
+    /// deleted at once. See [`Graph::connected_nodes`].
+    pub fn select_islands(&mut self, nodes: &[NodeId]) {
+        self.selected_nodes = self.graph.connected_nodes(nodes.iter().copied());
+    }
+
+    /// The nodes drawn as usual while the others are dimmed: the
+    /// highlighted path if there is one, or else the nodes related to the
+    /// selection in [`GraphEditorState::dependency_focus`] mode.
+    fn highlighted_nodes(&self) -> Option<SecondaryMap<NodeId, ()>> {
+        if let Some(path) = &self.highlighted_path {
+            return Some(path.nodes.iter().map(|node_id| (*node_id, ())).collect());
+        }
+        if !self.dependency_focus || self.selected_nodes.is_empty() {
+            return None;
+        }
+        let selected = self.selected_nodes.iter().copied();
+        let upstream = self.graph.upstream_closure(selected.clone());
+        let downstream = self.graph.downstream_closure(selected);
+        Some(
+            upstream
+                .into_iter()
+                .chain(downstream)
+                .map(|node_id| (node_id, ()))
+                .collect(),
+        )
+    }
+
+    /// Whether a connection or a box selection is being dragged, so the
+    /// editor needs to be redrawn as the pointer moves.
+    pub fn is_interacting(&self) -> bool {
+        self.connection_in_progress.is_some() || self.ongoing_box_selection.is_some()
+    }
+
+    /// Hashes what the layout of a node depends on, see [`LayoutKeyInput::key`].
+    fn node_layout_key(&self, node_id: NodeId, overview: bool) -> u64 {
+        LayoutKeyInput::new(
+            &self.graph,
+            &self.node_sizes,
+            &self.collapsed_nodes,
+            node_id,
+        )
+        .key(
+            overview,
+            self.node_orientations[node_id],
+            &self.graph.connections,
+        )
+    }
+
+    /// Stores the size and port locations of a node that was just drawn.
+    fn record_node_layout(&mut self, node_id: NodeId, key: u64, transform: emath::TSTransform) {
+        let Some(rect) = self.node_rects.get(&node_id) else {
+            return;
+        };
+        let min = transform * self.node_positions[node_id];
+        let zoom = transform.scaling;
+        let node = &self.graph[node_id];
+        let params = node
+            .input_ids()
+            .map(AnyParameterId::from)
+            .chain(node.output_ids().map(AnyParameterId::from));
+        let ports = params
+            .filter_map(|param| Some((param, (*self.port_locations.get(&param)? - min) / zoom)))
+            .collect();
+        self.node_layouts.insert(
+            node_id,
+            NodeLayout {
+                size: rect.size() / zoom,
+                ports,
+                key,
+            },
+        );
+    }
+
+    /// Turns the delta by which the pointer dragged a node on this frame
+    /// into the delta the node should move by. Holding shift keeps the node
+    /// on the axis it moved the most along since the drag started, and
+    /// holding ctrl (cmd on macOS) moves it by multiples of
+    /// [`GraphEditorState::drag_step`]. With
+    /// [`GraphEditorState::snap_to_grid`], the node lands on the grid.
+    fn constrain_drag(&mut self, node: NodeId, drag_delta: Vec2, modifiers: Modifiers) -> Vec2 {
+        let position = self.node_positions[node];
+        let snapping_grid = self.snapping_grid();
+        if self.node_drag.is_none_or(|drag| drag.node != node) {
+            self.node_drag = Some(NodeDrag {
+                node,
+                start: position,
+                offset: Vec2::ZERO,
+            });
+        }
+        let drag = self.node_drag.as_mut().unwrap();
+        drag.offset += drag_delta;
+
+        let mut offset = drag.offset;
+        if modifiers.shift {
+            if offset.x.abs() >= offset.y.abs() {
+                offset.y = 0.0;
+            } else {
+                offset.x = 0.0;
+            }
+        }
+        if modifiers.command && self.drag_step > 0.0 {
+            offset = (offset / self.drag_step).round() * self.drag_step;
+        }
+        match snapping_grid {
+            Some(grid) => grid.snap(drag.start + offset) - position,
+            None => drag.start + offset - position,
+        }
+    }
+
+    /// The response for dragging `node` by `drag_delta`: the whole selection
+    /// moves when the node is part of a selection of several nodes.
+    pub(crate) fn move_response(
+        &self,
+        node: NodeId,
+        drag_delta: Vec2,
+    ) -> NodeResponse<UserResponse, NodeData> {
+        if self.selected_nodes.len() > 1 && self.selected_nodes.contains(&node) {
+            NodeResponse::MoveSelection {
+                nodes: self.selected_nodes.clone(),
+                drag_delta,
+            }
+        } else {
+            NodeResponse::MoveNode { node, drag_delta }
+        }
+    }
+
+    /// Performs the changes requested by a response of the nodes, and returns
+    /// the responses this generates.
+    pub(crate) fn handle_node_response(
+        &mut self,
+        response: &NodeResponse<UserResponse, NodeData>,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        match response {
+            NodeResponse::ConnectEventStarted(node_id, port) => {
+                self.connection_in_progress = Some((*node_id, *port));
+            }
+            NodeResponse::ConnectEventEnded { input, output } => {
+                self.graph.add_connection(*output, *input)
+            }
+            NodeResponse::CreatedNode(_) => {
+                //Convenience NodeResponse for users
+            }
+            NodeResponse::SelectNode(node_id) => {
+                self.selected_nodes = Vec::from([*node_id]);
+            }
+            NodeResponse::DeleteNodeUi(node_id) => {
+                return self.delete_node(*node_id);
+            }
+            NodeResponse::DisconnectEvent { input, output } => {
+                let other_node = self.graph.get_output(*output).node;
+                self.graph.disconnect(*output, *input);
+                self.connection_in_progress = Some((other_node, AnyParameterId::Output(*output)));
+            }
+            NodeResponse::RaiseNode(node_id) => {
+                self.node_order.raise(*node_id);
+            }
+            NodeResponse::MoveNode { node, drag_delta } => {
+                self.node_positions[*node] += *drag_delta;
+            }
+            NodeResponse::MoveSelection { nodes, drag_delta } => {
+                for node in nodes {
+                    if let Some(position) = self.node_positions.get_mut(*node) {
+                        *position += *drag_delta;
+                    }
+                }
+            }
+            NodeResponse::AddProbe(output) => {
+                self.add_probe(*output);
+            }
+            NodeResponse::CreatedFrame(_) => {
+                // Convenience NodeResponse for users
+            }
+            NodeResponse::RenameFrame { frame, title } => {
+                if let Some(frame) = self.frames.get_mut(*frame) {
+                    frame.title.clone_from(title);
+                }
+            }
+            NodeResponse::MoveFrame {
+                frame,
+                nodes,
+                drag_delta,
+            } => {
+                if let Some(frame) = self.frames.get_mut(*frame) {
+                    frame.rect = frame.rect.translate(*drag_delta);
+                }
+                for node in nodes {
+                    if let Some(position) = self.node_positions.get_mut(*node) {
+                        *position += *drag_delta;
+                    }
+                }
+            }
+            NodeResponse::ResizeFrame { frame, drag_delta } => {
+                self.resize_frame(*frame, *drag_delta);
+            }
+            NodeResponse::DeleteFrame(frame) => {
+                self.remove_frame(*frame);
+            }
+            NodeResponse::ResizeNode { node, size } => {
+                if self.graph.nodes.contains_key(*node) {
+                    self.node_sizes.insert(*node, *size);
+                }
+            }
+            NodeResponse::CollapseNode { node, collapsed } => {
+                self.set_node_collapsed(*node, *collapsed);
+            }
+            NodeResponse::User(_) => {
+                // These are handled by the user code.
+            }
+            NodeResponse::DeleteNodeFull { .. } => {
+                unreachable!("The UI should never produce a DeleteNodeFull event.")
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// Paints the shapes cached for a node, moved to its current position.
+fn replay_node_shapes(ui: &Ui, cached: &NodeShapes, min: Pos2) {
+    let delta = min - cached.origin;
+    let clip_rect = ui.clip_rect();
+    ui.ctx().graphics_mut(|graphics| {
+        let list = graphics.entry(ui.layer_id());
+        for (clip, shape) in &cached.shapes {
+            let clip = clip.map_or(clip_rect, |clip| clip.translate(delta).intersect(clip_rect));
+            let mut shape = shape.clone();
+            shape.translate(delta);
+            list.add(clip, shape);
+        }
+    });
+}
+
+/// What the connection being dragged from a port is over.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PreviewTarget {
+    Free,
+    /// A port it snaps to and can end on.
+    Valid,
+    /// A port it can't end on.
+    Invalid,
+}
+
+/// Sets the text style of the parameter labels drawn in `ui`. The parameter
+/// widgets are drawn by the user, so this goes through the egui style.
+fn apply_label_style(ui: &mut Ui, label_style: &LabelStyle) {
+    if label_style.text_style.is_some() || label_style.size.is_some() {
+        let font_id = label_style.font_id(&TextStyle::Body, ui.style());
+        ui.style_mut().override_font_id = Some(font_id);
+    }
+    if let Some(color) = label_style.color {
+        ui.visuals_mut().widgets.noninteractive.fg_stroke.color = color;
+    }
+}
+
+/// Scales the sizes the widgets inside the nodes are laid out with, so that
+/// they follow the zoom along with their text.
+fn scale_spacing(spacing: &mut Spacing, factor: f32) {
+    spacing.item_spacing *= factor;
+    spacing.button_padding *= factor;
+    spacing.interact_size *= factor;
+    for length in [
+        &mut spacing.indent,
+        &mut spacing.slider_width,
+        &mut spacing.slider_rail_height,
+        &mut spacing.combo_width,
+        &mut spacing.text_edit_width,
+        &mut spacing.icon_width,
+        &mut spacing.icon_width_inner,
+        &mut spacing.icon_spacing,
+    ] {
+        *length *= factor;
+    }
+}
+
+/// Splits the line through `points` into dashes of length `dash`, separated
+/// by gaps of length `gap`.
+fn dash_path(points: &[Pos2], dash: f32, gap: f32) -> Vec<Vec<Pos2>> {
+    if dash <= 0.0 || gap < 0.0 {
+        return vec![points.to_vec()];
+    }
+    let mut dashes = Vec::new();
+    let mut current = Vec::new();
+    let mut drawing = true;
+    // What remains of the current dash or gap
+    let mut remaining = dash;
+    for segment in points.windows(2) {
+        let (mut start, end) = (segment[0], segment[1]);
+        if drawing && current.is_empty() {
+            current.push(start);
+        }
+        let mut length = start.distance(end);
+        while length > remaining {
+            let split = start.lerp(end, remaining / length);
+            // Ends the dash, or starts the next one
+            if current.last() != Some(&split) {
+                current.push(split);
+            }
+            if drawing {
+                dashes.push(std::mem::take(&mut current));
+            }
+            length -= remaining;
+            start = split;
+            drawing = !drawing;
+            remaining = if drawing { dash } else { gap };
+        }
+        remaining -= length;
+        if drawing {
+            current.push(end);
+        }
+    }
+    if current.len() >= 2 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+/// The distance from `pos` to the closest point of a line through `points`.
+fn polyline_distance(points: &[Pos2], pos: Pos2) -> f32 {
+    points
+        .windows(2)
+        .map(|segment| {
+            let (start, end) = (segment[0], segment[1]);
+            let length_sq = start.distance_sq(end);
+            let t = if length_sq > 0.0 {
+                ((pos - start).dot(end - start) / length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            pos.distance(start.lerp(end, t))
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Collects the connections of a frame into a single mesh, so they are
+/// submitted to egui as one shape rather than two per connection.
+struct ConnectionMesh {
+    tessellator: Tessellator,
+    mesh: Mesh,
+    clip_rect: Rect,
+    pixels_per_point: f32,
+    width: f32,
+    /// See [`GraphEditorStyle::connection_outline`].
+    outline: f32,
+    /// The spacing and the screen origin of the grid connections are routed
+    /// along, if any.
+    grid: Option<(f32, Pos2)>,
+    /// How connections are drawn when there is no grid.
+    style: ConnectionStyle,
+    scaling: f32,
+}
+
+impl ConnectionMesh {
+    fn new(painter: &Painter, width: f32, outline: f32) -> Self {
+        let ctx = painter.ctx();
+        let pixels_per_point = ctx.pixels_per_point();
+        let mut tessellator = Tessellator::new(
+            pixels_per_point,
+            ctx.tessellation_options(|options| *options),
+            ctx.fonts(|fonts| fonts.font_image_size()),
+            Vec::new(),
+        );
+        tessellator.set_clip_rect(painter.clip_rect());
+        Self {
+            tessellator,
+            mesh: Mesh::default(),
+            clip_rect: painter.clip_rect(),
+            pixels_per_point,
+            width,
+            outline,
+            grid: None,
+            style: ConnectionStyle::Bezier,
+            scaling: 1.0,
         }
+    }
 
-        if r.dragged() && ui.ctx().input(|i| i.pointer.middle_down()) {
-            self.pan_zoom.pan += ui.ctx().input(|i| i.pointer.delta());
+    #[allow(clippy::too_many_arguments)]
+    fn add_connection(
+        &mut self,
+        src_pos: Pos2,
+        src_control: Vec2,
+        waypoints: &[Pos2],
+        dst_pos: Pos2,
+        dst_control: Vec2,
+        color: Color32,
+    ) {
+        for points in self
+            .segments(src_pos, src_control, waypoints, dst_pos, dst_control)
+            .into_iter()
+            .flatten()
+        {
+            self.add_line(points, color);
         }
+    }
 
-        // Deselect and deactivate finder if the editor backround is clicked,
-        // *or* if the the mouse clicks off the ui
-        if mouse.any_pressed() && !cursor_in_finder {
-            if cursor_in_editor {
-                self.selected_nodes = Vec::new();
+    /// The points of each segment of a connection going through
+    /// `waypoints`, or `None` for the segments out of view. Connections
+    /// leave waypoints in the direction they leave their output.
+    fn segments(
+        &self,
+        src_pos: Pos2,
+        src_control: Vec2,
+        waypoints: &[Pos2],
+        dst_pos: Pos2,
+        dst_control: Vec2,
+    ) -> Vec<Option<Vec<Pos2>>> {
+        let mut ends = Vec::with_capacity(waypoints.len() + 2);
+        ends.push((src_pos, src_control, -src_control));
+        ends.extend(
+            waypoints
+                .iter()
+                .map(|pos| (*pos, src_control, -src_control)),
+        );
+        ends.push((dst_pos, -dst_control, dst_control));
+        ends.windows(2)
+            .map(|pair| {
+                let (src_pos, src_control, _) = pair[0];
+                let (dst_pos, _, dst_control) = pair[1];
+                self.connection_points(src_pos, src_control, dst_pos, dst_control)
+            })
+            .collect()
+    }
+
+    /// The points a connection is drawn through, or `None` when it is out
+    /// of view.
+    fn connection_points(
+        &self,
+        src_pos: Pos2,
+        src_control: Vec2,
+        dst_pos: Pos2,
+        dst_control: Vec2,
+    ) -> Option<Vec<Pos2>> {
+        match (self.grid, self.style) {
+            (Some((grid, origin)), _) if grid > 0.0 => {
+                let points =
+                    orthogonal_route(src_pos, src_control, dst_pos, dst_control, grid, origin);
+                self.is_visible(&points).then_some(points)
             }
-            self.node_finder = None;
-        }
+            (_, ConnectionStyle::Straight) => {
+                let points = vec![src_pos, dst_pos];
+                self.is_visible(&points).then_some(points)
+            }
+            (_, ConnectionStyle::Custom(route)) => {
+                let points = route(&ConnectionSegment {
+                    src_pos,
+                    src_dir: src_control,
+                    dst_pos,
+                    dst_dir: dst_control,
+                    scaling: self.scaling,
+                });
+                (points.len() >= 2 && self.is_visible(&points)).then_some(points)
+            }
+            _ => {
+                let control_scale = ((dst_pos.x - src_pos.x) / 2.0).abs().max(30.0);
+                let src_control = src_pos + src_control * control_scale;
+                let dst_control = dst_pos + dst_control * control_scale;
+                let points = [src_pos, src_control, dst_control, dst_pos];
+
+                // The curve lies within the hull of its control points, so
+                // connections whose hull is out of view can be skipped
+                // without tessellating them.
+                if !self.is_visible(&points) {
+                    return None;
+                }
 
-        if drag_started_on_background && mouse.primary_down() {
-            self.ongoing_box_selection = Some(cursor_pos);
-        }
-        if mouse.primary_released() || drag_released_on_background {
-            self.ongoing_box_selection = None;
+                // Flatten the curve with a fixed tolerance in screen space.
+                // The number of segments then follows the on-screen size of
+                // the curve, so connections get cheaper as the view zooms
+                // out. The default tolerance of epaint is relative to the
+                // horizontal extent of the curve instead, which makes mostly
+                // vertical connections extremely expensive.
+                let tolerance = CONNECTION_TOLERANCE / self.pixels_per_point;
+                let bezier = CubicBezierShape::from_points_stroke(
+                    points,
+                    false,
+                    Color32::TRANSPARENT,
+                    Stroke::NONE,
+                );
+                Some(bezier.flatten(Some(tolerance)))
+            }
         }
+    }
 
-        GraphResponse {
-            node_responses: delayed_responses,
-            cursor_in_editor,
-            cursor_in_finder,
+    fn add_line(&mut self, points: Vec<Pos2>, color: Color32) {
+        let connection_stroke = egui::Stroke {
+            width: self.width,
+            color,
+        };
+        self.tessellator.tessellate_path(
+            &PathShape::line(points.clone(), connection_stroke),
+            &mut self.mesh,
+        );
+        if self.outline > 1.0 {
+            let [r, g, b, a] = color.to_srgba_unmultiplied();
+            let wide_stroke = egui::Stroke {
+                width: self.width * self.outline,
+                color: Color32::from_rgba_unmultiplied(r / 2, g / 2, b / 2, a / 2),
+            };
+            self.tessellator
+                .tessellate_path(&PathShape::line(points, wide_stroke), &mut self.mesh);
         }
     }
-}
 
-fn draw_connection(
-    painter: &Painter,
-    src_pos: Pos2,
-    src_control: Vec2,
-    dst_pos: Pos2,
-    dst_control: Vec2,
-    color: Color32,
-) {
-    let connection_stroke = egui::Stroke { width: 5.0, color };
-
-    let control_scale = ((dst_pos.x - src_pos.x) / 2.0).abs().max(30.0);
-    let src_control = src_pos + src_control * control_scale;
-    let dst_control = dst_pos + dst_control * control_scale;
-
-    let bezier = CubicBezierShape::from_points_stroke(
-        [src_pos, src_control, dst_control, dst_pos],
-        false,
-        Color32::TRANSPARENT,
-        connection_stroke,
-    );
-
-    painter.add(bezier);
-
-    let [r, g, b, a] = color.to_srgba_unmultiplied();
-    let wide_stroke = egui::Stroke {
-        width: 10.0,
-        color: Color32::from_rgba_unmultiplied(r / 2, g / 2, b / 2, a / 2),
-    };
-
-    let wide_bezier = CubicBezierShape::from_points_stroke(
-        [src_pos, src_control, dst_control, dst_pos],
-        false,
-        Color32::TRANSPARENT,
-        wide_stroke,
-    );
-
-    painter.add(wide_bezier);
+    fn add_ring(&mut self, center: Pos2, radius: f32, stroke: Stroke) {
+        self.tessellator
+            .tessellate_circle(CircleShape::stroke(center, radius, stroke), &mut self.mesh);
+    }
+
+    /// Whether a connection within the hull of `points` may be in view.
+    fn is_visible(&self, points: &[Pos2]) -> bool {
+        let bounds = Rect::from_points(points).expand(self.width * self.outline.max(1.0) / 2.0);
+        self.clip_rect.intersects(bounds)
+    }
+
+    fn paint(self, painter: &Painter) {
+        if !self.mesh.is_empty() {
+            painter.add(Shape::mesh(self.mesh));
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
-struct OuterRectMemory(Rect);
+/// The size of nodes drawn in overview mode before they were ever drawn in
+/// full.
+pub(crate) const OVERVIEW_NODE_SIZE: Vec2 = vec2(150.0, 60.0);
 
-impl<NodeData, DataType, ValueType, UserResponse, UserState>
-    GraphNodeWidget<'_, NodeData, DataType, ValueType>
+impl<'a, NodeData, DataType, ValueType, UserResponse, UserState>
+    GraphNodeWidget<'a, NodeData, DataType, ValueType>
 where
     NodeData: NodeDataTrait<
         Response = UserResponse,
@@ -555,40 +2143,165 @@ where
         ui: &mut Ui,
         user_state: &mut UserState,
     ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = Vec::new();
+        self.show_into(ui, user_state, &mut responses);
+        responses
+    }
+
+    /// Same as [`GraphNodeWidget::show`], but adds the responses to
+    /// `responses`. Drawing many nodes into the same buffer avoids allocating
+    /// a list per node.
+    pub fn show_into(
+        self,
+        ui: &mut Ui,
+        user_state: &mut UserState,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) {
+        let constraints = self.graph[self.node_id].user_data.size_constraints(
+            self.node_id,
+            self.graph,
+            user_state,
+        );
+        let resizing = constraints.filter(|_| !self.collapsed).map(|constraints| {
+            (
+                constraints,
+                constraints.clamp(self.size.unwrap_or(constraints.default)),
+            )
+        });
+        let max_size = resizing.map_or(Vec2::from(Self::MAX_NODE_SIZE), |(_, size)| size);
         let mut child_ui = ui.new_child(
             UiBuilder::new()
                 .max_rect(Rect::from_min_size(
-                    *self.position + self.pan,
-                    Self::MAX_NODE_SIZE.into(),
+                    self.transform * *self.position,
+                    max_size * self.transform.scaling,
                 ))
                 .layout(*ui.layout())
                 .id_salt(self.node_id),
         );
 
-        Self::show_graph_node(self, &mut child_ui, user_state)
+        if self.overview {
+            Self::show_overview(self, &mut child_ui, user_state, responses)
+        } else {
+            Self::show_graph_node(self, &mut child_ui, user_state, resizing, responses)
+        }
+    }
+
+    /// Draws this node as a box with its title, without laying out any of its
+    /// contents. The ports are spread along the sides of the box, and can't
+    /// be interacted with.
+    fn show_overview(
+        mut self,
+        ui: &mut Ui,
+        user_state: &mut UserState,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) {
+        let first_response = responses.len();
+        let style = self.style;
+
+        let size = self
+            .node_extents
+            .get(self.node_id)
+            .map_or(OVERVIEW_NODE_SIZE, |extents| extents.size);
+        let node_rect = Rect::from_min_size(ui.max_rect().min, size * self.transform.scaling);
+        let window_response = ui.interact(
+            node_rect,
+            ui.id().with((self.node_id, "window")),
+            Sense::click_and_drag(),
+        );
+
+        let disabled =
+            self.graph[self.node_id]
+                .user_data
+                .is_disabled(self.node_id, self.graph, user_state);
+        if disabled {
+            ui.multiply_opacity(style.disabled.opacity);
+        }
+        if let Some(outline) = self.outline(&window_response) {
+            ui.painter()
+                .add(outline.shape(node_rect, style.corner_radius));
+        }
+        let node = &self.graph[self.node_id];
+        let corner_radius = CornerRadius::same(style.corner_radius);
+        let title_placement = node
+            .user_data
+            .title_placement(self.node_id, self.graph, user_state);
+        let title_style = node
+            .user_data
+            .text_style(self.node_id, self.graph, user_state)
+            .title;
+        let fill = match title_placement {
+            TitlePlacement::Top | TitlePlacement::Bottom => node
+                .user_data
+                .titlebar_color(ui, self.node_id, self.graph, user_state)
+                .unwrap_or(style.titlebar),
+            TitlePlacement::Body | TitlePlacement::Hidden => node
+                .user_data
+                .body_color(ui, self.node_id, self.graph, user_state)
+                .unwrap_or(style.node_background),
+        };
+        let fill = match title_placement {
+            TitlePlacement::Top | TitlePlacement::Bottom if disabled => {
+                style.disabled.titlebar_color(fill)
+            }
+            _ => fill,
+        };
+        let fill = style
+            .hover
+            .node_color(fill, window_response.contains_pointer());
+        ui.painter().rect_filled(node_rect, corner_radius, fill);
+        if title_placement != TitlePlacement::Hidden {
+            ui.painter().with_clip_rect(node_rect).text(
+                node_rect.min + style.node_margin,
+                Align2::LEFT_TOP,
+                &node.label,
+                title_style.font_id(&TextStyle::Button, ui.style()),
+                title_style.color.unwrap_or(style.text),
+            );
+        }
+
+        let (input_x, output_x) = match self.orientation {
+            NodeOrientation::LeftToRight => (node_rect.left(), node_rect.right()),
+            NodeOrientation::RightToLeft => (node_rect.right(), node_rect.left()),
+        };
+        let port_y = |index: usize, count: usize| {
+            node_rect.top() + node_rect.height() * (index + 1) as f32 / (count + 1) as f32
+        };
+        for (index, input) in node.input_ids().enumerate() {
+            let pos = pos2(input_x, port_y(index, node.inputs.len()));
+            self.port_locations.insert(input.into(), pos);
+        }
+        for (index, output) in node.output_ids().enumerate() {
+            let pos = pos2(output_x, port_y(index, node.outputs.len()));
+            self.port_locations.insert(output.into(), pos);
+        }
+        self.node_rects.insert(self.node_id, node_rect);
+
+        self.window_interaction(&window_response, responses, first_response);
     }
 
     /// Draws this node. Also fills in the list of port locations with all of its ports.
-    /// Returns responses indicating multiple events.
+    /// Adds responses indicating multiple events to `responses`.
+    /// `resizing` holds the size constraints of resizable nodes, along
+    /// with their current size.
     fn show_graph_node(
-        self,
+        mut self,
         ui: &mut Ui,
         user_state: &mut UserState,
-    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
-        let margin = egui::vec2(15.0, 5.0);
-        let mut responses = Vec::<NodeResponse<UserResponse, NodeData>>::new();
-
-        let background_color;
-        let text_color;
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#ffffff").unwrap();
-            text_color = color_from_hex("#505050").unwrap();
+        resizing: Option<(NodeSizeConstraints, Vec2)>,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) {
+        let style = self.style;
+        let margin = style.node_margin;
+        let first_response = responses.len();
+        let disabled =
+            self.graph[self.node_id]
+                .user_data
+                .is_disabled(self.node_id, self.graph, user_state);
+        if disabled {
+            ui.multiply_opacity(style.disabled.opacity);
         }
 
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, style.text);
 
         // Preallocate shapes to paint below contents
         let outline_shape = ui.painter().add(Shape::Noop);
@@ -603,51 +2316,59 @@ where
         inner_rect.max.y = inner_rect.max.y.max(inner_rect.min.y);
 
         let mut child_ui = ui.new_child(UiBuilder::new().max_rect(inner_rect).layout(*ui.layout()));
+        if resizing.is_some() {
+            // Resizable nodes fill their size, so the handle stays under
+            // the pointer
+            child_ui.set_min_size(inner_rect.size());
+        }
 
-        // Get interaction rect from memory, it may expand after the window response on resize.
-        let interaction_rect = ui
-            .ctx()
-            .memory_mut(|mem| {
-                mem.data
-                    .get_temp::<OuterRectMemory>(child_ui.id())
-                    .map(|stored| stored.0)
-            })
-            .unwrap_or(outer_rect_bounds);
+        // Use the interaction rect of the last frame, it may expand after the
+        // window response on resize.
+        let origin = outer_rect_bounds.min.to_vec2();
+        let interaction_rect = self
+            .node_extents
+            .get(self.node_id)
+            .map_or(outer_rect_bounds, |extents| {
+                (extents.outer_rect * self.transform.scaling).translate(origin)
+            });
         // After 0.20, layers added over others can block hover interaction. Call this first
         // before creating the node content.
         let window_response = ui.interact(
             interaction_rect,
-            Id::new((self.node_id, "window")),
+            ui.id().with((self.node_id, "window")),
             Sense::click_and_drag(),
         );
 
-        let mut title_height = 0.0;
+        let title_placement = match self.graph[self.node_id].user_data.title_placement(
+            self.node_id,
+            self.graph,
+            user_state,
+        ) {
+            // Collapsed nodes are only their title, and the button expanding
+            // them
+            TitlePlacement::Bottom | TitlePlacement::Hidden if self.collapsed => {
+                TitlePlacement::Top
+            }
+            title_placement => title_placement,
+        };
+        let text_style =
+            self.graph[self.node_id]
+                .user_data
+                .text_style(self.node_id, self.graph, user_state);
+        // The row with the title and the buttons, if any
+        let mut title_rect = None;
 
-        let mut input_port_heights = vec![];
-        let mut output_port_heights = vec![];
+        let mut input_port_heights = SVec::<f32>::new();
+        let mut output_port_heights = SVec::<f32>::new();
 
         child_ui.vertical(|ui| {
-            ui.horizontal(|ui| {
-                ui.add(
-                    Label::new(
-                        RichText::new(&self.graph[self.node_id].label)
-                            .text_style(TextStyle::Button)
-                            .color(text_color),
-                    )
-                    .selectable(false),
-                );
-                responses.extend(self.graph[self.node_id].user_data.top_bar_ui(
-                    ui,
-                    self.node_id,
-                    self.graph,
-                    user_state,
-                ));
-                ui.add_space(8.0); // The size of the little h-flip icon
-                ui.add_space(4.0); // margin
-                ui.add_space(8.0); // The size of the little cross icon
-            });
-            ui.add_space(margin.y);
-            title_height = ui.min_size().y;
+            if matches!(title_placement, TitlePlacement::Top | TitlePlacement::Body) {
+                title_rect = Some(self.title_row(ui, &text_style.title, user_state, responses));
+                if self.collapsed {
+                    return;
+                }
+                ui.add_space(margin.y);
+            }
 
             // First pass: Draw the inner fields. Compute port heights
             let input_layout = match self.orientation {
@@ -659,8 +2380,14 @@ where
                 NodeOrientation::RightToLeft => Layout::left_to_right(Align::default()),
             };
 
-            let inputs = self.graph[self.node_id].inputs.clone();
-            for (param_name, param_id) in inputs {
+            let node_style = ui.style().clone();
+            apply_label_style(ui, &text_style.parameters);
+
+            // Parameters are accessed by index rather than by iterating over
+            // the list, which would keep the graph borrowed while the value
+            // is taken out and put back.
+            for index in 0..self.graph[self.node_id].inputs.len() {
+                let param_id = self.graph[self.node_id].inputs[index].1;
                 if self.graph[param_id].shown_inline {
                     let height_before = ui.min_rect().bottom();
                     // NOTE: We want to pass the `user_data` to
@@ -671,11 +2398,12 @@ where
                     // dummy value. This requires `ValueType` to implement
                     // Default, but results in a totally safe alternative.
                     let mut value = std::mem::take(&mut self.graph[param_id].value);
+                    let param_name = &self.graph[self.node_id].inputs[index].0;
 
                     ui.with_layout(input_layout, |ui| {
                         if self.graph.connection(param_id).is_some() {
                             let node_responses = value.value_widget_connected(
-                                &param_name,
+                                param_name,
                                 self.node_id,
                                 ui,
                                 user_state,
@@ -685,7 +2413,7 @@ where
                             responses.extend(node_responses.into_iter().map(NodeResponse::User));
                         } else {
                             let node_responses = value.value_widget(
-                                &param_name,
+                                param_name,
                                 self.node_id,
                                 ui,
                                 user_state,
@@ -711,22 +2439,22 @@ where
                 }
             }
 
-            let outputs = self.graph[self.node_id].outputs.clone();
-            for (param_name, param_id) in outputs {
+            for (param_name, param_id) in &self.graph[self.node_id].outputs {
                 let height_before = ui.min_rect().bottom();
                 ui.with_layout(output_layout, |ui| {
-                    responses.extend(
-                        self.graph[self.node_id]
-                            .user_data
-                            .output_ui(ui, self.node_id, self.graph, user_state, &param_name)
-                            .into_iter(),
-                    );
+                    responses.extend(self.graph[self.node_id].user_data.output_ui(
+                        ui,
+                        self.node_id,
+                        self.graph,
+                        user_state,
+                        param_name,
+                    ));
                 });
 
                 self.graph[self.node_id].user_data.separator(
                     ui,
                     self.node_id,
-                    AnyParameterId::Output(param_id),
+                    AnyParameterId::Output(*param_id),
                     self.graph,
                     user_state,
                 );
@@ -734,6 +2462,7 @@ where
                 let height_after = ui.min_rect().bottom();
                 output_port_heights.push((height_before + height_after) / 2.0);
             }
+            ui.set_style(node_style);
 
             responses.extend(self.graph[self.node_id].user_data.bottom_ui(
                 ui,
@@ -741,6 +2470,11 @@ where
                 self.graph,
                 user_state,
             ));
+
+            if title_placement == TitlePlacement::Bottom {
+                ui.add_space(margin.y);
+                title_rect = Some(self.title_row(ui, &text_style.title, user_state, responses));
+            }
         });
 
         // Second pass, iterate again to draw the ports. This happens outside
@@ -749,12 +2483,20 @@ where
         let outer_rect = child_ui.min_rect().expand2(margin);
         let port_left = outer_rect.left();
         let port_right = outer_rect.right();
-
-        // Save expanded rect to memory.
-        ui.ctx().memory_mut(|mem| {
-            mem.data
-                .insert_temp(child_ui.id(), OuterRectMemory(outer_rect))
-        });
+        if self.collapsed {
+            // Spread the ports along the sides of the title bar
+            let spread = |count: usize| -> SVec<f32> {
+                (0..count)
+                    .map(|index| {
+                        outer_rect.top()
+                            + outer_rect.height() * (index + 1) as f32 / (count + 1) as f32
+                    })
+                    .collect()
+            };
+            let node = &self.graph[self.node_id];
+            input_port_heights = spread(node.inputs.len());
+            output_port_heights = spread(node.outputs.len());
+        }
 
         #[allow(clippy::too_many_arguments)]
         fn draw_port<NodeData, DataType, ValueType, UserResponse, UserState>(
@@ -768,17 +2510,18 @@ where
             port_locations: &mut PortLocations,
             ongoing_drag: Option<(NodeId, AnyParameterId)>,
             is_connected_input: bool,
+            data_type_colors: &mut DataTypeColors,
+            style: &GraphEditorStyle,
         ) where
             DataType: DataTypeTrait<UserState>,
             UserResponse: UserResponseTrait,
-            NodeData: NodeDataTrait,
+            NodeData:
+                NodeDataTrait<UserState = UserState, DataType = DataType, ValueType = ValueType>,
         {
             let port_type = graph.any_param_type(param_id).unwrap();
 
-            let port_rect = Rect::from_center_size(
-                port_pos,
-                egui::vec2(DISTANCE_TO_CONNECT * 2.0, DISTANCE_TO_CONNECT * 2.0),
-            );
+            let port_rect =
+                Rect::from_center_size(port_pos, Vec2::splat(style.port_hit_radius * 2.0));
 
             let sense = if ongoing_drag.is_some() {
                 Sense::hover()
@@ -786,28 +2529,95 @@ where
                 Sense::click_and_drag()
             };
 
-            let resp = ui.allocate_rect(port_rect, sense);
+            let param_name = || {
+                let node = &graph[node_id];
+                let name = match param_id {
+                    AnyParameterId::Input(id) => node
+                        .inputs
+                        .iter()
+                        .find(|(_, input)| *input == id)
+                        .map(|(name, _)| name),
+                    AnyParameterId::Output(id) => node
+                        .outputs
+                        .iter()
+                        .find(|(_, output)| *output == id)
+                        .map(|(name, _)| name),
+                };
+                name.map_or("", String::as_str)
+            };
+
+            let mut resp = ui.allocate_rect(port_rect, sense);
+            if ongoing_drag.is_none() {
+                resp = resp.on_hover_ui(|ui| {
+                    ui.strong(param_name());
+                    port_type.tooltip_ui(ui, param_id, user_state);
+                });
+            }
+            if let AnyParameterId::Output(output) = param_id {
+                resp.context_menu(|ui| {
+                    if ui.button("Probe value").clicked() {
+                        responses.push(NodeResponse::AddProbe(output));
+                        ui.close_menu();
+                    }
+                });
+            }
+            resp.widget_info(|| {
+                let kind = match param_id {
+                    AnyParameterId::Input(_) => "Input",
+                    AnyParameterId::Output(_) => "Output",
+                };
+                WidgetInfo::labeled(
+                    WidgetType::Button,
+                    true,
+                    format!("{kind} {} of {}", param_name(), graph[node_id].label),
+                )
+            });
 
             // Check if the distance between the port and the mouse is the distance to connect
             let close_enough = if let Some(pointer_pos) = ui.ctx().pointer_hover_pos() {
-                port_rect.center().distance(pointer_pos) < DISTANCE_TO_CONNECT
+                port_rect.center().distance(pointer_pos) < style.port_hit_radius
             } else {
                 false
             };
 
-            let port_color = if close_enough {
-                Color32::WHITE
-            } else {
-                port_type.data_type_color(user_state)
+            if close_enough && ongoing_drag.is_none() {
+                if let Some(cursor) = style.hover.port_cursor {
+                    ui.ctx().set_cursor_icon(cursor);
+                }
+            }
+
+            let data_type_color = data_type_colors.get(graph, param_id, user_state);
+            let port = PortDrawInfo {
+                param_id,
+                center: port_rect.center(),
+                radius: if close_enough {
+                    style.port_radius * style.hover.port_scale
+                } else {
+                    style.port_radius
+                },
+                color: if close_enough {
+                    style.port_hover
+                } else {
+                    data_type_color
+                },
+                data_type_color,
+                hovered: close_enough,
+                connected: match param_id {
+                    AnyParameterId::Input(_) => is_connected_input,
+                    AnyParameterId::Output(output) => !graph.output_targets(output).is_empty(),
+                },
             };
-            ui.painter()
-                .circle(port_rect.center(), 5.0, port_color, Stroke::NONE);
+            graph[node_id]
+                .user_data
+                .draw_port(ui.painter(), &port, node_id, graph, user_state);
 
             if resp.drag_started() {
                 if is_connected_input {
+                    // Multi-inputs give up their last connection
                     let input = param_id.assume_input();
-                    let corresp_output = graph
-                        .connection(input)
+                    let corresp_output = *graph
+                        .connections(input)
+                        .last()
                         .expect("Connection data should be valid");
                     responses.push(NodeResponse::DisconnectEvent {
                         input: param_id.assume_input(),
@@ -843,7 +2653,7 @@ where
         for ((_, param), port_height) in self.graph[self.node_id]
             .inputs
             .iter()
-            .zip(input_port_heights.into_iter())
+            .zip(input_port_heights)
         {
             let should_draw = match self.graph[*param].kind() {
                 InputParamKind::ConnectionOnly => true,
@@ -862,11 +2672,13 @@ where
                     self.node_id,
                     user_state,
                     port_pos,
-                    &mut responses,
+                    responses,
                     AnyParameterId::Input(*param),
                     self.port_locations,
                     self.ongoing_drag,
                     self.graph.connection(*param).is_some(),
+                    self.data_type_colors,
+                    style,
                 );
             }
         }
@@ -875,7 +2687,7 @@ where
         for ((_, param), port_height) in self.graph[self.node_id]
             .outputs
             .iter()
-            .zip(output_port_heights.into_iter())
+            .zip(output_port_heights)
         {
             let port_pos = match self.orientation {
                 NodeOrientation::LeftToRight => pos2(port_right, port_height),
@@ -887,89 +2699,120 @@ where
                 self.node_id,
                 user_state,
                 port_pos,
-                &mut responses,
+                responses,
                 AnyParameterId::Output(*param),
                 self.port_locations,
                 self.ongoing_drag,
                 false,
+                self.data_type_colors,
+                style,
             );
         }
 
         // Draw the background shape.
-        // NOTE: This code is a bit more involved than it needs to be because egui
-        // does not support drawing rectangles with asymmetrical round corners.
-
         let (shape, outline) = {
-            let rounding_radius = 4;
-            let corner_radius = CornerRadius::same(rounding_radius);
-
-            let titlebar_height = title_height + margin.y;
-            let titlebar_rect =
-                Rect::from_min_size(outer_rect.min, vec2(outer_rect.width(), titlebar_height));
-            let titlebar = Shape::Rect(RectShape {
-                blur_width: 0.0,
-                rect: titlebar_rect,
-                corner_radius,
-                fill: self.graph[self.node_id]
-                    .user_data
-                    .titlebar_color(ui, self.node_id, self.graph, user_state)
-                    .unwrap_or_else(|| background_color.lighten(0.8)),
-                stroke: Stroke::NONE,
-                stroke_kind: StrokeKind::Inside,
-                round_to_pixels: None,
-                brush: None,
-            });
-
-            let body_rect = Rect::from_min_size(
-                outer_rect.min + vec2(0.0, titlebar_height - rounding_radius as f32),
-                vec2(outer_rect.width(), outer_rect.height() - titlebar_height),
-            );
-            let body = Shape::Rect(RectShape {
-                blur_width: 0.0,
-                rect: body_rect,
-                corner_radius: CornerRadius::ZERO,
-                fill: background_color,
-                stroke: Stroke::NONE,
-                stroke_kind: StrokeKind::Inside,
-                round_to_pixels: None,
-                brush: None,
-            });
-
-            let bottom_body_rect = Rect::from_min_size(
-                body_rect.min + vec2(0.0, body_rect.height() - titlebar_height * 0.5),
-                vec2(outer_rect.width(), titlebar_height),
-            );
-            let bottom_body = Shape::Rect(RectShape {
-                blur_width: 0.0,
-                rect: bottom_body_rect,
-                corner_radius,
-                fill: background_color,
-                stroke: Stroke::NONE,
-                stroke_kind: StrokeKind::Inside,
-                round_to_pixels: None,
-                brush: None,
-            });
-
-            let node_rect = titlebar_rect.union(body_rect).union(bottom_body_rect);
-            let outline = if self.selected {
+            let rounding_radius = style.corner_radius;
+            let rounding = rounding_radius as f32;
+            let round_top = CornerRadius {
+                sw: 0,
+                se: 0,
+                ..CornerRadius::same(rounding_radius)
+            };
+            let round_bottom = CornerRadius {
+                nw: 0,
+                ne: 0,
+                ..CornerRadius::same(rounding_radius)
+            };
+            let rect_shape = |rect: Rect, corner_radius: CornerRadius, fill: Color32| {
                 Shape::Rect(RectShape {
                     blur_width: 0.0,
-                    rect: node_rect.expand(1.0),
+                    rect,
                     corner_radius,
-                    fill: Color32::WHITE.lighten(0.8),
+                    fill,
                     stroke: Stroke::NONE,
                     stroke_kind: StrokeKind::Inside,
                     round_to_pixels: None,
                     brush: None,
                 })
-            } else {
-                Shape::Noop
             };
+            let hovered = window_response.contains_pointer();
+            let mut titlebar_color = self.graph[self.node_id]
+                .user_data
+                .titlebar_color(ui, self.node_id, self.graph, user_state)
+                .unwrap_or(style.titlebar);
+            if disabled {
+                titlebar_color = style.disabled.titlebar_color(titlebar_color);
+            }
+            let titlebar_color = style.hover.node_color(titlebar_color, hovered);
+            let node_background = self.graph[self.node_id]
+                .user_data
+                .body_color(ui, self.node_id, self.graph, user_state)
+                .unwrap_or(style.node_background);
+            let node_background = style.hover.node_color(node_background, hovered);
+
+            let (node_rect, shapes) = match (title_placement, title_rect) {
+                (TitlePlacement::Top, Some(_)) if self.collapsed => (
+                    outer_rect,
+                    vec![rect_shape(
+                        outer_rect,
+                        CornerRadius::same(rounding_radius),
+                        titlebar_color,
+                    )],
+                ),
+                (TitlePlacement::Top, Some(title_rect)) => {
+                    // Nodes with a title bar on top leave half of its height
+                    // below their contents.
+                    let titlebar_height = title_rect.bottom() + margin.y - outer_rect.top();
+                    let mut node_rect = outer_rect;
+                    node_rect.max.y += (titlebar_height * 0.5 - rounding).max(0.0);
+                    let split = title_rect.bottom() + margin.y - rounding;
+                    let (titlebar, body) = node_rect.split_top_bottom_at_y(split);
+                    (
+                        node_rect,
+                        vec![
+                            rect_shape(titlebar, round_top, titlebar_color),
+                            rect_shape(body, round_bottom, node_background),
+                        ],
+                    )
+                }
+                (TitlePlacement::Bottom, Some(title_rect)) => {
+                    let split = title_rect.top() - margin.y + rounding;
+                    let (body, titlebar) = outer_rect.split_top_bottom_at_y(split);
+                    (
+                        outer_rect,
+                        vec![
+                            rect_shape(body, round_top, node_background),
+                            rect_shape(titlebar, round_bottom, titlebar_color),
+                        ],
+                    )
+                }
+                _ => (
+                    outer_rect,
+                    vec![rect_shape(
+                        outer_rect,
+                        CornerRadius::same(rounding_radius),
+                        node_background,
+                    )],
+                ),
+            };
+
+            let outline = self
+                .outline(&window_response)
+                .map_or(Shape::Noop, |outline| {
+                    outline.shape(node_rect, rounding_radius)
+                });
 
             // Take note of the node rect, so the editor can use it later to compute intersections.
             self.node_rects.insert(self.node_id, node_rect);
+            self.node_extents.insert(
+                self.node_id,
+                NodeExtents {
+                    outer_rect: outer_rect.translate(-origin) / self.transform.scaling,
+                    size: node_rect.size() / self.transform.scaling,
+                },
+            );
 
-            (Shape::Vec(vec![titlebar, body, bottom_body]), outline)
+            (Shape::Vec(shapes), outline)
         };
 
         ui.painter().set(background_shape, shape);
@@ -977,23 +2820,128 @@ where
 
         // --- Interaction ---
 
-        // Titlebar buttons
-        let can_delete = self.graph.nodes[self.node_id].user_data.can_delete(
-            self.node_id,
-            self.graph,
-            user_state,
-        );
+        // Titlebar buttons, at the end of the title row
+        if let Some(title_rect) = title_rect {
+            let bar_rect = Rect::from_x_y_ranges(
+                outer_rect.x_range(),
+                title_rect.top() - margin.y..=outer_rect.bottom(),
+            );
+            let can_delete = self.graph.nodes[self.node_id].user_data.can_delete(
+                self.node_id,
+                self.graph,
+                user_state,
+            );
+
+            if Self::flip_button(ui, bar_rect, style).clicked() {
+                *self.orientation = self.orientation.flip();
+            }
+
+            if Self::collapse_button(ui, bar_rect, self.collapsed, style).clicked() {
+                responses.push(NodeResponse::CollapseNode {
+                    node: self.node_id,
+                    collapsed: !self.collapsed,
+                });
+            }
+
+            if can_delete && Self::close_button(ui, bar_rect, style).clicked() {
+                responses.push(NodeResponse::DeleteNodeUi(self.node_id));
+            };
+        }
+
+        if let Some((constraints, size)) = resizing {
+            let node_rect = self.node_rects[&self.node_id];
+            let drag_delta = Self::resize_handle(ui, self.node_id, node_rect, style);
+            if drag_delta != Vec2::ZERO {
+                let new_size = constraints.clamp(size + drag_delta / self.transform.scaling);
+                if new_size != size {
+                    responses.push(NodeResponse::ResizeNode {
+                        node: self.node_id,
+                        size: new_size,
+                    });
+                }
+            }
+        }
+
+        self.window_interaction(&window_response, responses, first_response);
+    }
+
+    /// Shows the title of the node along with its top bar UI, leaving room
+    /// for the buttons. Returns the rect of the row.
+    fn title_row(
+        &mut self,
+        ui: &mut Ui,
+        title_style: &LabelStyle,
+        user_state: &mut UserState,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) -> Rect {
+        ui.horizontal(|ui| {
+            ui.add(
+                Label::new(
+                    RichText::new(&self.graph[self.node_id].label)
+                        .font(title_style.font_id(&TextStyle::Button, ui.style()))
+                        .color(title_style.color.unwrap_or(self.style.text)),
+                )
+                .selectable(false),
+            );
+            responses.extend(self.graph[self.node_id].user_data.top_bar_ui(
+                ui,
+                self.node_id,
+                self.graph,
+                user_state,
+            ));
+            ui.add_space(8.0); // The size of the little collapse icon
+            ui.add_space(4.0); // margin
+            ui.add_space(8.0); // The size of the little h-flip icon
+            ui.add_space(4.0); // margin
+            ui.add_space(8.0); // The size of the little cross icon
+        })
+        .response
+        .rect
+    }
 
-        if Self::flip_button(ui, outer_rect).clicked() {
-            *self.orientation = self.orientation.flip();
+    /// The outline to draw around this node, if any. Takes note of the node
+    /// holding the keyboard focus.
+    fn outline(&mut self, window_response: &Response) -> Option<&'a NodeOutline> {
+        if window_response.has_focus() {
+            *self.focused_node = Some(self.node_id);
+            Some(&self.style.focus_outline)
+        } else if self.selected {
+            Some(&self.style.selection_outline)
+        } else {
+            None
         }
+    }
+
+    /// Moves and selects the node when its window is dragged or clicked.
+    /// Responses of this node start at `first_response`.
+    fn window_interaction(
+        &self,
+        window_response: &Response,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+        first_response: usize,
+    ) {
+        window_response.widget_info(|| {
+            WidgetInfo::selected(
+                WidgetType::Window,
+                true,
+                self.selected,
+                &self.graph[self.node_id].label,
+            )
+        });
 
-        if can_delete && Self::close_button(ui, outer_rect).clicked() {
-            responses.push(NodeResponse::DeleteNodeUi(self.node_id));
+        let cursor = if window_response.dragged() {
+            self.style.hover.node_drag_cursor
+        } else if window_response.hovered() {
+            self.style.hover.node_cursor
+        } else {
+            None
         };
+        if let Some(cursor) = cursor {
+            window_response.ctx.set_cursor_icon(cursor);
+        }
 
         // Movement
-        let drag_delta = window_response.drag_delta();
+        let drag_delta = window_response.drag_delta() / self.transform.scaling;
         if drag_delta.length_sq() > 0.0 {
             responses.push(NodeResponse::MoveNode {
                 node: self.node_id,
@@ -1006,45 +2954,65 @@ where
         //
         // HACK: Only set the select response when no other response is active.
         // This prevents some issues.
-        if responses.is_empty() && window_response.clicked_by(PointerButton::Primary) {
+        if responses.len() == first_response && window_response.clicked_by(PointerButton::Primary) {
             responses.push(NodeResponse::SelectNode(self.node_id));
             responses.push(NodeResponse::RaiseNode(self.node_id));
         }
+    }
 
-        responses
+    /// Draws the handle resizing the node, and returns how far it was
+    /// dragged on the screen.
+    fn resize_handle(
+        ui: &mut Ui,
+        node_id: NodeId,
+        node_rect: Rect,
+        style: &GraphEditorStyle,
+    ) -> Vec2 {
+        let rect = Rect::from_min_max(
+            node_rect.max - Vec2::splat(NODE_RESIZE_HANDLE_SIZE),
+            node_rect.max,
+        );
+        let resp = ui.interact(rect, ui.id().with((node_id, "resize")), Sense::drag());
+        resp.widget_info(|| WidgetInfo::labeled(WidgetType::Other, true, "Resize node"));
+        if resp.hovered() || resp.dragged() {
+            ui.ctx().set_cursor_icon(CursorIcon::ResizeNwSe);
+        }
+        let color = if resp.hovered() || resp.dragged() {
+            style.button_hovered
+        } else {
+            style.button
+        };
+        let stroke = Stroke::new(1.0, color);
+        for inset in [0.0, NODE_RESIZE_HANDLE_SIZE / 2.0] {
+            ui.painter().line_segment(
+                [
+                    rect.right_top() + vec2(0.0, inset),
+                    rect.left_bottom() + vec2(inset, 0.0),
+                ],
+                stroke,
+            );
+        }
+        resp.drag_delta()
     }
 
-    fn close_button(ui: &mut Ui, node_rect: Rect) -> Response {
+    fn close_button(ui: &mut Ui, node_rect: Rect, style: &GraphEditorStyle) -> Response {
         // Measurements
         let margin = 8.0;
         let size = 10.0;
-        let stroke_width = 2.0;
+        let stroke_width = style.button_stroke;
         let offs = margin + size / 2.0;
 
         let position = pos2(node_rect.right() - offs, node_rect.top() + offs);
         let rect = Rect::from_center_size(position, vec2(size, size));
         let resp = ui.allocate_rect(rect, Sense::click());
+        resp.widget_info(|| WidgetInfo::labeled(WidgetType::Button, true, "Delete node"));
 
-        let dark_mode = ui.visuals().dark_mode;
         let color = if resp.clicked() {
-            if dark_mode {
-                color_from_hex("#ffffff").unwrap()
-            } else {
-                color_from_hex("#000000").unwrap()
-            }
+            style.button_active
         } else if resp.hovered() {
-            if dark_mode {
-                color_from_hex("#dddddd").unwrap()
-            } else {
-                color_from_hex("#222222").unwrap()
-            }
+            style.button_hovered
         } else {
-            #[allow(clippy::collapsible_else_if)]
-            if dark_mode {
-                color_from_hex("#aaaaaa").unwrap()
-            } else {
-                color_from_hex("#555555").unwrap()
-            }
+            style.button
         };
         let stroke = Stroke {
             width: stroke_width,
@@ -1059,37 +3027,80 @@ where
         resp
     }
 
-    fn flip_button(ui: &mut Ui, node_rect: Rect) -> Response {
+    fn collapse_button(
+        ui: &mut Ui,
+        node_rect: Rect,
+        collapsed: bool,
+        style: &GraphEditorStyle,
+    ) -> Response {
+        // Measurements
+        let margin = 8.0;
+        let size = 10.0;
+        let stroke_width = style.button_stroke;
+        let offs = margin + size / 2.0;
+
+        let position = pos2(node_rect.right() - offs * 3.0 - 8.0, node_rect.top() + offs);
+        let rect = Rect::from_center_size(position, vec2(size, size));
+        let resp = ui.allocate_rect(rect, Sense::click());
+        resp.widget_info(|| {
+            let label = if collapsed {
+                "Expand node"
+            } else {
+                "Collapse node"
+            };
+            WidgetInfo::labeled(WidgetType::Button, true, label)
+        });
+
+        let color = if resp.clicked() {
+            style.button_active
+        } else if resp.hovered() {
+            style.button_hovered
+        } else {
+            style.button
+        };
+        let stroke = Stroke {
+            width: stroke_width,
+            color,
+        };
+
+        // A chevron pointing right when collapsed, down when expanded
+        let (start, tip, end) = if collapsed {
+            (
+                rect.center_top().lerp(rect.left_top(), 0.5),
+                rect.right_center(),
+                rect.center_bottom().lerp(rect.left_bottom(), 0.5),
+            )
+        } else {
+            (
+                rect.left_center().lerp(rect.left_top(), 0.5),
+                rect.center_bottom(),
+                rect.right_center().lerp(rect.right_top(), 0.5),
+            )
+        };
+        ui.painter().line_segment([start, tip], stroke);
+        ui.painter().line_segment([tip, end], stroke);
+
+        resp
+    }
+
+    fn flip_button(ui: &mut Ui, node_rect: Rect, style: &GraphEditorStyle) -> Response {
         // Measurements
         let margin = 8.0;
         let size = 10.0;
-        let stroke_width = 2.0;
+        let stroke_width = style.button_stroke;
         let offs = margin + size / 2.0;
 
         let position = pos2(node_rect.right() - offs * 2.0 - 4.0, node_rect.top() + offs);
         let rect = Rect::from_center_size(position, vec2(size, size));
         let resp = ui.allocate_rect(rect, Sense::click());
+        resp.widget_info(|| WidgetInfo::labeled(WidgetType::Button, true, "Flip node"));
 
-        let dark_mode = ui.visuals().dark_mode;
         let color = if resp.clicked() {
-            if dark_mode {
-                color_from_hex("#ffffff").unwrap()
-            } else {
-                color_from_hex("#000000").unwrap()
-            }
+            style.button_active
         } else if resp.hovered() {
-            if dark_mode {
-                color_from_hex("#dddddd").unwrap()
-            } else {
-                color_from_hex("#222222").unwrap()
-            }
+            style.button_hovered
         } else {
-            #[allow(clippy::collapsible_else_if)]
-            if dark_mode {
-                color_from_hex("#aaaaaa").unwrap()
-            } else {
-                color_from_hex("#555555").unwrap()
-            }
+            style.button
         };
         let stroke = Stroke {
             width: stroke_width,
@@ -1123,3 +3134,40 @@ where
         resp
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dash_path() {
+        let points = [pos2(0.0, 0.0), pos2(10.0, 0.0), pos2(10.0, 10.0)];
+        let dashes = dash_path(&points, 4.0, 2.0);
+        assert_eq!(
+            dashes,
+            vec![
+                vec![pos2(0.0, 0.0), pos2(4.0, 0.0)],
+                vec![pos2(6.0, 0.0), pos2(10.0, 0.0)],
+                // Around the corner
+                vec![pos2(10.0, 2.0), pos2(10.0, 6.0)],
+                vec![pos2(10.0, 8.0), pos2(10.0, 10.0)],
+            ]
+        );
+    }
+    #[test]
+    fn test_node_size_constraints() {
+        let constraints = NodeSizeConstraints {
+            default: vec2(200.0, 100.0),
+            min: vec2(100.0, 50.0),
+            max: vec2(400.0, f32::INFINITY),
+        };
+        assert_eq!(constraints.clamp(vec2(50.0, 80.0)), vec2(100.0, 80.0));
+        assert_eq!(constraints.clamp(vec2(500.0, 900.0)), vec2(400.0, 900.0));
+        // The minimum wins over a smaller maximum
+        let fixed = NodeSizeConstraints {
+            max: Vec2::ZERO,
+            ..constraints
+        };
+        assert_eq!(fixed.clamp(vec2(300.0, 300.0)), vec2(100.0, 50.0));
+    }
+}
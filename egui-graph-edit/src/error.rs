@@ -7,4 +7,46 @@ pub enum EguiGraphError {
 
     #[error("Parameter {0:?} was not found in the graph.")]
     InvalidParameterId(AnyParameterId),
+
+    #[error("Node {0:?} was not found in the graph.")]
+    InvalidNodeId(NodeId),
+
+    #[error("Output {output:?} can't be connected to input {input:?}.")]
+    InvalidConnection { output: OutputId, input: InputId },
+
+    #[error("Stable id {0} is already in use.")]
+    DuplicateStableId(String),
+
+    #[error("Output {0:?} has no value, its node didn't set it.")]
+    MissingOutputValue(OutputId),
+
+    #[error("Input {1} of node {0:?} has a value of the wrong type.")]
+    WrongValueType(NodeId, String),
+
+    #[error(transparent)]
+    Cycle(#[from] CycleError),
+
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "json")]
+    #[error(
+        "The document has format version {found}, this crate reads up to version {supported}."
+    )]
+    UnsupportedFormatVersion { found: u32, supported: u32 },
+
+    #[cfg(feature = "json")]
+    #[error("Invalid document: {0}")]
+    InvalidDocument(String),
+}
+
+/// The graph has a cycle, so its nodes can't be ordered along the
+/// connections.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("The graph has a cycle through {} nodes: {nodes:?}", nodes.len())]
+pub struct CycleError {
+    /// The nodes of the cycle, each one feeding the next, and the last one
+    /// feeding the first.
+    pub nodes: Vec<NodeId>,
 }
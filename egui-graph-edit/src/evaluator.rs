@@ -0,0 +1,288 @@
+use super::*;
+
+/// Evaluates the nodes of a graph in dependency order, keeping the values of
+/// their outputs so each node is only computed once. The nodes are computed
+/// by a closure, which reads the inputs of the node and sets its outputs
+/// through a [`NodeContext`]:
+///
+/// ```
+/// # use egui_graph_edit::*;
+/// # let mut graph = Graph::<(), (), f32>::new();
+/// # let node = graph.add_node("double".into(), (), |graph, node| {
+/// #     graph.add_input_param(node, "x".into(), (), 2.0, InputParamKind::ConnectionOrConstant, true);
+/// #     graph.add_output_param(node, "out".into(), ());
+/// # });
+/// let mut evaluator = Evaluator::<f32>::new();
+/// evaluator.evaluate(&graph, node, |ctx| -> Result<(), EguiGraphError> {
+///     let x = ctx.input("x")?;
+///     ctx.set_output("out", x * 2.0)
+/// })?;
+/// let out = graph[node].get_output("out")?;
+/// assert_eq!(evaluator.output(out), Some(&4.0));
+/// # Ok::<(), EguiGraphError>(())
+/// ```
+///
+/// Unconnected inputs take their constant, converted with `From`. Values
+/// are usually the `ValueType` of the graph itself.
+#[derive(Clone, Debug)]
+pub struct Evaluator<Value> {
+    outputs: SecondaryMap<OutputId, Value>,
+    evaluated: SecondaryMap<NodeId, ()>,
+}
+
+impl<Value> Default for Evaluator<Value> {
+    fn default() -> Self {
+        Self {
+            outputs: SecondaryMap::new(),
+            evaluated: SecondaryMap::new(),
+        }
+    }
+}
+
+/// The node being computed by an [`Evaluator`], with the values of the nodes
+/// it depends on.
+pub struct NodeContext<'a, NodeData, DataType, ValueType, Value> {
+    pub graph: &'a Graph<NodeData, DataType, ValueType>,
+    pub node_id: NodeId,
+    outputs: &'a mut SecondaryMap<OutputId, Value>,
+}
+
+impl<'a, NodeData, DataType, ValueType, Value> NodeContext<'a, NodeData, DataType, ValueType, Value>
+where
+    ValueType: Clone,
+    Value: From<ValueType> + Clone,
+{
+    pub fn node(&self) -> &'a Node<NodeData> {
+        &self.graph[self.node_id]
+    }
+
+    pub fn user_data(&self) -> &'a NodeData {
+        &self.node().user_data
+    }
+
+    /// The value of the input named `name`: the value of the output
+    /// connected to it, or its constant otherwise.
+    pub fn input(&self, name: &str) -> Result<Value, EguiGraphError> {
+        let input = self.node().get_input(name)?;
+        self.input_value(input)
+    }
+
+    /// Like [`Self::input`], by id. Multi-inputs give the value of their
+    /// first connection.
+    pub fn input_value(&self, input: InputId) -> Result<Value, EguiGraphError> {
+        match self.graph.connection(input) {
+            Some(output) => self.output_value(output),
+            None => self.constant(input),
+        }
+    }
+
+    /// The values of all outputs connected to the input named `name`, in
+    /// the order the connections were made, e.g. for multi-inputs. Gives its
+    /// constant when nothing is connected.
+    pub fn inputs(&self, name: &str) -> Result<Vec<Value>, EguiGraphError> {
+        let input = self.node().get_input(name)?;
+        match self.graph.connections(input) {
+            [] => Ok(vec![self.constant(input)?]),
+            outputs => outputs
+                .iter()
+                .map(|output| self.output_value(*output))
+                .collect(),
+        }
+    }
+
+    fn output_value(&self, output: OutputId) -> Result<Value, EguiGraphError> {
+        self.outputs
+            .get(output)
+            .cloned()
+            .ok_or(EguiGraphError::MissingOutputValue(output))
+    }
+
+    fn constant(&self, input: InputId) -> Result<Value, EguiGraphError> {
+        self.graph
+            .inputs
+            .get(input)
+            .map(|param| param.value.clone().into())
+            .ok_or(EguiGraphError::InvalidParameterId(input.into()))
+    }
+
+    /// Sets the value of the output named `name`, for the nodes connected
+    /// to it.
+    pub fn set_output(&mut self, name: &str, value: Value) -> Result<(), EguiGraphError> {
+        let output = self.node().get_output(name)?;
+        self.outputs.insert(output, value);
+        Ok(())
+    }
+}
+
+impl<Value> Evaluator<Value> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value of `output`, once its node was evaluated.
+    pub fn output(&self, output: OutputId) -> Option<&Value> {
+        self.outputs.get(output)
+    }
+
+    pub fn is_evaluated(&self, node_id: NodeId) -> bool {
+        self.evaluated.contains_key(node_id)
+    }
+
+    /// Forgets the outputs of `node_id` and of the nodes depending on it,
+    /// e.g. after one of its inputs changed. They are computed again by the
+    /// next evaluation needing them.
+    pub fn invalidate<NodeData, DataType, ValueType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+    ) {
+        let mut pending = vec![node_id];
+        while let Some(node_id) = pending.pop() {
+            if self.evaluated.remove(node_id).is_none() {
+                continue;
+            }
+            if let Some(node) = graph.nodes.get(node_id) {
+                for output in node.output_ids() {
+                    self.outputs.remove(output);
+                }
+            }
+            pending.extend(graph.downstream_nodes(node_id));
+        }
+    }
+
+    /// Forgets all outputs.
+    pub fn clear(&mut self) {
+        self.outputs.clear();
+        self.evaluated.clear();
+    }
+
+    /// Evaluates `node_id`, after the nodes it depends on. Nodes evaluated
+    /// before, and not invalidated since, aren't computed again. Fails with
+    /// [`EguiGraphError::Cycle`] when the node depends on itself, or with
+    /// the first error returned by `compute`.
+    pub fn evaluate<NodeData, DataType, ValueType, E>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+        mut compute: impl FnMut(
+            &mut NodeContext<'_, NodeData, DataType, ValueType, Value>,
+        ) -> Result<(), E>,
+    ) -> Result<(), E>
+    where
+        E: From<EguiGraphError>,
+    {
+        for node_id in self.pending_nodes(graph, node_id)? {
+            let mut ctx = NodeContext {
+                graph,
+                node_id,
+                outputs: &mut self.outputs,
+            };
+            compute(&mut ctx)?;
+            self.evaluated.insert(node_id, ());
+        }
+        Ok(())
+    }
+
+    /// Evaluates all nodes of the graph, see [`Self::evaluate`].
+    pub fn evaluate_all<NodeData, DataType, ValueType, E>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        mut compute: impl FnMut(
+            &mut NodeContext<'_, NodeData, DataType, ValueType, Value>,
+        ) -> Result<(), E>,
+    ) -> Result<(), E>
+    where
+        E: From<EguiGraphError>,
+    {
+        for node_id in graph.nodes.keys() {
+            self.evaluate(graph, node_id, &mut compute)?;
+        }
+        Ok(())
+    }
+
+    /// `node_id` and the nodes it depends on that weren't evaluated yet, in
+    /// dependency order.
+    fn pending_nodes<NodeData, DataType, ValueType>(
+        &self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+    ) -> Result<Vec<NodeId>, EguiGraphError> {
+        if !graph.nodes.contains_key(node_id) {
+            return Err(EguiGraphError::InvalidNodeId(node_id));
+        }
+        Ok(graph.dependency_order_skipping(node_id, |node_id| self.is_evaluated(node_id))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adds a node computing the sum of its inputs `a` and `b`.
+    fn add_sum(graph: &mut Graph<(), (), f32>, a: f32, b: f32) -> NodeId {
+        graph.add_node("sum".into(), (), |graph, node| {
+            for (name, value) in [("a", a), ("b", b)] {
+                graph.add_input_param(
+                    node,
+                    name.into(),
+                    (),
+                    value,
+                    InputParamKind::ConnectionOrConstant,
+                    true,
+                );
+            }
+            graph.add_output_param(node, "out".into(), ());
+        })
+    }
+
+    fn sum(ctx: &mut NodeContext<(), (), f32, f32>) -> Result<(), EguiGraphError> {
+        let value = ctx.input("a")? + ctx.input("b")?;
+        ctx.set_output("out", value)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut graph = Graph::<(), (), f32>::new();
+        let first = add_sum(&mut graph, 1.0, 2.0);
+        let second = add_sum(&mut graph, 0.0, 10.0);
+        let first_out = graph[first].get_output("out").unwrap();
+        let second_out = graph[second].get_output("out").unwrap();
+        graph.add_connection(first_out, graph[second].get_input("a").unwrap());
+
+        let mut evaluator = Evaluator::new();
+        let mut computed = Vec::new();
+        let mut compute = |ctx: &mut NodeContext<(), (), f32, f32>| {
+            computed.push(ctx.node_id);
+            sum(ctx)
+        };
+        evaluator.evaluate(&graph, second, &mut compute).unwrap();
+        assert_eq!(evaluator.output(first_out), Some(&3.0));
+        assert_eq!(evaluator.output(second_out), Some(&13.0));
+
+        // Cached outputs are reused, until invalidated
+        evaluator.evaluate_all(&graph, &mut compute).unwrap();
+        evaluator.invalidate(&graph, second);
+        assert!(evaluator.is_evaluated(first));
+        assert_eq!(evaluator.output(second_out), None);
+        evaluator.evaluate(&graph, second, &mut compute).unwrap();
+        assert_eq!(computed, vec![first, second, second]);
+    }
+
+    #[test]
+    fn test_evaluate_cycle() {
+        let mut graph = Graph::<(), (), f32>::new();
+        let first = add_sum(&mut graph, 1.0, 2.0);
+        let second = add_sum(&mut graph, 0.0, 10.0);
+        let first_out = graph[first].get_output("out").unwrap();
+        let second_out = graph[second].get_output("out").unwrap();
+        graph.add_connection(first_out, graph[second].get_input("a").unwrap());
+        graph.add_connection(second_out, graph[first].get_input("b").unwrap());
+
+        let mut evaluator = Evaluator::new();
+        let result = evaluator.evaluate(&graph, second, sum);
+        assert!(matches!(
+            result,
+            Err(EguiGraphError::Cycle(CycleError { nodes })) if nodes.len() == 2
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use super::*;
+use egui::*;
+
+/// Something dropped onto the editor from outside of it.
+#[derive(Clone, Debug)]
+pub enum ExternalDrop<Payload> {
+    /// A file dropped from the operating system. Depending on the platform,
+    /// either the path or the bytes of the file are set.
+    File(DroppedFile),
+    /// An application-defined payload, started with egui's drag and drop
+    /// support (e.g. [`Response::dnd_set_drag_payload`]) from another widget.
+    Payload(Arc<Payload>),
+}
+
+/// Offset between the nodes created by a single drop, so they don't stack on
+/// top of each other.
+const DROP_CASCADE_OFFSET: Vec2 = vec2(30.0, 30.0);
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+        Response = UserResponse,
+        UserState = UserState,
+        DataType = DataType,
+        ValueType = ValueType,
+    >,
+    UserResponse: UserResponseTrait,
+    ValueType:
+        WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
+    CategoryType: CategoryTrait,
+{
+    /// Lets users create nodes by dropping files or drag and drop payloads of
+    /// type `Payload` onto the editor. Must be called every frame, right after
+    /// [`GraphEditorState::draw_graph_editor`], with the response it returned.
+    ///
+    /// For every drop, `on_drop` receives what was dropped and where, in graph
+    /// coordinates, and returns the templates of the nodes to create. Since
+    /// templates are user types, they can carry data from the drop (such as
+    /// the path of a file) into `NodeTemplateTrait::user_data`. Nodes are
+    /// created at the drop position, and a [`NodeResponse::CreatedNode`] is
+    /// added to `graph_response` for each of them.
+    pub fn handle_external_drops<Payload: Any + Send + Sync>(
+        &mut self,
+        ui: &Ui,
+        graph_response: &mut GraphResponse<UserResponse, NodeData>,
+        user_state: &mut UserState,
+        mut on_drop: impl FnMut(ExternalDrop<Payload>, Pos2, &mut UserState) -> Vec<NodeTemplate>,
+    ) {
+        if !graph_response.cursor_in_editor || graph_response.cursor_in_finder {
+            return;
+        }
+        let Some(drop_pos) = graph_response.cursor_graph_pos else {
+            return;
+        };
+
+        let mut drops = ui
+            .ctx()
+            .input(|i| i.raw.dropped_files.clone())
+            .into_iter()
+            .map(ExternalDrop::File)
+            .collect::<Vec<_>>();
+
+        if DragAndDrop::has_payload_of_type::<Payload>(ui.ctx()) {
+            if ui.ctx().input(|i| i.pointer.any_released()) {
+                if let Some(payload) = DragAndDrop::take_payload::<Payload>(ui.ctx()) {
+                    drops.push(ExternalDrop::Payload(payload));
+                }
+            } else {
+                ui.ctx().set_cursor_icon(CursorIcon::Copy);
+            }
+        }
+
+        let mut position = drop_pos;
+        for dropped in drops {
+            for template in on_drop(dropped, drop_pos, user_state) {
+                let node_id = self.add_node_from_template(&template, position, user_state);
+                graph_response
+                    .node_responses
+                    .push(NodeResponse::CreatedNode(node_id));
+                position += DROP_CASCADE_OFFSET;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "synthetic"))]
+mod tests {
+    use super::*;
+    use crate::synthetic::{SyntheticEditorState, SyntheticGraphOptions};
+
+    #[test]
+    fn test_handle_external_drops() {
+        let mut state = SyntheticGraphOptions {
+            nodes: 0,
+            ..Default::default()
+        }
+        .build_editor_state();
+        state.pan_zoom.pan = vec2(100.0, 50.0);
+        state.pan_zoom.zoom = 2.0;
+        // Keeps the nodes where the drop puts them
+        state.avoid_overlaps = false;
+        let template = SyntheticGraphOptions::default().template();
+
+        let ctx = Context::default();
+        let drop_frame = |state: &mut SyntheticEditorState, dropped_files| {
+            let input = RawInput {
+                screen_rect: Some(Rect::from_min_size(Pos2::ZERO, vec2(1000.0, 800.0))),
+                events: vec![Event::PointerMoved(pos2(500.0, 400.0))],
+                dropped_files,
+                ..Default::default()
+            };
+            let mut drops = Vec::new();
+            let mut result = None;
+            let _ = ctx.run(input, |ctx| {
+                CentralPanel::default().show(ctx, |ui| {
+                    let editor_rect = ui.max_rect();
+                    let mut response = state.draw_graph_editor(ui, template, &mut (), Vec::new());
+                    state.handle_external_drops::<()>(
+                        ui,
+                        &mut response,
+                        &mut (),
+                        |dropped, pos, _| {
+                            let ExternalDrop::File(file) = dropped else {
+                                panic!("only files are dropped");
+                            };
+                            drops.push((file.name.clone(), pos));
+                            // One node per letter of the file name
+                            vec![template; file.name.len()]
+                        },
+                    );
+                    let transform = state.pan_zoom.transform(editor_rect);
+                    result = Some((response, transform.inverse() * pos2(500.0, 400.0)));
+                });
+            });
+            let (response, drop_pos) = result.unwrap();
+            (response, drops, drop_pos)
+        };
+
+        let (response, drops, _) = drop_frame(&mut state, Vec::new());
+        assert!(drops.is_empty());
+        assert!(response.node_responses.is_empty());
+
+        let file = |name: &str| DroppedFile {
+            name: name.into(),
+            ..Default::default()
+        };
+        let (response, drops, drop_pos) = drop_frame(&mut state, vec![file("a"), file("bc")]);
+        assert_eq!(drops, vec![("a".into(), drop_pos), ("bc".into(), drop_pos)]);
+        let created: Vec<NodeId> = response
+            .node_responses
+            .iter()
+            .map(|response| match response {
+                NodeResponse::CreatedNode(node_id) => *node_id,
+                _ => panic!("unexpected response {response:?}"),
+            })
+            .collect();
+        assert_eq!(created.len(), 3);
+        assert_eq!(state.graph.nodes.len(), 3);
+        // The nodes are cascaded from the drop position
+        for (i, node_id) in created.into_iter().enumerate() {
+            assert_eq!(
+                state.node_positions[node_id],
+                drop_pos + DROP_CASCADE_OFFSET * i as f32
+            );
+        }
+    }
+}
@@ -0,0 +1,600 @@
+use super::*;
+
+#[cfg(all(feature = "ui", feature = "persistence"))]
+use serde::{Deserialize, Serialize};
+
+/// Relates the ids of a graph that was copied or imported to the ids of the
+/// elements that were created for them. Maps are indexed by the old ids.
+#[derive(Debug, Clone, Default)]
+pub struct IdMapping {
+    pub nodes: SecondaryMap<NodeId, NodeId>,
+    pub inputs: SecondaryMap<InputId, InputId>,
+    pub outputs: SecondaryMap<OutputId, OutputId>,
+}
+
+impl IdMapping {
+    /// The new id of an input or output parameter.
+    pub fn param(&self, param: AnyParameterId) -> Option<AnyParameterId> {
+        match param {
+            AnyParameterId::Input(input) => self.inputs.get(input).map(|id| (*id).into()),
+            AnyParameterId::Output(output) => self.outputs.get(output).map(|id| (*id).into()),
+        }
+    }
+}
+
+/// A self-contained piece of a graph along with its layout. Fragments can be
+/// imported into an editor with [`GraphEditorState::import_fragment`] and,
+/// with the `persistence` feature, stored on their own. This makes them
+/// suitable for asset libraries or pasting graphs from files.
+#[cfg(feature = "ui")]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct GraphFragment<NodeData, DataType, ValueType> {
+    pub graph: Graph<NodeData, DataType, ValueType>,
+    /// Node positions, relative to the point the fragment is imported at.
+    pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
+    pub node_orientations: SecondaryMap<NodeId, NodeOrientation>,
+    /// Where the top-left node was in the graph the fragment was copied
+    /// from, if any. [`GraphEditorState::paste_fragment`] places copies next
+    /// to it.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub source_position: Option<egui::Pos2>,
+}
+
+/// Lets the editor copy and paste nodes without the bounds the clipboard
+/// needs, see [`GraphEditorState::enable_clipboard_shortcuts`].
+#[cfg(feature = "ui")]
+pub(crate) struct ClipboardHooks<State> {
+    /// Copies the selected nodes, returning false when nothing was copied.
+    pub copy: fn(&mut State, &egui::Context) -> bool,
+    /// Pastes at the given position, with the text of the paste event, or
+    /// `None` for a press of the paste shortcut. Returns the pasted nodes.
+    pub paste: fn(&mut State, egui::Pos2, Option<&str>) -> Vec<NodeId>,
+    /// Returns the copies of the selected nodes.
+    pub duplicate: fn(&mut State) -> Vec<NodeId>,
+}
+
+#[cfg(feature = "ui")]
+impl<State> Clone for ClipboardHooks<State> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<State> Copy for ClipboardHooks<State> {}
+
+#[cfg(feature = "ui")]
+pub(crate) type EditorClipboardHooks<NodeData, DataType, ValueType, NodeTemplate, UserState> =
+    ClipboardHooks<GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>>;
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    DataType: PartialEq,
+{
+    /// Moves all nodes, parameters and connections of `other` into this graph.
+    /// Every element gets a fresh id; the returned [`IdMapping`] relates the
+    /// ids in `other` to the new ones.
+    pub fn import_graph(&mut self, other: Graph<NodeData, DataType, ValueType>) -> IdMapping {
+        let Graph {
+            nodes,
+            mut inputs,
+            mut outputs,
+            connections,
+            // The imported elements are new, so they don't inherit any
+            // stable ids that would clash with the originals.
+            stable_ids: _,
+            output_targets: _,
+        } = other;
+        let mut mapping = IdMapping::default();
+
+        for (old_node_id, node) in nodes {
+            let new_node_id = self.nodes.insert_with_key(|node_id| Node {
+                id: node_id,
+                label: node.label,
+                inputs: Vec::with_capacity(node.inputs.len()),
+                outputs: Vec::with_capacity(node.outputs.len()),
+                user_data: node.user_data,
+            });
+            mapping.nodes.insert(old_node_id, new_node_id);
+
+            for (name, old_input_id) in node.inputs {
+                let Some(param) = inputs.remove(old_input_id) else {
+                    continue;
+                };
+                let new_input_id = self.inputs.insert_with_key(|input_id| InputParam {
+                    id: input_id,
+                    node: new_node_id,
+                    ..param
+                });
+                self.nodes[new_node_id].inputs.push((name, new_input_id));
+                mapping.inputs.insert(old_input_id, new_input_id);
+            }
+
+            for (name, old_output_id) in node.outputs {
+                let Some(param) = outputs.remove(old_output_id) else {
+                    continue;
+                };
+                let new_output_id = self.outputs.insert_with_key(|output_id| OutputParam {
+                    id: output_id,
+                    node: new_node_id,
+                    ..param
+                });
+                self.nodes[new_node_id].outputs.push((name, new_output_id));
+                mapping.outputs.insert(old_output_id, new_output_id);
+            }
+        }
+
+        for (input, outputs) in connections {
+            for output in outputs {
+                if let (Some(input), Some(output)) =
+                    (mapping.inputs.get(input), mapping.outputs.get(output))
+                {
+                    self.add_connection(*output, *input);
+                }
+            }
+        }
+
+        mapping
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    NodeData: Clone,
+    DataType: PartialEq + Clone,
+    ValueType: Clone,
+{
+    /// Copies the given nodes, their parameters and the connections between
+    /// them into a new graph, e.g. to export them or to turn them into a
+    /// subgraph. Connections to other nodes are left out. The returned
+    /// [`IdMapping`] relates the ids in this graph to the ones in the copy.
+    pub fn extract_subgraph(&self, nodes: &[NodeId]) -> (Self, IdMapping) {
+        let mut graph = Graph::new();
+        let mut mapping = IdMapping::default();
+
+        for node_id in nodes {
+            let Some(node) = self.nodes.get(*node_id) else {
+                continue;
+            };
+            if mapping.nodes.contains_key(*node_id) {
+                continue;
+            }
+            let new_id = graph.add_node(
+                node.label.clone(),
+                node.user_data.clone(),
+                |graph, new_id| {
+                    for (name, input_id) in &node.inputs {
+                        let input = &self[*input_id];
+                        let new_input = graph.add_input_param(
+                            new_id,
+                            name.clone(),
+                            input.typ.clone(),
+                            input.value.clone(),
+                            input.kind,
+                            input.shown_inline,
+                        );
+                        graph.inputs[new_input].multi_input = input.multi_input;
+                        mapping.inputs.insert(*input_id, new_input);
+                    }
+                    for (name, output_id) in &node.outputs {
+                        let output = &self[*output_id];
+                        let new_output =
+                            graph.add_output_param(new_id, name.clone(), output.typ.clone());
+                        mapping.outputs.insert(*output_id, new_output);
+                    }
+                },
+            );
+            mapping.nodes.insert(*node_id, new_id);
+        }
+
+        for (input, output) in self.iter_connections() {
+            if let (Some(input), Some(output)) =
+                (mapping.inputs.get(input), mapping.outputs.get(output))
+            {
+                graph.add_connection(*output, *input);
+            }
+        }
+
+        (graph, mapping)
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: PartialEq + Clone,
+    ValueType: Clone,
+{
+    /// Copies the given nodes, along with the connections between them, into
+    /// a [`GraphFragment`]. Positions are made relative to the top-left most
+    /// node, so that importing the fragment at an offset places it there.
+    pub fn copy_fragment(&self, nodes: &[NodeId]) -> GraphFragment<NodeData, DataType, ValueType> {
+        let origin = nodes
+            .iter()
+            .filter_map(|node_id| self.node_positions.get(*node_id))
+            .fold(None, |min: Option<egui::Pos2>, pos| {
+                Some(min.map_or(*pos, |min| min.min(*pos)))
+            })
+            .unwrap_or(egui::Pos2::ZERO);
+
+        let (graph, mapping) = self.graph.extract_subgraph(nodes);
+        let mut node_positions = SecondaryMap::new();
+        let mut node_orientations = SecondaryMap::new();
+        for (node_id, new_id) in &mapping.nodes {
+            let pos = self.node_positions.get(node_id).copied().unwrap_or(origin);
+            node_positions.insert(*new_id, (pos - origin).to_pos2());
+            if let Some(orientation) = self.node_orientations.get(node_id) {
+                node_orientations.insert(*new_id, *orientation);
+            }
+        }
+
+        GraphFragment {
+            graph,
+            node_positions,
+            node_orientations,
+            source_position: Some(origin),
+        }
+    }
+
+    /// Copies the selected nodes and the connections between them into
+    /// [`GraphEditorState::clipboard`], see [`GraphEditorState::paste_at`].
+    /// Returns false, leaving the clipboard untouched, when nothing is
+    /// selected.
+    pub fn copy_selection(&mut self) -> bool {
+        if self.selected_nodes.is_empty() {
+            return false;
+        }
+        self.clipboard = Some(self.copy_fragment(&self.selected_nodes));
+        true
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<Response = UserResponse>,
+    UserResponse: UserResponseTrait,
+    DataType: PartialEq,
+{
+    /// Imports a [`GraphFragment`] into the edited graph. Node positions are
+    /// shifted by `offset`, and the new nodes are placed on top of the
+    /// existing ones.
+    ///
+    /// Returns the id mapping, along with a [`NodeResponse::CreatedNode`] for
+    /// every new node. Pass these responses to the next call to
+    /// [`GraphEditorState::draw_graph_editor`] so they reach user code along
+    /// with the rest of the editor events.
+    pub fn import_fragment(
+        &mut self,
+        fragment: GraphFragment<NodeData, DataType, ValueType>,
+        offset: egui::Vec2,
+    ) -> (IdMapping, Vec<NodeResponse<UserResponse, NodeData>>) {
+        let GraphFragment {
+            graph,
+            node_positions,
+            node_orientations,
+            ..
+        } = fragment;
+        let mapping = self.graph.import_graph(graph);
+
+        let mut responses = Vec::with_capacity(mapping.nodes.len());
+        for (old_id, new_id) in &mapping.nodes {
+            let pos = node_positions
+                .get(old_id)
+                .copied()
+                .unwrap_or(egui::Pos2::ZERO);
+            let orientation = node_orientations
+                .get(old_id)
+                .copied()
+                .unwrap_or(NodeOrientation::LeftToRight);
+            self.node_positions.insert(*new_id, pos + offset);
+            self.node_orientations.insert(*new_id, orientation);
+            self.node_order.push(*new_id);
+            responses.push(NodeResponse::CreatedNode(*new_id));
+        }
+
+        (mapping, responses)
+    }
+
+    /// Imports a [`GraphFragment`] next to where it was copied from, or at
+    /// `position` if it has no [`GraphFragment::source_position`], and
+    /// selects the new nodes so they can be dragged right away.
+    ///
+    /// Copies land [`GraphEditorState::paste_offset`] away from the
+    /// originals. Pasting again at the same spot moves each copy one more
+    /// offset away, so that they don't pile up.
+    pub fn paste_fragment(
+        &mut self,
+        fragment: GraphFragment<NodeData, DataType, ValueType>,
+        position: egui::Pos2,
+    ) -> (IdMapping, Vec<NodeResponse<UserResponse, NodeData>>) {
+        let anchor = fragment.source_position.unwrap_or(position);
+        let count = match self.paste_cascade {
+            Some((last, count)) if last == anchor => count + 1,
+            // Fragments pasted at a given position land right there
+            _ => fragment.source_position.is_some() as u32,
+        };
+        self.paste_cascade = Some((anchor, count));
+
+        let offset = anchor.to_vec2() + self.paste_offset * count as f32;
+        let (mapping, responses) = self.import_fragment(fragment, offset);
+        self.selected_nodes = mapping.nodes.values().copied().collect();
+        (mapping, responses)
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<Response = UserResponse> + Clone,
+    UserResponse: UserResponseTrait,
+    DataType: PartialEq + Clone,
+    ValueType: Clone,
+{
+    /// Copies the selected nodes and the connections between them, see
+    /// [`GraphEditorState::paste_fragment`]. The copies become the
+    /// selection, so duplicating again cascades.
+    pub fn duplicate_selection(&mut self) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        if self.selected_nodes.is_empty() {
+            return Vec::new();
+        }
+        let fragment = self.copy_fragment(&self.selected_nodes);
+        self.paste_fragment(fragment, egui::Pos2::ZERO).1
+    }
+
+    /// Pastes [`GraphEditorState::clipboard`] with its top-left node at
+    /// `position`, in graph coordinates. The copies get fresh ids and become
+    /// the selection. Pasting again at the same position cascades, as with
+    /// [`GraphEditorState::paste_fragment`].
+    pub fn paste_at(&mut self, position: egui::Pos2) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let Some(mut fragment) = self.clipboard.clone() else {
+            return Vec::new();
+        };
+        fragment.source_position = None;
+        self.paste_fragment(fragment, position).1
+    }
+
+    /// Makes the editor copy, cut and paste the selected nodes at the
+    /// cursor on egui's copy, cut and paste events, usually sent for the
+    /// usual keyboard shortcuts. This also enables
+    /// [`EditorKeybindings::duplicate`], setting it to Ctrl+D if it has no
+    /// shortcut yet.
+    ///
+    /// These shortcuts use [`GraphEditorState::clipboard`] and leave the
+    /// system clipboard alone, so nodes can only be pasted into the editor
+    /// they were copied from. With the `clipboard` feature, use
+    /// `enable_system_clipboard_shortcuts` instead to go through the system
+    /// clipboard.
+    ///
+    /// Since the system clipboard isn't needed, pressing the paste shortcut
+    /// (Ctrl+V or the paste key) pastes as well, for integrations that only
+    /// send paste events when the system clipboard holds text. egui-winit
+    /// sends neither the event nor the key press in that case, so there
+    /// pasting needs some text in the system clipboard.
+    pub fn enable_clipboard_shortcuts(&mut self) {
+        self.clipboard_hooks = Some(ClipboardHooks {
+            copy: |state: &mut Self, _ctx| state.copy_selection(),
+            paste: |state: &mut Self, position, _text| {
+                if state.paste_at(position).is_empty() {
+                    return Vec::new();
+                }
+                state.selected_nodes.clone()
+            },
+            duplicate: |state: &mut Self| {
+                if state.duplicate_selection().is_empty() {
+                    return Vec::new();
+                }
+                state.selected_nodes.clone()
+            },
+        });
+        self.keybindings
+            .duplicate
+            .get_or_insert(egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND,
+                egui::Key::D,
+            ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_graph() {
+        fn node(graph: &mut Graph<(), (), ()>, label: &str) -> (NodeId, InputId, OutputId) {
+            let node_id = graph.add_node(label.into(), (), |_, _| {});
+            let input = graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            let output = graph.add_output_param(node_id, "out".into(), ());
+            (node_id, input, output)
+        }
+
+        let mut graph = Graph::new();
+        node(&mut graph, "existing");
+
+        let mut other = Graph::new();
+        let (a, _, a_out) = node(&mut other, "a");
+        let (b, b_in, _) = node(&mut other, "b");
+        other.add_connection(a_out, b_in);
+
+        let mapping = graph.import_graph(other);
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph[mapping.nodes[a]].label, "a");
+        assert_eq!(graph[mapping.inputs[b_in]].node, mapping.nodes[b]);
+        assert_eq!(
+            graph.connection(mapping.inputs[b_in]),
+            Some(mapping.outputs[a_out])
+        );
+    }
+
+    #[test]
+    fn test_extract_subgraph() {
+        let mut graph = Graph::<(), (), f32>::new();
+        let mut add_node = |label: &str, value: f32| {
+            let node_id = graph.add_node(label.into(), (), |_, _| {});
+            let input = graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                value,
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            let output = graph.add_output_param(node_id, "out".into(), ());
+            (node_id, input, output)
+        };
+        let (a, _, a_out) = add_node("a", 1.0);
+        let (b, b_in, b_out) = add_node("b", 2.0);
+        let (_, c_in, _) = add_node("c", 3.0);
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(b_out, c_in);
+
+        let (subgraph, mapping) = graph.extract_subgraph(&[a, b]);
+        assert_eq!(subgraph.nodes.len(), 2);
+        assert_eq!(mapping.nodes.len(), 2);
+        assert_eq!(subgraph[mapping.nodes[b]].label, "b");
+        assert_eq!(subgraph[mapping.inputs[b_in]].value, 2.0);
+        assert_eq!(
+            subgraph.connection(mapping.inputs[b_in]),
+            Some(mapping.outputs[a_out])
+        );
+        // The connection to c is left out
+        assert!(subgraph.output_targets(mapping.outputs[b_out]).is_empty());
+    }
+
+    #[cfg(feature = "ui")]
+    #[test]
+    fn test_copy_paste() {
+        #[derive(Clone)]
+        struct Data;
+
+        #[derive(Clone, Debug)]
+        struct EmptyResponse;
+
+        impl UserResponseTrait for EmptyResponse {}
+
+        impl NodeDataTrait for Data {
+            type Response = EmptyResponse;
+            type UserState = ();
+            type DataType = ();
+            type ValueType = ();
+
+            fn bottom_ui(
+                &self,
+                _ui: &mut egui::Ui,
+                _node_id: NodeId,
+                _graph: &Graph<Self, (), ()>,
+                _user_state: &mut (),
+            ) -> Vec<NodeResponse<EmptyResponse, Self>> {
+                Vec::new()
+            }
+        }
+
+        let mut state = GraphEditorState::<Data, (), (), (), ()>::default();
+        let mut add = |label: &str, pos| {
+            let node = state.graph.add_node(label.into(), Data, |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    (),
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), ());
+            });
+            state.node_order.push(node);
+            state.node_positions.insert(node, pos);
+            state
+                .node_orientations
+                .insert(node, NodeOrientation::LeftToRight);
+            node
+        };
+        let a = add("a", egui::pos2(10.0, 10.0));
+        let b = add("b", egui::pos2(50.0, 30.0));
+        let out = state.graph[a].outputs[0].1;
+        let input = state.graph[b].inputs[0].1;
+        state.graph.add_connection(out, input);
+
+        assert!(!state.copy_selection());
+        state.selected_nodes = vec![a, b];
+        assert!(state.copy_selection());
+        let responses = state.paste_at(egui::pos2(100.0, 100.0));
+        assert_eq!(responses.len(), 2);
+        assert_eq!(state.graph.nodes.len(), 4);
+        assert_eq!(state.graph.connections.len(), 2);
+        assert!(!state.selected_nodes.contains(&a) && !state.selected_nodes.contains(&b));
+        let mut positions: Vec<_> = state
+            .selected_nodes
+            .iter()
+            .map(|node_id| state.node_positions[*node_id])
+            .collect();
+        positions.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert_eq!(
+            positions,
+            vec![egui::pos2(100.0, 100.0), egui::pos2(140.0, 120.0)]
+        );
+        assert!(state.validate_integrity().is_ok());
+    }
+
+    #[cfg(feature = "synthetic")]
+    #[test]
+    fn test_paste_shortcut_key() {
+        use crate::synthetic::{run_editor_frame, SyntheticEditorState, SyntheticGraphOptions};
+        use egui::{pos2, Event, Key, Modifiers, RawInput};
+
+        let mut state = SyntheticGraphOptions {
+            nodes: 1,
+            ..Default::default()
+        }
+        .build_editor_state();
+        state.enable_clipboard_shortcuts();
+        state.selected_nodes = state.node_order.to_vec();
+
+        let ctx = egui::Context::default();
+        let frame = |state: &mut SyntheticEditorState, mut events: Vec<Event>| {
+            events.insert(0, Event::PointerMoved(pos2(500.0, 400.0)));
+            let input = RawInput {
+                events,
+                ..Default::default()
+            };
+            run_editor_frame(&ctx, state, input).0.node_responses
+        };
+        let paste_key = Event::Key {
+            key: Key::V,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Modifiers::COMMAND,
+        };
+
+        // Nothing was copied yet
+        assert!(frame(&mut state, vec![paste_key.clone()]).is_empty());
+        assert_eq!(state.graph.nodes.len(), 1);
+
+        frame(&mut state, vec![Event::Copy]);
+        let responses = frame(&mut state, vec![paste_key.clone()]);
+        assert_eq!(state.graph.nodes.len(), 2);
+        let [NodeResponse::CreatedNode(pasted)] = responses[..] else {
+            panic!("expected a CreatedNode response");
+        };
+        assert_eq!(state.selected_nodes, vec![pasted]);
+
+        // Pastes once when both the key and the event are sent
+        let responses = frame(&mut state, vec![paste_key, Event::Paste("text".into())]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(state.graph.nodes.len(), 3);
+    }
+}
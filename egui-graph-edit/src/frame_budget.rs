@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use super::*;
+
+/// What the editor learns while drawing under a
+/// [`GraphEditorState::frame_budget`].
+#[derive(Clone, Debug, Default)]
+pub struct FrameBudgetState {
+    /// The average time it takes to draw a node in full, in seconds.
+    pub node_cost: f32,
+    /// The node that was last pressed, which may hold the keyboard focus.
+    pub active_node: Option<NodeId>,
+    /// The number of nodes whose update was put off on the last frame.
+    pub deferred: usize,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Nodes the user may be interacting with, which are always drawn in
+    /// full.
+    pub(crate) fn is_critical(&self, plan: &NodePlan) -> bool {
+        plan.hovered
+            || plan.selected
+            || self.frame_budget_state.active_node == Some(plan.node_id)
+            || self
+                .connection_in_progress
+                .is_some_and(|(node_id, _)| node_id == plan.node_id)
+    }
+
+    /// Puts off the updates that wouldn't fit in `budget`, according to the
+    /// average cost of a node. Nodes the user interacts with come first,
+    /// then nodes in view, then nodes out of view, from the bottom up.
+    pub(crate) fn defer_node_updates(&self, plans: &mut [NodePlan], budget: Duration) {
+        let cost = self.frame_budget_state.node_cost;
+        let mut left = budget.as_secs_f32();
+        for plan in plans.iter() {
+            if plan.draw == NodeDraw::Full && self.is_critical(plan) {
+                left -= cost;
+            }
+        }
+        for visible in [true, false] {
+            for plan in plans.iter_mut() {
+                if plan.draw != NodeDraw::Full || plan.visible != visible || self.is_critical(plan)
+                {
+                    continue;
+                }
+                if left >= cost {
+                    left -= cost;
+                } else {
+                    plan.draw = NodeDraw::Deferred;
+                }
+            }
+        }
+    }
+
+    /// Adds the time taken to draw a node to the average cost of a node.
+    pub(crate) fn record_node_cost(&mut self, elapsed: Duration) {
+        let sample = elapsed.as_secs_f32();
+        let cost = &mut self.frame_budget_state.node_cost;
+        *cost = if *cost == 0.0 {
+            sample
+        } else {
+            *cost * 0.9 + sample * 0.1
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defer_node_updates() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let [a, b, c, d] =
+            ["a", "b", "c", "d"].map(|label| state.graph.add_node(label.into(), (), |_, _| {}));
+        state.frame_budget_state.node_cost = 0.001;
+
+        let plan = |node_id, visible, hovered, draw| NodePlan {
+            node_id,
+            layout_key: 0,
+            shapes_key: 0,
+            selected: false,
+            rect: egui::Rect::NOTHING,
+            visible,
+            hovered,
+            dimmed: false,
+            draw,
+        };
+        let mut plans = [
+            plan(a, false, false, NodeDraw::Full),
+            plan(b, true, false, NodeDraw::Full),
+            plan(c, true, false, NodeDraw::Replay),
+            plan(d, true, true, NodeDraw::Full),
+        ];
+        state.defer_node_updates(&mut plans, Duration::from_micros(2500));
+
+        // The hovered node and the node in view fit, but not the one out of view
+        let draws = plans.map(|plan| plan.draw);
+        assert_eq!(
+            draws,
+            [
+                NodeDraw::Deferred,
+                NodeDraw::Full,
+                NodeDraw::Replay,
+                NodeDraw::Full
+            ]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use super::*;
+use egui::{
+    vec2, Color32, Key, Pos2, Rect, Sense, Stroke, StrokeKind, TextEdit, TextStyle, Ui, Vec2,
+};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+slotmap::new_key_type! { pub struct FrameId; }
+
+/// The space between a new frame and the nodes it is put around.
+const FRAME_PADDING: f32 = 20.0;
+/// The height of the title bar of the frames, in graph coordinates.
+const FRAME_TITLE_HEIGHT: f32 = 28.0;
+/// The size of the handle in the bottom right corner resizing the frames,
+/// on the screen.
+const FRAME_HANDLE_SIZE: f32 = 12.0;
+/// The smallest size frames can be resized to, in graph coordinates.
+const FRAME_MIN_SIZE: Vec2 = vec2(80.0, 60.0);
+
+/// A titled rectangle drawn behind the nodes, e.g. to comment or group part
+/// of a graph. Dragging its title bar moves the nodes inside it along, and
+/// the handle in its bottom right corner resizes it. Double clicking the
+/// title renames the frame, and its context menu deletes it.
+///
+/// Frames don't keep track of their nodes: a frame moves the nodes that
+/// are fully inside it when its drag starts, see
+/// [`GraphEditorState::frame_nodes`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct CommentFrame {
+    pub title: String,
+    /// Where the frame is, in graph coordinates, title bar included.
+    pub rect: Rect,
+    /// The fill of the frame. Defaults to a translucent version of the
+    /// title bar color of the style.
+    pub color: Option<Color32>,
+}
+
+/// A frame being renamed, see [`GraphEditorState::frame_renaming`].
+#[derive(Clone, Debug)]
+pub struct FrameRenaming {
+    pub frame: FrameId,
+    /// The title being typed.
+    pub title: String,
+    /// Whether the text field got the keyboard focus yet.
+    pub focused: bool,
+}
+
+/// The frame being dragged, see [`GraphEditorState::frame_drag`].
+#[derive(Clone, Debug)]
+pub struct FrameDrag {
+    pub frame: FrameId,
+    /// The nodes inside the frame when the drag started, which move along.
+    pub nodes: Vec<NodeId>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Puts a frame titled `title` around `nodes`. The frame is put at the
+    /// origin when none of the nodes exist.
+    pub fn add_frame(&mut self, title: impl Into<String>, nodes: &[NodeId]) -> FrameId {
+        let bounds = nodes
+            .iter()
+            .filter_map(|node_id| {
+                let position = self.node_positions.get(*node_id)?;
+                Some(
+                    self.node_rect_index
+                        .get(*node_id)
+                        .unwrap_or_else(|| Rect::from_min_size(*position, UNMEASURED_NODE_SIZE)),
+                )
+            })
+            .reduce(Rect::union);
+        let rect = match bounds {
+            Some(bounds) => {
+                let mut rect = bounds.expand(FRAME_PADDING);
+                rect.min.y -= FRAME_TITLE_HEIGHT;
+                rect
+            }
+            None => Rect::from_min_size(Pos2::ZERO, UNMEASURED_NODE_SIZE),
+        };
+        self.frames.insert(CommentFrame {
+            title: title.into(),
+            rect,
+            color: None,
+        })
+    }
+
+    /// Removes a frame. The nodes inside it are kept.
+    pub fn remove_frame(&mut self, frame: FrameId) -> Option<CommentFrame> {
+        if self
+            .frame_renaming
+            .as_ref()
+            .is_some_and(|renaming| renaming.frame == frame)
+        {
+            self.frame_renaming = None;
+        }
+        self.frames.remove(frame)
+    }
+
+    /// The nodes fully inside a frame, as of the last time they were drawn.
+    pub fn frame_nodes(&self, frame: FrameId) -> Vec<NodeId> {
+        let Some(frame) = self.frames.get(frame) else {
+            return Vec::new();
+        };
+        let mut nodes = self.node_rect_index.query(frame.rect);
+        nodes.retain(|node_id| {
+            self.node_rect_index
+                .get(*node_id)
+                .is_some_and(|rect| frame.rect.contains_rect(rect))
+        });
+        nodes
+    }
+
+    /// Draws the frames, behind the nodes, and returns the responses of
+    /// their title bars and handles.
+    pub(crate) fn draw_frames<UserResponse>(
+        &mut self,
+        ui: &mut Ui,
+        transform: egui::emath::TSTransform,
+        style: &GraphEditorStyle,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>>
+    where
+        UserResponse: UserResponseTrait,
+        NodeData: NodeDataTrait,
+    {
+        let mut responses = Vec::new();
+        let font_id = TextStyle::Body.resolve(ui.style());
+        let frame_ids: Vec<FrameId> = self.frames.keys().collect();
+        for frame_id in frame_ids {
+            let frame = &self.frames[frame_id];
+            let rect = transform.mul_rect(frame.rect);
+            if !ui.clip_rect().intersects(rect) {
+                continue;
+            }
+            let title_rect = Rect::from_min_size(
+                rect.min,
+                vec2(rect.width(), FRAME_TITLE_HEIGHT * transform.scaling),
+            );
+            let fill = frame
+                .color
+                .unwrap_or_else(|| style.titlebar.gamma_multiply(0.25));
+            let corner_radius = style.corner_radius;
+            ui.painter().rect(
+                rect,
+                corner_radius,
+                fill,
+                Stroke::new(1.0, fill.gamma_multiply(2.0)),
+                StrokeKind::Inside,
+            );
+            ui.painter().rect_filled(
+                title_rect,
+                egui::CornerRadius {
+                    nw: corner_radius,
+                    ne: corner_radius,
+                    sw: 0,
+                    se: 0,
+                },
+                fill.gamma_multiply(2.0),
+            );
+
+            let title = ui.interact(
+                title_rect,
+                ui.id().with(("frame", frame_id)),
+                Sense::click_and_drag(),
+            );
+            if title.drag_started() {
+                self.frame_drag = Some(FrameDrag {
+                    frame: frame_id,
+                    nodes: self.frame_nodes(frame_id),
+                });
+            }
+            let drag_delta = title.drag_delta() / transform.scaling;
+            if let Some(drag) = self
+                .frame_drag
+                .as_ref()
+                .filter(|drag| drag.frame == frame_id)
+            {
+                if title.dragged() && drag_delta != Vec2::ZERO {
+                    responses.push(NodeResponse::MoveFrame {
+                        frame: frame_id,
+                        nodes: drag.nodes.clone(),
+                        drag_delta,
+                    });
+                }
+            }
+            if title.drag_stopped() {
+                self.frame_drag = None;
+            }
+            if title.double_clicked() {
+                self.start_frame_renaming(frame_id);
+            }
+            title.context_menu(|ui| {
+                if ui.button("Rename").clicked() {
+                    self.start_frame_renaming(frame_id);
+                    ui.close_menu();
+                }
+                if ui.button("Delete frame").clicked() {
+                    responses.push(NodeResponse::DeleteFrame(frame_id));
+                    ui.close_menu();
+                }
+            });
+
+            // Added after the title bar so it gets the pointer
+            let renaming = self
+                .frame_renaming
+                .as_mut()
+                .filter(|renaming| renaming.frame == frame_id);
+            if let Some(renaming) = renaming {
+                let edit = ui.put(
+                    title_rect.shrink2(style.node_margin * vec2(1.0, 0.25)),
+                    TextEdit::singleline(&mut renaming.title)
+                        .font(font_id.clone())
+                        .text_color(style.text),
+                );
+                if !renaming.focused {
+                    edit.request_focus();
+                    renaming.focused = true;
+                } else if edit.lost_focus() {
+                    if !ui.input(|i| i.key_pressed(Key::Escape)) {
+                        responses.push(NodeResponse::RenameFrame {
+                            frame: frame_id,
+                            title: renaming.title.clone(),
+                        });
+                    }
+                    self.frame_renaming = None;
+                }
+            } else {
+                ui.painter().with_clip_rect(title_rect).text(
+                    title_rect.left_center() + vec2(style.node_margin.x, 0.0),
+                    egui::Align2::LEFT_CENTER,
+                    &self.frames[frame_id].title,
+                    font_id.clone(),
+                    style.text,
+                );
+            }
+
+            let handle_rect =
+                Rect::from_min_max(rect.max - Vec2::splat(FRAME_HANDLE_SIZE), rect.max);
+            let handle = ui.interact(
+                handle_rect,
+                ui.id().with(("frame_handle", frame_id)),
+                Sense::drag(),
+            );
+            if handle.hovered() || handle.dragged() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeNwSe);
+            }
+            let handle_color = if handle.hovered() || handle.dragged() {
+                style.button_hovered
+            } else {
+                style.button
+            };
+            ui.painter().line_segment(
+                [handle_rect.right_top(), handle_rect.left_bottom()],
+                Stroke::new(1.0, handle_color),
+            );
+            let drag_delta = handle.drag_delta() / transform.scaling;
+            if handle.dragged() && drag_delta != Vec2::ZERO {
+                responses.push(NodeResponse::ResizeFrame {
+                    frame: frame_id,
+                    drag_delta,
+                });
+            }
+        }
+        responses
+    }
+
+    pub(crate) fn start_frame_renaming(&mut self, frame: FrameId) {
+        self.frame_renaming = Some(FrameRenaming {
+            frame,
+            title: self.frames[frame].title.clone(),
+            focused: false,
+        });
+    }
+
+    /// Resizes a frame by moving its bottom right corner, keeping it above
+    /// the minimum size.
+    pub(crate) fn resize_frame(&mut self, frame: FrameId, drag_delta: Vec2) {
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.rect.max = (frame.rect.max + drag_delta).max(frame.rect.min + FRAME_MIN_SIZE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::pos2;
+
+    #[test]
+    fn test_frames() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let [a, b, outside] = [(0.0, 0.0), (300.0, 50.0), (1000.0, 0.0)].map(|(x, y)| {
+            let node = state.graph.add_node("node".into(), (), |_, _| {});
+            state.node_order.push(node);
+            state.node_positions.insert(node, pos2(x, y));
+            let rect = Rect::from_min_size(pos2(x, y), vec2(100.0, 50.0));
+            state.node_rect_index.insert(node, rect);
+            node
+        });
+        let frame = state.add_frame("Group", &[a, b]);
+        assert_eq!(
+            state.frames[frame].rect,
+            Rect::from_min_max(pos2(-20.0, -48.0), pos2(420.0, 120.0))
+        );
+        let mut nodes = state.frame_nodes(frame);
+        nodes.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(nodes, expected);
+        assert!(!nodes.contains(&outside));
+
+        state.resize_frame(frame, vec2(-1000.0, 10.0));
+        assert_eq!(state.frames[frame].rect.max, pos2(60.0, 130.0));
+        assert!(state.remove_frame(frame).is_some());
+        assert!(state.frame_nodes(frame).is_empty());
+    }
+}
@@ -58,6 +58,11 @@ pub struct InputParam<DataType, ValueType> {
     /// When true, the node is shown inline inside the node graph.
     #[cfg_attr(feature = "persistence", serde(default = "shown_inline_default"))]
     pub shown_inline: bool,
+    /// When true, the input accepts several connections rather than
+    /// replacing its connection with each new one. See
+    /// [`Graph::add_multi_input_param`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub multi_input: bool,
 }
 
 /// An output parameter. Output parameters are inside a node, and represent the
@@ -78,6 +83,10 @@ pub struct OutputParam<DataType> {
 /// crate to represent all the inner references in the data.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "persistence",
+    serde(from = "crate::adjacency::GraphData<NodeData, DataType, ValueType>")
+)]
 pub struct Graph<NodeData, DataType, ValueType> {
     /// The [`Node`]s of the graph
     pub nodes: SlotMap<NodeId, Node<NodeData>>,
@@ -85,7 +94,17 @@ pub struct Graph<NodeData, DataType, ValueType> {
     pub inputs: SlotMap<InputId, InputParam<DataType, ValueType>>,
     /// The [`OutputParam`]s of the graph
     pub outputs: SlotMap<OutputId, OutputParam<DataType>>,
-    // Connects the input of a node, to the output of its predecessor that
-    // produces it
-    pub connections: SecondaryMap<InputId, OutputId>,
+    // Connects the input of a node, to the outputs of its predecessors that
+    // produce it, in the order the connections were made. Only inputs with
+    // `multi_input` set have more than one. Prefer `add_connection` and
+    // `remove_connection` to modifying this directly, otherwise call
+    // `rebuild_connection_index`.
+    pub connections: SecondaryMap<InputId, SVec<OutputId>>,
+    /// Optional stable identifiers of nodes and parameters. See [`StableIds`]
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub stable_ids: StableIds,
+    /// The inputs each output is connected to, the reverse of `connections`.
+    /// Rebuilt when loading a graph.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub(crate) output_targets: SecondaryMap<OutputId, SVec<InputId>>,
 }
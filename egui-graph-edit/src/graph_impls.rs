@@ -10,6 +10,8 @@ where
             inputs: SlotMap::default(),
             outputs: SlotMap::default(),
             connections: SecondaryMap::default(),
+            stable_ids: StableIds::default(),
+            output_targets: SecondaryMap::default(),
         }
     }
 
@@ -51,11 +53,29 @@ where
             kind,
             node: node_id,
             shown_inline,
+            multi_input: false,
         });
         self.nodes[node_id].inputs.push((name, input_id));
         input_id
     }
 
+    /// Like [`Graph::add_input_param`], for an input accepting several
+    /// connections, e.g. the items of a list or the terms of a sum. The
+    /// editor fans the wires into its port.
+    pub fn add_multi_input_param(
+        &mut self,
+        node_id: NodeId,
+        name: String,
+        typ: DataType,
+        value: ValueType,
+        kind: InputParamKind,
+        shown_inline: bool,
+    ) -> InputId {
+        let input_id = self.add_input_param(node_id, name, typ, value, kind, shown_inline);
+        self.inputs[input_id].multi_input = true;
+        input_id
+    }
+
     pub fn update_input_param(
         &mut self,
         input_id: InputId,
@@ -95,8 +115,9 @@ where
     pub fn remove_input_param(&mut self, param: InputId) {
         let node = self[param].node;
         self[node].inputs.retain(|(_, id)| *id != param);
+        self.remove_connections(param);
         self.inputs.remove(param);
-        self.connections.retain(|i, _| i != param);
+        self.stable_ids.remove_param(param.into());
     }
 
     pub fn add_output_param(&mut self, node_id: NodeId, name: String, typ: DataType) -> OutputId {
@@ -136,8 +157,16 @@ where
     pub fn remove_output_param(&mut self, param: OutputId) {
         let node = self[param].node;
         self[node].outputs.retain(|(_, id)| *id != param);
+        for input in self.output_targets.remove(param).unwrap_or_default() {
+            if let Some(outputs) = self.connections.get_mut(input) {
+                outputs.retain(|output| *output != param);
+                if outputs.is_empty() {
+                    self.connections.remove(input);
+                }
+            }
+        }
         self.outputs.remove(param);
-        self.connections.retain(|_, o| *o != param);
+        self.stable_ids.remove_param(param.into());
     }
 
     /// Deletes mistyped connection made with param_id
@@ -157,12 +186,12 @@ where
 
             // connection has mismatched types
             if self.get_input(to_id).typ != self.get_output(from_id).typ {
-                to_remove.push(to_id);
+                to_remove.push((to_id, from_id));
             }
         }
 
-        for in_id in to_remove {
-            self.remove_connection(in_id);
+        for (in_id, out_id) in to_remove {
+            self.disconnect(out_id, in_id);
         }
     }
 
@@ -174,48 +203,74 @@ where
     /// ids in the pair (the one on `node_id`'s end) will be invalid after
     /// calling this function.
     pub fn remove_node(&mut self, node_id: NodeId) -> (Node<NodeData>, Vec<(InputId, OutputId)>) {
-        let mut disconnect_events = vec![];
-
-        self.connections.retain(|i, o| {
-            if self.outputs[*o].node == node_id || self.inputs[i].node == node_id {
-                disconnect_events.push((i, *o));
-                false
-            } else {
-                true
-            }
-        });
+        let disconnect_events: Vec<_> = self.connections_of(node_id).collect();
+        for (input, output) in &disconnect_events {
+            self.disconnect(*output, *input);
+        }
 
         // NOTE: Collect is needed because we can't borrow the input ids while
         // we remove them inside the loop.
         for input in self[node_id].input_ids().collect::<SVec<_>>() {
             self.inputs.remove(input);
+            self.stable_ids.remove_param(input.into());
         }
         for output in self[node_id].output_ids().collect::<SVec<_>>() {
             self.outputs.remove(output);
+            self.stable_ids.remove_param(output.into());
         }
+        self.stable_ids.remove_node(node_id);
         let removed_node = self.nodes.remove(node_id).expect("Node should exist");
 
         (removed_node, disconnect_events)
     }
 
+    /// Removes the last connection made to `input_id`, which is its only
+    /// one unless it is a multi-input, and returns the output it came from.
     pub fn remove_connection(&mut self, input_id: InputId) -> Option<OutputId> {
-        self.connections.remove(input_id)
+        let outputs = self.connections.get_mut(input_id)?;
+        let output = outputs.pop()?;
+        if outputs.is_empty() {
+            self.connections.remove(input_id);
+        }
+        self.unlink_target(output, input_id);
+        Some(output)
+    }
+
+    /// Removes all connections to `input_id`, returning the outputs they
+    /// came from.
+    pub fn remove_connections(&mut self, input_id: InputId) -> SVec<OutputId> {
+        let outputs = self.connections.remove(input_id).unwrap_or_default();
+        for output in &outputs {
+            self.unlink_target(*output, input_id);
+        }
+        outputs
     }
 
     pub fn iter_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
         self.nodes.iter().map(|(id, _)| id)
     }
 
+    /// Connects `output` to `input`. This replaces the connection of the
+    /// input, unless it is a multi-input, which gets one more connection.
     pub fn add_connection(&mut self, output: OutputId, input: InputId) {
-        self.connections.insert(input, output);
-    }
-
-    pub fn iter_connections(&self) -> impl Iterator<Item = (InputId, OutputId)> + '_ {
-        self.connections.iter().map(|(o, i)| (o, *i))
-    }
-
-    pub fn connection(&self, input: InputId) -> Option<OutputId> {
-        self.connections.get(input).copied()
+        let multi_input = self
+            .inputs
+            .get(input)
+            .is_some_and(|param| param.multi_input);
+        if multi_input {
+            if self.connections(input).contains(&output) {
+                return;
+            }
+        } else {
+            self.remove_connections(input);
+        }
+        let Some(entry) = self.connections.entry(input) else {
+            return;
+        };
+        entry.or_default().push(output);
+        if let Some(entry) = self.output_targets.entry(output) {
+            entry.or_default().push(input);
+        }
     }
 
     pub fn any_param_type(&self, param: AnyParameterId) -> Result<&DataType, EguiGraphError> {
@@ -0,0 +1,214 @@
+use super::*;
+use egui::{Pos2, Vec2};
+
+/// Lays out the graph in columns and rows of a grid, for schematic or
+/// circuit-like diagrams: each node goes one column past the nodes feeding
+/// it, and every node corner lands on a grid line. Nodes are moved down
+/// their column towards the nodes feeding them, so that connections stay
+/// as straight as possible.
+///
+/// Combine it with [`GraphEditorState::connection_grid`] set to the same
+/// spacing to route the connections along the grid lines too.
+#[derive(Debug, Clone)]
+pub struct GridLayout {
+    /// The spacing of the grid lines.
+    pub grid: f32,
+    /// The number of empty grid cells between two columns.
+    pub column_gap: u32,
+    /// The number of empty grid cells between two nodes of a column.
+    pub row_gap: u32,
+}
+
+impl Default for GridLayout {
+    fn default() -> Self {
+        Self {
+            grid: 20.0,
+            column_gap: 3,
+            row_gap: 1,
+        }
+    }
+}
+
+impl LayoutAlgorithm for GridLayout {
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+        let mut positions = LayoutPositions::new();
+        if input.nodes.is_empty() || self.grid <= 0.0 {
+            return positions;
+        }
+        let cells = |length: f32| (length / self.grid).ceil().max(1.0) as u32;
+        let size = |node_id: NodeId| {
+            input
+                .sizes
+                .get(node_id)
+                .copied()
+                .unwrap_or(UNMEASURED_NODE_SIZE)
+        };
+        let columns = longest_path_ranks(input);
+
+        // The layout stays where the graph was
+        let origin = input
+            .nodes
+            .iter()
+            .filter_map(|node_id| input.positions.get(*node_id))
+            .fold(Pos2::new(f32::INFINITY, f32::INFINITY), |min, pos| {
+                min.min(*pos)
+            });
+        let origin = if origin.is_finite() {
+            (origin / self.grid).floor() * self.grid
+        } else {
+            Pos2::ZERO
+        };
+
+        let mut column_nodes: Vec<Vec<NodeId>> = Vec::new();
+        for node_id in &input.nodes {
+            let column = columns[*node_id];
+            if column_nodes.len() <= column {
+                column_nodes.resize(column + 1, Vec::new());
+            }
+            column_nodes[column].push(*node_id);
+        }
+
+        let mut predecessors: SecondaryMap<NodeId, Vec<NodeId>> = SecondaryMap::new();
+        for (from, to) in &input.edges {
+            if let Some(list) = predecessors.entry(*to) {
+                list.or_default().push(*from);
+            }
+        }
+
+        let mut x = 0;
+        for nodes in &column_nodes {
+            // Where each node would like to be, in grid cells from the top
+            let wanted_row = |node_id: NodeId, positions: &LayoutPositions| {
+                let centers: Vec<f32> = predecessors
+                    .get(node_id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|pred| Some(positions.get(*pred)?.y + size(*pred).y / 2.0))
+                    .collect();
+                if centers.is_empty() {
+                    None
+                } else {
+                    let center = centers.iter().sum::<f32>() / centers.len() as f32;
+                    let top = center - size(node_id).y / 2.0 - origin.y;
+                    Some((top / self.grid).round().max(0.0) as u32)
+                }
+            };
+            let mut keys: Vec<(Option<u32>, f32, NodeId)> = nodes
+                .iter()
+                .map(|node_id| {
+                    let current = input.positions.get(*node_id).map_or(0.0, |pos| pos.y);
+                    (wanted_row(*node_id, &positions), current, *node_id)
+                })
+                .collect();
+            keys.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+            let mut next_row = 0;
+            let mut width = 0;
+            for (wanted, _, node_id) in keys {
+                let row = wanted.map_or(next_row, |wanted| wanted.max(next_row));
+                let node_size = size(node_id);
+                positions.insert(
+                    node_id,
+                    origin + Vec2::new(x as f32, row as f32) * self.grid,
+                );
+                next_row = row + cells(node_size.y) + self.row_gap;
+                width = width.max(cells(node_size.x));
+            }
+            x += width + self.column_gap;
+        }
+        positions
+    }
+}
+
+/// The points of a connection running from `src` to `dst` with only
+/// horizontal and vertical segments. Vertical segments follow the lines of
+/// a grid of spacing `grid` going through `origin`. `src_dir` and `dst_dir`
+/// point away from the ports, along the x axis.
+pub(crate) fn orthogonal_route(
+    src: Pos2,
+    src_dir: Vec2,
+    dst: Pos2,
+    dst_dir: Vec2,
+    grid: f32,
+    origin: Pos2,
+) -> Vec<Pos2> {
+    let snap = |x: f32| ((x - origin.x) / grid).round() * grid + origin.x;
+    // The first grid line out of each port, at least half a cell away
+    let src_out = snap(src.x + src_dir.x * grid);
+    let dst_out = snap(dst.x + dst_dir.x * grid);
+
+    let mut points = vec![src];
+    if src_dir.x == dst_dir.x {
+        // Both ports face the same way, so go around the outermost one
+        let x = if src_dir.x > 0.0 {
+            src_out.max(dst_out)
+        } else {
+            src_out.min(dst_out)
+        };
+        points.extend([Pos2::new(x, src.y), Pos2::new(x, dst.y)]);
+    } else if (dst_out - src_out) * src_dir.x >= 0.0 {
+        // The ports face each other
+        let x = snap((src_out + dst_out) / 2.0);
+        points.extend([Pos2::new(x, src.y), Pos2::new(x, dst.y)]);
+    } else {
+        // The destination is behind the source, so loop back between them
+        let y = (src.y + dst.y) / 2.0;
+        points.extend([
+            Pos2::new(src_out, src.y),
+            Pos2::new(src_out, y),
+            Pos2::new(dst_out, y),
+            Pos2::new(dst_out, dst.y),
+        ]);
+    }
+    points.push(dst);
+    points.dedup();
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_grid_layout() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b, c, d] =
+            ["a", "b", "c", "d"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+        let mut input = LayoutInput::default();
+        for node_id in [a, b, c, d] {
+            input.nodes.push(node_id);
+            input.positions.insert(node_id, pos2(15.0, 30.0));
+            input.sizes.insert(node_id, vec2(90.0, 50.0));
+        }
+        // d feeds a back, which is broken at a
+        input.edges = vec![(a, b), (a, c), (b, d), (c, d), (d, a)];
+
+        let positions = GridLayout::default().layout(&input);
+        // 5 cells wide, then 3 cells between columns
+        assert_eq!(positions[a], pos2(0.0, 20.0));
+        assert_eq!(positions[b], pos2(160.0, 20.0));
+        // 3 cells tall, then a cell between nodes
+        assert_eq!(positions[c], pos2(160.0, 100.0));
+        // Centered between b and c
+        assert_eq!(positions[d], pos2(320.0, 60.0));
+
+        let route = orthogonal_route(
+            pos2(105.0, 10.0),
+            Vec2::X,
+            pos2(300.0, 50.0),
+            -Vec2::X,
+            20.0,
+            pos2(5.0, 0.0),
+        );
+        assert_eq!(
+            route,
+            vec![
+                pos2(105.0, 10.0),
+                pos2(205.0, 10.0),
+                pos2(205.0, 50.0),
+                pos2(300.0, 50.0)
+            ]
+        );
+    }
+}
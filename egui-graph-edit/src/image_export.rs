@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use super::*;
+use egui::epaint::{ClippedPrimitive, ImageData, Primitive};
+use egui::{Color32, ColorImage, Pos2, Rect, TextureId, Vec2};
+
+/// Settings for [`GraphEditorState::render_to_image`].
+#[derive(Clone, Debug)]
+pub struct ImageExportOptions {
+    /// Physical pixels per graph unit. Use values above 1.0 for crisp
+    /// screenshots of small graphs.
+    pub scale: f32,
+    /// Empty space left around the graph extents, in graph units.
+    pub margin: f32,
+    /// The visuals used to draw the graph. Also decides between the dark and
+    /// light node palettes.
+    pub visuals: egui::Visuals,
+}
+
+impl Default for ImageExportOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            margin: 20.0,
+            visuals: egui::Visuals::dark(),
+        }
+    }
+}
+
+/// The node finder is never open while exporting, so no templates are needed.
+struct NoTemplates<NodeTemplate>(PhantomData<NodeTemplate>);
+
+impl<NodeTemplate> NodeTemplateIter for NoTemplates<NodeTemplate> {
+    type Item = NodeTemplate;
+
+    fn all_kinds(&self) -> Vec<Self::Item> {
+        Vec::new()
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+        Response = UserResponse,
+        UserState = UserState,
+        DataType = DataType,
+        ValueType = ValueType,
+    >,
+    UserResponse: UserResponseTrait,
+    ValueType:
+        WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
+    CategoryType: CategoryTrait,
+{
+    /// Returns the area covered by all nodes, in graph coordinates. Node sizes
+    /// are not known outside of drawing, so every node that wasn't resized is
+    /// assumed to take up [`GraphNodeWidget::MAX_NODE_SIZE`].
+    pub fn graph_extents(&self) -> Rect {
+        self.node_positions
+            .iter()
+            .map(|(node_id, pos)| {
+                let size = self.node_sizes.get(node_id).copied().unwrap_or_else(|| {
+                    GraphNodeWidget::<NodeData, DataType, ValueType>::MAX_NODE_SIZE.into()
+                });
+                Rect::from_min_size(*pos, size)
+            })
+            .reduce(|a, b| a.union(b))
+            .unwrap_or(Rect::from_min_size(Pos2::ZERO, Vec2::ZERO))
+    }
+
+    /// Renders the whole graph, regardless of the current viewport, into an
+    /// image. The graph is drawn by a private, offscreen egui context and
+    /// rasterized in software, so this can be called from anywhere, including
+    /// outside of a frame.
+    ///
+    /// The interactive state (selection, ongoing connections, the node finder,
+    /// panning, layout transitions) is hidden while rendering and restored
+    /// afterwards, as are the node rects, port locations and caches of the
+    /// last frame. Any responses produced by the offscreen pass are
+    /// discarded.
+    pub fn render_to_image(
+        &mut self,
+        user_state: &mut UserState,
+        options: &ImageExportOptions,
+    ) -> ColorImage {
+        let extents = self.graph_extents().expand(options.margin);
+
+        let pan_zoom = self.pan_zoom;
+        let selected_nodes = std::mem::take(&mut self.selected_nodes);
+        let connection_in_progress = self.connection_in_progress.take();
+        let ongoing_box_selection = self.ongoing_box_selection.take();
+        let node_finder = self.node_finder.take();
+        // The cached shapes refer to the font atlas of the context that
+        // painted them, so the offscreen context starts without any, and
+        // doesn't leave its own behind. Nodes are all drawn in one go.
+        let node_shapes = std::mem::take(&mut self.node_shapes);
+        let node_layouts = std::mem::take(&mut self.node_layouts);
+        let frame_budget = self.frame_budget.take();
+        // The offscreen frame is in the screen space of the image, which
+        // APIs reading these until the next frame wouldn't expect.
+        let node_rects = std::mem::take(&mut self.node_rects);
+        let port_locations = std::mem::take(&mut self.port_locations);
+        let node_rect_index = std::mem::take(&mut self.node_rect_index);
+        let node_extents = std::mem::take(&mut self.node_extents);
+        let layout_transition = std::mem::take(&mut self.layout_transition);
+        // Images are always drawn in full detail, at their own scale.
+        self.pan_zoom = PanZoom {
+            pan: -extents.min.to_vec2(),
+            zoom: 1.0,
+        };
+
+        let ctx = egui::Context::default();
+        ctx.set_visuals(options.visuals.clone());
+        let mut raw_input = egui::RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, extents.size())),
+            ..Default::default()
+        };
+        raw_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(options.scale);
+
+        // Node sizes are only known after a first layout pass, so the graph
+        // is drawn twice and only the second pass is kept.
+        let mut textures = HashMap::new();
+        let mut output = None;
+        for _ in 0..2 {
+            let full_output = ctx.run(raw_input.clone(), |ctx| {
+                let frame = egui::Frame::NONE.fill(ctx.style().visuals.panel_fill);
+                egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+                    let _ = self.draw_graph_editor(
+                        ui,
+                        NoTemplates(PhantomData),
+                        user_state,
+                        Vec::new(),
+                    );
+                });
+            });
+            for (id, delta) in &full_output.textures_delta.set {
+                apply_texture_delta(&mut textures, *id, delta);
+            }
+            output = Some(full_output);
+        }
+        let output = output.expect("The graph should be drawn at least once");
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+
+        self.pan_zoom = pan_zoom;
+        self.selected_nodes = selected_nodes;
+        self.connection_in_progress = connection_in_progress;
+        self.ongoing_box_selection = ongoing_box_selection;
+        self.node_finder = node_finder;
+        self.node_shapes = node_shapes;
+        self.node_layouts = node_layouts;
+        self.frame_budget = frame_budget;
+        self.node_rects = node_rects;
+        self.port_locations = port_locations;
+        self.node_rect_index = node_rect_index;
+        self.node_extents = node_extents;
+        self.layout_transition = layout_transition;
+
+        let size = extents.size() * output.pixels_per_point;
+        let mut image = ColorImage::new(
+            [size.x.round() as usize, size.y.round() as usize],
+            Color32::TRANSPARENT,
+        );
+        for primitive in &primitives {
+            rasterize_primitive(&mut image, &textures, primitive, output.pixels_per_point);
+        }
+
+        image
+    }
+}
+
+fn apply_texture_delta(
+    textures: &mut HashMap<TextureId, ColorImage>,
+    id: TextureId,
+    delta: &egui::epaint::ImageDelta,
+) {
+    let patch = match &delta.image {
+        ImageData::Color(image) => (**image).clone(),
+        ImageData::Font(image) => ColorImage {
+            size: image.size,
+            pixels: image.srgba_pixels(None).collect(),
+        },
+    };
+
+    match delta.pos {
+        None => {
+            textures.insert(id, patch);
+        }
+        Some([x, y]) => {
+            if let Some(texture) = textures.get_mut(&id) {
+                for row in 0..patch.size[1] {
+                    for col in 0..patch.size[0] {
+                        let dst = (y + row) * texture.size[0] + x + col;
+                        texture.pixels[dst] = patch.pixels[row * patch.size[0] + col];
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn rasterize_primitive(
+    image: &mut ColorImage,
+    textures: &HashMap<TextureId, ColorImage>,
+    primitive: &ClippedPrimitive,
+    pixels_per_point: f32,
+) {
+    let Primitive::Mesh(mesh) = &primitive.primitive else {
+        // Paint callbacks render with the host's graphics API and can't be
+        // reproduced here.
+        return;
+    };
+    let texture = textures.get(&mesh.texture_id);
+    let clip = Rect::from_min_max(
+        (primitive.clip_rect.min.to_vec2() * pixels_per_point).to_pos2(),
+        (primitive.clip_rect.max.to_vec2() * pixels_per_point).to_pos2(),
+    )
+    .intersect(Rect::from_min_size(
+        Pos2::ZERO,
+        Vec2::new(image.size[0] as f32, image.size[1] as f32),
+    ));
+
+    for tri in mesh.indices.chunks_exact(3) {
+        let v = [
+            mesh.vertices[tri[0] as usize],
+            mesh.vertices[tri[1] as usize],
+            mesh.vertices[tri[2] as usize],
+        ];
+        let p = v.map(|v| (v.pos.to_vec2() * pixels_per_point).to_pos2());
+
+        let area = edge_function(p[0], p[1], p[2]);
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let bounds = Rect::from_points(&p).intersect(clip);
+        if !bounds.is_positive() {
+            continue;
+        }
+
+        let (x0, x1) = (bounds.min.x.floor() as usize, bounds.max.x.ceil() as usize);
+        let (y0, y1) = (bounds.min.y.floor() as usize, bounds.max.y.ceil() as usize);
+        for y in y0..y1.min(image.size[1]) {
+            for x in x0..x1.min(image.size[0]) {
+                let sample = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if !clip.contains(sample) {
+                    continue;
+                }
+
+                let w = [
+                    edge_function(p[1], p[2], sample) / area,
+                    edge_function(p[2], p[0], sample) / area,
+                    edge_function(p[0], p[1], sample) / area,
+                ];
+                if w.iter().any(|w| *w < 0.0) {
+                    continue;
+                }
+
+                let mut color = [0.0; 4];
+                for (vertex, weight) in v.iter().zip(w) {
+                    for (channel, value) in color.iter_mut().zip(vertex.color.to_array()) {
+                        *channel += value as f32 * weight;
+                    }
+                }
+
+                if let Some(texture) = texture {
+                    let uv = v[0].uv.to_vec2() * w[0]
+                        + v[1].uv.to_vec2() * w[1]
+                        + v[2].uv.to_vec2() * w[2];
+                    let tx = ((uv.x * texture.size[0] as f32) as usize).min(texture.size[0] - 1);
+                    let ty = ((uv.y * texture.size[1] as f32) as usize).min(texture.size[1] - 1);
+                    let texel = texture.pixels[ty * texture.size[0] + tx].to_array();
+                    for (channel, value) in color.iter_mut().zip(texel) {
+                        *channel *= value as f32 / 255.0;
+                    }
+                }
+
+                // Both colors are premultiplied, so this is a regular "over".
+                let dst = &mut image.pixels[y * image.size[0] + x];
+                let inv_alpha = 1.0 - color[3] / 255.0;
+                let blended = [0, 1, 2, 3].map(|i| {
+                    (color[i] + dst.to_array()[i] as f32 * inv_alpha)
+                        .round()
+                        .clamp(0.0, 255.0) as u8
+                });
+                *dst = Color32::from_rgba_premultiplied(
+                    blended[0], blended[1], blended[2], blended[3],
+                );
+            }
+        }
+    }
+}
+
+fn edge_function(a: Pos2, b: Pos2, c: Pos2) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+#[cfg(all(test, feature = "synthetic"))]
+mod tests {
+    use super::*;
+    use egui::pos2;
+
+    #[test]
+    fn test_render_to_image() {
+        let mut state = SyntheticGraphOptions {
+            nodes: 2,
+            ..Default::default()
+        }
+        .build_editor_state();
+        let nodes: Vec<NodeId> = state.node_order.to_vec();
+        state.selected_nodes = vec![nodes[0]];
+        let screen_rect = Rect::from_min_size(pos2(5.0, 5.0), Vec2::splat(10.0));
+        state.node_rects.insert(nodes[0], screen_rect);
+
+        let options = ImageExportOptions {
+            scale: 0.5,
+            ..Default::default()
+        };
+        let extents = state.graph_extents().expand(options.margin);
+        let image = state.render_to_image(&mut (), &options);
+        let size = extents.size() * options.scale;
+        assert_eq!(
+            image.size,
+            [size.x.round() as usize, size.y.round() as usize]
+        );
+
+        // Inside the first node, a little below its title
+        let inside = (state.node_positions[nodes[0]] - extents.min + Vec2::splat(30.0)) * 0.5;
+        let pixel = image.pixels[inside.y as usize * image.size[0] + inside.x as usize];
+        assert_ne!(pixel, options.visuals.panel_fill);
+        assert_ne!(pixel, Color32::TRANSPARENT);
+
+        assert_eq!(state.selected_nodes, vec![nodes[0]]);
+        assert_eq!(state.pan_zoom.zoom, 1.0);
+        assert_eq!(state.pan_zoom.pan, Vec2::ZERO);
+        assert_eq!(state.node_rects.len(), 1);
+        assert_eq!(state.node_rects[&nodes[0]], screen_rect);
+        assert!(state.port_locations.is_empty());
+        assert!(state.node_extents.is_empty());
+        assert!(state.node_shapes.is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+use super::*;
+
+/// An inconsistency between the different parts of a [`Graph`] or
+/// [`GraphEditorState`]. These can't happen through the editor UI, but may
+/// be introduced by user code modifying the public fields directly, or by
+/// loading hand-edited documents.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IntegrityIssue {
+    #[error("Node {0:?} has a different id than its key.")]
+    NodeIdMismatch(NodeId),
+
+    #[error(
+        "Node {node:?} lists parameter {param:?}, which doesn't exist or belongs to another node."
+    )]
+    MissingParam { node: NodeId, param: AnyParameterId },
+
+    #[error("Parameter {0:?} isn't listed by the node it belongs to.")]
+    OrphanParam(AnyParameterId),
+
+    #[error("Connection from {output:?} to {input:?} refers to a missing parameter.")]
+    DanglingConnection { input: InputId, output: OutputId },
+
+    #[error("The connection index doesn't match the connections.")]
+    StaleConnectionIndex,
+
+    #[error("Node {0:?} is missing from node_order.")]
+    NodeNotInOrder(NodeId),
+
+    #[error("{field} refers to missing node {node:?}.")]
+    UnknownNode { field: &'static str, node: NodeId },
+
+    #[error("Node {0:?} has no position.")]
+    MissingPosition(NodeId),
+
+    #[error("Node {0:?} has no orientation.")]
+    MissingOrientation(NodeId),
+
+    #[error("The pan isn't finite, or the zoom is outside of zoom_range.")]
+    InvalidPanZoom,
+}
+
+/// The inconsistencies found by `validate_integrity`, or fixed by `repair`.
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Checks that nodes, parameters and connections agree with each other.
+    pub fn validate_integrity(&self) -> IntegrityReport {
+        let mut issues = Vec::new();
+
+        for (node_id, node) in &self.nodes {
+            if node.id != node_id {
+                issues.push(IntegrityIssue::NodeIdMismatch(node_id));
+            }
+            for param in self.missing_params(node_id, node) {
+                issues.push(IntegrityIssue::MissingParam {
+                    node: node_id,
+                    param,
+                });
+            }
+        }
+        for param in self.orphan_params() {
+            issues.push(IntegrityIssue::OrphanParam(param));
+        }
+        for (input, output) in self.dangling_connections() {
+            issues.push(IntegrityIssue::DanglingConnection { input, output });
+        }
+        if !self.connection_index_is_valid() {
+            issues.push(IntegrityIssue::StaleConnectionIndex);
+        }
+
+        IntegrityReport { issues }
+    }
+
+    /// Fixes the issues reported by [`Graph::validate_integrity`] by removing
+    /// the elements that are inconsistent, and returns them.
+    pub fn repair(&mut self) -> IntegrityReport {
+        let mut issues = Vec::new();
+
+        let node_ids: Vec<_> = self.nodes.keys().collect();
+        for node_id in node_ids {
+            let missing = self.missing_params(node_id, &self.nodes[node_id]);
+            let node = &mut self.nodes[node_id];
+            if node.id != node_id {
+                issues.push(IntegrityIssue::NodeIdMismatch(node_id));
+                node.id = node_id;
+            }
+            node.inputs
+                .retain(|(_, id)| !missing.contains(&AnyParameterId::Input(*id)));
+            node.outputs
+                .retain(|(_, id)| !missing.contains(&AnyParameterId::Output(*id)));
+            issues.extend(
+                missing
+                    .into_iter()
+                    .map(|param| IntegrityIssue::MissingParam {
+                        node: node_id,
+                        param,
+                    }),
+            );
+        }
+
+        for param in self.orphan_params() {
+            match param {
+                AnyParameterId::Input(input) => {
+                    self.inputs.remove(input);
+                }
+                AnyParameterId::Output(output) => {
+                    self.outputs.remove(output);
+                }
+            }
+            self.stable_ids.remove_param(param);
+            issues.push(IntegrityIssue::OrphanParam(param));
+        }
+
+        for (input, output) in self.dangling_connections() {
+            self.disconnect(output, input);
+            issues.push(IntegrityIssue::DanglingConnection { input, output });
+        }
+        if !self.connection_index_is_valid() {
+            issues.push(IntegrityIssue::StaleConnectionIndex);
+        }
+        self.rebuild_connection_index();
+
+        IntegrityReport { issues }
+    }
+
+    fn missing_params(&self, node_id: NodeId, node: &Node<NodeData>) -> Vec<AnyParameterId> {
+        let inputs = node
+            .input_ids()
+            .filter(|id| self.inputs.get(*id).is_none_or(|p| p.node != node_id))
+            .map(AnyParameterId::Input);
+        let outputs = node
+            .output_ids()
+            .filter(|id| self.outputs.get(*id).is_none_or(|p| p.node != node_id))
+            .map(AnyParameterId::Output);
+        inputs.chain(outputs).collect()
+    }
+
+    fn orphan_params(&self) -> Vec<AnyParameterId> {
+        let listed = |node: NodeId, param: AnyParameterId| {
+            self.nodes.get(node).is_some_and(|node| match param {
+                AnyParameterId::Input(input) => node.input_ids().any(|id| id == input),
+                AnyParameterId::Output(output) => node.output_ids().any(|id| id == output),
+            })
+        };
+        let inputs = self
+            .inputs
+            .iter()
+            .filter(|(id, param)| !listed(param.node, AnyParameterId::Input(*id)))
+            .map(|(id, _)| AnyParameterId::Input(id));
+        let outputs = self
+            .outputs
+            .iter()
+            .filter(|(id, param)| !listed(param.node, AnyParameterId::Output(*id)))
+            .map(|(id, _)| AnyParameterId::Output(id));
+        inputs.chain(outputs).collect()
+    }
+
+    fn dangling_connections(&self) -> Vec<(InputId, OutputId)> {
+        self.iter_connections()
+            .filter(|(input, output)| {
+                !self.inputs.contains_key(*input) || !self.outputs.contains_key(*output)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Checks the graph with [`Graph::validate_integrity`], and that the
+    /// editor data (draw order, positions, orientations and selection)
+    /// matches the nodes of the graph.
+    pub fn validate_integrity(&self) -> IntegrityReport {
+        let mut report = self.graph.validate_integrity();
+        report.issues.extend(self.editor_issues());
+        report
+    }
+
+    /// Fixes the issues reported by [`GraphEditorState::validate_integrity`]
+    /// and returns them. Invalid graph elements and references to missing
+    /// nodes are removed; nodes without editor data are put on top of the
+    /// others at the origin, and the zoom is brought back into
+    /// [`GraphEditorState::zoom_range`].
+    pub fn repair(&mut self) -> IntegrityReport {
+        let mut report = self.graph.repair();
+        let issues = self.editor_issues();
+
+        for issue in &issues {
+            match *issue {
+                IntegrityIssue::NodeNotInOrder(node) => self.node_order.push(node),
+                IntegrityIssue::MissingPosition(node) => {
+                    self.node_positions.insert(node, egui::Pos2::ZERO);
+                }
+                IntegrityIssue::MissingOrientation(node) => {
+                    self.node_orientations
+                        .insert(node, NodeOrientation::LeftToRight);
+                }
+                IntegrityIssue::InvalidPanZoom => self.pan_zoom.sanitize(self.zoom_range),
+                IntegrityIssue::UnknownNode { node, .. } => {
+                    self.node_order.remove(node);
+                    self.selected_nodes.retain(|id| *id != node);
+                    self.node_positions.remove(node);
+                    self.node_orientations.remove(node);
+                    self.node_sizes.remove(node);
+                    self.collapsed_nodes.remove(node);
+                    self.node_layouts.remove(node);
+                    self.node_extents.remove(node);
+                    self.node_shapes.remove(node);
+                    if self
+                        .connection_in_progress
+                        .is_some_and(|(id, _)| id == node)
+                    {
+                        self.connection_in_progress = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+        report.issues.extend(issues);
+        report
+    }
+
+    /// Whether `repair` needs to be called before drawing. This is cheaper
+    /// than `validate_integrity`, and catches the inconsistencies that would
+    /// make drawing fail.
+    pub(crate) fn needs_repair(&self) -> bool {
+        let nodes = self.graph.nodes.len();
+        self.node_order.len() != nodes
+            || self.node_positions.len() != nodes
+            || self.node_orientations.len() != nodes
+            || !self.pan_zoom.is_valid(self.zoom_range)
+            || self
+                .node_order
+                .iter()
+                .any(|id| !self.graph.nodes.contains_key(id))
+    }
+
+    fn editor_issues(&self) -> Vec<IntegrityIssue> {
+        let mut issues = Vec::new();
+        let exists = |node: NodeId| self.graph.nodes.contains_key(node);
+        let mut unknown = |field: &'static str, node: NodeId| {
+            if !exists(node) {
+                issues.push(IntegrityIssue::UnknownNode { field, node });
+            }
+        };
+
+        for node in &self.node_order {
+            unknown("node_order", node);
+        }
+        for node in &self.selected_nodes {
+            unknown("selected_nodes", *node);
+        }
+        for node in self.node_positions.keys() {
+            unknown("node_positions", node);
+        }
+        for node in self.node_orientations.keys() {
+            unknown("node_orientations", node);
+        }
+        for node in self.node_sizes.keys() {
+            unknown("node_sizes", node);
+        }
+        for node in self.collapsed_nodes.keys() {
+            unknown("collapsed_nodes", node);
+        }
+        for node in self.node_layouts.keys() {
+            unknown("node_layouts", node);
+        }
+        for node in self.node_extents.keys() {
+            unknown("node_extents", node);
+        }
+        for node in self.node_shapes.keys() {
+            unknown("node_shapes", node);
+        }
+        if let Some((node, _)) = self.connection_in_progress {
+            unknown("connection_in_progress", node);
+        }
+
+        if !self.pan_zoom.is_valid(self.zoom_range) {
+            issues.push(IntegrityIssue::InvalidPanZoom);
+        }
+        for node in self.graph.nodes.keys() {
+            if !self.node_order.contains(node) {
+                issues.push(IntegrityIssue::NodeNotInOrder(node));
+            }
+            if !self.node_positions.contains_key(node) {
+                issues.push(IntegrityIssue::MissingPosition(node));
+            }
+            if !self.node_orientations.contains_key(node) {
+                issues.push(IntegrityIssue::MissingOrientation(node));
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(all(test, feature = "ui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let a = state.graph.add_node("a".into(), (), |_, _| {});
+        let b = state.graph.add_node("b".into(), (), |_, _| {});
+        let out = state.graph.add_output_param(a, "out".into(), ());
+        let input = state.graph.add_input_param(
+            b,
+            "in".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        state.graph.add_connection(out, input);
+        state.node_order = vec![a].into();
+        state.node_positions.insert(a, egui::Pos2::ZERO);
+        state
+            .node_orientations
+            .insert(a, NodeOrientation::LeftToRight);
+        // Drop the output without going through `remove_output_param`
+        state.graph.outputs.remove(out);
+
+        let report = state.validate_integrity();
+        assert!(report.issues.contains(&IntegrityIssue::MissingParam {
+            node: a,
+            param: out.into()
+        }));
+        assert!(report
+            .issues
+            .contains(&IntegrityIssue::DanglingConnection { input, output: out }));
+        assert!(report.issues.contains(&IntegrityIssue::NodeNotInOrder(b)));
+        assert!(report.issues.contains(&IntegrityIssue::MissingPosition(b)));
+
+        let repaired = state.repair();
+        assert_eq!(repaired.issues, report.issues);
+        assert!(state.validate_integrity().is_ok());
+        assert_eq!(state.node_order.to_vec(), vec![a, b]);
+        assert!(state.graph[a].outputs.is_empty());
+        assert!(state.graph.connections.is_empty());
+    }
+
+    #[test]
+    fn test_repair_pan_zoom() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        for (zoom, repaired) in [(0.0, 1.0), (f32::NAN, 1.0), (-2.0, 1.0), (10.0, 4.0)] {
+            state.pan_zoom.zoom = zoom;
+            let report = state.validate_integrity();
+            assert_eq!(report.issues, vec![IntegrityIssue::InvalidPanZoom]);
+            state.repair();
+            assert_eq!(state.pan_zoom.zoom, repaired);
+            assert!(state.validate_integrity().is_ok());
+        }
+        state.pan_zoom.pan = egui::vec2(f32::INFINITY, 0.0);
+        state.repair();
+        assert_eq!(state.pan_zoom.pan, egui::Vec2::ZERO);
+    }
+}
@@ -0,0 +1,200 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+/// A document in the [JSON Canvas](https://jsoncanvas.org) format. This is the
+/// format used by Obsidian canvases and a growing number of other tools.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JsonCanvas {
+    pub nodes: Vec<JsonCanvasNode>,
+    pub edges: Vec<JsonCanvasEdge>,
+}
+
+/// The kind of a [`JsonCanvasNode`], with its kind-specific fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JsonCanvasNodeKind {
+    /// A node containing plain text (or markdown).
+    Text { text: String },
+    /// A labelled rectangle drawn behind other nodes.
+    Group {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonCanvasNode {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: JsonCanvasNodeKind,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    /// Either a hex color (`#rrggbb`) or one of the preset colors `"1"` to `"6"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// The side of a node an edge attaches to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonCanvasSide {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCanvasEdge {
+    pub id: String,
+    pub from_node: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_side: Option<JsonCanvasSide>,
+    pub to_node: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_side: Option<JsonCanvasSide>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl JsonCanvas {
+    /// Serializes the canvas into a pretty-printed `.canvas` JSON document.
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Canvas ids are opaque strings. Using the raw slotmap key keeps them unique
+/// within a document and stable for as long as the graph isn't reloaded.
+fn canvas_id(key: impl slotmap::Key) -> String {
+    format!("{:016x}", key.data().as_ffi())
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Exports the graph as a [`JsonCanvas`] document. Every node becomes a
+    /// text node containing its label, and every connection becomes an edge
+    /// labelled with the names of the two connected parameters.
+    ///
+    /// The editor does not store node sizes, so `node_size` is used to query
+    /// them. Returning [`GraphNodeWidget::MAX_NODE_SIZE`] is a good fallback.
+    pub fn to_json_canvas(&self, node_size: impl Fn(NodeId) -> egui::Vec2) -> JsonCanvas {
+        let mut canvas = JsonCanvas::default();
+
+        for node_id in &self.node_order {
+            let node = &self.graph[node_id];
+            let pos = self
+                .node_positions
+                .get(node_id)
+                .copied()
+                .unwrap_or(egui::Pos2::ZERO);
+            let size = node_size(node_id);
+
+            canvas.nodes.push(JsonCanvasNode {
+                id: canvas_id(node_id),
+                kind: JsonCanvasNodeKind::Text {
+                    text: node.label.clone(),
+                },
+                x: pos.x.round() as i64,
+                y: pos.y.round() as i64,
+                width: size.x.round() as i64,
+                height: size.y.round() as i64,
+                color: None,
+            });
+        }
+
+        fn param_name<Id: PartialEq>(params: &[(String, Id)], id: Id) -> Option<String> {
+            params
+                .iter()
+                .find(|(_, param_id)| *param_id == id)
+                .map(|(name, _)| name.clone())
+        }
+
+        for (input, output) in self.graph.iter_connections() {
+            let src_node = self.graph[output].node;
+            let dst_node = self.graph[input].node;
+            let side = |node_id, is_output: bool| {
+                let orientation = self
+                    .node_orientations
+                    .get(node_id)
+                    .copied()
+                    .unwrap_or(NodeOrientation::LeftToRight);
+                match (orientation, is_output) {
+                    (NodeOrientation::LeftToRight, true)
+                    | (NodeOrientation::RightToLeft, false) => JsonCanvasSide::Right,
+                    _ => JsonCanvasSide::Left,
+                }
+            };
+
+            let label = match (
+                param_name(&self.graph[src_node].outputs, output),
+                param_name(&self.graph[dst_node].inputs, input),
+            ) {
+                (Some(from), Some(to)) => Some(format!("{from} → {to}")),
+                _ => None,
+            };
+
+            canvas.edges.push(JsonCanvasEdge {
+                id: canvas_id(input),
+                from_node: canvas_id(src_node),
+                from_side: Some(side(src_node, true)),
+                to_node: canvas_id(dst_node),
+                to_side: Some(side(dst_node, false)),
+                label,
+            });
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), (), (), (), ()>;
+
+    #[test]
+    fn test_json_canvas_export() {
+        let mut state = TestState::default();
+        let add = |state: &mut TestState, label: &str, pos| {
+            let id = state.graph.add_node(label.into(), (), |_, _| {});
+            state.node_order.push(id);
+            state.node_positions.insert(id, pos);
+            state
+                .node_orientations
+                .insert(id, NodeOrientation::LeftToRight);
+            id
+        };
+        let a = add(&mut state, "a", egui::pos2(10.0, 20.0));
+        let b = add(&mut state, "b", egui::pos2(300.0, 20.0));
+        let out = state.graph.add_output_param(a, "out".into(), ());
+        let inp = state.graph.add_input_param(
+            b,
+            "in".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        state.graph.add_connection(out, inp);
+
+        let canvas = state.to_json_canvas(|_| egui::vec2(100.0, 50.0));
+        assert_eq!(canvas.nodes.len(), 2);
+        assert_eq!(canvas.nodes[1].x, 300);
+        assert_eq!(canvas.nodes[1].width, 100);
+        assert_eq!(canvas.edges.len(), 1);
+        assert_eq!(canvas.edges[0].from_node, canvas.nodes[0].id);
+        assert_eq!(canvas.edges[0].from_side, Some(JsonCanvasSide::Right));
+        assert_eq!(canvas.edges[0].label.as_deref(), Some("out → in"));
+
+        let json = canvas.to_json_string().unwrap();
+        assert!(json.contains("\"type\": \"text\""));
+        assert!(json.contains("\"fromNode\""));
+    }
+}
@@ -0,0 +1,597 @@
+use super::*;
+use egui::{pos2, vec2, Color32, Rect};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// The value of the `format` field of the documents written by
+/// [`GraphEditorState::save_to_json`].
+pub const JSON_FORMAT_NAME: &str = "egui-graph-edit";
+
+/// The version of the documents written by
+/// [`GraphEditorState::save_to_json`]. Bumped, with a new entry in
+/// [`JSON_MIGRATIONS`], whenever the document changes in a way older readers
+/// or the current reader wouldn't understand.
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+/// Upgrades a document by one version, in place. See [`JSON_MIGRATIONS`].
+pub type JsonMigration = fn(&mut Value) -> Result<(), String>;
+
+/// The migrations of older documents, the first one upgrading version 1 to
+/// version 2 and so on, so there is always one less than
+/// [`JSON_FORMAT_VERSION`]. Documents are upgraded as raw JSON before being
+/// read, so migrations keep working whatever becomes of the types of this
+/// module.
+pub const JSON_MIGRATIONS: &[JsonMigration] = &[];
+
+/// A saved editor state, see [`GraphEditorState::save_to_json`]. Unlike the
+/// serde derives of [`GraphEditorState`], which follow its fields and the
+/// slotmap keys, the document only refers to nodes and parameters by their
+/// index, so that internal refactors don't change it.
+///
+/// Only the document is kept: selections, caches and editor settings are
+/// left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    deserialize = "NodeData: Deserialize<'de>, DataType: Deserialize<'de>, ValueType: Deserialize<'de>"
+))]
+pub struct JsonDocument<NodeData, DataType, ValueType> {
+    /// Always [`JSON_FORMAT_NAME`].
+    pub format: String,
+    pub version: u32,
+    /// The nodes, in drawing order.
+    pub nodes: Vec<JsonNode<NodeData, DataType, ValueType>>,
+    #[serde(default)]
+    pub connections: Vec<JsonConnection>,
+    #[serde(default)]
+    pub frames: Vec<JsonFrame>,
+    #[serde(default)]
+    pub view: JsonView,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    deserialize = "NodeData: Deserialize<'de>, DataType: Deserialize<'de>, ValueType: Deserialize<'de>"
+))]
+pub struct JsonNode<NodeData, DataType, ValueType> {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+    pub position: [f32; 2],
+    #[serde(default)]
+    pub orientation: JsonOrientation,
+    /// The size of resizable nodes, see [`GraphEditorState::node_sizes`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub inputs: Vec<JsonInput<DataType, ValueType>>,
+    #[serde(default)]
+    pub outputs: Vec<JsonOutput<DataType>>,
+    pub data: NodeData,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonOrientation {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonInputKind {
+    ConnectionOnly,
+    ConstantOnly,
+    ConnectionOrConstant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonInput<DataType, ValueType> {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+    pub typ: DataType,
+    pub value: ValueType,
+    pub kind: JsonInputKind,
+    #[serde(default = "shown_inline_default")]
+    pub shown_inline: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub multi_input: bool,
+}
+
+fn shown_inline_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonOutput<DataType> {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+    pub typ: DataType,
+}
+
+/// A parameter of a node, as the index of the node in
+/// [`JsonDocument::nodes`] and the index of the parameter in the inputs or
+/// outputs of the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonPort {
+    pub node: usize,
+    pub param: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonConnection {
+    pub output: JsonPort,
+    pub input: JsonPort,
+    /// See [`GraphEditorState::waypoints`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waypoints: Vec<[f32; 2]>,
+}
+
+/// A [`CommentFrame`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonFrame {
+    pub title: String,
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    /// The fill of the frame, as unmultiplied RGBA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[u8; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct JsonView {
+    pub pan: [f32; 2],
+    pub zoom: f32,
+}
+
+impl Default for JsonView {
+    fn default() -> Self {
+        Self {
+            pan: [0.0; 2],
+            zoom: 1.0,
+        }
+    }
+}
+
+/// Upgrades a versioned document to [`JSON_FORMAT_VERSION`] with
+/// `migrations`, the first of which upgrades version 1.
+pub(crate) fn migrate_document(
+    document: &mut Value,
+    migrations: &[JsonMigration],
+) -> Result<(), EguiGraphError> {
+    let supported = migrations.len() as u32 + 1;
+    let found = document
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .filter(|version| *version > 0)
+        .ok_or_else(|| EguiGraphError::InvalidDocument("missing or invalid version".into()))?;
+    if found > supported {
+        return Err(EguiGraphError::UnsupportedFormatVersion { found, supported });
+    }
+    for (version, migration) in (found..).zip(&migrations[found as usize - 1..]) {
+        migration(document).map_err(|err| {
+            EguiGraphError::InvalidDocument(format!(
+                "upgrading from version {version} failed: {err}"
+            ))
+        })?;
+        document["version"] = Value::from(version + 1);
+    }
+    Ok(())
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone + PartialEq,
+    ValueType: Clone,
+{
+    /// The document of the editor, see [`JsonDocument`].
+    pub fn to_json_document(&self) -> JsonDocument<NodeData, DataType, ValueType> {
+        let graph = &self.graph;
+        let stable_id = |param: AnyParameterId| graph.param_stable_id(param).map(str::to_owned);
+        let node_indices: SecondaryMap<NodeId, usize> = self
+            .node_order
+            .iter()
+            .enumerate()
+            .map(|(index, node_id)| (node_id, index))
+            .collect();
+        let port = |node_id: NodeId, index: Option<usize>| {
+            Some(JsonPort {
+                node: *node_indices.get(node_id)?,
+                param: index?,
+            })
+        };
+
+        let nodes = self
+            .node_order
+            .iter()
+            .map(|node_id| {
+                let node = &graph[node_id];
+                let position = self
+                    .node_positions
+                    .get(node_id)
+                    .copied()
+                    .unwrap_or(egui::Pos2::ZERO);
+                let orientation = match self.node_orientations.get(node_id) {
+                    Some(NodeOrientation::RightToLeft) => JsonOrientation::RightToLeft,
+                    _ => JsonOrientation::LeftToRight,
+                };
+                JsonNode {
+                    label: node.label.clone(),
+                    stable_id: graph.node_stable_id(node_id).map(str::to_owned),
+                    position: [position.x, position.y],
+                    orientation,
+                    size: self.node_sizes.get(node_id).map(|size| [size.x, size.y]),
+                    collapsed: self.collapsed_nodes.contains_key(node_id),
+                    inputs: node
+                        .inputs
+                        .iter()
+                        .map(|(name, input_id)| {
+                            let input = &graph[*input_id];
+                            JsonInput {
+                                name: name.clone(),
+                                stable_id: stable_id((*input_id).into()),
+                                typ: input.typ.clone(),
+                                value: input.value.clone(),
+                                kind: match input.kind {
+                                    InputParamKind::ConnectionOnly => JsonInputKind::ConnectionOnly,
+                                    InputParamKind::ConstantOnly => JsonInputKind::ConstantOnly,
+                                    InputParamKind::ConnectionOrConstant => {
+                                        JsonInputKind::ConnectionOrConstant
+                                    }
+                                },
+                                shown_inline: input.shown_inline,
+                                multi_input: input.multi_input,
+                            }
+                        })
+                        .collect(),
+                    outputs: node
+                        .outputs
+                        .iter()
+                        .map(|(name, output_id)| JsonOutput {
+                            name: name.clone(),
+                            stable_id: stable_id((*output_id).into()),
+                            typ: graph[*output_id].typ.clone(),
+                        })
+                        .collect(),
+                    data: node.user_data.clone(),
+                }
+            })
+            .collect();
+
+        let connections = graph
+            .iter_connections()
+            .filter_map(|(input, output)| {
+                let (input_node, output_node) = (graph[input].node, graph[output].node);
+                let input_index = graph[input_node]
+                    .inputs
+                    .iter()
+                    .position(|(_, id)| *id == input);
+                let output_index = graph[output_node]
+                    .outputs
+                    .iter()
+                    .position(|(_, id)| *id == output);
+                Some(JsonConnection {
+                    output: port(output_node, output_index)?,
+                    input: port(input_node, input_index)?,
+                    waypoints: self
+                        .connection_waypoints(input, output)
+                        .iter()
+                        .map(|point| [point.x, point.y])
+                        .collect(),
+                })
+            })
+            .collect();
+
+        let frames = self
+            .frames
+            .values()
+            .map(|frame| JsonFrame {
+                title: frame.title.clone(),
+                min: [frame.rect.min.x, frame.rect.min.y],
+                max: [frame.rect.max.x, frame.rect.max.y],
+                color: frame.color.map(|color| color.to_srgba_unmultiplied()),
+            })
+            .collect();
+
+        JsonDocument {
+            format: JSON_FORMAT_NAME.into(),
+            version: JSON_FORMAT_VERSION,
+            nodes,
+            connections,
+            frames,
+            view: JsonView {
+                pan: [self.pan_zoom.pan.x, self.pan_zoom.pan.y],
+                zoom: self.pan_zoom.zoom,
+            },
+        }
+    }
+
+    /// Builds an editor state from a document. Fails if a connection refers
+    /// to a missing node or parameter, if stable ids are used twice, or if
+    /// the zoom isn't a positive number.
+    pub fn from_json_document(
+        document: JsonDocument<NodeData, DataType, ValueType>,
+    ) -> Result<Self, EguiGraphError> {
+        let zoom = document.view.zoom;
+        if !zoom.is_finite() || zoom <= 0.0 {
+            return Err(EguiGraphError::InvalidDocument(format!(
+                "invalid zoom {zoom}"
+            )));
+        }
+        let mut state = Self::default();
+        let mut node_ids = Vec::with_capacity(document.nodes.len());
+        for node in document.nodes {
+            let node_id = state.graph.add_node(node.label, node.data, |_, _| {});
+            if let Some(id) = node.stable_id {
+                state.graph.set_node_stable_id(node_id, id)?;
+            }
+            for input in node.inputs {
+                let kind = match input.kind {
+                    JsonInputKind::ConnectionOnly => InputParamKind::ConnectionOnly,
+                    JsonInputKind::ConstantOnly => InputParamKind::ConstantOnly,
+                    JsonInputKind::ConnectionOrConstant => InputParamKind::ConnectionOrConstant,
+                };
+                let input_id = state.graph.add_input_param(
+                    node_id,
+                    input.name,
+                    input.typ,
+                    input.value,
+                    kind,
+                    input.shown_inline,
+                );
+                state.graph.inputs[input_id].multi_input = input.multi_input;
+                if let Some(id) = input.stable_id {
+                    state.graph.set_param_stable_id(input_id.into(), id)?;
+                }
+            }
+            for output in node.outputs {
+                let output_id = state
+                    .graph
+                    .add_output_param(node_id, output.name, output.typ);
+                if let Some(id) = output.stable_id {
+                    state.graph.set_param_stable_id(output_id.into(), id)?;
+                }
+            }
+
+            let [x, y] = node.position;
+            state.node_positions.insert(node_id, pos2(x, y));
+            let orientation = match node.orientation {
+                JsonOrientation::LeftToRight => NodeOrientation::LeftToRight,
+                JsonOrientation::RightToLeft => NodeOrientation::RightToLeft,
+            };
+            state.node_orientations.insert(node_id, orientation);
+            if let Some([width, height]) = node.size {
+                state.node_sizes.insert(node_id, vec2(width, height));
+            }
+            if node.collapsed {
+                state.collapsed_nodes.insert(node_id, ());
+            }
+            state.node_order.push(node_id);
+            node_ids.push(node_id);
+        }
+
+        for connection in document.connections {
+            let missing = || {
+                EguiGraphError::InvalidDocument(format!(
+                    "connection from {:?} to {:?} refers to a missing parameter",
+                    connection.output, connection.input
+                ))
+            };
+            let node = |port: JsonPort| node_ids.get(port.node).copied().ok_or_else(missing);
+            let output = state.graph[node(connection.output)?]
+                .outputs
+                .get(connection.output.param)
+                .ok_or_else(missing)?
+                .1;
+            let input = state.graph[node(connection.input)?]
+                .inputs
+                .get(connection.input.param)
+                .ok_or_else(missing)?
+                .1;
+            state.graph.add_connection(output, input);
+            for (index, [x, y]) in connection.waypoints.into_iter().enumerate() {
+                state.insert_waypoint(input, output, index, pos2(x, y));
+            }
+        }
+        // Inputs that aren't multi-inputs keep their last connection
+        state.prune_waypoints();
+
+        for frame in document.frames {
+            state.frames.insert(CommentFrame {
+                title: frame.title,
+                rect: Rect::from_min_max(frame.min.into(), frame.max.into()),
+                color: frame
+                    .color
+                    .map(|[r, g, b, a]| Color32::from_rgba_unmultiplied(r, g, b, a)),
+            });
+        }
+        state.pan_zoom = PanZoom {
+            pan: document.view.pan.into(),
+            zoom: document.view.zoom,
+        };
+        Ok(state)
+    }
+
+    /// Saves the editor as a [`JsonDocument`], the format to use for files
+    /// since [`Self::load_from_json`] keeps reading it across versions of
+    /// this crate.
+    pub fn save_to_json(&self) -> Result<String, EguiGraphError>
+    where
+        NodeData: Serialize,
+        DataType: Serialize,
+        ValueType: Serialize,
+    {
+        Ok(serde_json::to_string_pretty(&self.to_json_document())?)
+    }
+
+    /// Loads a document saved by [`Self::save_to_json`], upgrading documents
+    /// of older versions with [`JSON_MIGRATIONS`]. Documents without a
+    /// format, i.e. editor states serialized directly with serde, are read
+    /// as a [`SavedEditorState`].
+    pub fn load_from_json(json: &str) -> Result<Self, EguiGraphError>
+    where
+        NodeData: DeserializeOwned,
+        DataType: DeserializeOwned,
+        ValueType: DeserializeOwned,
+        SavedEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>: DeserializeOwned,
+    {
+        let mut document: Value = serde_json::from_str(json)?;
+        match document.get("format") {
+            Some(Value::String(format)) if format == JSON_FORMAT_NAME => {}
+            Some(format) => {
+                return Err(EguiGraphError::InvalidDocument(format!(
+                    "unknown format {format}"
+                )))
+            }
+            None => {
+                let saved: SavedEditorState<_, _, _, _, _> = serde_json::from_value(document)?;
+                return Ok(saved.into_state());
+            }
+        }
+        migrate_document(&mut document, JSON_MIGRATIONS)?;
+        Self::from_json_document(serde_json::from_value(document)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type State = GraphEditorState<String, u32, f32, (), ()>;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut state = State::default();
+        let a = state.graph.add_node("a".into(), "A".into(), |_, _| {});
+        let b = state.graph.add_node("b".into(), "B".into(), |_, _| {});
+        let output = state.graph.add_output_param(a, "out".into(), 1);
+        let input = state.graph.add_input_param(
+            b,
+            "in".into(),
+            1,
+            0.5,
+            InputParamKind::ConnectionOrConstant,
+            true,
+        );
+        state.graph.add_connection(output, input);
+        state.graph.set_node_stable_id(b, "sink").unwrap();
+        state.node_order = vec![b, a].into();
+        state.node_positions.insert(a, pos2(1.0, 2.0));
+        state.node_positions.insert(b, pos2(3.0, 4.0));
+        state
+            .node_orientations
+            .insert(b, NodeOrientation::RightToLeft);
+        state.collapsed_nodes.insert(a, ());
+        state.insert_waypoint(input, output, 0, pos2(5.0, 6.0));
+        state.add_frame("Group", &[a]);
+
+        let json = state.save_to_json().unwrap();
+        let loaded = State::load_from_json(&json).unwrap();
+        assert!(loaded.validate_integrity().is_ok());
+        let order: Vec<NodeId> = loaded.node_order.to_vec();
+        let [b, a] = order[..] else {
+            panic!("expected two nodes, got {order:?}");
+        };
+        assert_eq!(loaded.graph[a].user_data, "A");
+        assert_eq!(loaded.graph.node_by_stable_id("sink"), Some(b));
+        assert_eq!(loaded.node_positions[b], pos2(3.0, 4.0));
+        assert!(matches!(
+            loaded.node_orientations[b],
+            NodeOrientation::RightToLeft
+        ));
+        assert!(loaded.collapsed_nodes.contains_key(a));
+        let (input, output) = loaded.graph.iter_connections().next().unwrap();
+        assert_eq!(loaded.graph[input].value, 0.5);
+        assert_eq!(loaded.connection_waypoints(input, output), [pos2(5.0, 6.0)]);
+        assert_eq!(loaded.frames.len(), 1);
+        assert_eq!(loaded.save_to_json().unwrap(), json);
+
+        // States serialized directly still load
+        let raw = serde_json::to_string(&state).unwrap();
+        assert_eq!(State::load_from_json(&raw).unwrap().graph.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_json_migrations() {
+        fn rename_title(document: &mut Value) -> Result<(), String> {
+            for node in document["nodes"].as_array_mut().ok_or("no nodes")? {
+                let node = node.as_object_mut().ok_or("invalid node")?;
+                let title = node.remove("title").ok_or("node without title")?;
+                node.insert("label".into(), title);
+            }
+            Ok(())
+        }
+        const MIGRATIONS: &[JsonMigration] = &[rename_title];
+
+        let mut document = json!({
+            "format": JSON_FORMAT_NAME,
+            "version": 1,
+            "nodes": [{"title": "a", "position": [0.0, 0.0], "data": "A"}],
+        });
+        migrate_document(&mut document, MIGRATIONS).unwrap();
+        assert_eq!(document["version"], 2);
+        assert_eq!(document["nodes"][0]["label"], "a");
+        let document: JsonDocument<String, u32, f32> = serde_json::from_value(document).unwrap();
+        let state = State::from_json_document(document).unwrap();
+        assert_eq!(state.graph.nodes.len(), 1);
+
+        let mut newer = json!({"format": JSON_FORMAT_NAME, "version": 3, "nodes": []});
+        assert!(matches!(
+            migrate_document(&mut newer, MIGRATIONS),
+            Err(EguiGraphError::UnsupportedFormatVersion {
+                found: 3,
+                supported: 2
+            })
+        ));
+        let dangling = json!({
+            "format": JSON_FORMAT_NAME,
+            "version": 1,
+            "nodes": [],
+            "connections": [{"output": {"node": 0, "param": 0}, "input": {"node": 1, "param": 0}}],
+        });
+        assert!(matches!(
+            State::load_from_json(&dangling.to_string()),
+            Err(EguiGraphError::InvalidDocument(_))
+        ));
+    }
+
+    #[test]
+    fn test_json_invalid_zoom() {
+        let document = |zoom| {
+            json!({
+                "format": JSON_FORMAT_NAME,
+                "version": 1,
+                "nodes": [],
+                "view": {"pan": [0.0, 0.0], "zoom": zoom},
+            })
+            .to_string()
+        };
+        assert_eq!(
+            State::load_from_json(&document(2.0)).unwrap().pan_zoom.zoom,
+            2.0
+        );
+        for zoom in [0.0, -1.0] {
+            assert!(matches!(
+                State::load_from_json(&document(zoom)),
+                Err(EguiGraphError::InvalidDocument(_))
+            ));
+        }
+
+        // Not representable in JSON, but documents can be built in code
+        let mut document = State::default().to_json_document();
+        document.view.zoom = f32::NAN;
+        assert!(matches!(
+            State::from_json_document(document),
+            Err(EguiGraphError::InvalidDocument(_))
+        ));
+    }
+}
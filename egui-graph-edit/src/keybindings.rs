@@ -0,0 +1,278 @@
+use super::*;
+use egui::{Key, KeyboardShortcut, Modifiers, Ui};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The keyboard shortcuts of the editor, see
+/// [`GraphEditorState::keybindings`]. Set a shortcut to `None` to disable
+/// its action, e.g. when the application handles the key itself.
+///
+/// The shortcuts only apply while the pointer is over the editor and no
+/// widget has the keyboard focus, so that they don't get in the way of
+/// typing in the nodes. The [`Default`] ones are:
+///
+/// | Action | Shortcut |
+/// |---|---|
+/// | `delete`, `delete_alt` | Delete, Backspace |
+/// | `select_all` | Ctrl+A |
+/// | `duplicate` | None, Ctrl+D once clipboard shortcuts are enabled |
+/// | `cancel_connection` | Escape |
+/// | `highlight_path` | Alt+P |
+/// | `select_islands` | Alt+I |
+/// | `frame_selection` | Ctrl+G |
+/// | `undo`, `redo` | Ctrl+Z, Ctrl+Shift+Z, once undo is enabled |
+/// | `arrange` | Alt+arrows to align edges, Alt+V and Alt+H to align centers, Alt+Shift+H and Alt+Shift+V to distribute |
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct EditorKeybindings {
+    /// Deletes the selected nodes, except those whose
+    /// [`NodeDataTrait::can_delete`] returns false.
+    pub delete: Option<KeyboardShortcut>,
+    /// Another shortcut deleting the selected nodes, Backspace by default
+    /// for keyboards without a Delete key.
+    pub delete_alt: Option<KeyboardShortcut>,
+    pub select_all: Option<KeyboardShortcut>,
+    /// Duplicates the selected nodes, see
+    /// [`GraphEditorState::duplicate_selection`]. Duplicating needs the
+    /// bounds of copying nodes, so this is opt-in: it is `None` by default,
+    /// and [`GraphEditorState::enable_clipboard_shortcuts`] sets it to
+    /// Ctrl+D unless another shortcut was set before.
+    pub duplicate: Option<KeyboardShortcut>,
+    /// Drops the connection being dragged from a port.
+    pub cancel_connection: Option<KeyboardShortcut>,
+    /// Highlights the paths between the two selected nodes, see
+    /// [`GraphEditorState::highlight_path`]. Pressing it again with another
+    /// selection, or pressing escape, clears the highlight.
+    pub highlight_path: Option<KeyboardShortcut>,
+    /// Selects the nodes connected to the node under the cursor, or to the
+    /// selected nodes when the cursor isn't over a node, see
+    /// [`GraphEditorState::select_islands`].
+    pub select_islands: Option<KeyboardShortcut>,
+    /// Puts a frame around the selected nodes, see
+    /// [`GraphEditorState::add_frame`].
+    pub frame_selection: Option<KeyboardShortcut>,
+    /// Undoes the last edit, see [`GraphEditorState::undo`]. Only used once
+    /// [`GraphEditorState::enable_undo`] was called.
+    pub undo: Option<KeyboardShortcut>,
+    /// Redoes the last undone edit, see [`GraphEditorState::redo`]. Checked
+    /// before `undo`, so it may add modifiers to the undo shortcut.
+    pub redo: Option<KeyboardShortcut>,
+    /// Performs the [`ArrangeAction`]s on the selected nodes, in the order
+    /// of [`ArrangeAction::ALL`]. The selection menu shows them too.
+    pub arrange: [Option<KeyboardShortcut>; 8],
+}
+
+impl Default for EditorKeybindings {
+    fn default() -> Self {
+        Self {
+            delete: Some(KeyboardShortcut::new(Modifiers::NONE, Key::Delete)),
+            delete_alt: Some(KeyboardShortcut::new(Modifiers::NONE, Key::Backspace)),
+            select_all: Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::A)),
+            duplicate: None,
+            cancel_connection: Some(KeyboardShortcut::new(Modifiers::NONE, Key::Escape)),
+            highlight_path: Some(KeyboardShortcut::new(Modifiers::ALT, Key::P)),
+            select_islands: Some(KeyboardShortcut::new(Modifiers::ALT, Key::I)),
+            frame_selection: Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::G)),
+            undo: Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)),
+            redo: Some(KeyboardShortcut::new(
+                Modifiers::COMMAND.plus(Modifiers::SHIFT),
+                Key::Z,
+            )),
+            arrange: [
+                Some(KeyboardShortcut::new(Modifiers::ALT, Key::ArrowLeft)),
+                Some(KeyboardShortcut::new(Modifiers::ALT, Key::V)),
+                Some(KeyboardShortcut::new(Modifiers::ALT, Key::ArrowRight)),
+                Some(KeyboardShortcut::new(Modifiers::ALT, Key::ArrowUp)),
+                Some(KeyboardShortcut::new(Modifiers::ALT, Key::H)),
+                Some(KeyboardShortcut::new(Modifiers::ALT, Key::ArrowDown)),
+                Some(KeyboardShortcut::new(
+                    Modifiers::ALT.plus(Modifiers::SHIFT),
+                    Key::H,
+                )),
+                Some(KeyboardShortcut::new(
+                    Modifiers::ALT.plus(Modifiers::SHIFT),
+                    Key::V,
+                )),
+            ],
+        }
+    }
+}
+
+impl EditorKeybindings {
+    /// No shortcuts at all.
+    pub fn none() -> Self {
+        Self {
+            delete: None,
+            delete_alt: None,
+            select_all: None,
+            duplicate: None,
+            cancel_connection: None,
+            highlight_path: None,
+            select_islands: None,
+            frame_selection: None,
+            undo: None,
+            redo: None,
+            arrange: [None; 8],
+        }
+    }
+
+    /// The shortcut of `action`.
+    pub fn arrange_shortcut(&self, action: ArrangeAction) -> Option<KeyboardShortcut> {
+        ArrangeAction::ALL
+            .iter()
+            .position(|(other, _)| *other == action)
+            .and_then(|index| self.arrange[index])
+    }
+}
+
+/// Consumes `shortcut` if it was pressed on this frame.
+pub(crate) fn consume_shortcut(ui: &Ui, shortcut: Option<KeyboardShortcut>) -> bool {
+    shortcut.is_some_and(|shortcut| ui.input_mut(|i| i.consume_shortcut(&shortcut)))
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+        Response = UserResponse,
+        UserState = UserState,
+        DataType = DataType,
+        ValueType = ValueType,
+    >,
+    UserResponse: UserResponseTrait,
+    DataType: PartialEq,
+{
+    /// Performs the actions of the [`EditorKeybindings`] pressed on this
+    /// frame, and returns the responses of the nodes they created or
+    /// deleted.
+    pub(crate) fn handle_keybindings(
+        &mut self,
+        ui: &Ui,
+        user_state: &mut UserState,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let keybindings = self.keybindings;
+        let pressed = |shortcut| consume_shortcut(ui, shortcut);
+        let mut responses = Vec::new();
+
+        if pressed(keybindings.cancel_connection) {
+            self.connection_in_progress = None;
+        }
+        if pressed(keybindings.select_all) {
+            self.selected_nodes = self.node_order.to_vec();
+        }
+        if let Some(hooks) = self.clipboard_hooks {
+            if pressed(keybindings.duplicate) {
+                let nodes = (hooks.duplicate)(self);
+                responses.extend(nodes.into_iter().map(NodeResponse::CreatedNode));
+            }
+        }
+        // Both shortcuts are consumed
+        let delete = pressed(keybindings.delete);
+        if pressed(keybindings.delete_alt) || delete {
+            responses.extend(self.delete_selection(user_state));
+        }
+        responses
+    }
+
+    /// Deletes the selected nodes, except those whose
+    /// [`NodeDataTrait::can_delete`] returns false.
+    pub(crate) fn delete_selection(
+        &mut self,
+        user_state: &mut UserState,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = Vec::new();
+        for node_id in self.selected_nodes.clone() {
+            let can_delete =
+                self.graph.nodes.get(node_id).is_some_and(|node| {
+                    node.user_data.can_delete(node_id, &self.graph, user_state)
+                });
+            if can_delete {
+                responses.extend(self.delete_node(node_id));
+            }
+        }
+        responses
+    }
+}
+
+#[cfg(all(test, feature = "synthetic"))]
+mod tests {
+    use super::*;
+    use crate::synthetic::{run_editor_frame, SyntheticEditorState, SyntheticGraphOptions};
+    use egui::{pos2, Event, RawInput};
+
+    #[test]
+    fn test_keybindings() {
+        let mut state = SyntheticGraphOptions {
+            nodes: 3,
+            ..Default::default()
+        }
+        .build_editor_state();
+        let nodes = state.node_order.to_vec();
+
+        let ctx = egui::Context::default();
+        let press = |state: &mut SyntheticEditorState, modifiers, key| {
+            let input = RawInput {
+                events: vec![
+                    Event::PointerMoved(pos2(500.0, 400.0)),
+                    Event::Key {
+                        key,
+                        physical_key: None,
+                        pressed: true,
+                        repeat: false,
+                        modifiers,
+                    },
+                ],
+                modifiers,
+                ..Default::default()
+            };
+            run_editor_frame(&ctx, state, input).0.node_responses
+        };
+
+        press(&mut state, Modifiers::COMMAND, Key::A);
+        assert_eq!(state.selected_nodes, nodes);
+
+        // Duplicating waits for the clipboard shortcuts
+        assert_eq!(state.keybindings.duplicate, None);
+        assert!(press(&mut state, Modifiers::COMMAND, Key::D).is_empty());
+        assert_eq!(state.graph.nodes.len(), 3);
+        state.enable_clipboard_shortcuts();
+        assert_eq!(
+            state.keybindings.duplicate,
+            Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::D))
+        );
+        let responses = press(&mut state, Modifiers::COMMAND, Key::D);
+        assert_eq!(state.graph.nodes.len(), 6);
+        assert_eq!(responses.len(), 3);
+        assert!(responses
+            .iter()
+            .all(|response| matches!(response, NodeResponse::CreatedNode(_))));
+        let duplicates = state.selected_nodes.clone();
+        assert!(duplicates.iter().all(|node| !nodes.contains(node)));
+
+        let output = state.graph[nodes[0]].output_ids().next().unwrap();
+        state.connection_in_progress = Some((nodes[0], AnyParameterId::Output(output)));
+        press(&mut state, Modifiers::NONE, Key::Escape);
+        assert_eq!(state.connection_in_progress, None);
+
+        // Disabled shortcuts are left alone
+        state.keybindings.delete = None;
+        assert!(press(&mut state, Modifiers::NONE, Key::Delete).is_empty());
+        assert_eq!(state.graph.nodes.len(), 6);
+
+        let responses = press(&mut state, Modifiers::NONE, Key::Backspace);
+        assert_eq!(state.graph.nodes.len(), 3);
+        for node in &duplicates {
+            assert!(!state.graph.nodes.contains_key(*node));
+            assert!(responses.iter().any(|response| matches!(
+                response,
+                NodeResponse::DeleteNodeFull { node_id, .. } if node_id == node
+            )));
+        }
+
+        state.selected_nodes = nodes.clone();
+        let responses = press(&mut state, Modifiers::COMMAND, Key::G);
+        assert!(matches!(responses[..], [NodeResponse::CreatedFrame(_)]));
+        assert_eq!(state.frames.len(), 1);
+    }
+}
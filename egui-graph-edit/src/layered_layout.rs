@@ -0,0 +1,383 @@
+use super::*;
+use egui::{Pos2, Vec2};
+
+/// Lays out the whole graph in layers from left to right, following the
+/// connections (the Sugiyama method): each node goes one layer past the
+/// nodes feeding it, the nodes of each layer are ordered to reduce the
+/// number of crossing connections, then moved up or down towards the nodes
+/// they are connected to. Cycles are broken at the earliest node in draw
+/// order.
+///
+/// The layout keeps the top left corner of the nodes where it was. See
+/// [`GraphEditorState::auto_layout`].
+#[derive(Debug, Clone)]
+pub struct LayeredLayout {
+    /// The space between two layers.
+    pub layer_spacing: f32,
+    /// The space between two nodes of a layer.
+    pub node_spacing: f32,
+    /// The number of passes ordering the layers, and moving the nodes
+    /// towards their neighbours.
+    pub sweeps: usize,
+}
+
+impl Default for LayeredLayout {
+    fn default() -> Self {
+        Self {
+            layer_spacing: 80.0,
+            node_spacing: 30.0,
+            sweeps: 8,
+        }
+    }
+}
+
+/// A node of the layout, or a point a connection spanning several layers
+/// goes through.
+struct Vertex {
+    node: Option<NodeId>,
+    size: Vec2,
+    layer: usize,
+    /// The vertices connected to it in the previous and the next layer.
+    ups: Vec<usize>,
+    downs: Vec<usize>,
+}
+
+impl LayoutAlgorithm for LayeredLayout {
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+        let nodes: Vec<NodeId> = input
+            .nodes
+            .iter()
+            .copied()
+            .filter(|node_id| input.positions.contains_key(*node_id))
+            .collect();
+        let Some(origin) = nodes
+            .iter()
+            .map(|node_id| input.positions[*node_id])
+            .reduce(Pos2::min)
+        else {
+            return LayoutPositions::new();
+        };
+        let ranks = longest_path_ranks(input);
+        let size = |node_id: NodeId| {
+            input
+                .sizes
+                .get(node_id)
+                .copied()
+                .unwrap_or(UNMEASURED_NODE_SIZE)
+        };
+
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(nodes.len());
+        let mut vertex_of: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        // Where the vertices are across their layer, to order them at first
+        let mut keys: Vec<f32> = Vec::with_capacity(nodes.len());
+        for node_id in &nodes {
+            vertex_of.insert(*node_id, vertices.len());
+            keys.push(input.positions[*node_id].y + size(*node_id).y / 2.0);
+            vertices.push(Vertex {
+                node: Some(*node_id),
+                size: size(*node_id),
+                layer: ranks[*node_id],
+                ups: Vec::new(),
+                downs: Vec::new(),
+            });
+        }
+        for (from, to) in &input.edges {
+            let (Some(&from), Some(&to)) = (vertex_of.get(*from), vertex_of.get(*to)) else {
+                continue;
+            };
+            // Connections closing a cycle go backwards
+            let (from, to) = match vertices[from].layer.cmp(&vertices[to].layer) {
+                std::cmp::Ordering::Less => (from, to),
+                std::cmp::Ordering::Greater => (to, from),
+                std::cmp::Ordering::Equal => continue,
+            };
+            let (from_layer, to_layer) = (vertices[from].layer, vertices[to].layer);
+            let mut previous = from;
+            for layer in from_layer + 1..=to_layer {
+                let next = if layer == to_layer {
+                    to
+                } else {
+                    let t = (layer - from_layer) as f32 / (to_layer - from_layer) as f32;
+                    keys.push(keys[from] + (keys[to] - keys[from]) * t);
+                    vertices.push(Vertex {
+                        node: None,
+                        size: Vec2::ZERO,
+                        layer,
+                        ups: Vec::new(),
+                        downs: Vec::new(),
+                    });
+                    vertices.len() - 1
+                };
+                vertices[previous].downs.push(next);
+                vertices[next].ups.push(previous);
+                previous = next;
+            }
+        }
+
+        let layer_count = vertices
+            .iter()
+            .map(|vertex| vertex.layer + 1)
+            .max()
+            .unwrap_or(0);
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+        for (index, vertex) in vertices.iter().enumerate() {
+            layers[vertex.layer].push(index);
+        }
+        for layer in &mut layers {
+            layer.sort_by(|a, b| keys[*a].total_cmp(&keys[*b]));
+        }
+        let layers = self.order_layers(&vertices, layers);
+
+        // Layers are as wide as their widest node
+        let mut x = origin.x;
+        let mut layer_xs = Vec::with_capacity(layer_count);
+        for layer in &layers {
+            layer_xs.push(x);
+            let width = layer
+                .iter()
+                .map(|index| vertices[*index].size.x)
+                .fold(0.0, f32::max);
+            x += width + self.layer_spacing;
+        }
+        let tops = self.place_in_layers(&vertices, &layers);
+
+        let top = vertices
+            .iter()
+            .zip(&tops)
+            .filter(|(vertex, _)| vertex.node.is_some())
+            .map(|(_, top)| *top)
+            .fold(f32::INFINITY, f32::min);
+        vertices
+            .iter()
+            .zip(&tops)
+            .filter_map(|(vertex, y)| {
+                let node_id = vertex.node?;
+                let x = layer_xs[vertex.layer];
+                Some((node_id, Pos2::new(x, origin.y + y - top)))
+            })
+            .collect()
+    }
+}
+
+impl LayeredLayout {
+    /// Reorders the vertices of each layer by the mean position of their
+    /// neighbours in the layer before it, going right then left, and keeps
+    /// the order with the fewest crossings.
+    fn order_layers(&self, vertices: &[Vertex], mut layers: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+        // The position of each vertex in its layer
+        let mut positions = vec![0; vertices.len()];
+        for layer in &layers {
+            for (position, index) in layer.iter().enumerate() {
+                positions[*index] = position;
+            }
+        }
+        let mut best_crossings = crossings(vertices, &layers, &positions);
+        let mut best = layers.clone();
+
+        for sweep in 0..self.sweeps {
+            if best_crossings == 0 {
+                break;
+            }
+            let downwards = sweep % 2 == 0;
+            let order: Vec<usize> = if downwards {
+                (1..layers.len()).collect()
+            } else {
+                (0..layers.len().saturating_sub(1)).rev().collect()
+            };
+            for layer in order {
+                let barycenters: Vec<f32> = layers[layer]
+                    .iter()
+                    .map(|index| {
+                        let vertex = &vertices[*index];
+                        let neighbours = if downwards {
+                            &vertex.ups
+                        } else {
+                            &vertex.downs
+                        };
+                        if neighbours.is_empty() {
+                            positions[*index] as f32
+                        } else {
+                            neighbours.iter().map(|n| positions[*n] as f32).sum::<f32>()
+                                / neighbours.len() as f32
+                        }
+                    })
+                    .collect();
+                let mut order: Vec<usize> = (0..layers[layer].len()).collect();
+                order.sort_by(|a, b| barycenters[*a].total_cmp(&barycenters[*b]));
+                layers[layer] = order.iter().map(|i| layers[layer][*i]).collect();
+                for (position, index) in layers[layer].iter().enumerate() {
+                    positions[*index] = position;
+                }
+            }
+            let crossings = crossings(vertices, &layers, &positions);
+            if crossings < best_crossings {
+                best_crossings = crossings;
+                best = layers.clone();
+            }
+        }
+        best
+    }
+
+    /// The top of each vertex: stacked in the order of their layer, and
+    /// moved as close as the order allows to the mean center of their
+    /// neighbours.
+    fn place_in_layers(&self, vertices: &[Vertex], layers: &[Vec<usize>]) -> Vec<f32> {
+        let mut tops = vec![0.0; vertices.len()];
+        for layer in layers {
+            let mut y = 0.0;
+            for index in layer {
+                tops[*index] = y;
+                y += vertices[*index].size.y + self.node_spacing;
+            }
+        }
+
+        for _ in 0..self.sweeps {
+            for layer in layers {
+                let center =
+                    |index: usize, tops: &[f32]| tops[index] + vertices[index].size.y / 2.0;
+                // Each vertex is at least this far below the top of the first
+                let mut offsets = Vec::with_capacity(layer.len());
+                let mut offset = 0.0;
+                for index in layer {
+                    offsets.push(offset);
+                    offset += vertices[*index].size.y + self.node_spacing;
+                }
+                let targets: Vec<f32> = layer
+                    .iter()
+                    .zip(&offsets)
+                    .map(|(index, offset)| {
+                        let vertex = &vertices[*index];
+                        let neighbours = vertex.ups.iter().chain(&vertex.downs);
+                        let count = vertex.ups.len() + vertex.downs.len();
+                        let target = if count == 0 {
+                            center(*index, &tops)
+                        } else {
+                            neighbours.map(|n| center(*n, &tops)).sum::<f32>() / count as f32
+                        };
+                        target - vertex.size.y / 2.0 - offset
+                    })
+                    .collect();
+                for ((index, offset), shift) in layer.iter().zip(&offsets).zip(monotonic(&targets))
+                {
+                    tops[*index] = offset + shift;
+                }
+            }
+        }
+        tops
+    }
+}
+
+/// The number of connections crossing each other between the layers.
+fn crossings(vertices: &[Vertex], layers: &[Vec<usize>], positions: &[usize]) -> usize {
+    let mut total = 0;
+    for pair in layers.windows(2) {
+        let mut edges: Vec<(usize, usize)> = pair[0]
+            .iter()
+            .flat_map(|index| {
+                vertices[*index]
+                    .downs
+                    .iter()
+                    .map(|down| (positions[*index], positions[*down]))
+            })
+            .collect();
+        edges.sort_unstable();
+        // Counts the connections starting above and ending below each one
+        let mut tree = vec![0; pair[1].len() + 1];
+        for (seen, (_, end)) in edges.iter().enumerate() {
+            let mut below_or_at = 0;
+            let mut i = end + 1;
+            while i > 0 {
+                below_or_at += tree[i];
+                i &= i - 1;
+            }
+            total += seen - below_or_at;
+            let mut i = end + 1;
+            while i < tree.len() {
+                tree[i] += 1;
+                i += i & i.wrapping_neg();
+            }
+        }
+    }
+    total
+}
+
+/// The nondecreasing sequence closest to `targets`, by least squares.
+fn monotonic(targets: &[f32]) -> Vec<f32> {
+    // Blocks of values pooled to their mean, with their length
+    let mut blocks: Vec<(f32, usize)> = Vec::with_capacity(targets.len());
+    for target in targets {
+        let mut block = (*target, 1);
+        while let Some(&(mean, len)) = blocks.last() {
+            if mean < block.0 {
+                break;
+            }
+            blocks.pop();
+            let total = len + block.1;
+            block = (
+                (mean * len as f32 + block.0 * block.1 as f32) / total as f32,
+                total,
+            );
+        }
+        blocks.push(block);
+    }
+    blocks
+        .into_iter()
+        .flat_map(|(mean, len)| std::iter::repeat_n(mean, len))
+        .collect()
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Arranges the whole graph with the default [`LayeredLayout`], e.g.
+    /// after importing or generating a graph. Nodes glide to their new
+    /// positions unless [`GraphEditorState::layout_transition_duration`] is
+    /// zero.
+    pub fn auto_layout(&mut self) {
+        self.run_layout(&LayeredLayout::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_layered_layout() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b, c, d, e] =
+            ["a", "b", "c", "d", "e"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+        let mut input = LayoutInput::default();
+        for (node_id, y) in [(a, 0.0), (b, 500.0), (c, 0.0), (d, 300.0), (e, 100.0)] {
+            input.nodes.push(node_id);
+            input.positions.insert(node_id, pos2(100.0, y + 10.0));
+            input.sizes.insert(node_id, vec2(100.0, 50.0));
+        }
+        // a and b cross over to d and c, b skips a layer to e, and e loops
+        // back to a
+        input.edges = vec![(a, d), (b, c), (c, e), (b, e), (d, e), (e, a)];
+
+        let positions = LayeredLayout::default().layout(&input);
+        assert_eq!(positions.len(), 5);
+        assert_eq!(positions[a].x, 100.0);
+        assert_eq!(positions[b].x, 100.0);
+        assert_eq!(positions[c].x, 280.0);
+        assert_eq!(positions[e].x, 460.0);
+        // The connections from a and b no longer cross
+        assert!(positions[d].y < positions[c].y);
+        assert_eq!(
+            positions.values().map(|pos| pos.y).reduce(f32::min),
+            Some(10.0)
+        );
+        // Nodes of a layer don't overlap
+        assert!(positions[b].y - positions[a].y >= 80.0);
+        assert!(positions[c].y - positions[d].y >= 80.0);
+    }
+
+    #[test]
+    fn test_monotonic() {
+        assert_eq!(monotonic(&[1.0, 3.0, 2.0, 5.0]), vec![1.0, 2.5, 2.5, 5.0]);
+        assert_eq!(crossings(&[], &[], &[]), 0);
+    }
+}
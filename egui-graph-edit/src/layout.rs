@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+use super::*;
+use egui::{Pos2, Vec2};
+
+/// The size assumed for nodes that haven't been drawn yet.
+pub(crate) const UNMEASURED_NODE_SIZE: Vec2 = Vec2::new(200.0, 100.0);
+
+/// The parts of an editor state that layout algorithms work with: the
+/// nodes, their current position and size, and which nodes are connected.
+/// It is copied out of the editor so that layouts can run on another thread
+/// while the editor keeps being drawn.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutInput {
+    /// Nodes in draw order.
+    pub nodes: Vec<NodeId>,
+    pub positions: SecondaryMap<NodeId, Pos2>,
+    /// The size of each node as of the last time it was drawn.
+    pub sizes: SecondaryMap<NodeId, Vec2>,
+    pub orientations: SecondaryMap<NodeId, NodeOrientation>,
+    /// Connections, as pairs of the node producing a value and the node
+    /// receiving it. Several connections between two nodes are listed once.
+    pub edges: Vec<(NodeId, NodeId)>,
+}
+
+/// The new positions computed by a layout. Nodes that aren't in it keep
+/// their position.
+pub type LayoutPositions = SecondaryMap<NodeId, Pos2>;
+
+/// A way to arrange the nodes of a graph. Implement it to provide layouts
+/// from other crates, and run them with [`GraphEditorState::run_layout`],
+/// or on another thread with [`GraphEditorState::spawn_layout`].
+///
+/// It is implemented for closures, so boxed algorithms can be stored next
+/// to simple functions, e.g. to list them in a menu.
+pub trait LayoutAlgorithm: Send {
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions;
+}
+
+impl<F> LayoutAlgorithm for F
+where
+    F: Fn(&LayoutInput) -> LayoutPositions + Send,
+{
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+        self(input)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Copies what layout algorithms need out of the editor state.
+    pub fn layout_input(&self) -> LayoutInput {
+        let mut edges: Vec<(NodeId, NodeId)> = self
+            .graph
+            .iter_connections()
+            .filter_map(|(input, output)| {
+                let from = self.graph.outputs.get(output)?.node;
+                let to = self.graph.inputs.get(input)?.node;
+                Some((from, to))
+            })
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+
+        let size = |node_id: NodeId| {
+            self.node_layouts
+                .get(node_id)
+                .map(|layout| layout.size)
+                .or_else(|| {
+                    self.node_rects
+                        .get(&node_id)
+                        .map(|rect| rect.size() / self.pan_zoom.zoom)
+                })
+                .unwrap_or(UNMEASURED_NODE_SIZE)
+        };
+        LayoutInput {
+            nodes: self.node_order.to_vec(),
+            positions: self.node_positions.clone(),
+            sizes: self.node_order.iter().map(|id| (id, size(id))).collect(),
+            orientations: self.node_orientations.clone(),
+            edges,
+        }
+    }
+
+    /// Moves nodes to the positions computed by a layout, see
+    /// [`GraphEditorState::move_nodes_animated`]. Nodes that were deleted
+    /// since the layout started are ignored.
+    pub fn apply_layout(&mut self, positions: &LayoutPositions) {
+        self.move_nodes_animated(positions.iter().map(|(id, pos)| (id, *pos)));
+    }
+
+    /// Runs `layout` on the calling thread and moves the nodes to the
+    /// positions it computes, see [`GraphEditorState::apply_layout`].
+    pub fn run_layout(&mut self, layout: &dyn LayoutAlgorithm) {
+        let positions = layout.layout(&self.layout_input());
+        self.apply_layout(&positions);
+    }
+
+    /// Runs `layout` on a new thread with the current [`LayoutInput`], so
+    /// that arranging large graphs doesn't freeze the UI. `ctx` is repainted
+    /// once the layout is done; poll the returned [`LayoutJob`] every frame
+    /// and pass its result to [`GraphEditorState::apply_layout`].
+    ///
+    /// Edits made while the layout runs are kept, except for the positions
+    /// of the nodes it moves. To run a [`LayoutAlgorithm`], pass a closure
+    /// calling [`LayoutAlgorithm::layout`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn_layout(
+        &self,
+        ctx: &egui::Context,
+        layout: impl FnOnce(&LayoutInput) -> LayoutPositions + Send + 'static,
+    ) -> LayoutJob {
+        let input = self.layout_input();
+        let ctx = ctx.clone();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            // The receiving end may have been dropped to cancel the layout
+            let _ = sender.send(layout(&input));
+            ctx.request_repaint();
+        });
+        LayoutJob { receiver }
+    }
+}
+
+/// A layout running on another thread, see
+/// [`GraphEditorState::spawn_layout`]. Dropping it discards the result.
+pub struct LayoutJob {
+    receiver: mpsc::Receiver<LayoutPositions>,
+}
+
+impl LayoutJob {
+    /// The computed positions once the layout is done. Returns `None` while
+    /// it runs, and after the result has been taken.
+    pub fn poll(&self) -> Option<LayoutPositions> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Blocks until the layout is done. Returns `None` if the layout
+    /// panicked.
+    pub fn wait(self) -> Option<LayoutPositions> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// The rank of each node in the order of the connections: one past the
+/// highest rank of the nodes feeding it, starting from zero. Cycles are
+/// broken at the earliest node in draw order.
+pub(crate) fn longest_path_ranks(input: &LayoutInput) -> SecondaryMap<NodeId, usize> {
+    let mut successors: SecondaryMap<NodeId, Vec<NodeId>> = SecondaryMap::new();
+    let mut pending: SecondaryMap<NodeId, usize> =
+        input.nodes.iter().map(|node_id| (*node_id, 0)).collect();
+    for (from, to) in &input.edges {
+        if pending.contains_key(*from) && pending.contains_key(*to) && from != to {
+            successors.entry(*from).unwrap().or_default().push(*to);
+            pending[*to] += 1;
+        }
+    }
+
+    // Ranks are final once a node is taken out of `ready`
+    let mut ranks: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+    let mut earliest: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+    let mut ready: VecDeque<NodeId> = input
+        .nodes
+        .iter()
+        .copied()
+        .filter(|node_id| pending[*node_id] == 0)
+        .collect();
+    let mut unvisited = input.nodes.iter();
+    while ranks.len() < pending.len() {
+        let node_id = match ready.pop_front() {
+            Some(node_id) => node_id,
+            // Only cycles are left
+            None => *unvisited
+                .find(|node_id| !ranks.contains_key(**node_id))
+                .unwrap(),
+        };
+        if ranks.contains_key(node_id) {
+            continue;
+        }
+        let rank = earliest.get(node_id).copied().unwrap_or(0);
+        ranks.insert(node_id, rank);
+        for next in successors.get(node_id).into_iter().flatten() {
+            if ranks.contains_key(*next) {
+                continue;
+            }
+            let next_rank = earliest.entry(*next).unwrap().or_insert(0);
+            *next_rank = (*next_rank).max(rank + 1);
+            pending[*next] -= 1;
+            if pending[*next] == 0 {
+                ready.push_back(*next);
+            }
+        }
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_layout() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let mut add_node = |x: f32| {
+            let node = state.graph.add_node("node".into(), (), |_, _| {});
+            state.node_order.push(node);
+            state.node_positions.insert(node, egui::pos2(x, 0.0));
+            node
+        };
+        let (a, b) = (add_node(0.0), add_node(0.0));
+        let output = state.graph.add_output_param(a, "out".into(), ());
+        for _ in 0..2 {
+            let input = state.graph.add_input_param(
+                b,
+                "in".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            state.graph.add_connection(output, input);
+        }
+        assert_eq!(state.layout_input().edges, vec![(a, b)]);
+
+        // Lay out the nodes in a row, in draw order
+        let job = state.spawn_layout(&egui::Context::default(), |input| {
+            let mut x = 0.0;
+            let mut positions = LayoutPositions::new();
+            for node in &input.nodes {
+                positions.insert(*node, egui::pos2(x, 0.0));
+                x += input.sizes[*node].x + 10.0;
+            }
+            positions
+        });
+        state.graph.remove_node(a);
+        state.node_order.remove(a);
+        state.node_positions.remove(a);
+
+        state.apply_layout(&job.wait().unwrap());
+        assert_eq!(state.node_positions.len(), 1);
+        assert_eq!(
+            state.node_positions[b],
+            egui::pos2(UNMEASURED_NODE_SIZE.x + 10.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_run_layout() {
+        /// Stacks the nodes in a column, in draw order
+        struct Column {
+            spacing: f32,
+        }
+        impl LayoutAlgorithm for Column {
+            fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+                let mut y = 0.0;
+                let mut positions = LayoutPositions::new();
+                for node in &input.nodes {
+                    positions.insert(*node, egui::pos2(0.0, y));
+                    y += input.sizes[*node].y + self.spacing;
+                }
+                positions
+            }
+        }
+
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let [a, b] = ["a", "b"].map(|label| {
+            let node = state.graph.add_node(label.into(), (), |_, _| {});
+            state.node_order.push(node);
+            state.node_positions.insert(node, egui::pos2(50.0, 50.0));
+            node
+        });
+        let layouts: Vec<Box<dyn LayoutAlgorithm>> = vec![
+            Box::new(|_: &LayoutInput| LayoutPositions::new()),
+            Box::new(Column { spacing: 10.0 }),
+        ];
+        for layout in &layouts {
+            state.run_layout(layout.as_ref());
+        }
+        assert_eq!(state.node_positions[a], egui::pos2(0.0, 0.0));
+        assert_eq!(
+            state.node_positions[b],
+            egui::pos2(0.0, UNMEASURED_NODE_SIZE.y + 10.0)
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::marker::PhantomData;
+
+use super::*;
+
+use serde::Deserialize;
+
+/// The editor state as saved by the upstream `egui_node_graph` crate, which
+/// this crate is a fork of. Use [`GraphEditorState::from_legacy`], or
+/// deserialize a [`SavedEditorState`], to open documents written by
+/// applications that used it.
+///
+/// Transient UI state (ongoing connections, box selection, node finder) is
+/// not kept, and older saves with a single `selected_node` are supported.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LegacyGraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    pub graph: Graph<NodeData, DataType, ValueType>,
+    #[serde(default)]
+    pub node_order: Vec<NodeId>,
+    #[serde(default)]
+    pub selected_nodes: Vec<NodeId>,
+    #[serde(default)]
+    pub selected_node: Option<NodeId>,
+    pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
+    #[serde(default)]
+    pub pan_zoom: PanZoom,
+    #[serde(skip)]
+    pub _phantom: PhantomData<fn() -> (NodeTemplate, UserState)>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    DataType: PartialEq,
+{
+    /// Converts a state saved by `egui_node_graph`. Fields that didn't exist
+    /// there get their default values, e.g. all nodes are oriented left to
+    /// right, and the result goes through [`GraphEditorState::repair`] since
+    /// older versions didn't always keep `node_order` up to date.
+    pub fn from_legacy(
+        legacy: LegacyGraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> Self {
+        let LegacyGraphEditorState {
+            graph,
+            node_order,
+            mut selected_nodes,
+            selected_node,
+            node_positions,
+            pan_zoom,
+            _phantom,
+        } = legacy;
+        selected_nodes.extend(selected_node);
+
+        let node_orientations = graph
+            .iter_nodes()
+            .map(|node_id| (node_id, NodeOrientation::LeftToRight))
+            .collect();
+        let mut state = Self {
+            graph,
+            node_order: node_order.into(),
+            selected_nodes,
+            node_positions,
+            node_orientations,
+            pan_zoom,
+            ..Default::default()
+        };
+        state.repair();
+        state
+    }
+}
+
+/// A saved editor state in either the current format or the format of
+/// `egui_node_graph`. Deserialize this instead of [`GraphEditorState`] to
+/// keep opening documents saved before switching to this crate.
+// Only lives between deserializing and `into_state`, so boxing would not
+// save anything.
+#[allow(clippy::large_enum_variant)]
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    bound(
+        deserialize = "GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>: \
+                         Deserialize<'de>, \
+                         LegacyGraphEditorState<NodeData, DataType, ValueType, NodeTemplate, \
+                         UserState>: Deserialize<'de>"
+    )
+)]
+pub enum SavedEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    Current(GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>),
+    Legacy(LegacyGraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>),
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    SavedEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    DataType: PartialEq,
+{
+    pub fn into_state(
+        self,
+    ) -> GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        match self {
+            SavedEditorState::Current(state) => state,
+            SavedEditorState::Legacy(legacy) => GraphEditorState::from_legacy(legacy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type State = GraphEditorState<(), (), (), (), ()>;
+
+    #[test]
+    fn test_load_legacy_state() {
+        let mut state = State::default();
+        let a = state.graph.add_node("a".into(), (), |_, _| {});
+        let b = state.graph.add_node("b".into(), (), |_, _| {});
+        state.node_order = vec![a, b].into();
+        state.node_positions.insert(a, egui::pos2(1.0, 2.0));
+        state.node_positions.insert(b, egui::pos2(3.0, 4.0));
+        state
+            .node_orientations
+            .insert(a, NodeOrientation::RightToLeft);
+        state
+            .node_orientations
+            .insert(b, NodeOrientation::RightToLeft);
+
+        let mut saved = serde_json::to_value(&state).unwrap();
+        let current: SavedEditorState<(), (), (), (), ()> =
+            serde_json::from_value(saved.clone()).unwrap();
+        assert!(matches!(current, SavedEditorState::Current(_)));
+
+        // Strip the value down to what `egui_node_graph` 0.3 used to write.
+        let object = saved.as_object_mut().unwrap();
+        object.remove("node_orientations");
+        object.remove("selected_nodes");
+        object.insert(
+            "selected_node".into(),
+            serde_json::to_value(Some(b)).unwrap(),
+        );
+        object["graph"]
+            .as_object_mut()
+            .unwrap()
+            .remove("stable_ids");
+
+        let legacy: SavedEditorState<(), (), (), (), ()> = serde_json::from_value(saved).unwrap();
+        assert!(matches!(legacy, SavedEditorState::Legacy(_)));
+        let loaded = legacy.into_state();
+        assert!(loaded.validate_integrity().is_ok());
+        assert_eq!(loaded.selected_nodes, vec![b]);
+        assert_eq!(loaded.node_positions[b], egui::pos2(3.0, 4.0));
+        assert!(matches!(
+            loaded.node_orientations[a],
+            NodeOrientation::LeftToRight
+        ));
+    }
+
+    #[test]
+    fn test_load_zero_zoom() {
+        let mut state = State::default();
+        let a = state.graph.add_node("a".into(), (), |_, _| {});
+        state.node_order = vec![a].into();
+        state.node_positions.insert(a, egui::pos2(1.0, 2.0));
+        state
+            .node_orientations
+            .insert(a, NodeOrientation::LeftToRight);
+
+        // Saves from before zoom ranges, and from egui_node_graph, store a
+        // zoom of 0.
+        let mut saved = serde_json::to_value(&state).unwrap();
+        saved["pan_zoom"]["zoom"] = 0.0.into();
+        let mut legacy = saved.clone();
+        legacy.as_object_mut().unwrap().remove("node_orientations");
+
+        for saved in [saved, legacy] {
+            let loaded: SavedEditorState<(), (), (), (), ()> =
+                serde_json::from_value(saved).unwrap();
+            let mut loaded = loaded.into_state();
+            assert_eq!(loaded.pan_zoom.zoom, 1.0);
+            assert!(loaded.validate_integrity().is_ok());
+
+            let range = loaded.zoom_range;
+            loaded
+                .pan_zoom
+                .zoom_around(2.0, egui::vec2(100.0, 100.0), range);
+            assert!(loaded.pan_zoom.pan.is_finite());
+            assert_eq!(loaded.pan_zoom.zoom, 2.0);
+        }
+    }
+}
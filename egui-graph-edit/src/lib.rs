@@ -1,4 +1,8 @@
 #![forbid(unsafe_code)]
+//! Without the default `ui` feature, this crate only contains the graph model
+//! ([`Graph`], its ids and the algorithms working on it) and doesn't depend on
+//! egui. This lets servers and command line tools load, validate and evaluate
+//! graphs made with the editor.
 
 use slotmap::{SecondaryMap, SlotMap};
 
@@ -19,28 +23,314 @@ pub mod index_impls;
 /// Implementing the main methods for the `Graph`
 pub mod graph_impls;
 
+/// Fast lookups of the connections of a node or an output
+pub mod adjacency;
+
+/// Ordering the nodes along their connections, and finding their cycles,
+/// their islands and the paths between them
+pub mod topology;
+pub use topology::*;
+
+/// Finding nodes and parameters by label, kind, data or name
+pub mod query;
+pub use query::*;
+
+/// Statistics about the size and shape of graphs
+pub mod stats;
+pub use stats::*;
+
+/// Finding the nodes affected by edits, to recompute only those
+pub mod dirty;
+pub use dirty::*;
+
+/// Checking graphs for problems, reported as diagnostics on their elements
+pub mod validation;
+pub use validation::*;
+
+/// Stable identifiers for graph elements, which survive across sessions
+pub mod stable_id;
+pub use stable_id::*;
+
+/// Importing graphs and graph fragments into an existing graph
+pub mod fragment;
+pub use fragment::*;
+
+/// Rebuilding the storage of graphs to reclaim the memory of removed elements
+pub mod compact;
+
+/// Describing graphs in the DOT language of Graphviz
+pub mod dot;
+pub use dot::*;
+
+/// A serializable stream of graph operations, to synchronize graphs
+#[cfg(feature = "ui")]
+pub mod operations;
+#[cfg(feature = "ui")]
+pub use operations::*;
+
+/// Undoing and redoing the edits made to the graph
+#[cfg(feature = "ui")]
+pub mod undo;
+#[cfg(feature = "ui")]
+pub use undo::*;
+
+/// Editing the graph from code, the same way the UI does
+#[cfg(feature = "ui")]
+pub mod commands;
+#[cfg(feature = "ui")]
+pub use commands::*;
+
+/// Evaluating graphs in dependency order, caching the values of outputs
+#[cfg(feature = "evaluator")]
+pub mod evaluator;
+#[cfg(feature = "evaluator")]
+pub use evaluator::*;
+
+/// Nodes computing their outputs by port name, run by a scheduler
+#[cfg(feature = "evaluator")]
+pub mod compute;
+#[cfg(feature = "evaluator")]
+pub use compute::*;
+
 /// Custom error types, crate-wide
 pub mod error;
 pub use error::*;
 
 /// The main struct in the library, contains all the necessary state to draw the
 /// UI graph
+#[cfg(feature = "ui")]
 pub mod ui_state;
+#[cfg(feature = "ui")]
 pub use ui_state::*;
 
+/// The draw order of the nodes of an editor
+#[cfg(feature = "ui")]
+pub mod node_order;
+#[cfg(feature = "ui")]
+pub use node_order::*;
+
 /// The node finder is a tiny widget allowing to create new node types
+#[cfg(feature = "ui")]
 pub mod node_finder;
+#[cfg(feature = "ui")]
 pub use node_finder::*;
 
 /// The inner details of the egui implementation. Most egui code lives here.
+#[cfg(feature = "ui")]
 pub mod editor_ui;
+#[cfg(feature = "ui")]
 pub use editor_ui::*;
 
+/// The colors and measurements the editor is drawn with
+#[cfg(feature = "ui")]
+pub mod style;
+#[cfg(feature = "ui")]
+pub use style::*;
+
 /// Several traits that must be implemented by the user to customize the
 /// behavior of this library.
 pub mod traits;
 pub use traits::*;
 
+/// Creating nodes from files and payloads dropped onto the editor
+#[cfg(feature = "ui")]
+pub mod external_drop;
+#[cfg(feature = "ui")]
+pub use external_drop::*;
+
+/// Rendering of remote collaborators' cursors and selections
+#[cfg(feature = "ui")]
+pub mod presence;
+#[cfg(feature = "ui")]
+pub use presence::*;
+
+/// Live values shown next to outputs, fed by the application
+#[cfg(feature = "ui")]
+pub mod probe;
+#[cfg(feature = "ui")]
+pub use probe::*;
+
+/// Painting application overlays above the graph
+#[cfg(feature = "ui")]
+pub mod overlay;
+#[cfg(feature = "ui")]
+pub use overlay::*;
+
+/// Configurable keyboard shortcuts for the common editing actions
+#[cfg(feature = "ui")]
+pub mod keybindings;
+#[cfg(feature = "ui")]
+pub use keybindings::*;
+
+/// Titled frames drawn behind groups of nodes
+#[cfg(feature = "ui")]
+pub mod frames;
+#[cfg(feature = "ui")]
+pub use frames::*;
+
+/// Routing connections through waypoints
+#[cfg(feature = "ui")]
+pub mod waypoints;
+#[cfg(feature = "ui")]
+pub use waypoints::*;
+
+/// Choosing how connections are drawn
+#[cfg(feature = "ui")]
+pub mod connection_style;
+#[cfg(feature = "ui")]
+pub use connection_style::*;
+
+/// A scaled-down view of the whole graph in a corner of the editor
+#[cfg(feature = "ui")]
+pub mod minimap;
+#[cfg(feature = "ui")]
+pub use minimap::*;
+
+/// Offscreen rendering of the whole graph into an image
+#[cfg(feature = "ui")]
+pub mod image_export;
+#[cfg(feature = "ui")]
+pub use image_export::*;
+
+/// Export of the editor state to the JSON Canvas format
+#[cfg(feature = "json_canvas")]
+pub mod json_canvas;
+#[cfg(feature = "json_canvas")]
+pub use json_canvas::*;
+
+/// Spreading node updates over several frames on huge graphs
+#[cfg(feature = "ui")]
+pub mod frame_budget;
+#[cfg(feature = "ui")]
+pub use frame_budget::*;
+
+/// Finding the nodes in an area of the graph
+#[cfg(feature = "ui")]
+pub mod spatial_index;
+#[cfg(feature = "ui")]
+pub use spatial_index::*;
+
+/// Aligning and distributing the selected nodes
+#[cfg(feature = "ui")]
+pub mod align;
+#[cfg(feature = "ui")]
+pub use align::*;
+
+/// Separating overlapping nodes, while dragging or all at once
+#[cfg(feature = "ui")]
+pub mod push_aside;
+#[cfg(feature = "ui")]
+pub use push_aside::*;
+
+/// Running layout algorithms on the graph, possibly on another thread
+#[cfg(feature = "ui")]
+pub mod layout;
+#[cfg(feature = "ui")]
+pub use layout::*;
+
+/// Laying out the dependency trees of chosen root nodes
+#[cfg(feature = "ui")]
+pub mod tree_layout;
+#[cfg(feature = "ui")]
+pub use tree_layout::*;
+
+/// A grid behind the nodes, which nodes can snap to
+#[cfg(feature = "ui")]
+pub mod background_grid;
+#[cfg(feature = "ui")]
+pub use background_grid::*;
+
+/// Laying out the whole graph in layers following the connections
+#[cfg(feature = "ui")]
+pub mod layered_layout;
+#[cfg(feature = "ui")]
+pub use layered_layout::*;
+
+/// Laying out the graph on a grid, for schematic diagrams
+#[cfg(feature = "ui")]
+pub mod grid_layout;
+#[cfg(feature = "ui")]
+pub use grid_layout::*;
+
+/// Laying out nodes in lanes, e.g. by pipeline stage
+#[cfg(feature = "ui")]
+pub mod swimlane_layout;
+#[cfg(feature = "ui")]
+pub use swimlane_layout::*;
+
+/// Animating nodes to the positions set by layouts
+#[cfg(feature = "ui")]
+pub mod transition;
+#[cfg(feature = "ui")]
+pub use transition::*;
+
+/// Several graphs edited in tabs, with copy and paste between them
+#[cfg(feature = "ui")]
+pub mod workspace;
+#[cfg(feature = "ui")]
+pub use workspace::*;
+
+/// Graphs of a workspace hosted in the tabs of a docking layout
+#[cfg(feature = "dock")]
+pub mod dock;
+
+/// Consistency checks and repairs for graphs and editor states
+pub mod integrity;
+pub use integrity::*;
+
+/// Copy and paste of nodes through the system clipboard
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+
+/// Loading of states saved by the upstream `egui_node_graph` crate
+#[cfg(all(feature = "ui", feature = "persistence"))]
+pub mod legacy;
+#[cfg(all(feature = "ui", feature = "persistence"))]
+pub use legacy::*;
+
+/// Loading saved graphs with detailed reports of invalid elements
+#[cfg(feature = "load_diagnostics")]
+pub mod load_report;
+#[cfg(feature = "load_diagnostics")]
+pub use load_report::*;
+
+/// Versioned JSON documents, with migrations of older versions
+#[cfg(feature = "json")]
+pub mod json_format;
+#[cfg(feature = "json")]
+pub use json_format::*;
+
+/// Loading huge saved graphs over several frames or on a worker thread
+#[cfg(all(feature = "ui", feature = "load_diagnostics"))]
+pub mod chunked_load;
+#[cfg(all(feature = "ui", feature = "load_diagnostics"))]
+pub use chunked_load::*;
+
+/// Conversion between the editor state and ComfyUI workflows
+#[cfg(feature = "comfyui")]
+pub mod comfyui;
+#[cfg(feature = "comfyui")]
+pub use comfyui::*;
+
+/// Conversions between [`Graph`] and the `petgraph` graph types
+#[cfg(feature = "petgraph")]
+pub mod petgraph_impls;
+#[cfg(feature = "petgraph")]
+pub use petgraph_impls::*;
+
+/// Generated graphs for benchmarks and stress tests
+#[cfg(feature = "synthetic")]
+pub mod synthetic;
+#[cfg(feature = "synthetic")]
+pub use synthetic::*;
+
+#[cfg(feature = "ui")]
+mod prelayout;
+#[cfg(feature = "ui")]
+use prelayout::*;
+
+#[cfg(feature = "ui")]
 mod utils;
 
+#[cfg(feature = "ui")]
 mod color_hex_utils;
@@ -0,0 +1,419 @@
+use std::fmt;
+
+use super::*;
+use crate::adjacency::SavedConnections;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use slotmap::{Key, KeyData};
+
+/// What to do when a saved graph contains invalid elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Loading fails if anything is invalid. The [`LoadReport`] lists every
+    /// problem, not only the first one.
+    #[default]
+    Strict,
+    /// Invalid nodes, parameters and connections are left out, along with
+    /// everything that refers to them, and the rest of the graph is loaded.
+    SkipInvalid,
+}
+
+/// The part of a saved graph a [`LoadIssue`] is about. Ids are the ones
+/// found in the saved data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadElement {
+    /// The document as a whole, e.g. malformed JSON or a missing field.
+    Document,
+    Node(NodeId),
+    Input(InputId),
+    Output(OutputId),
+    /// The connection leading to this input.
+    Connection(InputId),
+    /// Editor data outside the graph: node order, positions, selection...
+    EditorState,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadIssue {
+    pub element: LoadElement,
+    /// Location of the problem in the saved JSON, e.g. `graph.nodes[3]`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for LoadIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// The problems found while loading a saved graph. See
+/// [`Graph::from_json_str_with_report`] and
+/// [`GraphEditorState::from_json_str_with_report`].
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub issues: Vec<LoadIssue>,
+}
+
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Whether an issue was reported about this element.
+    pub fn mentions(&self, element: LoadElement) -> bool {
+        self.issues.iter().any(|issue| issue.element == element)
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        element: LoadElement,
+        path: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.issues.push(LoadIssue {
+            element,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    pub(crate) fn finish<T>(self, value: T, mode: LoadMode) -> Result<(T, LoadReport), LoadReport> {
+        if mode == LoadMode::Strict && !self.is_clean() {
+            Err(self)
+        } else {
+            Ok((value, self))
+        }
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LoadReport {}
+
+pub(crate) fn join_path(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{prefix}.{field}")
+    }
+}
+
+/// Checks every slot of a serialized slotmap, turning the slots that fail to
+/// deserialize into vacant ones so the rest of the map can still be loaded.
+pub(crate) fn check_slots<K: Key, T: DeserializeOwned>(
+    graph: &mut Value,
+    field: &str,
+    prefix: &str,
+    element: fn(K) -> LoadElement,
+    report: &mut LoadReport,
+) {
+    let Some(Value::Array(slots)) = graph.get_mut(field) else {
+        report.push(
+            LoadElement::Document,
+            join_path(prefix, field),
+            "expected a list of slots",
+        );
+        return;
+    };
+
+    // The first slot is a sentinel, which slotmap validates on its own.
+    for (index, slot) in slots.iter_mut().enumerate().skip(1) {
+        let path = format!("{}[{index}]", join_path(prefix, field));
+        let Some(version) = slot.get("version").and_then(Value::as_u64) else {
+            report.push(LoadElement::Document, path, "slot has no valid version");
+            *slot = serde_json::json!({ "value": null, "version": 0 });
+            continue;
+        };
+        let version = version as u32;
+        let key = K::from(KeyData::from_ffi(((version as u64) << 32) | index as u64));
+        let occupied = version % 2 == 1;
+
+        let message = match slot.get("value") {
+            None | Some(Value::Null) if occupied => "slot is occupied but has no value".into(),
+            None | Some(Value::Null) => continue,
+            Some(_) if !occupied => "slot is vacant but has a value".into(),
+            Some(value) => match serde_json::from_value::<T>(value.clone()) {
+                Ok(_) => continue,
+                Err(err) => err.to_string(),
+            },
+        };
+        report.push(element(key), path, message);
+        *slot = serde_json::json!({ "value": null, "version": version.wrapping_add(1) & !1 });
+    }
+}
+
+/// Adds the fixes made by [`Graph::repair`] or [`GraphEditorState::repair`]
+/// to the report. Issues that follow from an element already reported, e.g.
+/// a connection to a node that failed to load, are left out.
+pub(crate) fn report_repairs(repairs: IntegrityReport, prefix: &str, report: &mut LoadReport) {
+    let slot = |field: &str, key: KeyData| {
+        format!("{}[{}]", join_path(prefix, field), key.as_ffi() as u32)
+    };
+    let param_element = |param: AnyParameterId| match param {
+        AnyParameterId::Input(input) => LoadElement::Input(input),
+        AnyParameterId::Output(output) => LoadElement::Output(output),
+    };
+
+    for issue in repairs.issues {
+        let (element, path) = match &issue {
+            IntegrityIssue::NodeIdMismatch(node) => {
+                (LoadElement::Node(*node), slot("nodes", node.data()))
+            }
+            IntegrityIssue::MissingParam { node, param } => {
+                if report.mentions(param_element(*param)) {
+                    continue;
+                }
+                (LoadElement::Node(*node), slot("nodes", node.data()))
+            }
+            IntegrityIssue::OrphanParam(param) => {
+                let (field, key) = match param {
+                    AnyParameterId::Input(input) => ("inputs", input.data()),
+                    AnyParameterId::Output(output) => ("outputs", output.data()),
+                };
+                (param_element(*param), slot(field, key))
+            }
+            IntegrityIssue::DanglingConnection { input, .. } => {
+                if report.mentions(LoadElement::Connection(*input)) {
+                    continue;
+                }
+                (
+                    LoadElement::Connection(*input),
+                    slot("connections", input.data()),
+                )
+            }
+            // The index isn't saved, it is rebuilt from the connections.
+            IntegrityIssue::StaleConnectionIndex => continue,
+            IntegrityIssue::UnknownNode { field, node } => {
+                if report.mentions(LoadElement::Node(*node)) {
+                    continue;
+                }
+                (LoadElement::EditorState, join_path(prefix, field))
+            }
+            IntegrityIssue::NodeNotInOrder(_) => {
+                (LoadElement::EditorState, join_path(prefix, "node_order"))
+            }
+            IntegrityIssue::MissingPosition(_) => (
+                LoadElement::EditorState,
+                join_path(prefix, "node_positions"),
+            ),
+            IntegrityIssue::MissingOrientation(_) => (
+                LoadElement::EditorState,
+                join_path(prefix, "node_orientations"),
+            ),
+            IntegrityIssue::InvalidPanZoom => {
+                (LoadElement::EditorState, join_path(prefix, "pan_zoom"))
+            }
+        };
+        report.push(element, path, issue.to_string());
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    NodeData: DeserializeOwned,
+    DataType: DeserializeOwned + PartialEq,
+    ValueType: DeserializeOwned,
+{
+    /// Loads a graph saved as JSON, reporting every invalid node, parameter
+    /// and connection instead of stopping at the first serde error. With
+    /// [`LoadMode::SkipInvalid`], the invalid elements are dropped and the
+    /// remaining graph is returned along with the report.
+    pub fn from_json_str_with_report(
+        json: &str,
+        mode: LoadMode,
+    ) -> Result<(Self, LoadReport), LoadReport> {
+        match serde_json::from_str(json) {
+            Ok(value) => Self::from_json_value_with_report(value, mode),
+            Err(err) => {
+                let mut report = LoadReport::default();
+                report.push(LoadElement::Document, "", err.to_string());
+                Err(report)
+            }
+        }
+    }
+
+    /// Same as [`Graph::from_json_str_with_report`], for already parsed JSON.
+    pub fn from_json_value_with_report(
+        value: Value,
+        mode: LoadMode,
+    ) -> Result<(Self, LoadReport), LoadReport> {
+        let mut report = LoadReport::default();
+        let graph = Self::load_value(value, "", &mut report).ok_or_else(|| report.clone())?;
+        report.finish(graph, mode)
+    }
+
+    fn load_value(mut value: Value, prefix: &str, report: &mut LoadReport) -> Option<Self> {
+        check_slots::<NodeId, Node<NodeData>>(
+            &mut value,
+            "nodes",
+            prefix,
+            LoadElement::Node,
+            report,
+        );
+        check_slots::<InputId, InputParam<DataType, ValueType>>(
+            &mut value,
+            "inputs",
+            prefix,
+            LoadElement::Input,
+            report,
+        );
+        check_slots::<OutputId, OutputParam<DataType>>(
+            &mut value,
+            "outputs",
+            prefix,
+            LoadElement::Output,
+            report,
+        );
+        check_slots::<InputId, SavedConnections>(
+            &mut value,
+            "connections",
+            prefix,
+            LoadElement::Connection,
+            report,
+        );
+
+        match serde_json::from_value::<Self>(value) {
+            Ok(mut graph) => {
+                report_repairs(graph.repair(), prefix, report);
+                Some(graph)
+            }
+            Err(err) => {
+                report.push(LoadElement::Document, prefix, err.to_string());
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: DeserializeOwned,
+    DataType: DeserializeOwned + PartialEq,
+    ValueType: DeserializeOwned,
+    NodeTemplate: DeserializeOwned,
+{
+    /// Loads an editor state saved as JSON, with the same diagnostics as
+    /// [`Graph::from_json_str_with_report`]. Editor data referring to nodes
+    /// that don't exist is removed, and nodes missing from it are given a
+    /// default position.
+    pub fn from_json_str_with_report(
+        json: &str,
+        mode: LoadMode,
+    ) -> Result<(Self, LoadReport), LoadReport> {
+        match serde_json::from_str(json) {
+            Ok(value) => Self::from_json_value_with_report(value, mode),
+            Err(err) => {
+                let mut report = LoadReport::default();
+                report.push(LoadElement::Document, "", err.to_string());
+                Err(report)
+            }
+        }
+    }
+
+    /// Same as [`GraphEditorState::from_json_str_with_report`], for already
+    /// parsed JSON.
+    pub fn from_json_value_with_report(
+        mut value: Value,
+        mode: LoadMode,
+    ) -> Result<(Self, LoadReport), LoadReport> {
+        let mut report = LoadReport::default();
+        let Some(graph_value) = value.get_mut("graph").map(Value::take) else {
+            report.push(LoadElement::Document, "graph", "missing field");
+            return Err(report);
+        };
+        let graph = Graph::load_value(graph_value, "graph", &mut report);
+
+        // The graph was handled above, the rest of the state is loaded with a
+        // placeholder graph that is swapped out afterwards.
+        value["graph"] = serde_json::to_value(Graph::<(), (), ()>::new())
+            .expect("An empty graph should always serialize");
+        let state = match serde_json::from_value::<Self>(value) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                report.push(LoadElement::EditorState, "", err.to_string());
+                None
+            }
+        };
+        let (Some(graph), Some(mut state)) = (graph, state) else {
+            return Err(report);
+        };
+        state.graph = graph;
+        report_repairs(state.repair(), "", &mut report);
+
+        report.finish(state, mode)
+    }
+}
+
+#[cfg(all(test, feature = "ui"))]
+mod tests {
+    use super::*;
+
+    type State = GraphEditorState<u32, (), u32, (), ()>;
+
+    fn saved_state() -> (Value, NodeId, NodeId) {
+        let mut state = State::default();
+        let a = state.graph.add_node("a".into(), 1, |_, _| {});
+        let b = state.graph.add_node("b".into(), 2, |_, _| {});
+        let out = state.graph.add_output_param(a, "out".into(), ());
+        let input = state.graph.add_input_param(
+            b,
+            "in".into(),
+            (),
+            0,
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        state.graph.add_connection(out, input);
+        for node in [a, b] {
+            state.node_order.push(node);
+            state.node_positions.insert(node, egui::Pos2::ZERO);
+            state
+                .node_orientations
+                .insert(node, NodeOrientation::LeftToRight);
+        }
+        (serde_json::to_value(&state).unwrap(), a, b)
+    }
+
+    #[test]
+    fn test_load_report() {
+        let (mut value, a, b) = saved_state();
+        let slot = a.data().as_ffi() as u32 as usize;
+        value["graph"]["nodes"][slot]["value"]["user_data"] = "not a number".into();
+        let json = value.to_string();
+
+        let report = State::from_json_str_with_report(&json, LoadMode::Strict)
+            .err()
+            .unwrap();
+        assert!(report.mentions(LoadElement::Node(a)));
+        assert_eq!(report.issues[0].path, format!("graph.nodes[{slot}]"));
+
+        let (state, report) =
+            State::from_json_str_with_report(&json, LoadMode::SkipInvalid).unwrap();
+        assert!(report.mentions(LoadElement::Node(a)));
+        assert!(report.mentions(LoadElement::Connection(state.graph[b].inputs[0].1)));
+        assert_eq!(state.graph.nodes.len(), 1);
+        assert!(state.graph.outputs.is_empty());
+        assert!(state.graph.connections.is_empty());
+        assert_eq!(state.node_order.to_vec(), vec![b]);
+
+        let (_, report) =
+            State::from_json_str_with_report(&saved_state().0.to_string(), LoadMode::Strict)
+                .unwrap();
+        assert!(report.is_clean());
+    }
+}
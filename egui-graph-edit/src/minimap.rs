@@ -0,0 +1,185 @@
+use super::*;
+use egui::{emath::TSTransform, Align2, Painter, Rect, Sense, Stroke, StrokeKind, Ui, Vec2};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// A scaled-down view of the whole graph, drawn in a corner of the editor
+/// along with the part of the graph in view. Clicking or dragging on it pans
+/// the editor to the point under the pointer. See
+/// [`GraphEditorState::minimap`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct Minimap {
+    /// The corner of the editor the minimap is drawn in.
+    pub corner: Align2,
+    /// The size of the minimap on the screen.
+    pub size: Vec2,
+    /// The distance between the minimap and the edges of the editor.
+    pub margin: f32,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self {
+            corner: Align2::RIGHT_BOTTOM,
+            size: Vec2::new(200.0, 150.0),
+            margin: 8.0,
+        }
+    }
+}
+
+/// The space between the edges of the minimap and the graph drawn inside.
+const MINIMAP_PADDING: f32 = 4.0;
+
+/// Where the minimap is drawn on this frame, and how the graph fits in it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MinimapView {
+    pub rect: Rect,
+    /// From graph coordinates to the screen coordinates of the minimap.
+    pub transform: TSTransform,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// The rect of a node in graph coordinates, as of the last time it was
+    /// drawn, or an estimate for nodes that were never drawn.
+    fn minimap_node_rect(&self, node_id: NodeId) -> Rect {
+        self.node_rect_index.get(node_id).unwrap_or_else(|| {
+            let position = self
+                .node_positions
+                .get(node_id)
+                .copied()
+                .unwrap_or_default();
+            Rect::from_min_size(position, UNMEASURED_NODE_SIZE)
+        })
+    }
+
+    /// Fits all nodes and the part of the graph in view into the minimap,
+    /// keeping their aspect ratio. `None` when the minimap is disabled.
+    pub(crate) fn minimap_view(&self, editor_rect: Rect) -> Option<MinimapView> {
+        let minimap = self.minimap?;
+        let rect = minimap
+            .corner
+            .align_size_within_rect(minimap.size, editor_rect.shrink(minimap.margin));
+        let viewport = self
+            .pan_zoom
+            .transform(editor_rect)
+            .inverse()
+            .mul_rect(editor_rect);
+        let bounds = self
+            .graph
+            .nodes
+            .keys()
+            .map(|node_id| self.minimap_node_rect(node_id))
+            .fold(viewport, Rect::union);
+        let inner = rect.shrink(MINIMAP_PADDING);
+        let scale = (inner.width() / bounds.width()).min(inner.height() / bounds.height());
+        let translation = inner.center().to_vec2() - bounds.center().to_vec2() * scale;
+        Some(MinimapView {
+            rect,
+            transform: TSTransform::new(translation, scale),
+        })
+    }
+
+    /// Pans the editor so that the point of the minimap under the pointer is
+    /// in the middle of it, while the minimap is clicked or dragged.
+    pub(crate) fn interact_with_minimap(
+        &mut self,
+        ui: &Ui,
+        view: &MinimapView,
+        editor_rect: Rect,
+    ) -> egui::Response {
+        let response = ui.interact(view.rect, ui.id().with("minimap"), Sense::click_and_drag());
+        if response.is_pointer_button_down_on() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let target = view.transform.inverse() * pointer;
+                self.pan_zoom.pan =
+                    editor_rect.size() / 2.0 - target.to_vec2() * self.pan_zoom.zoom;
+            }
+        }
+        response
+    }
+
+    /// Draws the nodes and connections of the graph into the minimap, with
+    /// the outline of the part of the graph in view.
+    pub(crate) fn draw_minimap(
+        &self,
+        painter: &Painter,
+        view: &MinimapView,
+        editor_rect: Rect,
+        style: &GraphEditorStyle,
+    ) {
+        let painter = painter.with_clip_rect(view.rect);
+        painter.rect(
+            view.rect,
+            style.corner_radius,
+            style.node_background.gamma_multiply(0.9),
+            Stroke::new(1.0, style.text.gamma_multiply(0.3)),
+            StrokeKind::Inside,
+        );
+
+        let node_rect = |node_id: NodeId| view.transform.mul_rect(self.minimap_node_rect(node_id));
+        let connection_stroke = Stroke::new(1.0, style.text.gamma_multiply(0.4));
+        for (input, output) in self.graph.iter_connections() {
+            let (Some(input), Some(output)) =
+                (self.graph.inputs.get(input), self.graph.outputs.get(output))
+            else {
+                continue;
+            };
+            painter.line_segment(
+                [
+                    node_rect(output.node).center(),
+                    node_rect(input.node).center(),
+                ],
+                connection_stroke,
+            );
+        }
+        for node_id in self.node_order.iter() {
+            let color = if self.selected_nodes.contains(&node_id) {
+                style.selection_outline.color
+            } else {
+                style.titlebar
+            };
+            painter.rect_filled(node_rect(node_id), 1.0, color);
+        }
+
+        let viewport = self
+            .pan_zoom
+            .transform(editor_rect)
+            .inverse()
+            .mul_rect(editor_rect);
+        painter.rect(
+            view.transform.mul_rect(viewport),
+            0.0,
+            style.box_selection_fill,
+            Stroke::new(1.0, style.box_selection_stroke),
+            StrokeKind::Inside,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_view() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let node = state.graph.add_node("node".into(), (), |_, _| {});
+        state.node_order.push(node);
+        state.node_positions.insert(node, egui::pos2(2000.0, 0.0));
+        state.minimap = Some(Minimap::default());
+
+        let editor_rect = Rect::from_min_size(egui::Pos2::ZERO, Vec2::new(800.0, 600.0));
+        let view = state.minimap_view(editor_rect).unwrap();
+        assert_eq!(view.rect.max, egui::pos2(792.0, 592.0));
+        assert_eq!(view.rect.size(), Vec2::new(200.0, 150.0));
+        // Both the node and the viewport fit in the minimap
+        let inner = view.rect.shrink(MINIMAP_PADDING - 0.01);
+        let node_rect = view.transform.mul_rect(state.minimap_node_rect(node));
+        assert!(inner.contains_rect(node_rect));
+        assert!(inner.contains_rect(view.transform.mul_rect(editor_rect)));
+    }
+}
@@ -1,6 +1,9 @@
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+};
 
-use crate::{color_hex_utils::*, CategoryTrait, NodeTemplateIter, NodeTemplateTrait};
+use crate::{CategoryTrait, GraphEditorStyle, NodeTemplateIter, NodeTemplateTrait};
 
 use egui::*;
 
@@ -11,7 +14,62 @@ pub struct NodeFinder<NodeTemplate> {
     /// Reset every frame. When set, the node finder will be moved at that position
     pub position: Option<Pos2>,
     pub just_spawned: bool,
-    _phantom: PhantomData<NodeTemplate>,
+    /// Categories the user opened or closed, which are otherwise open only
+    /// while searching. Cleared when the query changes.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub toggled_categories: BTreeSet<String>,
+    // NOTE: The finder doesn't own any templates, so it shouldn't inherit
+    // their auto traits (e.g. `Send` and `Sync`).
+    _phantom: PhantomData<fn() -> NodeTemplate>,
+}
+
+/// The templates recently created from the node finder, and the ones the
+/// user pinned, which the finder lists above the others, see
+/// [`GraphEditorState::template_history`](crate::GraphEditorState::template_history).
+/// Templates are told apart by their
+/// [`NodeTemplateTrait::node_finder_label`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "persistence", serde(default))]
+pub struct TemplateHistory {
+    /// The labels of the templates created recently, most recent first.
+    pub recent: Vec<String>,
+    /// The labels of the pinned templates, in the order they were pinned.
+    pub favorites: Vec<String>,
+    /// How many recent templates are remembered.
+    pub max_recent: usize,
+}
+
+impl Default for TemplateHistory {
+    fn default() -> Self {
+        Self {
+            recent: Vec::new(),
+            favorites: Vec::new(),
+            max_recent: 5,
+        }
+    }
+}
+
+impl TemplateHistory {
+    /// Takes note that the template labeled `label` was just created.
+    pub fn record(&mut self, label: &str) {
+        self.recent.retain(|recent| recent != label);
+        self.recent.insert(0, label.to_owned());
+        self.recent.truncate(self.max_recent);
+    }
+
+    pub fn is_favorite(&self, label: &str) -> bool {
+        self.favorites.iter().any(|favorite| favorite == label)
+    }
+
+    /// Pins the template labeled `label`, or unpins it.
+    pub fn set_favorite(&mut self, label: &str, favorite: bool) {
+        if !favorite {
+            self.favorites.retain(|pinned| pinned != label);
+        } else if !self.is_favorite(label) {
+            self.favorites.push(label.to_owned());
+        }
+    }
 }
 
 impl<NodeTemplate, NodeData, UserState, CategoryType> NodeFinder<NodeTemplate>
@@ -25,6 +83,7 @@ where
             query: "".into(),
             position: Some(pos),
             just_spawned: true,
+            toggled_categories: Default::default(),
             _phantom: Default::default(),
         }
     }
@@ -38,21 +97,41 @@ where
         all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
         user_state: &mut UserState,
     ) -> Option<NodeTemplate> {
-        let background_color;
-        let text_color;
-
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#fefefe").unwrap();
-            text_color = color_from_hex("#3f3f3f").unwrap();
-        }
+        let style = GraphEditorStyle::for_visuals(ui.visuals());
+        self.show_with_style(ui, all_kinds, user_state, &style)
+    }
 
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+    /// Same as [`NodeFinder::show`], with the colors of `style`.
+    pub fn show_with_style(
+        &mut self,
+        ui: &mut Ui,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        user_state: &mut UserState,
+        style: &GraphEditorStyle,
+    ) -> Option<NodeTemplate> {
+        let mut history = TemplateHistory {
+            max_recent: 0,
+            ..Default::default()
+        };
+        self.show_with_history(ui, all_kinds, user_state, style, &mut history)
+    }
+
+    /// Same as [`NodeFinder::show_with_style`], listing the favorite and
+    /// recent templates of `history` first. The selected template is
+    /// recorded in `history`, and the context menu of the templates pins
+    /// them.
+    pub fn show_with_history(
+        &mut self,
+        ui: &mut Ui,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        user_state: &mut UserState,
+        style: &GraphEditorStyle,
+        history: &mut TemplateHistory,
+    ) -> Option<NodeTemplate> {
+        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, style.text);
 
         let frame = Frame::dark_canvas(ui.style())
-            .fill(background_color)
+            .fill(style.node_background)
             .inner_margin(vec2(5.0, 5.0));
 
         // The archetype that will be returned.
@@ -66,11 +145,18 @@ where
                 }
                 let update_open = resp.changed();
 
-                let mut query_submit = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                let query_submit = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
 
                 let max_height = ui.input(|i| i.screen_rect.height() * 0.5);
                 let scroll_area_width = resp.rect.width() - 30.0;
 
+                // Categories go back to their default state when the query changes
+                if update_open {
+                    self.toggled_categories.clear();
+                }
+                let default_open = !self.query.is_empty();
+                let query = self.query.to_lowercase();
+
                 let all_kinds = all_kinds.all_kinds();
                 let mut categories: BTreeMap<String, Vec<&NodeTemplate>> = Default::default();
                 let mut orphan_kinds = Vec::new();
@@ -87,59 +173,140 @@ where
                     }
                 }
 
+                // Only the rows in view are shown, so list them all first
+                let mut rows = Vec::new();
+                let labeled_kinds: Vec<(&NodeTemplate, String)> = all_kinds
+                    .iter()
+                    .map(|kind| (kind, kind.node_finder_label(user_state).to_string()))
+                    .collect();
+                let pinned = history.favorites.iter();
+                let recent = history
+                    .recent
+                    .iter()
+                    .filter(|label| !history.is_favorite(label));
+                for (section, labels) in [
+                    ("★ Favorites", pinned.collect::<Vec<_>>()),
+                    ("Recent", recent.collect()),
+                ] {
+                    let section_kinds: Vec<_> = labels
+                        .into_iter()
+                        .filter(|label| label.to_lowercase().contains(query.as_str()))
+                        .filter_map(|label| {
+                            labeled_kinds
+                                .iter()
+                                .find(|(_, kind_label)| kind_label == label)
+                        })
+                        .collect();
+                    if !section_kinds.is_empty() {
+                        rows.push(FinderRow::Section(section));
+                        rows.extend(section_kinds.into_iter().map(|(kind, label)| {
+                            FinderRow::Kind {
+                                kind: *kind,
+                                label: label.clone(),
+                                indented: true,
+                            }
+                        }));
+                    }
+                }
+                for (category, kinds) in categories {
+                    let filtered_kinds: Vec<_> = kinds
+                        .into_iter()
+                        .map(|kind| (kind, kind.node_finder_label(user_state).to_string()))
+                        .filter(|(_kind, kind_name)| {
+                            kind_name.to_lowercase().contains(query.as_str())
+                        })
+                        .collect();
+
+                    if !filtered_kinds.is_empty() {
+                        let open = default_open != self.toggled_categories.contains(&category);
+                        rows.push(FinderRow::Category {
+                            name: category,
+                            open,
+                        });
+                        if open {
+                            rows.extend(filtered_kinds.into_iter().map(|(kind, label)| {
+                                FinderRow::Kind {
+                                    kind,
+                                    label,
+                                    indented: true,
+                                }
+                            }));
+                        }
+                    }
+                }
+                for kind in orphan_kinds {
+                    rows.push(FinderRow::Kind {
+                        kind,
+                        label: kind.node_finder_label(user_state).to_string(),
+                        indented: false,
+                    });
+                }
+
+                if query_submit {
+                    submitted_archetype = rows.iter().find_map(|row| match row {
+                        FinderRow::Kind { kind, .. } => Some((*kind).clone()),
+                        FinderRow::Section(_) | FinderRow::Category { .. } => None,
+                    });
+                }
+
+                // The height of a selectable label
+                let row_height = (ui.text_style_height(&TextStyle::Button)
+                    + 2.0 * ui.spacing().button_padding.y)
+                    .max(ui.spacing().interact_size.y);
+
                 Frame::default()
                     .inner_margin(vec2(10.0, 10.0))
                     .show(ui, |ui| {
                         ScrollArea::vertical()
                             .min_scrolled_height(max_height)
                             .max_height(max_height)
-                            .show(ui, |ui| {
+                            .show_rows(ui, row_height, rows.len(), |ui, range| {
                                 ui.set_width(scroll_area_width);
-                                for (category, kinds) in categories {
-                                    let filtered_kinds: Vec<_> = kinds
-                                        .into_iter()
-                                        .map(|kind| {
-                                            let kind_name =
-                                                kind.node_finder_label(user_state).to_string();
-                                            (kind, kind_name)
-                                        })
-                                        .filter(|(_kind, kind_name)| {
-                                            kind_name
-                                                .to_lowercase()
-                                                .contains(self.query.to_lowercase().as_str())
-                                        })
-                                        .collect();
-
-                                    if !filtered_kinds.is_empty() {
-                                        let default_open = !self.query.is_empty();
-
-                                        CollapsingHeader::new(&category)
-                                            .default_open(default_open)
-                                            .open(update_open.then_some(default_open))
-                                            .show(ui, |ui| {
-                                                for (kind, kind_name) in filtered_kinds {
-                                                    if ui
-                                                        .selectable_label(false, kind_name)
-                                                        .clicked()
-                                                    {
-                                                        submitted_archetype = Some(kind.clone());
-                                                    } else if query_submit {
-                                                        submitted_archetype = Some(kind.clone());
-                                                        query_submit = false;
-                                                    }
+                                for row in &rows[range] {
+                                    match row {
+                                        FinderRow::Section(name) => {
+                                            ui.add_sized(
+                                                [scroll_area_width, row_height],
+                                                Label::new(RichText::new(*name).weak()),
+                                            );
+                                        }
+                                        FinderRow::Category { name, open } => {
+                                            let icon = if *open { "⏷" } else { "⏵" };
+                                            if ui
+                                                .selectable_label(false, format!("{icon} {name}"))
+                                                .clicked()
+                                                && !self.toggled_categories.remove(name)
+                                            {
+                                                self.toggled_categories.insert(name.clone());
+                                            }
+                                        }
+                                        FinderRow::Kind {
+                                            kind,
+                                            label,
+                                            indented,
+                                        } => {
+                                            ui.horizontal(|ui| {
+                                                if *indented {
+                                                    ui.add_space(ui.spacing().indent);
+                                                }
+                                                let resp = ui.selectable_label(false, label);
+                                                if resp.clicked() {
+                                                    submitted_archetype = Some((*kind).clone());
                                                 }
+                                                resp.context_menu(|ui| {
+                                                    let favorite = history.is_favorite(label);
+                                                    let text = if favorite {
+                                                        "Remove from favorites"
+                                                    } else {
+                                                        "Add to favorites"
+                                                    };
+                                                    if ui.button(text).clicked() {
+                                                        history.set_favorite(label, !favorite);
+                                                        ui.close_menu();
+                                                    }
+                                                });
                                             });
-                                    }
-                                }
-
-                                for kind in orphan_kinds {
-                                    let kind_name = kind.node_finder_label(user_state).to_string();
-
-                                    if ui.selectable_label(false, kind_name).clicked() {
-                                        submitted_archetype = Some(kind.clone());
-                                    } else if query_submit {
-                                        submitted_archetype = Some(kind.clone());
-                                        query_submit = false;
+                                        }
                                     }
                                 }
                             });
@@ -147,6 +314,52 @@ where
             });
         });
 
+        if let Some(kind) = &submitted_archetype {
+            history.record(&kind.node_finder_label(user_state));
+        }
         submitted_archetype
     }
 }
+
+/// A line of the node finder list.
+enum FinderRow<'a, NodeTemplate> {
+    /// The title of the favorite or recent templates.
+    Section(&'static str),
+    Category {
+        name: String,
+        open: bool,
+    },
+    Kind {
+        kind: &'a NodeTemplate,
+        label: String,
+        /// Whether the kind is listed under a category.
+        indented: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_history() {
+        let mut history = TemplateHistory {
+            max_recent: 2,
+            ..Default::default()
+        };
+        history.record("a");
+        history.record("b");
+        history.record("a");
+        assert_eq!(history.recent, ["a", "b"]);
+        history.record("c");
+        assert_eq!(history.recent, ["c", "a"]);
+
+        history.set_favorite("b", true);
+        history.set_favorite("c", true);
+        history.set_favorite("b", true);
+        assert_eq!(history.favorites, ["b", "c"]);
+        history.set_favorite("b", false);
+        assert!(!history.is_favorite("b"));
+        assert!(history.is_favorite("c"));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{btree_map, BTreeMap};
+
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The order nodes are drawn in, from bottom to top. Each node has an order
+/// key, and raising a node gives it a key above all others, so that raising
+/// and removing nodes take logarithmic time even on huge graphs.
+///
+/// A node appears at most once. This is saved as a list of node ids.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "persistence",
+    derive(Serialize, Deserialize),
+    serde(from = "Vec<NodeId>", into = "Vec<NodeId>")
+)]
+pub struct NodeOrder {
+    keys: SecondaryMap<NodeId, u64>,
+    nodes: BTreeMap<u64, NodeId>,
+    next_key: u64,
+}
+
+impl NodeOrder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts a node on top of all others, adding it if needed.
+    pub fn push(&mut self, node: NodeId) {
+        self.remove(node);
+        self.keys.insert(node, self.next_key);
+        self.nodes.insert(self.next_key, node);
+        self.next_key += 1;
+    }
+
+    /// Puts a node on top of all others. Returns false, without adding it,
+    /// if the node isn't in the order.
+    pub fn raise(&mut self, node: NodeId) -> bool {
+        let present = self.contains(node);
+        if present {
+            self.push(node);
+        }
+        present
+    }
+
+    /// Returns whether the node was in the order.
+    pub fn remove(&mut self, node: NodeId) -> bool {
+        match self.keys.remove(node) {
+            Some(key) => {
+                self.nodes.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.keys.contains_key(node)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.nodes.clear();
+        self.next_key = 0;
+    }
+
+    /// The node drawn on top of all others.
+    pub fn top(&self) -> Option<NodeId> {
+        self.nodes.values().next_back().copied()
+    }
+
+    /// Iterates over the nodes from bottom to top.
+    pub fn iter(&self) -> NodeOrderIter<'_> {
+        self.nodes.values().copied()
+    }
+
+    /// Keeps the nodes for which `f` returns true.
+    pub fn retain(&mut self, mut f: impl FnMut(NodeId) -> bool) {
+        let keys = &mut self.keys;
+        self.nodes.retain(|_, node| {
+            let keep = f(*node);
+            if !keep {
+                keys.remove(*node);
+            }
+            keep
+        });
+    }
+
+    /// The nodes from bottom to top.
+    pub fn to_vec(&self) -> Vec<NodeId> {
+        self.iter().collect()
+    }
+}
+
+pub type NodeOrderIter<'a> = std::iter::Copied<btree_map::Values<'a, u64, NodeId>>;
+
+impl<'a> IntoIterator for &'a NodeOrder {
+    type Item = NodeId;
+    type IntoIter = NodeOrderIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Extend<NodeId> for NodeOrder {
+    fn extend<T: IntoIterator<Item = NodeId>>(&mut self, nodes: T) {
+        for node in nodes {
+            self.push(node);
+        }
+    }
+}
+
+impl FromIterator<NodeId> for NodeOrder {
+    fn from_iter<T: IntoIterator<Item = NodeId>>(nodes: T) -> Self {
+        let mut order = Self::new();
+        order.extend(nodes);
+        order
+    }
+}
+
+/// Nodes listed several times end up at their last position.
+impl From<Vec<NodeId>> for NodeOrder {
+    fn from(nodes: Vec<NodeId>) -> Self {
+        nodes.into_iter().collect()
+    }
+}
+
+impl From<NodeOrder> for Vec<NodeId> {
+    fn from(order: NodeOrder) -> Self {
+        order.to_vec()
+    }
+}
+
+/// Two orders are equal when they list the same nodes in the same order,
+/// regardless of their keys.
+impl PartialEq for NodeOrder {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for NodeOrder {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_order() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b, c] = ["a", "b", "c"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+
+        let mut order = NodeOrder::from(vec![a, b, c, a]);
+        assert_eq!(order.to_vec(), vec![b, c, a]);
+        assert_eq!(order.top(), Some(a));
+
+        assert!(order.raise(b));
+        assert_eq!(order.to_vec(), vec![c, a, b]);
+        assert!(order.remove(a));
+        assert!(!order.raise(a));
+        assert_eq!(order.to_vec(), vec![c, b]);
+
+        order.push(a);
+        order.retain(|node| node != c);
+        assert_eq!(order, NodeOrder::from(vec![b, a]));
+        assert!(!order.contains(c));
+    }
+}
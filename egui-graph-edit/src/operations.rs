@@ -0,0 +1,444 @@
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// A full copy of a node, its parameters and its layout. Carried by
+/// [`GraphOperation::AddNode`] so the node can be recreated elsewhere.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct NodeSnapshot<NodeData, DataType, ValueType> {
+    pub id: NodeId,
+    pub label: String,
+    pub user_data: NodeData,
+    pub inputs: Vec<(String, InputParam<DataType, ValueType>)>,
+    pub outputs: Vec<(String, OutputParam<DataType>)>,
+    pub position: egui::Pos2,
+    pub orientation: NodeOrientation,
+}
+
+/// A single change to the edited graph. A sequence of operations fully
+/// describes how a graph evolved, so they can be stored in a journal or sent
+/// to other editors showing the same graph.
+///
+/// Ids in operations refer to the graph that produced them. When applying
+/// operations to a different graph, use an [`IdMapping`] to translate them,
+/// see [`GraphEditorState::apply_operation`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum GraphOperation<NodeData, DataType, ValueType> {
+    AddNode(NodeSnapshot<NodeData, DataType, ValueType>),
+    RemoveNode {
+        node: NodeId,
+    },
+    Connect {
+        output: OutputId,
+        input: InputId,
+    },
+    Disconnect {
+        output: OutputId,
+        input: InputId,
+    },
+    SetValue {
+        input: InputId,
+        value: ValueType,
+    },
+    /// Moves a node to an absolute position.
+    MoveNode {
+        node: NodeId,
+        position: egui::Pos2,
+    },
+}
+
+/// Produces the [`GraphOperation`]s performed on an editor. The recorder keeps
+/// a shadow copy of the parts of the state it tracks and reports differences,
+/// so it catches every change no matter its origin: responses, inline value
+/// widgets, multi-node moves or direct modifications by user code.
+///
+/// Call [`OperationRecorder::record`] once per frame, after drawing the
+/// editor. After applying operations that came from elsewhere, call
+/// [`OperationRecorder::reset`] so they aren't reported again.
+#[derive(Debug, Clone)]
+pub struct OperationRecorder<ValueType> {
+    nodes: SecondaryMap<NodeId, ()>,
+    connections: SecondaryMap<InputId, SVec<OutputId>>,
+    values: SecondaryMap<InputId, ValueType>,
+    positions: SecondaryMap<NodeId, egui::Pos2>,
+}
+
+impl<ValueType> Default for OperationRecorder<ValueType> {
+    fn default() -> Self {
+        Self {
+            nodes: Default::default(),
+            connections: Default::default(),
+            values: Default::default(),
+            positions: Default::default(),
+        }
+    }
+}
+
+impl<ValueType: Clone + PartialEq> OperationRecorder<ValueType> {
+    /// Creates a recorder which considers the current contents of `state` as
+    /// already known.
+    pub fn new<NodeData, DataType: PartialEq, NodeTemplate, UserState>(
+        state: &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> Self {
+        let mut recorder = Self::default();
+        recorder.reset(state);
+        recorder
+    }
+
+    /// Forgets all pending changes, taking the current contents of `state` as
+    /// the new baseline.
+    pub fn reset<NodeData, DataType: PartialEq, NodeTemplate, UserState>(
+        &mut self,
+        state: &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) {
+        self.nodes = state.graph.iter_nodes().map(|id| (id, ())).collect();
+        self.connections = state.graph.connections.clone();
+        self.values = state
+            .graph
+            .inputs
+            .iter()
+            .map(|(id, param)| (id, param.value.clone()))
+            .collect();
+        self.positions = state.node_positions.clone();
+    }
+
+    /// Returns the operations that turn the last recorded state into the
+    /// current one, and makes the current state the new baseline.
+    ///
+    /// Operations are ordered so that they can be applied in sequence:
+    /// disconnections, node removals, node additions, connections, value
+    /// changes and finally moves.
+    pub fn record<NodeData, DataType, NodeTemplate, UserState>(
+        &mut self,
+        state: &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> Vec<GraphOperation<NodeData, DataType, ValueType>>
+    where
+        NodeData: Clone,
+        DataType: Clone + PartialEq,
+    {
+        let graph = &state.graph;
+        let mut operations = Vec::new();
+
+        // Connections removed along with their nodes are implied by the
+        // RemoveNode operation.
+        for (input, outputs) in &self.connections {
+            for output in outputs {
+                let still_exists = graph.connections(input).contains(output);
+                let endpoints_exist =
+                    graph.inputs.contains_key(input) && graph.outputs.contains_key(*output);
+                if !still_exists && endpoints_exist {
+                    operations.push(GraphOperation::Disconnect {
+                        output: *output,
+                        input,
+                    });
+                }
+            }
+        }
+
+        for node in self.nodes.keys() {
+            if !graph.nodes.contains_key(node) {
+                operations.push(GraphOperation::RemoveNode { node });
+            }
+        }
+
+        for node_id in graph.iter_nodes() {
+            if !self.nodes.contains_key(node_id) {
+                operations.push(GraphOperation::AddNode(NodeSnapshot::capture(
+                    state, node_id,
+                )));
+            }
+        }
+
+        for (input, output) in graph.iter_connections() {
+            let known = self
+                .connections
+                .get(input)
+                .is_some_and(|outputs| outputs.contains(&output));
+            if !known {
+                operations.push(GraphOperation::Connect { output, input });
+            }
+        }
+
+        for (input, param) in &graph.inputs {
+            // New nodes carry their values in the snapshot
+            if !self.nodes.contains_key(param.node) {
+                continue;
+            }
+            if self.values.get(input) != Some(&param.value) {
+                operations.push(GraphOperation::SetValue {
+                    input,
+                    value: param.value.clone(),
+                });
+            }
+        }
+
+        for (node, position) in &state.node_positions {
+            if !self.nodes.contains_key(node) {
+                continue;
+            }
+            if self.positions.get(node) != Some(position) {
+                operations.push(GraphOperation::MoveNode {
+                    node,
+                    position: *position,
+                });
+            }
+        }
+
+        self.reset(state);
+        operations
+    }
+}
+
+impl<NodeData, DataType, ValueType> NodeSnapshot<NodeData, DataType, ValueType>
+where
+    NodeData: Clone,
+    DataType: Clone + PartialEq,
+    ValueType: Clone,
+{
+    /// Copies the node `node_id` out of the editor state.
+    pub fn capture<NodeTemplate, UserState>(
+        state: &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+        node_id: NodeId,
+    ) -> Self {
+        let node = &state.graph[node_id];
+        Self {
+            id: node_id,
+            label: node.label.clone(),
+            user_data: node.user_data.clone(),
+            inputs: node
+                .inputs
+                .iter()
+                .map(|(name, id)| (name.clone(), state.graph[*id].clone()))
+                .collect(),
+            outputs: node
+                .outputs
+                .iter()
+                .map(|(name, id)| (name.clone(), state.graph[*id].clone()))
+                .collect(),
+            position: state
+                .node_positions
+                .get(node_id)
+                .copied()
+                .unwrap_or(egui::Pos2::ZERO),
+            orientation: state
+                .node_orientations
+                .get(node_id)
+                .copied()
+                .unwrap_or(NodeOrientation::LeftToRight),
+        }
+    }
+}
+
+impl IdMapping {
+    fn node(&self, id: NodeId) -> NodeId {
+        self.nodes.get(id).copied().unwrap_or(id)
+    }
+
+    fn input(&self, id: InputId) -> InputId {
+        self.inputs.get(id).copied().unwrap_or(id)
+    }
+
+    fn output(&self, id: OutputId) -> OutputId {
+        self.outputs.get(id).copied().unwrap_or(id)
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Applies an operation produced by an [`OperationRecorder`].
+    ///
+    /// Ids in the operation are translated through `mapping`, and ids missing
+    /// from it are used as they are. This means an empty mapping is enough to
+    /// replay operations on the graph that produced them. Nodes created by
+    /// [`GraphOperation::AddNode`] get fresh ids, which are added to `mapping`
+    /// so that later operations referring to them are translated.
+    pub fn apply_operation(
+        &mut self,
+        operation: GraphOperation<NodeData, DataType, ValueType>,
+        mapping: &mut IdMapping,
+    ) -> Result<(), EguiGraphError> {
+        match operation {
+            GraphOperation::AddNode(snapshot) => {
+                let node_id = self
+                    .graph
+                    .add_node(snapshot.label, snapshot.user_data, |_, _| {});
+                mapping.nodes.insert(snapshot.id, node_id);
+                for (name, param) in snapshot.inputs {
+                    let input_id = self.graph.add_input_param(
+                        node_id,
+                        name,
+                        param.typ,
+                        param.value,
+                        param.kind,
+                        param.shown_inline,
+                    );
+                    self.graph.inputs[input_id].multi_input = param.multi_input;
+                    mapping.inputs.insert(param.id, input_id);
+                }
+                for (name, param) in snapshot.outputs {
+                    let output_id = self.graph.add_output_param(node_id, name, param.typ);
+                    mapping.outputs.insert(param.id, output_id);
+                }
+                self.node_positions.insert(node_id, snapshot.position);
+                self.node_orientations.insert(node_id, snapshot.orientation);
+                self.node_order.push(node_id);
+            }
+            GraphOperation::RemoveNode { node } => {
+                let node = mapping.node(node);
+                if !self.graph.nodes.contains_key(node) {
+                    return Err(EguiGraphError::InvalidNodeId(node));
+                }
+                let (removed, _) = self.graph.remove_node(node);
+                self.forget_node(node, &removed);
+            }
+            GraphOperation::Connect { output, input } => {
+                let (output, input) = (mapping.output(output), mapping.input(input));
+                self.graph.any_param_type(output.into())?;
+                self.graph.any_param_type(input.into())?;
+                self.graph.add_connection(output, input);
+            }
+            GraphOperation::Disconnect { output, input } => {
+                let input = mapping.input(input);
+                if !self.graph.disconnect(mapping.output(output), input) {
+                    return Err(EguiGraphError::InvalidParameterId(input.into()));
+                }
+            }
+            GraphOperation::SetValue { input, value } => {
+                let input = mapping.input(input);
+                let param = self
+                    .graph
+                    .inputs
+                    .get_mut(input)
+                    .ok_or(EguiGraphError::InvalidParameterId(input.into()))?;
+                param.value = value;
+            }
+            GraphOperation::MoveNode { node, position } => {
+                let node = mapping.node(node);
+                let pos = self
+                    .node_positions
+                    .get_mut(node)
+                    .ok_or(EguiGraphError::InvalidNodeId(node))?;
+                *pos = position;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), (), i32, (), ()>;
+
+    fn add_node(state: &mut TestState) -> NodeId {
+        let node_id = state.graph.add_node("node".into(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                0,
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), ());
+        });
+        state.node_positions.insert(node_id, egui::Pos2::ZERO);
+        state
+            .node_orientations
+            .insert(node_id, NodeOrientation::LeftToRight);
+        state.node_order.push(node_id);
+        node_id
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut local = TestState::default();
+        let mut remote = TestState::default();
+        let mut recorder = OperationRecorder::new(&local);
+        let mut mapping = IdMapping::default();
+
+        let a = add_node(&mut local);
+        let b = add_node(&mut local);
+        let a_out = local.graph[a].get_output("out").unwrap();
+        let b_in = local.graph[b].get_input("in").unwrap();
+        local.graph.add_connection(a_out, b_in);
+        for operation in recorder.record(&local) {
+            remote.apply_operation(operation, &mut mapping).unwrap();
+        }
+        assert_eq!(remote.graph.nodes.len(), 2);
+        assert_eq!(
+            remote.graph.connection(mapping.inputs[b_in]),
+            Some(mapping.outputs[a_out])
+        );
+
+        local.graph[b_in].value = 42;
+        local.node_positions[a] = egui::pos2(10.0, 20.0);
+        local.graph.remove_connection(b_in);
+        let operations = recorder.record(&local);
+        assert_eq!(operations.len(), 3);
+        for operation in operations {
+            remote.apply_operation(operation, &mut mapping).unwrap();
+        }
+        assert_eq!(remote.graph[mapping.inputs[b_in]].value, 42);
+        assert_eq!(
+            remote.node_positions[mapping.nodes[a]],
+            egui::pos2(10.0, 20.0)
+        );
+        assert!(remote.graph.connections.is_empty());
+
+        assert!(recorder.record(&local).is_empty());
+    }
+
+    #[test]
+    fn test_replay_remove_node() {
+        let mut state = TestState::default();
+        let a = add_node(&mut state);
+        let b = add_node(&mut state);
+        let a_out = state.graph[a].get_output("out").unwrap();
+        let b_in = state.graph[b].get_input("in").unwrap();
+        state.graph.add_connection(a_out, b_in);
+
+        // The data the editor keeps about a node once it was drawn
+        let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(100.0, 50.0));
+        state.node_sizes.insert(a, rect.size());
+        state.collapsed_nodes.insert(a, ());
+        state.node_layouts.insert(
+            a,
+            NodeLayout {
+                size: rect.size(),
+                ports: Vec::new(),
+                key: 0,
+            },
+        );
+        state.node_extents.insert(
+            a,
+            NodeExtents {
+                outer_rect: rect,
+                size: rect.size(),
+            },
+        );
+        state.node_rect_index.insert(a, rect);
+        state.add_probe(a_out);
+        state.selected_nodes = vec![a, b];
+
+        state
+            .apply_operation(
+                GraphOperation::RemoveNode { node: a },
+                &mut IdMapping::default(),
+            )
+            .unwrap();
+        assert!(state.validate_integrity().is_ok());
+        assert!(!state.node_layouts.contains_key(a));
+        assert!(!state.node_extents.contains_key(a));
+        assert_eq!(state.node_rect_index.len(), 0);
+        assert!(state.probes.is_empty());
+        assert_eq!(state.selected_nodes, vec![b]);
+        assert!(state.graph.connections.is_empty());
+    }
+}
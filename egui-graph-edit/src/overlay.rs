@@ -0,0 +1,35 @@
+use super::*;
+use egui::{emath::TSTransform, Painter, Pos2, Rect};
+
+/// What an overlay painted above the graph gets to know about the frame
+/// being drawn, see [`GraphEditorState::draw_graph_editor_with_overlay`].
+pub struct EditorOverlay<'a> {
+    /// Paints above the nodes and connections, clipped to the editor.
+    pub painter: &'a Painter,
+    /// Where the editor is on the screen.
+    pub editor_rect: Rect,
+    /// From graph coordinates, such as
+    /// [`GraphEditorState::node_positions`], to screen coordinates. See
+    /// [`PanZoom::transform`].
+    pub transform: TSTransform,
+    /// The screen rect of each node drawn on this frame.
+    pub node_rects: &'a NodeRects,
+    /// The screen position of each port drawn on this frame.
+    pub port_locations: &'a PortLocations,
+    pub selected_nodes: &'a [NodeId],
+}
+
+impl EditorOverlay<'_> {
+    pub fn graph_to_screen(&self, pos: Pos2) -> Pos2 {
+        self.transform * pos
+    }
+
+    pub fn screen_to_graph(&self, pos: Pos2) -> Pos2 {
+        self.transform.inverse() * pos
+    }
+
+    /// The part of the graph in view, in graph coordinates.
+    pub fn viewport(&self) -> Rect {
+        self.transform.inverse().mul_rect(self.editor_rect)
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+
+use petgraph::graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+
+/// The edge weight of a graph converted to petgraph: the input and output
+/// that make up the connection, in the same order as
+/// [`Graph::iter_connections`].
+pub type PetgraphEdge = (InputId, OutputId);
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    DataType: PartialEq,
+{
+    /// Converts the graph topology into a [`petgraph::Graph`]. Node weights are
+    /// the [`NodeId`]s of this graph, and every connection becomes an edge
+    /// going from the node producing the value to the node consuming it.
+    ///
+    /// The returned map gives the petgraph index of every node, so results of
+    /// petgraph algorithms can be related back to this graph in both
+    /// directions.
+    pub fn to_petgraph(
+        &self,
+    ) -> (
+        petgraph::Graph<NodeId, PetgraphEdge>,
+        SecondaryMap<NodeId, NodeIndex>,
+    ) {
+        let mut graph = petgraph::Graph::with_capacity(self.nodes.len(), self.connections.len());
+        let mut mapping = SecondaryMap::with_capacity(self.nodes.len());
+        for node_id in self.iter_nodes() {
+            mapping.insert(node_id, graph.add_node(node_id));
+        }
+        for (input, output) in self.iter_connections() {
+            graph.add_edge(
+                mapping[self[output].node],
+                mapping[self[input].node],
+                (input, output),
+            );
+        }
+        (graph, mapping)
+    }
+
+    /// Same as [`Graph::to_petgraph`], but produces a [`StableGraph`], whose
+    /// indices stay valid when nodes are removed from it.
+    pub fn to_stable_petgraph(
+        &self,
+    ) -> (
+        StableGraph<NodeId, PetgraphEdge>,
+        SecondaryMap<NodeId, NodeIndex>,
+    ) {
+        let (graph, mapping) = self.to_petgraph();
+        (graph.into(), mapping)
+    }
+
+    /// Builds a graph from a petgraph graph. Since petgraph has no notion of
+    /// parameters, the caller decides what each node and edge turns into:
+    ///
+    /// - `add_node` is called once per petgraph node, in index order. It must
+    ///   add the node (and its parameters) to the graph and return its id.
+    /// - `connect` is called once per petgraph edge with the ids of its two
+    ///   endpoints, and returns the output and input to connect, if any.
+    ///
+    /// Returns the new graph along with the id of every petgraph node.
+    pub fn from_petgraph<N, E, Ty, Ix>(
+        source: &petgraph::Graph<N, E, Ty, Ix>,
+        mut add_node: impl FnMut(&mut Self, NodeIndex<Ix>, &N) -> NodeId,
+        mut connect: impl FnMut(&Self, NodeId, NodeId, &E) -> Option<(OutputId, InputId)>,
+    ) -> (Self, std::collections::HashMap<NodeIndex<Ix>, NodeId>)
+    where
+        Ty: petgraph::EdgeType,
+        Ix: petgraph::graph::IndexType,
+    {
+        let mut graph = Self::new();
+        let mut mapping = std::collections::HashMap::with_capacity(source.node_count());
+        for index in source.node_indices() {
+            let node_id = add_node(&mut graph, index, &source[index]);
+            mapping.insert(index, node_id);
+        }
+        for edge in source.raw_edges() {
+            let src = mapping[&edge.source()];
+            let dst = mapping[&edge.target()];
+            if let Some((output, input)) = connect(&graph, src, dst, &edge.weight) {
+                graph.add_connection(output, input);
+            }
+        }
+        (graph, mapping)
+    }
+}
+
+impl<NodeData, DataType, ValueType> From<&Graph<NodeData, DataType, ValueType>>
+    for petgraph::Graph<NodeId, PetgraphEdge>
+where
+    DataType: PartialEq,
+{
+    fn from(graph: &Graph<NodeData, DataType, ValueType>) -> Self {
+        graph.to_petgraph().0
+    }
+}
+
+impl<NodeData, DataType, ValueType> From<&Graph<NodeData, DataType, ValueType>>
+    for StableGraph<NodeId, PetgraphEdge>
+where
+    DataType: PartialEq,
+{
+    fn from(graph: &Graph<NodeData, DataType, ValueType>) -> Self {
+        graph.to_stable_petgraph().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_petgraph_roundtrip() {
+        let mut source = petgraph::Graph::<&str, ()>::new();
+        let a = source.add_node("a");
+        let b = source.add_node("b");
+        let c = source.add_node("c");
+        source.add_edge(b, c, ());
+        source.add_edge(a, b, ());
+
+        let (graph, mapping) = Graph::<(), (), ()>::from_petgraph(
+            &source,
+            |graph, _, label| {
+                graph.add_node(label.to_string(), (), |graph, node_id| {
+                    graph.add_input_param(
+                        node_id,
+                        "in".into(),
+                        (),
+                        (),
+                        InputParamKind::ConnectionOnly,
+                        true,
+                    );
+                    graph.add_output_param(node_id, "out".into(), ());
+                })
+            },
+            |graph, src, dst, _| {
+                Some((
+                    graph[src].get_output("out").ok()?,
+                    graph[dst].get_input("in").ok()?,
+                ))
+            },
+        );
+        assert_eq!(graph.connections.len(), 2);
+
+        let (converted, indices) = graph.to_petgraph();
+        let order = petgraph::algo::toposort(&converted, None).unwrap();
+        let labels: Vec<_> = order
+            .into_iter()
+            .map(|index| graph[converted[index]].label.as_str())
+            .collect();
+        assert_eq!(labels, ["a", "b", "c"]);
+        assert_eq!(converted[indices[mapping[&a]]], mapping[&a]);
+    }
+}
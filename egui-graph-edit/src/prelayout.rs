@@ -0,0 +1,337 @@
+use std::hash::{Hash, Hasher};
+
+use super::*;
+use egui::{emath::TSTransform, Pos2, Rect, Ui};
+
+/// Below this many nodes, planning on the calling thread is faster than
+/// spreading the work.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_NODES: usize = 512;
+
+/// How a node is drawn on the current frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeDraw {
+    /// Out of view with an up to date layout, so only its metrics are
+    /// recorded.
+    Hidden,
+    /// In view with an up to date layout, drawn by replaying the shapes
+    /// painted on a previous frame.
+    Replay,
+    /// Laid out and drawn in full.
+    Full,
+    /// Needs to be drawn in full, but was put off to a later frame to stay
+    /// within the frame budget. It shows how it was last drawn instead.
+    Deferred,
+}
+
+/// What the editor decided about a node before drawing it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct NodePlan {
+    pub node_id: NodeId,
+    pub layout_key: u64,
+    pub shapes_key: u64,
+    pub selected: bool,
+    /// The screen rect of the node, as of its last known size.
+    pub rect: Rect,
+    /// Whether the node is in view.
+    pub visible: bool,
+    /// Whether the pointer is over the node or its ports.
+    pub hovered: bool,
+    /// Whether the node is off the highlighted path.
+    pub dimmed: bool,
+    pub draw: NodeDraw,
+}
+
+/// The parts of a node its layout depends on. Unlike the node, these can be
+/// shared between threads whatever the node data and values are.
+pub(crate) struct LayoutKeyInput<'a> {
+    pub node_id: NodeId,
+    pub label: &'a str,
+    pub inputs: &'a [(String, InputId)],
+    pub outputs: &'a [(String, OutputId)],
+    /// Whether each input is shown inline, in the same order as `inputs`.
+    pub shown_inline: SVec<Option<bool>>,
+    /// The size the node was resized to, if it was.
+    pub size: Option<egui::Vec2>,
+    pub collapsed: bool,
+}
+
+impl<'a> LayoutKeyInput<'a> {
+    pub fn new<NodeData, DataType, ValueType>(
+        graph: &'a Graph<NodeData, DataType, ValueType>,
+        node_sizes: &SecondaryMap<NodeId, egui::Vec2>,
+        collapsed_nodes: &SecondaryMap<NodeId, ()>,
+        node_id: NodeId,
+    ) -> Self {
+        let node = &graph[node_id];
+        LayoutKeyInput {
+            node_id,
+            label: &node.label,
+            inputs: &node.inputs,
+            outputs: &node.outputs,
+            shown_inline: node
+                .inputs
+                .iter()
+                .map(|(_, input)| graph.inputs.get(*input).map(|p| p.shown_inline))
+                .collect(),
+            size: node_sizes.get(node_id).copied(),
+            collapsed: collapsed_nodes.contains_key(node_id),
+        }
+    }
+
+    /// Hashes what the layout of the node depends on and the editor can
+    /// detect: its label, parameters, connected inputs, orientation, size
+    /// and whether it is collapsed.
+    pub fn key(
+        &self,
+        overview: bool,
+        orientation: NodeOrientation,
+        connections: &SecondaryMap<InputId, SVec<OutputId>>,
+    ) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        overview.hash(&mut hasher);
+        matches!(orientation, NodeOrientation::LeftToRight).hash(&mut hasher);
+        self.label.hash(&mut hasher);
+        self.size
+            .map(|size| [size.x.to_bits(), size.y.to_bits()])
+            .hash(&mut hasher);
+        self.collapsed.hash(&mut hasher);
+        for ((name, input), shown_inline) in self.inputs.iter().zip(&self.shown_inline) {
+            name.hash(&mut hasher);
+            input.hash(&mut hasher);
+            shown_inline.hash(&mut hasher);
+            connections.contains_key(*input).hash(&mut hasher);
+        }
+        for (name, output) in self.outputs {
+            name.hash(&mut hasher);
+            output.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Decides how nodes are drawn, and computes their rects from the sizes
+/// measured on previous frames. This is pure math over the editor data, so
+/// with the `parallel` feature, large graphs are planned on several threads
+/// before drawing starts.
+pub(crate) struct NodePlanner<'a> {
+    pub node_positions: &'a SecondaryMap<NodeId, Pos2>,
+    pub node_orientations: &'a SecondaryMap<NodeId, NodeOrientation>,
+    pub node_layouts: &'a SecondaryMap<NodeId, NodeLayout>,
+    pub node_extents: &'a SecondaryMap<NodeId, NodeExtents>,
+    pub node_shapes: &'a SecondaryMap<NodeId, NodeShapes>,
+    pub selected_nodes: &'a [NodeId],
+    pub connections: &'a SecondaryMap<InputId, SVec<OutputId>>,
+    /// The nodes on the highlighted path, when there is one.
+    pub highlighted_nodes: Option<&'a SecondaryMap<NodeId, ()>>,
+    pub overview: bool,
+    /// From graph to screen coordinates.
+    pub transform: TSTransform,
+    /// See [`GraphEditorStyle::port_hit_radius`].
+    pub port_hit_radius: f32,
+    pub clip_rect: Rect,
+    pub cursor_pos: Pos2,
+    /// Whether nodes away from the cursor may replay their cached shapes.
+    pub can_replay: bool,
+    /// See [`style_key`].
+    pub style_key: u64,
+}
+
+impl NodePlanner<'_> {
+    /// Plans the nodes, in the same order as `inputs`. This doesn't take
+    /// the frame budget into account.
+    pub fn plan_nodes(&self, inputs: &[LayoutKeyInput]) -> Vec<NodePlan> {
+        #[cfg(feature = "parallel")]
+        if inputs.len() >= PARALLEL_MIN_NODES {
+            use rayon::prelude::*;
+            return inputs
+                .par_iter()
+                .map(|input| self.plan_node(input))
+                .collect();
+        }
+        inputs.iter().map(|input| self.plan_node(input)).collect()
+    }
+
+    fn plan_node(&self, input: &LayoutKeyInput) -> NodePlan {
+        let node_id = input.node_id;
+        let layout_key = input.key(
+            self.overview,
+            self.node_orientations[node_id],
+            self.connections,
+        );
+        let selected = self.selected_nodes.contains(&node_id);
+        let dimmed = self
+            .highlighted_nodes
+            .is_some_and(|highlighted| !highlighted.contains_key(node_id));
+        let shapes_key = shapes_key(layout_key, selected, dimmed, self.style_key);
+        let layout = self.node_layouts.get(node_id);
+        let size = layout.map(|layout| layout.size).unwrap_or_else(|| {
+            self.node_extents
+                .get(node_id)
+                .map_or(OVERVIEW_NODE_SIZE, |extents| extents.size)
+        });
+        let rect = Rect::from_min_size(
+            self.transform * self.node_positions[node_id],
+            size * self.transform.scaling,
+        );
+        // Ports stick out of the node, and should stay interactive
+        let interaction_rect = rect.expand(self.port_hit_radius * 2.0);
+        let visible = self.clip_rect.intersects(interaction_rect);
+        let hovered = interaction_rect.contains(self.cursor_pos);
+
+        let fresh = layout.is_some_and(|layout| layout.key == layout_key);
+        let replayable = self.can_replay
+            && !hovered
+            && self
+                .node_shapes
+                .get(node_id)
+                .is_some_and(|cached| cached.key == shapes_key);
+        let draw = match (fresh, visible) {
+            (true, false) => NodeDraw::Hidden,
+            (true, true) if replayable => NodeDraw::Replay,
+            _ => NodeDraw::Full,
+        };
+        NodePlan {
+            node_id,
+            layout_key,
+            shapes_key,
+            selected,
+            rect,
+            visible,
+            hovered,
+            dimmed,
+            draw,
+        }
+    }
+}
+
+/// Summarizes what the shapes of all nodes depend on, besides their layout.
+pub(crate) fn style_key(ui: &Ui, style: &GraphEditorStyle) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    // Widgets inside the nodes follow the egui theme
+    ui.visuals().dark_mode.hash(&mut hasher);
+    style.hash(&mut hasher);
+    // Text shapes refer to the font atlas, which depends on the scale
+    ui.ctx().pixels_per_point().to_bits().hash(&mut hasher);
+    // Fonts are scaled with the zoom
+    for font_id in ui.style().text_styles.values() {
+        font_id.size.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Summarizes what the shapes of a node depend on, on top of its layout.
+fn shapes_key(layout_key: u64, selected: bool, dimmed: bool, style_key: u64) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    layout_key.hash(&mut hasher);
+    selected.hash(&mut hasher);
+    dimmed.hash(&mut hasher);
+    style_key.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(all(test, feature = "parallel", feature = "synthetic"))]
+mod tests {
+    use super::*;
+    use crate::synthetic::{
+        run_editor_frame, SyntheticEditorState, SyntheticGraphOptions, SyntheticShape,
+    };
+    use egui::{pos2, vec2, Event, RawInput};
+    use std::time::Duration;
+
+    /// Plans the nodes of `state` on the calling thread, then with
+    /// [`NodePlanner::plan_nodes`].
+    fn plan(state: &SyntheticEditorState) -> (Vec<NodePlan>, Vec<NodePlan>) {
+        let inputs: Vec<LayoutKeyInput> = state
+            .node_order
+            .iter()
+            .map(|node_id| {
+                LayoutKeyInput::new(
+                    &state.graph,
+                    &state.node_sizes,
+                    &state.collapsed_nodes,
+                    node_id,
+                )
+            })
+            .collect();
+        assert!(inputs.len() >= PARALLEL_MIN_NODES);
+        let highlighted_nodes = state
+            .node_order
+            .iter()
+            .step_by(3)
+            .map(|node_id| (node_id, ()))
+            .collect();
+        let screen_rect = Rect::from_min_size(Pos2::ZERO, vec2(1000.0, 800.0));
+        let planner = NodePlanner {
+            node_positions: &state.node_positions,
+            node_orientations: &state.node_orientations,
+            node_layouts: &state.node_layouts,
+            node_extents: &state.node_extents,
+            node_shapes: &state.node_shapes,
+            selected_nodes: &state.selected_nodes,
+            connections: &state.graph.connections,
+            highlighted_nodes: Some(&highlighted_nodes),
+            overview: false,
+            transform: state.pan_zoom.transform(screen_rect),
+            port_hit_radius: 10.0,
+            clip_rect: screen_rect,
+            cursor_pos: pos2(500.0, 400.0),
+            can_replay: true,
+            style_key: 0,
+        };
+        let serial = inputs
+            .iter()
+            .map(|input| planner.plan_node(input))
+            .collect();
+        (serial, planner.plan_nodes(&inputs))
+    }
+
+    #[test]
+    fn test_parallel_plan_nodes() {
+        let mut state = SyntheticGraphOptions {
+            shape: SyntheticShape::Grid,
+            nodes: 1024,
+            ..Default::default()
+        }
+        .build_editor_state();
+        state.cache_node_shapes = true;
+        // Lays out every node, and caches the shapes of those in view
+        let input = RawInput {
+            events: vec![Event::PointerMoved(pos2(500.0, 400.0))],
+            ..Default::default()
+        };
+        let _ = run_editor_frame(&egui::Context::default(), &mut state, input);
+
+        let nodes = state.node_order.to_vec();
+        for node_id in nodes.iter().step_by(7) {
+            state.graph[*node_id].label.push('!');
+        }
+        state.selected_nodes = nodes.iter().copied().step_by(5).collect();
+        // The shapes were cached with the style of the editor, so make some
+        // of them match the style of the planner.
+        let (serial, _) = plan(&state);
+        for plan in serial.iter().step_by(2) {
+            if let Some(cached) = state.node_shapes.get_mut(plan.node_id) {
+                cached.key = plan.shapes_key;
+            }
+        }
+
+        let (mut serial, mut parallel) = plan(&state);
+        assert_eq!(serial, parallel);
+        state.frame_budget_state.node_cost = 0.001;
+        state.defer_node_updates(&mut serial, Duration::from_millis(20));
+        state.defer_node_updates(&mut parallel, Duration::from_millis(20));
+        assert_eq!(serial, parallel);
+        for draw in [
+            NodeDraw::Hidden,
+            NodeDraw::Replay,
+            NodeDraw::Full,
+            NodeDraw::Deferred,
+        ] {
+            assert!(
+                parallel.iter().any(|plan| plan.draw == draw),
+                "no node is planned as {draw:?}"
+            );
+        }
+    }
+}
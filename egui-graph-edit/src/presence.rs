@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use super::*;
+use egui::*;
+
+/// Another user editing the same graph, as reported by the application's
+/// collaboration layer. Participants are drawn on top of the graph: their
+/// cursor as a colored pointer with their name, and their selection as colored
+/// outlines around the selected nodes.
+#[derive(Clone, Debug)]
+pub struct RemoteParticipant {
+    /// Identifies the participant. Only used by the application.
+    pub id: u64,
+    pub name: String,
+    pub color: Color32,
+    /// Cursor position in graph coordinates (the same space as
+    /// `node_positions`). `None` hides the cursor, for example when the
+    /// participant's pointer left their editor.
+    pub cursor: Option<Pos2>,
+    pub selected_nodes: Vec<NodeId>,
+}
+
+/// Draws the participant cursors and selections. `transform` converts graph
+/// coordinates to screen coordinates.
+pub(crate) fn draw_remote_participants(
+    painter: &Painter,
+    participants: &[RemoteParticipant],
+    node_rects: &NodeRects,
+    transform: egui::emath::TSTransform,
+) {
+    // Outlines of several participants selecting the same node are nested
+    // instead of being drawn on top of each other.
+    let mut outlines_per_node = HashMap::<NodeId, f32>::new();
+    for participant in participants {
+        for node_id in &participant.selected_nodes {
+            let Some(rect) = node_rects.get(node_id) else {
+                continue;
+            };
+            let expand = outlines_per_node.entry(*node_id).or_insert(2.0);
+            painter.rect_stroke(
+                rect.expand(*expand),
+                4.0,
+                Stroke::new(2.0, participant.color),
+                StrokeKind::Outside,
+            );
+            *expand += 3.0;
+        }
+    }
+
+    for participant in participants {
+        let Some(cursor) = participant.cursor else {
+            continue;
+        };
+        let tip = transform * cursor;
+        let pointer = vec![
+            tip,
+            tip + vec2(0.0, 16.0),
+            tip + vec2(4.5, 12.0),
+            tip + vec2(11.0, 11.0),
+        ];
+        painter.add(Shape::convex_polygon(
+            pointer,
+            participant.color,
+            Stroke::new(1.0, Color32::WHITE),
+        ));
+
+        let galley = painter.layout_no_wrap(
+            participant.name.clone(),
+            TextStyle::Small.resolve(&painter.ctx().style()),
+            contrasting_text_color(participant.color),
+        );
+        let label_rect =
+            Rect::from_min_size(tip + vec2(10.0, 16.0), galley.size() + vec2(8.0, 4.0));
+        painter.rect_filled(label_rect, 3.0, participant.color);
+        painter.galley(label_rect.min + vec2(4.0, 2.0), galley, Color32::WHITE);
+    }
+}
+
+fn contrasting_text_color(background: Color32) -> Color32 {
+    let [r, g, b, _] = background.to_array();
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luminance > 150.0 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::emath::TSTransform;
+    use egui::epaint::{PathShape, RectShape, TextShape};
+
+    #[test]
+    fn test_draw_remote_participants() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b] = ["a", "b"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+        let rect = Rect::from_min_size(pos2(100.0, 100.0), vec2(80.0, 40.0));
+        // `b` isn't on screen, so it has no rect
+        let node_rects = NodeRects::from([(a, rect)]);
+        let participants = [
+            RemoteParticipant {
+                id: 1,
+                name: "Ada".into(),
+                color: Color32::RED,
+                cursor: Some(pos2(10.0, 20.0)),
+                selected_nodes: vec![a, b],
+            },
+            RemoteParticipant {
+                id: 2,
+                name: "Grace".into(),
+                color: Color32::BLUE,
+                cursor: None,
+                selected_nodes: vec![a],
+            },
+        ];
+        let transform = TSTransform::new(vec2(50.0, 0.0), 2.0);
+
+        let ctx = Context::default();
+        let output = ctx.run(RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(LayerId::background());
+            draw_remote_participants(&painter, &participants, &node_rects, transform);
+        });
+        let shapes: Vec<Shape> = output.shapes.into_iter().map(|s| s.shape).collect();
+
+        // Both selections outline `a`, the second one around the first
+        let outlines: Vec<(Rect, Color32)> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Rect(RectShape {
+                    rect,
+                    stroke,
+                    fill: Color32::TRANSPARENT,
+                    ..
+                }) => Some((*rect, stroke.color)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            outlines,
+            vec![
+                (rect.expand(2.0), Color32::RED),
+                (rect.expand(5.0), Color32::BLUE)
+            ]
+        );
+
+        // Only the first participant has a cursor, pointing at its position
+        // on screen, and labeled with their name.
+        let pointers: Vec<&PathShape> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Path(path) => Some(path),
+                _ => None,
+            })
+            .collect();
+        let [pointer] = pointers[..] else {
+            panic!("expected a single pointer, got {pointers:?}");
+        };
+        assert_eq!(pointer.points[0], pos2(70.0, 40.0));
+        assert_eq!(pointer.fill, Color32::RED);
+        assert!(shapes.iter().any(|shape| matches!(
+            shape,
+            Shape::Rect(RectShape { rect, fill: Color32::RED, .. })
+                if rect.min == pos2(80.0, 56.0)
+        )));
+        let labels: Vec<&str> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Text(TextShape { galley, .. }) => Some(galley.text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels, vec!["Ada"]);
+    }
+}
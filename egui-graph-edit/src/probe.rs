@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+
+use super::*;
+use egui::{vec2, Color32, Pos2, Rect, Sense, Shape, Stroke, Ui, UiBuilder, Vec2};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// A live value shown next to an output, e.g. to debug a pipeline by
+/// watching what flows out of a node. Probes are added from the context
+/// menu of the output ports, or with [`GraphEditorState::add_probe`]. The
+/// application feeds them every frame, see [`GraphEditorState::probes`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct Probe {
+    /// Where the probe is drawn, relative to its output port.
+    pub offset: Vec2,
+    /// What the probe shows. This is live data, so it is never persisted.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub value: ProbeValue,
+}
+
+impl Default for Probe {
+    fn default() -> Self {
+        Self {
+            offset: vec2(24.0, -12.0),
+            value: ProbeValue::Empty,
+        }
+    }
+}
+
+impl Probe {
+    /// Appends a sample to the values shown as a sparkline, keeping the
+    /// last `capacity` ones.
+    pub fn push_sample(&mut self, sample: f32, capacity: usize) {
+        if !matches!(self.value, ProbeValue::Samples(_)) {
+            self.value = ProbeValue::Samples(VecDeque::with_capacity(capacity));
+        }
+        let ProbeValue::Samples(samples) = &mut self.value else {
+            unreachable!()
+        };
+        samples.push_back(sample);
+        while samples.len() > capacity {
+            samples.pop_front();
+        }
+    }
+}
+
+/// The value shown by a [`Probe`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ProbeValue {
+    /// Nothing was fed to the probe yet.
+    #[default]
+    Empty,
+    Number(f64),
+    Text(String),
+    /// A history of values, drawn as a sparkline along with the last one.
+    Samples(VecDeque<f32>),
+}
+
+/// The size of the sparkline of [`ProbeValue::Samples`].
+const SPARKLINE_SIZE: Vec2 = vec2(100.0, 24.0);
+
+impl ProbeValue {
+    /// Draws the value, in `color` for sparklines. This is what
+    /// [`NodeDataTrait::probe_ui`] does by default.
+    pub fn show(&self, ui: &mut Ui, color: Color32) {
+        match self {
+            ProbeValue::Empty => {
+                ui.weak("No value");
+            }
+            ProbeValue::Number(value) => {
+                ui.monospace(format!("{value:.3}"));
+            }
+            ProbeValue::Text(text) => {
+                ui.monospace(text);
+            }
+            ProbeValue::Samples(samples) => {
+                let (rect, _) = ui.allocate_exact_size(SPARKLINE_SIZE, Sense::hover());
+                ui.painter().add(sparkline(samples, rect, color));
+                if let Some(last) = samples.back() {
+                    ui.monospace(format!("{last:.3}"));
+                }
+            }
+        }
+    }
+}
+
+/// A line through `samples`, stretched to fill `rect`.
+fn sparkline(samples: &VecDeque<f32>, rect: Rect, color: Color32) -> Shape {
+    let (min, max) = samples
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), sample| {
+            (min.min(*sample), max.max(*sample))
+        });
+    let range = (max - min).max(f32::EPSILON);
+    let step = rect.width() / (samples.len().max(2) - 1) as f32;
+    let points = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            Pos2::new(
+                rect.left() + i as f32 * step,
+                rect.bottom() - (sample - min) / range * rect.height(),
+            )
+        })
+        .collect();
+    Shape::line(points, Stroke::new(1.5, color))
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Shows the value of `output` in a probe next to its port, unless it
+    /// already has one.
+    pub fn add_probe(&mut self, output: OutputId) {
+        if !self.probes.contains_key(output) {
+            self.probes.insert(output, Probe::default());
+        }
+    }
+
+    pub fn remove_probe(&mut self, output: OutputId) -> Option<Probe> {
+        self.probes.remove(output)
+    }
+}
+
+/// Draws the probes attached to the outputs drawn on this frame, with a
+/// button removing them. Returns the outputs of the removed probes.
+pub(crate) fn draw_probes<NodeData, DataType, ValueType, UserState>(
+    ui: &mut Ui,
+    probes: &SecondaryMap<OutputId, Probe>,
+    graph: &Graph<NodeData, DataType, ValueType>,
+    port_locations: &PortLocations,
+    data_type_colors: &mut DataTypeColors,
+    style: &GraphEditorStyle,
+    user_state: &mut UserState,
+) -> Vec<OutputId>
+where
+    NodeData: NodeDataTrait<UserState = UserState, DataType = DataType, ValueType = ValueType>,
+    DataType: DataTypeTrait<UserState>,
+{
+    let mut removed = Vec::new();
+    for (output, probe) in probes {
+        let Some(port) = port_locations.get(&AnyParameterId::Output(output)) else {
+            continue;
+        };
+        let Some(param) = graph.outputs.get(output) else {
+            continue;
+        };
+        let color = data_type_colors.get(graph, AnyParameterId::Output(output), user_state);
+        let min = *port + probe.offset;
+        if !ui.clip_rect().contains(min) {
+            continue;
+        }
+        ui.painter().line_segment(
+            [*port, min + vec2(0.0, 12.0)],
+            Stroke::new(1.0, color.gamma_multiply(0.6)),
+        );
+        let builder = UiBuilder::new()
+            .max_rect(Rect::from_min_size(min, vec2(200.0, 100.0)))
+            .id_salt(("probe", output));
+        ui.scope_builder(builder, |ui| {
+            egui::Frame::new()
+                .fill(style.node_background)
+                .stroke(Stroke::new(1.0, color))
+                .corner_radius(style.corner_radius)
+                .inner_margin(4.0)
+                .show(ui, |ui| {
+                    ui.visuals_mut().override_text_color = Some(style.text);
+                    ui.horizontal(|ui| {
+                        graph[param.node].user_data.probe_ui(
+                            ui,
+                            output,
+                            &probe.value,
+                            graph,
+                            user_state,
+                        );
+                        if ui.small_button("x").on_hover_text("Remove probe").clicked() {
+                            removed.push(output);
+                        }
+                    });
+                });
+        });
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_sample() {
+        let mut probe = Probe {
+            value: ProbeValue::Number(1.0),
+            ..Default::default()
+        };
+        for sample in 0..5 {
+            probe.push_sample(sample as f32, 3);
+        }
+        assert_eq!(
+            probe.value,
+            ProbeValue::Samples(VecDeque::from([2.0, 3.0, 4.0]))
+        );
+    }
+}
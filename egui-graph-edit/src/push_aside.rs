@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+
+use super::*;
+use egui::{Rect, Vec2};
+
+/// The space kept between a pushed node and the node pushing it.
+const PUSH_MARGIN: f32 = 10.0;
+/// How many times a node can be pushed while resolving a single move, which
+/// bounds the work done when nodes are packed tightly.
+const MAX_PUSHES_PER_NODE: usize = 4;
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Pushes the nodes overlapping `moved` out of their way, along the
+    /// axis needing the smallest move. Pushed nodes push the nodes they now
+    /// overlap in turn, but never the nodes of `moved`. Returns a
+    /// [`NodeResponse::MoveNode`] for each pushed node.
+    ///
+    /// Node sizes are the ones measured on the last frame. The editor calls
+    /// this for dragged nodes when [`GraphEditorState::push_aside`] is set.
+    pub fn push_nodes_aside<UserResponse>(
+        &mut self,
+        moved: &[NodeId],
+    ) -> Vec<NodeResponse<UserResponse, NodeData>>
+    where
+        UserResponse: UserResponseTrait,
+        NodeData: NodeDataTrait,
+    {
+        let rect_of = |state: &Self, node_id: NodeId| {
+            let size = state.node_rect_index.get(node_id)?.size();
+            Some(Rect::from_min_size(
+                *state.node_positions.get(node_id)?,
+                size,
+            ))
+        };
+
+        let mut pushes: SecondaryMap<NodeId, (Vec2, usize)> = SecondaryMap::new();
+        let mut pushers: VecDeque<NodeId> = moved.iter().copied().collect();
+        while let Some(pusher) = pushers.pop_front() {
+            let Some(pusher_rect) = rect_of(self, pusher) else {
+                continue;
+            };
+            let area = pusher_rect.expand(PUSH_MARGIN);
+            for node_id in self.node_rect_index.query(area) {
+                if node_id == pusher || moved.contains(&node_id) {
+                    continue;
+                }
+                let Some(rect) = rect_of(self, node_id) else {
+                    continue;
+                };
+                let delta = separation(area, rect);
+                if delta == Vec2::ZERO {
+                    continue;
+                }
+                let (total, count) = pushes.entry(node_id).unwrap().or_insert((Vec2::ZERO, 0));
+                if *count >= MAX_PUSHES_PER_NODE {
+                    continue;
+                }
+                *total += delta;
+                *count += 1;
+                self.node_positions[node_id] += delta;
+                self.node_rect_index.insert(node_id, rect.translate(delta));
+                pushers.push_back(node_id);
+            }
+        }
+        pushes
+            .into_iter()
+            .filter(|(_, (total, _))| *total != Vec2::ZERO)
+            .map(|(node, (drag_delta, _))| NodeResponse::MoveNode { node, drag_delta })
+            .collect()
+    }
+}
+
+/// Moves the nodes that overlap apart, each pair by the smallest move that
+/// separates them, shared equally between the two. Nodes that don't overlap
+/// stay put. Useful after importing nodes in bulk, or loading a graph saved
+/// with different node sizes. Run it with [`GraphEditorState::run_layout`].
+#[derive(Debug, Clone)]
+pub struct RemoveOverlaps {
+    /// The space to keep between nodes.
+    pub margin: f32,
+    /// Moving nodes apart can make them overlap others, so the nodes are
+    /// moved in rounds until no nodes overlap, or this many rounds passed.
+    pub max_rounds: usize,
+}
+
+impl Default for RemoveOverlaps {
+    fn default() -> Self {
+        Self {
+            margin: PUSH_MARGIN,
+            max_rounds: 50,
+        }
+    }
+}
+
+impl LayoutAlgorithm for RemoveOverlaps {
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+        let mut rects: SecondaryMap<NodeId, Rect> = input
+            .nodes
+            .iter()
+            .filter_map(|node_id| {
+                let size = input
+                    .sizes
+                    .get(*node_id)
+                    .copied()
+                    .unwrap_or(UNMEASURED_NODE_SIZE);
+                Some((
+                    *node_id,
+                    Rect::from_min_size(*input.positions.get(*node_id)?, size),
+                ))
+            })
+            .collect();
+        let order: SecondaryMap<NodeId, usize> = input
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node_id)| (*node_id, index))
+            .collect();
+
+        let mut moved: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let mut index = NodeRectIndex::new();
+        for _ in 0..self.max_rounds {
+            for (node_id, rect) in &rects {
+                index.insert(node_id, *rect);
+            }
+            let mut separated = false;
+            for node_id in &input.nodes {
+                let Some(rect) = rects.get(*node_id).copied() else {
+                    continue;
+                };
+                let area = rect.expand(self.margin);
+                for other in index.query(area) {
+                    // Each pair is handled once
+                    if order[other] <= order[*node_id] {
+                        continue;
+                    }
+                    let delta = separation(area, rects[other]);
+                    if delta == Vec2::ZERO {
+                        continue;
+                    }
+                    rects[*node_id] = rects[*node_id].translate(-delta / 2.0);
+                    rects[other] = rects[other].translate(delta / 2.0);
+                    moved.insert(*node_id, ());
+                    moved.insert(other, ());
+                    separated = true;
+                }
+            }
+            if !separated {
+                break;
+            }
+        }
+        moved
+            .keys()
+            .map(|node_id| (node_id, rects[node_id].min))
+            .collect()
+    }
+}
+
+/// The smallest move taking `rect` out of `obstacle`, away from its
+/// center. Zero when they don't overlap.
+fn separation(obstacle: Rect, rect: Rect) -> Vec2 {
+    if !obstacle.intersects(rect) {
+        return Vec2::ZERO;
+    }
+    let away = rect.center() - obstacle.center();
+    let x = if away.x >= 0.0 {
+        obstacle.max.x - rect.min.x
+    } else {
+        obstacle.min.x - rect.max.x
+    };
+    let y = if away.y >= 0.0 {
+        obstacle.max.y - rect.min.y
+    } else {
+        obstacle.min.y - rect.max.y
+    };
+    if x.abs() <= y.abs() {
+        Vec2::new(x, 0.0)
+    } else {
+        Vec2::new(0.0, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_remove_overlaps() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b, c, d] =
+            ["a", "b", "c", "d"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+        let mut input = LayoutInput::default();
+        for (node_id, position) in [
+            (a, pos2(0.0, 0.0)),
+            (b, pos2(80.0, 10.0)),
+            (c, pos2(0.0, 0.0)),
+            (d, pos2(1000.0, 0.0)),
+        ] {
+            input.nodes.push(node_id);
+            input.positions.insert(node_id, position);
+            input.sizes.insert(node_id, vec2(100.0, 50.0));
+        }
+        let positions = RemoveOverlaps::default().layout(&input);
+        assert!(!positions.contains_key(d));
+
+        let rects: Vec<Rect> = [a, b, c]
+            .iter()
+            .map(|node_id| Rect::from_min_size(positions[*node_id], vec2(100.0, 50.0)))
+            .collect();
+        for (i, rect) in rects.iter().enumerate() {
+            for other in &rects[i + 1..] {
+                assert!(!rect.expand(PUSH_MARGIN - 0.01).intersects(*other));
+            }
+        }
+    }
+}
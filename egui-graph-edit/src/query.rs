@@ -0,0 +1,116 @@
+use super::*;
+
+/// Node data telling which kind of node it is, usually the template the node
+/// was created from, so nodes can be found by kind with
+/// [`Graph::find_nodes_by_kind`].
+pub trait NodeKind {
+    type Kind: PartialEq;
+
+    fn node_kind(&self) -> Self::Kind;
+}
+
+/// Finding nodes and parameters. The queries return ids in the order of
+/// [`Graph::nodes`], ready to be used as a selection, e.g. with
+/// `GraphCommand::SetSelection`, or with the navigation and layout methods.
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// The nodes whose data matches `predicate`.
+    pub fn find_nodes(&self, mut predicate: impl FnMut(&NodeData) -> bool) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| predicate(&node.user_data))
+            .map(|(node_id, _)| node_id)
+            .collect()
+    }
+
+    /// The nodes whose label contains `text`, ignoring case.
+    pub fn find_nodes_by_label(&self, text: &str) -> Vec<NodeId> {
+        let text = text.to_lowercase();
+        self.nodes
+            .iter()
+            .filter(|(_, node)| node.label.to_lowercase().contains(&text))
+            .map(|(node_id, _)| node_id)
+            .collect()
+    }
+
+    /// The nodes of the given kind, see [`NodeKind`].
+    pub fn find_nodes_by_kind(&self, kind: &NodeData::Kind) -> Vec<NodeId>
+    where
+        NodeData: NodeKind,
+    {
+        self.find_nodes(|user_data| user_data.node_kind() == *kind)
+    }
+
+    /// The parameters named `name`, on all nodes. The inputs of each node
+    /// come before its outputs.
+    pub fn find_params_by_name(&self, name: &str) -> Vec<AnyParameterId> {
+        let mut params = Vec::new();
+        for node in self.nodes.values() {
+            let inputs = node
+                .inputs
+                .iter()
+                .filter(|(param_name, _)| param_name == name)
+                .map(|(_, input)| AnyParameterId::Input(*input));
+            let outputs = node
+                .outputs
+                .iter()
+                .filter(|(param_name, _)| param_name == name)
+                .map(|(_, output)| AnyParameterId::Output(*output));
+            params.extend(inputs.chain(outputs));
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Kind {
+        Source,
+        Filter,
+    }
+
+    impl NodeKind for Kind {
+        type Kind = Kind;
+
+        fn node_kind(&self) -> Kind {
+            *self
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut graph = Graph::<Kind, (), ()>::new();
+        let add_node = |graph: &mut Graph<Kind, (), ()>, label: &str, kind: Kind| {
+            let node = graph.add_node(label.into(), kind, |_, _| {});
+            let input = graph.add_input_param(
+                node,
+                "value".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            let output = graph.add_output_param(node, "value".into(), ());
+            (node, input, output)
+        };
+        let (image, image_in, image_out) = add_node(&mut graph, "Load Image", Kind::Source);
+        let (blur, blur_in, blur_out) = add_node(&mut graph, "Blur image", Kind::Filter);
+        let (sharpen, _, _) = add_node(&mut graph, "Sharpen", Kind::Filter);
+
+        assert_eq!(graph.find_nodes_by_label("IMAGE"), vec![image, blur]);
+        assert_eq!(graph.find_nodes_by_kind(&Kind::Filter), vec![blur, sharpen]);
+        assert_eq!(graph.find_nodes(|kind| *kind == Kind::Source), vec![image]);
+        assert_eq!(graph.find_params_by_name("missing"), vec![]);
+        assert_eq!(
+            graph.find_params_by_name("value")[..4],
+            [
+                AnyParameterId::Input(image_in),
+                AnyParameterId::Output(image_out),
+                AnyParameterId::Input(blur_in),
+                AnyParameterId::Output(blur_out),
+            ]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use super::*;
+use egui::{Pos2, Rect, Vec2};
+
+/// The side of the cells of a [`NodeRectIndex`], in graph coordinates. A
+/// bit larger than a typical node, so that most nodes overlap few cells.
+const CELL_SIZE: f32 = 256.0;
+
+/// The space [`NodeRectIndex::free_position`] keeps around nodes.
+const FREE_MARGIN: f32 = 10.0;
+/// The distance between the positions tried by
+/// [`NodeRectIndex::free_position`], and how many rings of them it tries.
+const FREE_STEP: f32 = 20.0;
+const FREE_RINGS: i32 = 50;
+
+/// The rects of the nodes in graph coordinates, bucketed in a uniform grid
+/// so that finding the nodes in an area only looks at the nodes nearby.
+/// The editor keeps it up to date as nodes are drawn.
+#[derive(Clone, Debug, Default)]
+pub struct NodeRectIndex {
+    rects: SecondaryMap<NodeId, Rect>,
+    cells: HashMap<(i32, i32), SVec<NodeId>>,
+}
+
+impl NodeRectIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the rect of a node, adding it if needed.
+    pub fn insert(&mut self, node_id: NodeId, rect: Rect) {
+        if self.rects.get(node_id) == Some(&rect) {
+            return;
+        }
+        self.remove(node_id);
+        for cell in cells(rect) {
+            self.cells.entry(cell).or_default().push(node_id);
+        }
+        self.rects.insert(node_id, rect);
+    }
+
+    /// Returns whether the node was in the index.
+    pub fn remove(&mut self, node_id: NodeId) -> bool {
+        let Some(rect) = self.rects.remove(node_id) else {
+            return false;
+        };
+        for cell in cells(rect) {
+            if let Some(nodes) = self.cells.get_mut(&cell) {
+                nodes.retain(|id| *id != node_id);
+                if nodes.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        true
+    }
+
+    /// Keeps the nodes for which `f` returns true.
+    pub fn retain(&mut self, mut f: impl FnMut(NodeId) -> bool) {
+        let removed: Vec<NodeId> = self.rects.keys().filter(|id| !f(*id)).collect();
+        for node_id in removed {
+            self.remove(node_id);
+        }
+    }
+
+    pub fn get(&self, node_id: NodeId) -> Option<Rect> {
+        self.rects.get(node_id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.rects.clear();
+        self.cells.clear();
+    }
+
+    /// The nodes whose rect intersects `area`, each listed once, in no
+    /// particular order.
+    pub fn query(&self, area: Rect) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        for cell in cells(area) {
+            let Some(candidates) = self.cells.get(&cell) else {
+                continue;
+            };
+            for node_id in candidates {
+                let rect = self.rects[*node_id];
+                // A node overlapping several cells of the area is only
+                // reported by the first of them.
+                if rect.intersects(area) && cell_of(rect.min.max(area.min)) == cell {
+                    nodes.push(*node_id);
+                }
+            }
+        }
+        nodes
+    }
+
+    /// The position nearest to `position` where a node of `size` would
+    /// overlap no other node. Positions are tried in growing square rings
+    /// around `position`, which is returned as is when all of them are
+    /// taken.
+    pub fn free_position(&self, position: Pos2, size: Vec2) -> Pos2 {
+        let is_free = |min: Pos2| {
+            self.query(Rect::from_min_size(min, size).expand(FREE_MARGIN))
+                .is_empty()
+        };
+        if is_free(position) {
+            return position;
+        }
+        for ring in 1..=FREE_RINGS {
+            let mut offsets: Vec<Vec2> = (-ring..=ring)
+                .flat_map(|x| (-ring..=ring).map(move |y| (x, y)))
+                .filter(|(x, y)| x.abs().max(y.abs()) == ring)
+                .map(|(x, y)| Vec2::new(x as f32, y as f32) * FREE_STEP)
+                .collect();
+            offsets.sort_by(|a, b| a.length_sq().total_cmp(&b.length_sq()));
+            if let Some(offset) = offsets
+                .into_iter()
+                .find(|offset| is_free(position + *offset))
+            {
+                return position + offset;
+            }
+        }
+        position
+    }
+}
+
+fn cell_of(pos: Pos2) -> (i32, i32) {
+    (
+        (pos.x / CELL_SIZE).floor() as i32,
+        (pos.y / CELL_SIZE).floor() as i32,
+    )
+}
+
+/// The cells overlapped by `rect`.
+fn cells(rect: Rect) -> impl Iterator<Item = (i32, i32)> {
+    let (min, max) = (cell_of(rect.min), cell_of(rect.max));
+    (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_node_rect_index() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [a, b, c] = ["a", "b", "c"].map(|label| graph.add_node(label.into(), (), |_, _| {}));
+
+        let mut index = NodeRectIndex::new();
+        index.insert(a, Rect::from_min_size(pos2(0.0, 0.0), vec2(100.0, 50.0)));
+        // Spans several cells
+        index.insert(
+            b,
+            Rect::from_min_size(pos2(200.0, 200.0), vec2(400.0, 100.0)),
+        );
+        index.insert(
+            c,
+            Rect::from_min_size(pos2(-1000.0, 0.0), vec2(100.0, 50.0)),
+        );
+
+        let mut found = index.query(Rect::from_min_max(pos2(50.0, 0.0), pos2(600.0, 250.0)));
+        found.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        index.insert(
+            b,
+            Rect::from_min_size(pos2(-1000.0, 100.0), vec2(100.0, 50.0)),
+        );
+        assert_eq!(
+            index.query(Rect::from_min_max(pos2(50.0, 0.0), pos2(600.0, 250.0))),
+            vec![a]
+        );
+        assert!(index.remove(a));
+        assert!(!index.remove(a));
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index
+                .query(Rect::from_min_max(
+                    pos2(-2000.0, -2000.0),
+                    pos2(2000.0, 2000.0)
+                ))
+                .len(),
+            2
+        );
+
+        // Just above c, keeping a margin
+        let free = index.free_position(pos2(-1000.0, 0.0), vec2(100.0, 20.0));
+        assert_eq!(free, pos2(-1000.0, -40.0));
+    }
+}
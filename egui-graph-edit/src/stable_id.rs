@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Optional identifiers for nodes and parameters that, unlike slotmap keys,
+/// stay the same across sessions and processes. External systems (databases,
+/// scripts, other applications) can use them to durably reference elements of
+/// the graph.
+///
+/// Stable ids are strings chosen by the application, with the only
+/// requirement being that they are unique among nodes and among parameters.
+/// With the `uuid` feature, [`Graph::assign_missing_stable_ids`] can generate
+/// them automatically.
+///
+/// The table is owned by the [`Graph`] and kept up to date when elements are
+/// removed. Use the `stable_id` methods on [`Graph`] to modify it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "persistence",
+    derive(Serialize, Deserialize),
+    serde(from = "StableIdMaps", into = "StableIdMaps")
+)]
+pub struct StableIds {
+    nodes: SecondaryMap<NodeId, String>,
+    params: HashMap<AnyParameterId, String>,
+    node_lookup: HashMap<String, NodeId>,
+    param_lookup: HashMap<String, AnyParameterId>,
+}
+
+/// The serialized form of [`StableIds`]. Lookup tables are rebuilt on load.
+#[cfg(feature = "persistence")]
+#[derive(Serialize, Deserialize)]
+struct StableIdMaps {
+    nodes: SecondaryMap<NodeId, String>,
+    params: Vec<(AnyParameterId, String)>,
+}
+
+#[cfg(feature = "persistence")]
+impl From<StableIdMaps> for StableIds {
+    fn from(maps: StableIdMaps) -> Self {
+        Self {
+            node_lookup: maps
+                .nodes
+                .iter()
+                .map(|(node, id)| (id.clone(), node))
+                .collect(),
+            param_lookup: maps
+                .params
+                .iter()
+                .map(|(param, id)| (id.clone(), *param))
+                .collect(),
+            nodes: maps.nodes,
+            params: maps.params.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl From<StableIds> for StableIdMaps {
+    fn from(ids: StableIds) -> Self {
+        Self {
+            nodes: ids.nodes,
+            params: ids.params.into_iter().collect(),
+        }
+    }
+}
+
+impl StableIds {
+    pub fn iter_nodes(&self) -> impl Iterator<Item = (NodeId, &str)> + '_ {
+        self.nodes.iter().map(|(node, id)| (node, id.as_str()))
+    }
+
+    pub fn iter_params(&self) -> impl Iterator<Item = (AnyParameterId, &str)> + '_ {
+        self.params.iter().map(|(param, id)| (*param, id.as_str()))
+    }
+
+    pub(crate) fn remove_node(&mut self, node: NodeId) {
+        if let Some(id) = self.nodes.remove(node) {
+            self.node_lookup.remove(&id);
+        }
+    }
+
+    pub(crate) fn remove_param(&mut self, param: AnyParameterId) {
+        if let Some(id) = self.params.remove(&param) {
+            self.param_lookup.remove(&id);
+        }
+    }
+
+    /// Translates the table to the ids of `mapping`, dropping the elements
+    /// that don't appear in it.
+    pub(crate) fn remap(self, mapping: &IdMapping) -> Self {
+        let mut remapped = StableIds::default();
+        for (node, id) in self.nodes {
+            if let Some(node) = mapping.nodes.get(node) {
+                remapped.node_lookup.insert(id.clone(), *node);
+                remapped.nodes.insert(*node, id);
+            }
+        }
+        for (param, id) in self.params {
+            if let Some(param) = mapping.param(param) {
+                remapped.param_lookup.insert(id.clone(), param);
+                remapped.params.insert(param, id);
+            }
+        }
+        remapped
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    DataType: PartialEq,
+{
+    /// Sets the stable id of a node, replacing its previous one. Fails if the
+    /// id is already used by another node.
+    pub fn set_node_stable_id(
+        &mut self,
+        node: NodeId,
+        id: impl Into<String>,
+    ) -> Result<(), EguiGraphError> {
+        let id = id.into();
+        if !self.nodes.contains_key(node) {
+            return Err(EguiGraphError::InvalidNodeId(node));
+        }
+        match self.stable_ids.node_lookup.get(&id) {
+            Some(owner) if *owner == node => return Ok(()),
+            Some(_) => return Err(EguiGraphError::DuplicateStableId(id)),
+            None => {}
+        }
+
+        self.stable_ids.remove_node(node);
+        self.stable_ids.node_lookup.insert(id.clone(), node);
+        self.stable_ids.nodes.insert(node, id);
+        Ok(())
+    }
+
+    /// Sets the stable id of an input or output parameter, replacing its
+    /// previous one. Fails if the id is already used by another parameter.
+    pub fn set_param_stable_id(
+        &mut self,
+        param: AnyParameterId,
+        id: impl Into<String>,
+    ) -> Result<(), EguiGraphError> {
+        let id = id.into();
+        self.any_param_type(param)?;
+        match self.stable_ids.param_lookup.get(&id) {
+            Some(owner) if *owner == param => return Ok(()),
+            Some(_) => return Err(EguiGraphError::DuplicateStableId(id)),
+            None => {}
+        }
+
+        self.stable_ids.remove_param(param);
+        self.stable_ids.param_lookup.insert(id.clone(), param);
+        self.stable_ids.params.insert(param, id);
+        Ok(())
+    }
+
+    pub fn node_stable_id(&self, node: NodeId) -> Option<&str> {
+        self.stable_ids.nodes.get(node).map(String::as_str)
+    }
+
+    pub fn param_stable_id(&self, param: AnyParameterId) -> Option<&str> {
+        self.stable_ids.params.get(&param).map(String::as_str)
+    }
+
+    pub fn node_by_stable_id(&self, id: &str) -> Option<NodeId> {
+        self.stable_ids.node_lookup.get(id).copied()
+    }
+
+    pub fn param_by_stable_id(&self, id: &str) -> Option<AnyParameterId> {
+        self.stable_ids.param_lookup.get(id).copied()
+    }
+
+    /// Gives a random UUID (v4) to every node and parameter that doesn't
+    /// have a stable id yet.
+    #[cfg(feature = "uuid")]
+    pub fn assign_missing_stable_ids(&mut self) {
+        let nodes: Vec<_> = self
+            .iter_nodes()
+            .filter(|node| !self.stable_ids.nodes.contains_key(*node))
+            .collect();
+        for node in nodes {
+            self.set_node_stable_id(node, uuid::Uuid::new_v4().to_string())
+                .expect("Random UUIDs should not collide");
+        }
+
+        let params: Vec<AnyParameterId> = self
+            .inputs
+            .keys()
+            .map(AnyParameterId::from)
+            .chain(self.outputs.keys().map(AnyParameterId::from))
+            .filter(|param| !self.stable_ids.params.contains_key(param))
+            .collect();
+        for param in params {
+            self.set_param_stable_id(param, uuid::Uuid::new_v4().to_string())
+                .expect("Random UUIDs should not collide");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_ids() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let a = graph.add_node("a".into(), (), |_, _| {});
+        let b = graph.add_node("b".into(), (), |_, _| {});
+        let out = graph.add_output_param(a, "out".into(), ());
+
+        graph.set_node_stable_id(a, "node-a").unwrap();
+        graph.set_param_stable_id(out.into(), "a.out").unwrap();
+        assert!(graph.set_node_stable_id(b, "node-a").is_err());
+        assert_eq!(graph.node_by_stable_id("node-a"), Some(a));
+        assert_eq!(graph.param_by_stable_id("a.out"), Some(out.into()));
+
+        graph.set_node_stable_id(a, "renamed").unwrap();
+        assert_eq!(graph.node_by_stable_id("node-a"), None);
+        assert_eq!(graph.node_stable_id(a), Some("renamed"));
+
+        graph.remove_node(a);
+        assert_eq!(graph.node_by_stable_id("renamed"), None);
+        assert_eq!(graph.param_by_stable_id("a.out"), None);
+        graph.set_node_stable_id(b, "renamed").unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use super::*;
+
+/// Statistics about the size and shape of a graph, see [`Graph::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphStats<DataType> {
+    pub node_count: usize,
+    pub connection_count: usize,
+    pub input_count: usize,
+    pub output_count: usize,
+    /// The number of ports of each data type, in the order the types are
+    /// first found in.
+    pub ports_by_type: Vec<PortTypeCount<DataType>>,
+    /// The largest number of connections on a path through the graph, or
+    /// `None` when the graph has cycles.
+    pub max_depth: Option<usize>,
+    /// How many nodes have each number of connected inputs: `fan_in[n]` is
+    /// the number of nodes with `n` of them.
+    pub fan_in: Vec<usize>,
+    /// How many nodes have each number of connections leaving their
+    /// outputs: `fan_out[n]` is the number of nodes with `n` of them.
+    pub fan_out: Vec<usize>,
+    /// The number of groups of nodes connected to each other, including
+    /// nodes without connections.
+    pub component_count: usize,
+}
+
+/// The number of ports of a data type, see [`GraphStats::ports_by_type`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortTypeCount<DataType> {
+    pub data_type: DataType,
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    DataType: Clone + PartialEq,
+{
+    /// Counts the elements of the graph and measures its shape, e.g. for
+    /// HUDs, telemetry, or to decide when to enable the performance options
+    /// of the editor. This walks the whole graph.
+    pub fn stats(&self) -> GraphStats<DataType> {
+        let mut ports_by_type: Vec<PortTypeCount<DataType>> = Vec::new();
+        let mut count_port = |data_type: &DataType, is_input: bool| {
+            let index = match ports_by_type
+                .iter()
+                .position(|count| count.data_type == *data_type)
+            {
+                Some(index) => index,
+                None => {
+                    ports_by_type.push(PortTypeCount {
+                        data_type: data_type.clone(),
+                        inputs: 0,
+                        outputs: 0,
+                    });
+                    ports_by_type.len() - 1
+                }
+            };
+            if is_input {
+                ports_by_type[index].inputs += 1;
+            } else {
+                ports_by_type[index].outputs += 1;
+            }
+        };
+        for input in self.inputs.values() {
+            count_port(&input.typ, true);
+        }
+        for output in self.outputs.values() {
+            count_port(&output.typ, false);
+        }
+
+        let mut fan_in = Vec::new();
+        let mut fan_out = Vec::new();
+        for node_id in self.nodes.keys() {
+            for (histogram, count) in [
+                (&mut fan_in, self.upstream_nodes(node_id).count()),
+                (&mut fan_out, self.downstream_nodes(node_id).count()),
+            ] {
+                if histogram.len() <= count {
+                    histogram.resize(count + 1, 0);
+                }
+                histogram[count] += 1;
+            }
+        }
+
+        GraphStats {
+            node_count: self.nodes.len(),
+            connection_count: self.connections.len(),
+            input_count: self.inputs.len(),
+            output_count: self.outputs.len(),
+            ports_by_type,
+            max_depth: self.max_depth(),
+            fan_in,
+            fan_out,
+            component_count: self.connected_components().len(),
+        }
+    }
+
+    /// The largest number of connections on a path, unless there are cycles.
+    fn max_depth(&self) -> Option<usize> {
+        let order = self.topological_order().ok()?;
+        let mut depths: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        let mut max_depth = 0;
+        for node_id in order {
+            let depth = self
+                .upstream_nodes(node_id)
+                .map(|upstream| depths[upstream] + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(node_id, depth);
+            max_depth = max_depth.max(depth);
+        }
+        Some(max_depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut graph = Graph::<(), &str, ()>::new();
+        let add_node = |graph: &mut Graph<(), &str, ()>| {
+            let node = graph.add_node("node".into(), (), |_, _| {});
+            let input = graph.add_input_param(
+                node,
+                "in".into(),
+                "scalar",
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            let output = graph.add_output_param(node, "out".into(), "vector");
+            (input, output)
+        };
+        let (a_in, a_out) = add_node(&mut graph);
+        let (b_in, b_out) = add_node(&mut graph);
+        let (c_in, _) = add_node(&mut graph);
+        add_node(&mut graph);
+        // a -> b -> c, and d on its own
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(b_out, c_in);
+
+        let stats = graph.stats();
+        assert_eq!(stats.node_count, 4);
+        assert_eq!(stats.connection_count, 2);
+        assert_eq!((stats.input_count, stats.output_count), (4, 4));
+        assert_eq!(
+            stats.ports_by_type,
+            vec![
+                PortTypeCount {
+                    data_type: "scalar",
+                    inputs: 4,
+                    outputs: 0
+                },
+                PortTypeCount {
+                    data_type: "vector",
+                    inputs: 0,
+                    outputs: 4
+                },
+            ]
+        );
+        assert_eq!(stats.max_depth, Some(2));
+        assert_eq!(stats.fan_in, vec![2, 2]);
+        assert_eq!(stats.fan_out, vec![2, 2]);
+        assert_eq!(stats.component_count, 2);
+
+        graph.add_connection(b_out, a_in);
+        assert_eq!(graph.stats().max_depth, None);
+    }
+}
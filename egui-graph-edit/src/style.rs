@@ -0,0 +1,718 @@
+use std::hash::{Hash, Hasher};
+
+use crate::color_hex_utils::*;
+use crate::utils::ColorUtils;
+use egui::{
+    Color32, CornerRadius, CursorIcon, FontId, Rect, Shadow, Shape, Stroke, Style, TextStyle,
+    Theme, Vec2, Visuals,
+};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The colors and measurements the editor draws nodes and its own widgets
+/// with, see [`GraphEditorState::style`](crate::GraphEditorState::style).
+/// Connections and ports take the color of their data type, see
+/// [`DataTypeTrait::data_type_color`](crate::DataTypeTrait::data_type_color).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct GraphEditorStyle {
+    /// The fill of the node bodies, and of the node finder.
+    pub node_background: Color32,
+    /// The fill of the node title bars, unless
+    /// [`NodeDataTrait::titlebar_color`](crate::NodeDataTrait::titlebar_color)
+    /// returns one.
+    pub titlebar: Color32,
+    /// The color of node titles and labels.
+    pub text: Color32,
+    /// The outline drawn around selected nodes.
+    pub selection_outline: NodeOutline,
+    /// The outline drawn around the node holding the keyboard focus, see
+    /// [`GraphEditorState::focused_node`](crate::GraphEditorState::focused_node).
+    pub focus_outline: NodeOutline,
+    /// The radius of the port circles.
+    pub port_radius: f32,
+    /// How close the pointer must be to a port to drag a connection from
+    /// it, or drop one onto it.
+    pub port_hit_radius: f32,
+    /// The color of a port under the pointer.
+    pub port_hover: Color32,
+    /// The space between the border of a node and its contents.
+    pub node_margin: Vec2,
+    /// The rounding of the node corners.
+    pub corner_radius: u8,
+    /// The fill of the box selection rectangle.
+    pub box_selection_fill: Color32,
+    /// The stroke of the box selection rectangle.
+    pub box_selection_stroke: Color32,
+    /// The color of the buttons in the node title bars.
+    pub button: Color32,
+    /// The color of a title bar button under the pointer.
+    pub button_hovered: Color32,
+    /// The color of a title bar button being clicked.
+    pub button_active: Color32,
+    /// The width of the lines the title bar buttons are drawn with.
+    pub button_stroke: f32,
+    /// The width of the connections.
+    pub connection_width: f32,
+    /// The width of the translucent outline drawn around connections,
+    /// relative to their width. No outline is drawn at 1 or less.
+    pub connection_outline: f32,
+    /// How the connection being dragged from a port is drawn.
+    pub connection_preview: ConnectionPreviewStyle,
+    /// How nodes and ports react to the pointer.
+    pub hover: HoverStyle,
+    /// How disabled or bypassed nodes are drawn, see
+    /// [`NodeDataTrait::is_disabled`](crate::NodeDataTrait::is_disabled).
+    pub disabled: DisabledStyle,
+    /// How strokes, ports and the contents of the nodes follow the zoom of
+    /// the editor.
+    pub zoom_scaling: ZoomScalingRules,
+    /// How the path set by
+    /// [`GraphEditorState::highlight_path`](crate::GraphEditorState::highlight_path)
+    /// stands out from the rest of the graph. Also dims the nodes out of the
+    /// [`GraphEditorState::dependency_focus`](crate::GraphEditorState::dependency_focus).
+    pub path_highlight: PathHighlightStyle,
+    /// How the badges of the elements with diagnostics are drawn, see
+    /// [`GraphEditorState::diagnostics`](crate::GraphEditorState::diagnostics).
+    pub diagnostics: DiagnosticStyle,
+}
+
+/// How a size on the screen follows the zoom of the editor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum ZoomScaling {
+    /// Scales with the zoom, like the rest of the graph.
+    Linear,
+    /// Scales with the zoom, but never by less than `min` or more than
+    /// `max`.
+    Clamped { min: f32, max: f32 },
+    /// Keeps the same size on the screen at any zoom.
+    Constant,
+}
+
+impl ZoomScaling {
+    /// The factor sizes are multiplied by on the screen at `zoom`.
+    pub fn factor(&self, zoom: f32) -> f32 {
+        match *self {
+            Self::Linear => zoom,
+            Self::Clamped { min, max } => zoom.clamp(min, max),
+            Self::Constant => 1.0,
+        }
+    }
+}
+
+/// How the sizes of a [`GraphEditorStyle`] follow the zoom of the editor,
+/// see [`GraphEditorStyle::zoom_scaling`]. Scaling strokes linearly makes
+/// connections hairline-thin when zoomed out, and very thick when zoomed
+/// in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ZoomScalingRules {
+    /// The widths of the connections and of the node outlines.
+    pub strokes: ZoomScaling,
+    /// The radius of the ports, and how close to them the pointer must be.
+    pub ports: ZoomScaling,
+    /// The font sizes in the nodes, along with the margins and the spacing
+    /// of their contents.
+    pub text: ZoomScaling,
+}
+
+impl Default for ZoomScalingRules {
+    fn default() -> Self {
+        Self {
+            strokes: ZoomScaling::Clamped { min: 0.5, max: 1.5 },
+            ports: ZoomScaling::Clamped { min: 0.5, max: 2.0 },
+            text: ZoomScaling::Linear,
+        }
+    }
+}
+
+/// How disabled or bypassed nodes are drawn, see
+/// [`GraphEditorStyle::disabled`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct DisabledStyle {
+    /// The opacity of the whole node, ports included.
+    pub opacity: f32,
+    /// The saturation left to the title bar, from 0 (gray) to 1 (unchanged).
+    pub titlebar_saturation: f32,
+}
+
+impl DisabledStyle {
+    /// `color` with its saturation reduced to [`Self::titlebar_saturation`].
+    pub fn titlebar_color(&self, color: Color32) -> Color32 {
+        let luma = 0.299 * color.r() as f32 + 0.587 * color.g() as f32 + 0.114 * color.b() as f32;
+        let gray = Color32::from_rgba_premultiplied(luma as u8, luma as u8, luma as u8, color.a());
+        gray.lerp_to_gamma(color, self.titlebar_saturation)
+    }
+}
+
+impl Default for DisabledStyle {
+    fn default() -> Self {
+        Self {
+            opacity: 0.4,
+            titlebar_saturation: 0.0,
+        }
+    }
+}
+
+impl Hash for DisabledStyle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.opacity.to_bits().hash(state);
+        self.titlebar_saturation.to_bits().hash(state);
+    }
+}
+
+/// How a highlighted path is drawn, see [`GraphEditorStyle::path_highlight`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct PathHighlightStyle {
+    /// The opacity of the nodes and connections off the path, or unrelated
+    /// to the selection in dependency focus mode.
+    pub dim_opacity: f32,
+    /// How much wider the connections on the path are drawn.
+    pub width_factor: f32,
+}
+
+impl Default for PathHighlightStyle {
+    fn default() -> Self {
+        Self {
+            dim_opacity: 0.25,
+            width_factor: 1.5,
+        }
+    }
+}
+
+impl Hash for PathHighlightStyle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dim_opacity.to_bits().hash(state);
+        self.width_factor.to_bits().hash(state);
+    }
+}
+
+/// How diagnostics are drawn, see [`GraphEditorStyle::diagnostics`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct DiagnosticStyle {
+    pub error: Color32,
+    pub warning: Color32,
+    /// The radius of the badges.
+    pub badge_radius: f32,
+}
+
+impl DiagnosticStyle {
+    pub fn color(&self, severity: crate::Severity) -> Color32 {
+        match severity {
+            crate::Severity::Warning => self.warning,
+            crate::Severity::Error => self.error,
+        }
+    }
+}
+
+impl Default for DiagnosticStyle {
+    fn default() -> Self {
+        Self {
+            error: color_from_hex("#e53935").unwrap(),
+            warning: color_from_hex("#fb8c00").unwrap(),
+            badge_radius: 7.0,
+        }
+    }
+}
+
+/// How nodes and ports react to the pointer, see [`GraphEditorStyle::hover`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct HoverStyle {
+    /// How much a node under the pointer is brightened, from 0 (not at all)
+    /// to 1 (white).
+    pub node_brighten: f32,
+    /// How much a port under the pointer is enlarged.
+    pub port_scale: f32,
+    /// The cursor over a node.
+    pub node_cursor: Option<CursorIcon>,
+    /// The cursor while dragging nodes.
+    pub node_drag_cursor: Option<CursorIcon>,
+    /// The cursor over a port a connection can be dragged from.
+    pub port_cursor: Option<CursorIcon>,
+    /// The cursor while dragging a connection.
+    pub connecting_cursor: Option<CursorIcon>,
+}
+
+impl HoverStyle {
+    /// `color` brightened by [`Self::node_brighten`] when `hovered`.
+    pub fn node_color(&self, color: Color32, hovered: bool) -> Color32 {
+        if hovered {
+            color.lerp_to_gamma(Color32::WHITE, self.node_brighten)
+        } else {
+            color
+        }
+    }
+}
+
+impl Default for HoverStyle {
+    fn default() -> Self {
+        Self {
+            node_brighten: 0.06,
+            port_scale: 1.3,
+            node_cursor: None,
+            node_drag_cursor: Some(CursorIcon::Grabbing),
+            port_cursor: Some(CursorIcon::PointingHand),
+            connecting_cursor: Some(CursorIcon::Grabbing),
+        }
+    }
+}
+
+/// An outline drawn around a node, behind it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct NodeOutline {
+    pub color: Color32,
+    /// How far the outline extends past the node.
+    pub width: f32,
+    /// The rounding of the outline corners. Follows the corners of the node
+    /// when unset.
+    pub corner_radius: Option<u8>,
+    /// A soft glow around the outline.
+    pub glow: Option<Shadow>,
+}
+
+impl NodeOutline {
+    pub fn new(color: Color32, width: f32) -> Self {
+        Self {
+            color,
+            width,
+            corner_radius: None,
+            glow: None,
+        }
+    }
+
+    /// The shape of the outline around `node_rect`, whose corners have a
+    /// radius of `corner_radius`.
+    pub fn shape(&self, node_rect: Rect, corner_radius: u8) -> Shape {
+        let rect = node_rect.expand(self.width);
+        let corner_radius = CornerRadius::same(self.corner_radius.unwrap_or(corner_radius));
+        let outline = Shape::rect_filled(rect, corner_radius, self.color);
+        match self.glow {
+            Some(glow) => Shape::Vec(vec![glow.as_shape(rect, corner_radius).into(), outline]),
+            None => outline,
+        }
+    }
+}
+
+impl Hash for NodeOutline {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Self {
+            color,
+            width,
+            corner_radius,
+            glow,
+        } = self;
+        color.hash(state);
+        width.to_bits().hash(state);
+        corner_radius.hash(state);
+        if let Some(Shadow {
+            offset,
+            blur,
+            spread,
+            color,
+        }) = glow
+        {
+            (offset, blur, spread, color).hash(state);
+        }
+    }
+}
+
+/// How the labels of a node are written, see
+/// [`NodeDataTrait::text_style`](crate::NodeDataTrait::text_style).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeTextStyle {
+    /// The style of the node title.
+    pub title: LabelStyle,
+    /// The style of the inputs and outputs, which also applies to the
+    /// widgets of their values.
+    pub parameters: LabelStyle,
+}
+
+/// The text style, size and color of some labels. Unset fields keep the
+/// editor defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabelStyle {
+    /// E.g. [`TextStyle::Monospace`] for code, or [`TextStyle::Heading`]
+    /// for group headers.
+    pub text_style: Option<TextStyle>,
+    /// Overrides the font size of the text style.
+    pub size: Option<f32>,
+    /// Overrides [`GraphEditorStyle::text`].
+    pub color: Option<Color32>,
+}
+
+impl LabelStyle {
+    /// The font of these labels, starting from `default` when
+    /// [`Self::text_style`] is unset.
+    pub fn font_id(&self, default: &TextStyle, style: &Style) -> FontId {
+        let mut font_id = self.text_style.as_ref().unwrap_or(default).resolve(style);
+        if let Some(size) = self.size {
+            font_id.size = size;
+        }
+        font_id
+    }
+}
+
+/// How the connection being dragged from a port is drawn, see
+/// [`GraphEditorStyle::connection_preview`]. By default, it looks like the
+/// other connections.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ConnectionPreviewStyle {
+    /// The color of the connection while it snaps to a port it can end on,
+    /// e.g. green. The color of its data type when unset.
+    pub valid_color: Option<Color32>,
+    /// The color of the connection while the pointer is over a port it
+    /// can't end on, e.g. red. The color of its data type when unset.
+    pub invalid_color: Option<Color32>,
+    /// A ring drawn around the port the connection snaps to.
+    pub snap_ring: Option<Stroke>,
+    /// Draws the connection dashed, with dashes and gaps of these lengths.
+    pub dash: Option<(f32, f32)>,
+}
+
+impl ConnectionPreviewStyle {
+    /// Green over ports the connection can end on, red over the others,
+    /// with a ring around the port it snaps to.
+    pub fn highlighted() -> Self {
+        Self {
+            valid_color: Some(color_from_hex("#4caf50").unwrap()),
+            invalid_color: Some(color_from_hex("#e53935").unwrap()),
+            snap_ring: Some(Stroke::new(2.0, Color32::WHITE)),
+            dash: None,
+        }
+    }
+}
+
+impl GraphEditorStyle {
+    /// The default style with a dark egui theme.
+    pub fn dark() -> Self {
+        let node_background = color_from_hex("#3f3f3f").unwrap();
+        Self {
+            node_background,
+            titlebar: node_background.lighten(0.8),
+            text: color_from_hex("#fefefe").unwrap(),
+            selection_outline: NodeOutline::new(Color32::WHITE.lighten(0.8), 1.0),
+            focus_outline: NodeOutline::new(color_from_hex("#4a9eff").unwrap(), 2.0),
+            port_radius: 5.0,
+            port_hit_radius: 10.0,
+            port_hover: Color32::WHITE,
+            node_margin: Vec2::new(15.0, 5.0),
+            corner_radius: 4,
+            button_stroke: 2.0,
+            connection_width: 5.0,
+            connection_outline: 2.0,
+            box_selection_fill: Color32::from_rgba_unmultiplied(200, 200, 200, 20),
+            box_selection_stroke: Color32::from_rgba_unmultiplied(200, 200, 200, 180),
+            button: color_from_hex("#aaaaaa").unwrap(),
+            button_hovered: color_from_hex("#dddddd").unwrap(),
+            button_active: color_from_hex("#ffffff").unwrap(),
+            connection_preview: ConnectionPreviewStyle::default(),
+            hover: HoverStyle::default(),
+            disabled: DisabledStyle::default(),
+            zoom_scaling: ZoomScalingRules::default(),
+            path_highlight: PathHighlightStyle::default(),
+            diagnostics: DiagnosticStyle::default(),
+        }
+    }
+
+    /// The default style with a light egui theme.
+    pub fn light() -> Self {
+        let node_background = color_from_hex("#ffffff").unwrap();
+        Self {
+            node_background,
+            titlebar: node_background.lighten(0.8),
+            text: color_from_hex("#505050").unwrap(),
+            button: color_from_hex("#555555").unwrap(),
+            button_hovered: color_from_hex("#222222").unwrap(),
+            button_active: color_from_hex("#000000").unwrap(),
+            ..Self::dark()
+        }
+    }
+
+    /// Black nodes with white text, a thick yellow selection outline and
+    /// larger ports, for low-vision users. Pair it with data type colors
+    /// that stand out against black.
+    pub fn high_contrast() -> Self {
+        Self {
+            node_background: Color32::BLACK,
+            titlebar: Color32::BLACK,
+            text: Color32::WHITE,
+            selection_outline: NodeOutline::new(color_from_hex("#ffff00").unwrap(), 3.0),
+            focus_outline: NodeOutline::new(color_from_hex("#00ffff").unwrap(), 4.0),
+            port_radius: 7.0,
+            port_hover: color_from_hex("#ffff00").unwrap(),
+            box_selection_fill: Color32::from_rgba_unmultiplied(255, 255, 0, 30),
+            box_selection_stroke: color_from_hex("#ffff00").unwrap(),
+            button: Color32::WHITE,
+            button_hovered: color_from_hex("#ffff00").unwrap(),
+            button_active: color_from_hex("#ffff00").unwrap(),
+            connection_preview: ConnectionPreviewStyle {
+                snap_ring: Some(Stroke::new(3.0, color_from_hex("#ffff00").unwrap())),
+                ..Default::default()
+            },
+            hover: HoverStyle {
+                node_brighten: 0.15,
+                port_scale: 1.5,
+                ..Default::default()
+            },
+            ..Self::dark()
+        }
+    }
+
+    /// White lines on blue, like a technical drawing.
+    pub fn blueprint() -> Self {
+        let node_background = color_from_hex("#1e3a5f").unwrap();
+        Self {
+            node_background,
+            titlebar: color_from_hex("#2b5283").unwrap(),
+            text: color_from_hex("#e8f1ff").unwrap(),
+            selection_outline: NodeOutline::new(Color32::WHITE, 1.0),
+            focus_outline: NodeOutline::new(color_from_hex("#ffd54f").unwrap(), 2.0),
+            port_hover: Color32::WHITE,
+            corner_radius: 0,
+            box_selection_fill: Color32::from_rgba_unmultiplied(232, 241, 255, 20),
+            box_selection_stroke: color_from_hex("#e8f1ff").unwrap(),
+            button: color_from_hex("#9db8dc").unwrap(),
+            button_hovered: color_from_hex("#d0e0f5").unwrap(),
+            button_active: Color32::WHITE,
+            ..Self::dark()
+        }
+    }
+
+    /// The dark variant of the Solarized palette.
+    pub fn solarized() -> Self {
+        Self {
+            node_background: color_from_hex("#002b36").unwrap(),
+            titlebar: color_from_hex("#073642").unwrap(),
+            text: color_from_hex("#93a1a1").unwrap(),
+            selection_outline: NodeOutline::new(color_from_hex("#b58900").unwrap(), 1.0),
+            focus_outline: NodeOutline::new(color_from_hex("#268bd2").unwrap(), 2.0),
+            port_hover: color_from_hex("#fdf6e3").unwrap(),
+            box_selection_fill: Color32::from_rgba_unmultiplied(147, 161, 161, 20),
+            box_selection_stroke: color_from_hex("#93a1a1").unwrap(),
+            button: color_from_hex("#839496").unwrap(),
+            button_hovered: color_from_hex("#93a1a1").unwrap(),
+            button_active: color_from_hex("#fdf6e3").unwrap(),
+            ..Self::dark()
+        }
+    }
+
+    /// The built-in styles with their names, e.g. for a palette picker.
+    pub fn palettes() -> [(&'static str, Self); 5] {
+        [
+            ("Classic dark", Self::dark()),
+            ("Light", Self::light()),
+            ("Blueprint", Self::blueprint()),
+            ("Solarized", Self::solarized()),
+            ("High contrast", Self::high_contrast()),
+        ]
+    }
+
+    /// This style with its strokes, ports and node margins sized for
+    /// `zoom`, following [`Self::zoom_scaling`]. The margins follow the
+    /// text, along with the rest of the contents of the nodes. The editor
+    /// scales the fonts and the spacing of the widgets itself.
+    pub fn at_zoom(&self, zoom: f32) -> Self {
+        let stroke_factor = self.zoom_scaling.strokes.factor(zoom);
+        let text_factor = self.zoom_scaling.text.factor(zoom);
+        let mut style = self.clone();
+        style.node_margin *= text_factor;
+        style.corner_radius = (self.corner_radius as f32 * text_factor).round() as u8;
+        style.connection_width *= stroke_factor;
+        style.selection_outline.width *= stroke_factor;
+        style.focus_outline.width *= stroke_factor;
+        if let Some(ring) = &mut style.connection_preview.snap_ring {
+            ring.width *= stroke_factor;
+        }
+        style.port_radius *= self.zoom_scaling.ports.factor(zoom);
+        style.port_hit_radius *= self.zoom_scaling.ports.factor(zoom);
+        style.diagnostics.badge_radius *= self.zoom_scaling.ports.factor(zoom);
+        style
+    }
+
+    /// The default style matching `visuals`.
+    pub fn for_visuals(visuals: &Visuals) -> Self {
+        if visuals.dark_mode {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+}
+
+impl Default for GraphEditorStyle {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Hash for GraphEditorStyle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Self {
+            node_background,
+            titlebar,
+            text,
+            selection_outline,
+            focus_outline,
+            port_radius,
+            port_hit_radius,
+            port_hover,
+            node_margin,
+            corner_radius,
+            box_selection_fill,
+            box_selection_stroke,
+            button,
+            button_hovered,
+            button_active,
+            button_stroke,
+            connection_preview,
+            connection_width,
+            connection_outline,
+            hover,
+            disabled,
+            zoom_scaling,
+            path_highlight,
+            diagnostics,
+        } = self;
+        selection_outline.hash(state);
+        disabled.hash(state);
+        path_highlight.hash(state);
+        focus_outline.hash(state);
+        for color in [
+            node_background,
+            titlebar,
+            text,
+            port_hover,
+            box_selection_fill,
+            box_selection_stroke,
+            button,
+            button_hovered,
+            button_active,
+        ] {
+            color.hash(state);
+        }
+        for length in [port_radius, button_stroke, &node_margin.x, &node_margin.y] {
+            length.to_bits().hash(state);
+        }
+        corner_radius.hash(state);
+        // Only drawn with the connections, which aren't cached
+        let _ = (connection_width, connection_outline, connection_preview);
+        // The shapes of hovered nodes aren't cached
+        let _ = (hover, port_hit_radius);
+        // Badges are drawn above the nodes
+        let _ = diagnostics;
+        // Applied to the other fields by `at_zoom`, and to the fonts
+        let _ = zoom_scaling;
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    crate::GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Switches the style the editor is drawn with, e.g. to one of
+    /// [`GraphEditorStyle::palettes`]. Nodes are drawn with it from the next
+    /// frame on.
+    pub fn set_style(&mut self, style: GraphEditorStyle) {
+        self.style = Some(style);
+    }
+
+    /// The style the editor is drawn with: [`Self::style`] when set, the
+    /// default style of [`Self::theme`] or matching `visuals` otherwise.
+    pub fn current_style(&self, visuals: &Visuals) -> GraphEditorStyle {
+        if let Some(style) = &self.style {
+            return style.clone();
+        }
+        match self.theme {
+            Some(Theme::Dark) => GraphEditorStyle::dark(),
+            Some(Theme::Light) => GraphEditorStyle::light(),
+            None => GraphEditorStyle::for_visuals(visuals),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_style() {
+        let mut state = crate::GraphEditorState::<(), (), (), (), ()>::default();
+        assert_eq!(
+            state.current_style(&Visuals::light()),
+            GraphEditorStyle::light()
+        );
+        let style = GraphEditorStyle {
+            port_radius: 8.0,
+            ..GraphEditorStyle::dark()
+        };
+        state.theme = Some(Theme::Dark);
+        assert_eq!(
+            state.current_style(&Visuals::light()),
+            GraphEditorStyle::dark()
+        );
+        state.set_style(style.clone());
+        assert_eq!(state.current_style(&Visuals::light()), style);
+    }
+
+    #[test]
+    fn test_at_zoom() {
+        let style = GraphEditorStyle::dark();
+        assert_eq!(style.at_zoom(1.0), style);
+
+        let zoomed_out = style.at_zoom(0.1);
+        assert_eq!(zoomed_out.connection_width, style.connection_width * 0.5);
+        assert_eq!(zoomed_out.port_radius, style.port_radius * 0.5);
+        assert_eq!(zoomed_out.node_margin, style.node_margin * 0.1);
+
+        let constant = GraphEditorStyle {
+            zoom_scaling: ZoomScalingRules {
+                strokes: ZoomScaling::Constant,
+                ports: ZoomScaling::Linear,
+                text: ZoomScaling::Linear,
+            },
+            ..style.clone()
+        };
+        let zoomed_in = constant.at_zoom(3.0);
+        assert_eq!(zoomed_in.connection_width, style.connection_width);
+        assert_eq!(zoomed_in.port_radius, style.port_radius * 3.0);
+    }
+
+    #[test]
+    fn test_disabled_titlebar_color() {
+        let color = Color32::from_rgb(200, 40, 40);
+        let gray = DisabledStyle::default().titlebar_color(color);
+        assert_eq!(gray.r(), gray.g());
+        assert_eq!(gray.g(), gray.b());
+        let unchanged = DisabledStyle {
+            titlebar_saturation: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(unchanged.titlebar_color(color), color);
+    }
+
+    #[test]
+    fn test_label_font_id() {
+        let style = Style::default();
+        let label = LabelStyle::default();
+        assert_eq!(
+            label.font_id(&TextStyle::Button, &style),
+            TextStyle::Button.resolve(&style)
+        );
+        let label = LabelStyle {
+            text_style: Some(TextStyle::Monospace),
+            size: Some(20.0),
+            color: None,
+        };
+        assert_eq!(
+            label.font_id(&TextStyle::Button, &style),
+            FontId::monospace(20.0)
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use egui::Pos2;
+
+/// Lays out nodes in vertical lanes, one for each distinct key, e.g. the
+/// category of their template or the stage of a pipeline. Lanes are sorted
+/// by key from left to right. Within the lanes, nodes go in rows following
+/// the connections: a node is always in a lower row than the nodes feeding
+/// it, whatever their lane, so the graph reads from top to bottom.
+///
+/// Nodes without a key don't move.
+#[derive(Debug, Clone)]
+pub struct SwimlaneLayout<K> {
+    /// The lane of each node.
+    pub lanes: SecondaryMap<NodeId, K>,
+    /// The space between two lanes.
+    pub lane_spacing: f32,
+    /// The space between two nodes of a lane.
+    pub node_spacing: f32,
+}
+
+impl<K> SwimlaneLayout<K> {
+    pub fn new(lanes: impl IntoIterator<Item = (NodeId, K)>) -> Self {
+        Self {
+            lanes: lanes.into_iter().collect(),
+            lane_spacing: 80.0,
+            node_spacing: 30.0,
+        }
+    }
+
+    /// Uses `key` to find the lane of each node of `graph`.
+    pub fn from_graph<NodeData, DataType, ValueType>(
+        graph: &Graph<NodeData, DataType, ValueType>,
+        mut key: impl FnMut(&Node<NodeData>) -> K,
+    ) -> Self {
+        Self::new(
+            graph
+                .nodes
+                .iter()
+                .map(|(node_id, node)| (node_id, key(node))),
+        )
+    }
+}
+
+impl<K: Ord + Send> LayoutAlgorithm for SwimlaneLayout<K> {
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+        let size = |node_id: NodeId| {
+            input
+                .sizes
+                .get(node_id)
+                .copied()
+                .unwrap_or(UNMEASURED_NODE_SIZE)
+        };
+        let nodes: Vec<NodeId> = input
+            .nodes
+            .iter()
+            .copied()
+            .filter(|node_id| {
+                self.lanes.contains_key(*node_id) && input.positions.contains_key(*node_id)
+            })
+            .collect();
+        // The layout stays where the nodes were
+        let Some(origin) = nodes
+            .iter()
+            .map(|node_id| input.positions[*node_id])
+            .reduce(Pos2::min)
+        else {
+            return LayoutPositions::new();
+        };
+        let ranks = longest_path_ranks(input);
+
+        let mut lanes: BTreeMap<&K, usize> = nodes
+            .iter()
+            .map(|node_id| (&self.lanes[*node_id], 0))
+            .collect();
+        for (index, lane) in lanes.values_mut().enumerate() {
+            *lane = index;
+        }
+
+        // The nodes of each row and lane, from top to bottom
+        let mut cells: BTreeMap<(usize, usize), Vec<NodeId>> = BTreeMap::new();
+        let mut lane_widths = vec![0.0_f32; lanes.len()];
+        for node_id in &nodes {
+            let lane = lanes[&self.lanes[*node_id]];
+            cells
+                .entry((ranks[*node_id], lane))
+                .or_default()
+                .push(*node_id);
+            lane_widths[lane] = lane_widths[lane].max(size(*node_id).x);
+        }
+        for cell in cells.values_mut() {
+            cell.sort_by(|a, b| input.positions[*a].y.total_cmp(&input.positions[*b].y));
+        }
+
+        let mut lane_x = Vec::with_capacity(lane_widths.len());
+        let mut x = origin.x;
+        for width in &lane_widths {
+            lane_x.push(x);
+            x += width + self.lane_spacing;
+        }
+
+        let mut positions = LayoutPositions::new();
+        let mut row_y = origin.y;
+        let mut cells = cells.into_iter().peekable();
+        while let Some(((rank, _), _)) = cells.peek() {
+            let rank = *rank;
+            let mut row_height = 0.0_f32;
+            while let Some(((_, lane), cell)) = cells.next_if(|((r, _), _)| *r == rank) {
+                let mut y = row_y;
+                for node_id in cell {
+                    positions.insert(node_id, Pos2::new(lane_x[lane], y));
+                    y += size(node_id).y + self.node_spacing;
+                }
+                row_height = row_height.max(y - row_y);
+            }
+            row_y += row_height;
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_swimlane_layout() {
+        let mut graph = Graph::<&str, (), ()>::new();
+        let [extract, clean, load, report, note] = [
+            ("extract", "1 source"),
+            ("clean", "2 transform"),
+            ("load", "3 sink"),
+            ("report", "3 sink"),
+            ("note", ""),
+        ]
+        .map(|(label, stage)| graph.add_node(label.into(), stage, |_, _| {}));
+        let mut input = LayoutInput::default();
+        for node_id in [extract, clean, load, report, note] {
+            input.nodes.push(node_id);
+            input.positions.insert(node_id, pos2(100.0, 100.0));
+            input.sizes.insert(node_id, vec2(100.0, 50.0));
+        }
+        input.positions[report] = pos2(100.0, 200.0);
+        input.edges = vec![(extract, clean), (clean, load), (clean, report)];
+
+        let mut layout = SwimlaneLayout::from_graph(&graph, |node| node.user_data);
+        layout.lanes.remove(note);
+        let positions = layout.layout(&input);
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[extract], pos2(100.0, 100.0));
+        assert_eq!(positions[clean], pos2(280.0, 180.0));
+        // Both in the last lane and row, in their current order
+        assert_eq!(positions[load], pos2(460.0, 260.0));
+        assert_eq!(positions[report], pos2(460.0, 340.0));
+    }
+}
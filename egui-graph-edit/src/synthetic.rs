@@ -0,0 +1,372 @@
+use std::borrow::Cow;
+
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// How the nodes of a synthetic graph are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticShape {
+    /// Connections between random pairs of nodes. Outputs always connect to
+    /// nodes created later, so the graph has no cycles.
+    Random,
+    /// Nodes in a square grid, each connected to the nodes of the previous
+    /// column.
+    Grid,
+    /// Every node connected to the previous one.
+    Chain,
+}
+
+/// Describes a graph to generate, for benchmarks and stress tests. The same
+/// options always generate the same graph.
+#[derive(Debug, Clone)]
+pub struct SyntheticGraphOptions {
+    pub shape: SyntheticShape,
+    pub nodes: usize,
+    /// Inputs of each node.
+    pub inputs: usize,
+    /// Outputs of each node.
+    pub outputs: usize,
+    /// The number of connections to attempt for [`SyntheticShape::Random`].
+    /// Fewer are made when inputs run out. Other shapes ignore this.
+    pub connections: usize,
+    /// Space between the nodes of the generated editor state.
+    pub spacing: f32,
+    pub seed: u64,
+}
+
+impl Default for SyntheticGraphOptions {
+    fn default() -> Self {
+        Self {
+            shape: SyntheticShape::Random,
+            nodes: 100,
+            inputs: 2,
+            outputs: 1,
+            connections: 100,
+            spacing: 250.0,
+            seed: 0,
+        }
+    }
+}
+
+/// The node data of synthetic graphs: the index the node was created at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct SyntheticNode(pub usize);
+
+/// The only data type of synthetic graphs, so that any output can connect to
+/// any input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct SyntheticType;
+
+/// The constant value of the inputs of synthetic graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct SyntheticValue(pub f32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntheticResponse;
+
+impl UserResponseTrait for SyntheticResponse {}
+
+/// Creates nodes with the ports given by its fields, like the generator does.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct SyntheticTemplate {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+pub type SyntheticGraph = Graph<SyntheticNode, SyntheticType, SyntheticValue>;
+
+#[cfg(feature = "ui")]
+pub type SyntheticEditorState =
+    GraphEditorState<SyntheticNode, SyntheticType, SyntheticValue, SyntheticTemplate, ()>;
+
+impl SyntheticGraphOptions {
+    pub fn template(&self) -> SyntheticTemplate {
+        SyntheticTemplate {
+            inputs: self.inputs,
+            outputs: self.outputs,
+        }
+    }
+
+    /// Generates the graph. Nodes are created in order, so that the `i`th
+    /// node in `graph.nodes` has `SyntheticNode(i)` as its data.
+    pub fn build_graph(&self) -> SyntheticGraph {
+        let mut graph = SyntheticGraph::new();
+        let template = self.template();
+        let nodes: Vec<NodeId> = (0..self.nodes)
+            .map(|index| {
+                graph.add_node(
+                    format!("Node {index}"),
+                    SyntheticNode(index),
+                    |graph, id| template.build_node(graph, &mut (), id),
+                )
+            })
+            .collect();
+        let input = |graph: &SyntheticGraph, node: usize, index: usize| {
+            graph[nodes[node]].inputs.get(index).map(|(_, id)| *id)
+        };
+        let output = |graph: &SyntheticGraph, node: usize, index: usize| {
+            graph[nodes[node]].outputs.get(index).map(|(_, id)| *id)
+        };
+
+        match self.shape {
+            SyntheticShape::Random => {
+                let mut rng = SplitMix64(self.seed);
+                for _ in 0..self.connections {
+                    if self.nodes < 2 || self.inputs == 0 || self.outputs == 0 {
+                        break;
+                    }
+                    let from = rng.below(self.nodes - 1);
+                    let to = from + 1 + rng.below(self.nodes - from - 1);
+                    let (Some(output), Some(input)) = (
+                        output(&graph, from, rng.below(self.outputs)),
+                        input(&graph, to, rng.below(self.inputs)),
+                    ) else {
+                        continue;
+                    };
+                    if graph.connection(input).is_none() {
+                        graph.add_connection(output, input);
+                    }
+                }
+            }
+            SyntheticShape::Grid => {
+                let rows = self.rows();
+                for node in rows..self.nodes {
+                    for index in 0..self.inputs {
+                        let from = node - rows + index;
+                        // Stay in the previous column
+                        if from / rows != node / rows - 1 {
+                            break;
+                        }
+                        if let (Some(output), Some(input)) =
+                            (output(&graph, from, 0), input(&graph, node, index))
+                        {
+                            graph.add_connection(output, input);
+                        }
+                    }
+                }
+            }
+            SyntheticShape::Chain => {
+                for node in 1..self.nodes {
+                    if let (Some(output), Some(input)) =
+                        (output(&graph, node - 1, 0), input(&graph, node, 0))
+                    {
+                        graph.add_connection(output, input);
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Generates the graph, with its nodes laid out column by column in a
+    /// square grid.
+    #[cfg(feature = "ui")]
+    pub fn build_editor_state(&self) -> SyntheticEditorState {
+        let mut state = SyntheticEditorState {
+            graph: self.build_graph(),
+            ..Default::default()
+        };
+        let rows = self.rows();
+        for (node_id, node) in &state.graph.nodes {
+            let index = node.user_data.0;
+            let position = egui::pos2(
+                (index / rows) as f32 * self.spacing,
+                (index % rows) as f32 * self.spacing * 0.6,
+            );
+            state.node_order.push(node_id);
+            state.node_positions.insert(node_id, position);
+            state
+                .node_orientations
+                .insert(node_id, NodeOrientation::LeftToRight);
+        }
+        state
+    }
+
+    fn rows(&self) -> usize {
+        (self.nodes as f64).sqrt().ceil().max(1.0) as usize
+    }
+}
+
+impl NodeTemplateTrait for SyntheticTemplate {
+    type NodeData = SyntheticNode;
+    type DataType = SyntheticType;
+    type ValueType = SyntheticValue;
+    type UserState = ();
+    type CategoryType = ();
+
+    fn node_finder_label(&self, _user_state: &mut ()) -> Cow<'_, str> {
+        "Node".into()
+    }
+
+    fn node_graph_label(&self, _user_state: &mut ()) -> String {
+        "Node".into()
+    }
+
+    fn user_data(&self, _user_state: &mut ()) -> SyntheticNode {
+        SyntheticNode(0)
+    }
+
+    fn build_node(&self, graph: &mut SyntheticGraph, _user_state: &mut (), node_id: NodeId) {
+        for index in 0..self.inputs {
+            graph.add_input_param(
+                node_id,
+                format!("in {index}"),
+                SyntheticType,
+                SyntheticValue(index as f32),
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+        }
+        for index in 0..self.outputs {
+            graph.add_output_param(node_id, format!("out {index}"), SyntheticType);
+        }
+    }
+}
+
+impl NodeTemplateIter for SyntheticTemplate {
+    type Item = SyntheticTemplate;
+
+    fn all_kinds(&self) -> Vec<SyntheticTemplate> {
+        vec![*self]
+    }
+}
+
+#[cfg(feature = "ui")]
+impl DataTypeTrait<()> for SyntheticType {
+    fn data_type_color(&self, _user_state: &mut ()) -> egui::Color32 {
+        egui::Color32::from_rgb(238, 207, 60)
+    }
+
+    fn name(&self) -> Cow<'_, str> {
+        "value".into()
+    }
+}
+
+#[cfg(feature = "ui")]
+impl WidgetValueTrait for SyntheticValue {
+    type Response = SyntheticResponse;
+    type UserState = ();
+    type NodeData = SyntheticNode;
+
+    fn value_widget(
+        &mut self,
+        param_name: &str,
+        _node_id: NodeId,
+        ui: &mut egui::Ui,
+        _user_state: &mut (),
+        _node_data: &SyntheticNode,
+    ) -> Vec<SyntheticResponse> {
+        ui.horizontal(|ui| {
+            ui.label(param_name);
+            ui.add(egui::DragValue::new(&mut self.0));
+        });
+        Vec::new()
+    }
+}
+
+#[cfg(feature = "ui")]
+impl NodeDataTrait for SyntheticNode {
+    type Response = SyntheticResponse;
+    type UserState = ();
+    type DataType = SyntheticType;
+    type ValueType = SyntheticValue;
+
+    fn bottom_ui(
+        &self,
+        _ui: &mut egui::Ui,
+        _node_id: NodeId,
+        _graph: &SyntheticGraph,
+        _user_state: &mut (),
+    ) -> Vec<NodeResponse<SyntheticResponse, SyntheticNode>> {
+        Vec::new()
+    }
+}
+
+/// Draws `state` in a frame of `ctx`, in a 1000x800 window unless `input`
+/// says otherwise, for the tests driving the editor. Returns the response
+/// of the editor and the output of the frame.
+#[cfg(all(test, feature = "ui"))]
+pub(crate) fn run_editor_frame(
+    ctx: &egui::Context,
+    state: &mut SyntheticEditorState,
+    mut input: egui::RawInput,
+) -> (
+    GraphResponse<SyntheticResponse, SyntheticNode>,
+    egui::FullOutput,
+) {
+    input.screen_rect = input.screen_rect.or(Some(egui::Rect::from_min_size(
+        egui::Pos2::ZERO,
+        egui::vec2(1000.0, 800.0),
+    )));
+    let mut response = None;
+    let output = ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let templates = SyntheticGraphOptions::default().template();
+            response = Some(state.draw_graph_editor(ui, templates, &mut (), Vec::new()));
+        });
+    });
+    (response.expect("The editor should be drawn"), output)
+}
+
+/// A small, fast and seedable random number generator, so that generated
+/// graphs don't depend on a random number crate.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`. `bound` must not be zero.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_shapes() {
+        let options = |shape| SyntheticGraphOptions {
+            shape,
+            nodes: 16,
+            ..Default::default()
+        };
+
+        let chain = options(SyntheticShape::Chain).build_graph();
+        assert_eq!(chain.nodes.len(), 16);
+        assert_eq!(chain.inputs.len(), 32);
+        assert_eq!(chain.connections.len(), 15);
+
+        // 4 columns of 4 nodes. Both inputs of the nodes past the first
+        // column are connected, except for the last row.
+        let grid = options(SyntheticShape::Grid).build_graph();
+        assert_eq!(grid.connections.len(), 3 * (3 * 2 + 1));
+
+        let random = options(SyntheticShape::Random).build_graph();
+        let again = options(SyntheticShape::Random).build_graph();
+        assert!(!random.connections.is_empty());
+        assert_eq!(
+            random.connections.values().collect::<Vec<_>>(),
+            again.connections.values().collect::<Vec<_>>()
+        );
+        for (input, output) in random.iter_connections() {
+            let from = random[random[output].node].user_data.0;
+            let to = random[random[input].node].user_data.0;
+            assert!(from < to);
+        }
+    }
+}
@@ -0,0 +1,612 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// The nodes feeding `node` through its connected inputs, once per
+    /// connection.
+    pub(crate) fn upstream_nodes(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .get(node)
+            .into_iter()
+            .flat_map(|node| node.input_ids())
+            .flat_map(|input| self.connections(input))
+            .filter_map(|output| Some(self.outputs.get(*output)?.node))
+    }
+
+    /// The nodes fed by the outputs of `node`, once per connection.
+    pub(crate) fn downstream_nodes(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .get(node)
+            .into_iter()
+            .flat_map(|node| node.output_ids())
+            .flat_map(|output| self.output_targets(output))
+            .filter_map(|input| Some(self.inputs.get(*input)?.node))
+    }
+
+    /// `nodes` and all the nodes depending on them through connections, each
+    /// once, nearest first. These are the nodes to recompute after `nodes`
+    /// changed.
+    pub fn downstream_closure(&self, nodes: impl IntoIterator<Item = NodeId>) -> Vec<NodeId> {
+        let mut seen: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let mut closure: Vec<NodeId> = nodes
+            .into_iter()
+            .filter(|node_id| {
+                self.nodes.contains_key(*node_id) && seen.insert(*node_id, ()).is_none()
+            })
+            .collect();
+        let mut next = 0;
+        while let Some(node_id) = closure.get(next).copied() {
+            next += 1;
+            for downstream in self.downstream_nodes(node_id) {
+                if seen.insert(downstream, ()).is_none() {
+                    closure.push(downstream);
+                }
+            }
+        }
+        closure
+    }
+
+    /// `nodes` and all the nodes they depend on through connections, each
+    /// once, nearest first.
+    pub fn upstream_closure(&self, nodes: impl IntoIterator<Item = NodeId>) -> Vec<NodeId> {
+        let mut seen: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let mut closure: Vec<NodeId> = nodes
+            .into_iter()
+            .filter(|node_id| {
+                self.nodes.contains_key(*node_id) && seen.insert(*node_id, ()).is_none()
+            })
+            .collect();
+        let mut next = 0;
+        while let Some(node_id) = closure.get(next).copied() {
+            next += 1;
+            for upstream in self.upstream_nodes(node_id) {
+                if seen.insert(upstream, ()).is_none() {
+                    closure.push(upstream);
+                }
+            }
+        }
+        closure
+    }
+
+    /// Whether connecting `output` to `input` would close a cycle: the node
+    /// of `output` depends on the node of `input` already, or is the same
+    /// node. Returns false when either parameter doesn't exist.
+    pub fn would_create_cycle(&self, output: OutputId, input: InputId) -> bool {
+        let (Some(output), Some(input)) = (self.outputs.get(output), self.inputs.get(input)) else {
+            return false;
+        };
+        let (source, target) = (output.node, input.node);
+        let mut seen: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        seen.insert(target, ());
+        let mut pending = vec![target];
+        while let Some(node_id) = pending.pop() {
+            if node_id == source {
+                return true;
+            }
+            for downstream in self.downstream_nodes(node_id) {
+                if seen.insert(downstream, ()).is_none() {
+                    pending.push(downstream);
+                }
+            }
+        }
+        false
+    }
+
+    /// `nodes` and all the nodes connected to them, directly or through
+    /// other nodes, in any direction: the islands they are part of. The
+    /// nodes are in the order of [`Graph::nodes`].
+    pub fn connected_nodes(&self, nodes: impl IntoIterator<Item = NodeId>) -> Vec<NodeId> {
+        let mut seen: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let mut pending: Vec<NodeId> = nodes
+            .into_iter()
+            .filter(|node_id| {
+                self.nodes.contains_key(*node_id) && seen.insert(*node_id, ()).is_none()
+            })
+            .collect();
+        while let Some(node_id) = pending.pop() {
+            for next in self
+                .upstream_nodes(node_id)
+                .chain(self.downstream_nodes(node_id))
+            {
+                if seen.insert(next, ()).is_none() {
+                    pending.push(next);
+                }
+            }
+        }
+        self.nodes
+            .keys()
+            .filter(|node_id| seen.contains_key(*node_id))
+            .collect()
+    }
+
+    /// The groups of nodes connected to each other, directly or through
+    /// other nodes, e.g. to find disconnected experiments left in a graph.
+    /// Nodes without connections are groups of their own. The groups are in
+    /// the order of their first node in [`Graph::nodes`], and so are their
+    /// nodes.
+    pub fn connected_components(&self) -> Vec<Vec<NodeId>> {
+        let mut component_of: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        let mut components: Vec<Vec<NodeId>> = Vec::new();
+        for root in self.nodes.keys() {
+            if component_of.contains_key(root) {
+                continue;
+            }
+            let index = components.len();
+            components.push(Vec::new());
+            component_of.insert(root, index);
+            let mut pending = vec![root];
+            while let Some(node_id) = pending.pop() {
+                for next in self
+                    .upstream_nodes(node_id)
+                    .chain(self.downstream_nodes(node_id))
+                {
+                    if !component_of.contains_key(next) {
+                        component_of.insert(next, index);
+                        pending.push(next);
+                    }
+                }
+            }
+        }
+        for node_id in self.nodes.keys() {
+            components[component_of[node_id]].push(node_id);
+        }
+        components
+    }
+
+    /// All nodes, each one after the nodes feeding its inputs. Nodes that
+    /// don't depend on each other keep the order of [`Graph::nodes`].
+    /// Fails with one of the cycles of the graph when there are any.
+    pub fn topological_order(&self) -> Result<Vec<NodeId>, CycleError> {
+        let mut pending: SecondaryMap<NodeId, usize> = self
+            .nodes
+            .keys()
+            .map(|node_id| (node_id, self.upstream_nodes(node_id).count()))
+            .collect();
+        let mut ready: VecDeque<NodeId> = self
+            .nodes
+            .keys()
+            .filter(|node_id| pending[*node_id] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node_id) = ready.pop_front() {
+            order.push(node_id);
+            for next in self.downstream_nodes(node_id) {
+                pending[next] -= 1;
+                if pending[next] == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+        if order.len() == self.nodes.len() {
+            return Ok(order);
+        }
+
+        // The nodes left each have an upstream node left, so walking
+        // upstream from any of them ends up going around a cycle.
+        let mut walked: Vec<NodeId> = Vec::new();
+        let mut node_id = self
+            .nodes
+            .keys()
+            .find(|node_id| pending[*node_id] > 0)
+            .expect("Nodes with pending inputs should be left");
+        while !walked.contains(&node_id) {
+            walked.push(node_id);
+            node_id = self
+                .upstream_nodes(node_id)
+                .find(|upstream| pending[*upstream] > 0)
+                .expect("Nodes left should have an upstream node left");
+        }
+        let start = walked.iter().position(|walked| *walked == node_id).unwrap();
+        let mut nodes = walked.split_off(start);
+        nodes.reverse();
+        Err(CycleError { nodes })
+    }
+
+    /// `node` and the nodes it depends on, directly or not, each one after
+    /// the nodes feeding its inputs, and `node` last. These are the nodes to
+    /// compute, in order, to get the outputs of `node`. Fails with the first
+    /// cycle found upstream of `node`, and is empty when `node` doesn't
+    /// exist.
+    pub fn dependency_order(&self, node: NodeId) -> Result<Vec<NodeId>, CycleError> {
+        if !self.nodes.contains_key(node) {
+            return Ok(Vec::new());
+        }
+        self.dependency_order_skipping(node, |_| false)
+    }
+
+    /// Like [`Self::dependency_order`], leaving out the nodes for which
+    /// `skip` returns true, along with the nodes only they depend on.
+    pub(crate) fn dependency_order_skipping(
+        &self,
+        node: NodeId,
+        skip: impl Fn(NodeId) -> bool,
+    ) -> Result<Vec<NodeId>, CycleError> {
+        let mut order = Vec::new();
+        let mut done: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        // The nodes being visited, each with the nodes it depends on
+        let mut path: Vec<(NodeId, Vec<NodeId>)> = Vec::new();
+        // The nodes of `path`, to find cycles without walking it
+        let mut on_path: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let visit = |node_id: NodeId,
+                     path: &mut Vec<(NodeId, Vec<NodeId>)>,
+                     on_path: &mut SecondaryMap<NodeId, ()>| {
+            // Popped from the end, so the first input comes first
+            let mut upstream: Vec<NodeId> = self.upstream_nodes(node_id).collect();
+            upstream.reverse();
+            path.push((node_id, upstream));
+            on_path.insert(node_id, ());
+        };
+        if !skip(node) {
+            visit(node, &mut path, &mut on_path);
+        }
+        while let Some((node_id, upstream)) = path.last_mut() {
+            let node_id = *node_id;
+            let Some(next) = upstream.pop() else {
+                path.pop();
+                on_path.remove(node_id);
+                done.insert(node_id, ());
+                order.push(node_id);
+                continue;
+            };
+            if skip(next) || done.contains_key(next) {
+                continue;
+            }
+            if on_path.contains_key(next) {
+                let start = path.iter().position(|(node_id, _)| *node_id == next);
+                // The path goes upstream, cycles are reported downstream
+                let nodes = path[start.unwrap()..]
+                    .iter()
+                    .rev()
+                    .map(|(node_id, _)| *node_id)
+                    .collect();
+                return Err(CycleError { nodes });
+            }
+            visit(next, &mut path, &mut on_path);
+        }
+        Ok(order)
+    }
+}
+
+/// The nodes and connections data flows through from one node to another,
+/// see [`Graph::path_between`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphPath {
+    /// The nodes on the paths, the first node first.
+    pub nodes: Vec<NodeId>,
+    /// The connections on the paths, as input-output pairs.
+    pub connections: Vec<(InputId, OutputId)>,
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// The nodes and connections on every path going downstream from `from`
+    /// to `to`, e.g. to trace how data reaches an output in a tangled
+    /// graph. Returns `None` when `to` doesn't depend on `from`. The nodes of
+    /// cycles met along the way are part of the paths.
+    pub fn path_between(&self, from: NodeId, to: NodeId) -> Option<GraphPath> {
+        let upstream: SecondaryMap<NodeId, ()> = self
+            .upstream_closure([to])
+            .into_iter()
+            .map(|node_id| (node_id, ()))
+            .collect();
+        if !upstream.contains_key(from) {
+            return None;
+        }
+        let nodes: Vec<NodeId> = self
+            .downstream_closure([from])
+            .into_iter()
+            .filter(|node_id| upstream.contains_key(*node_id))
+            .collect();
+        let members: SecondaryMap<NodeId, ()> = nodes.iter().map(|node| (*node, ())).collect();
+        let connections = self
+            .iter_connections()
+            .filter(|(input, output)| {
+                let ends = self.inputs.get(*input).zip(self.outputs.get(*output));
+                ends.is_some_and(|(input, output)| {
+                    members.contains_key(input.node) && members.contains_key(output.node)
+                })
+            })
+            .collect();
+        Some(GraphPath { nodes, connections })
+    }
+}
+
+/// Nodes that feed each other through their connections, see
+/// [`Graph::find_cycles`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphCycle {
+    pub nodes: Vec<NodeId>,
+    /// The connections between the nodes of the cycle, as input-output pairs.
+    pub connections: Vec<(InputId, OutputId)>,
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// The cycles of the graph, e.g. to highlight their connections or to
+    /// reject the graph before evaluating it. Cycles sharing nodes are
+    /// reported as one, with all the nodes that can reach each other. Nodes
+    /// connected to themselves are cycles too.
+    pub fn find_cycles(&self) -> Vec<GraphCycle> {
+        let mut tarjan = Tarjan::default();
+        let mut cycles = Vec::new();
+        for root in self.nodes.keys() {
+            if tarjan.index.contains_key(root) {
+                continue;
+            }
+            tarjan.visit(self, root);
+            while let Some(frame) = tarjan.frames.last_mut() {
+                let node = frame.node;
+                if let Some(next) = frame.downstream.get(frame.visited).copied() {
+                    frame.visited += 1;
+                    match tarjan.index.get(next) {
+                        Some(next_index) if tarjan.on_stack.contains_key(next) => {
+                            tarjan.lowlink[node] = tarjan.lowlink[node].min(*next_index);
+                        }
+                        Some(_) => {}
+                        None => tarjan.visit(self, next),
+                    }
+                    continue;
+                }
+
+                tarjan.frames.pop();
+                if let Some(parent) = tarjan.frames.last() {
+                    tarjan.lowlink[parent.node] =
+                        tarjan.lowlink[parent.node].min(tarjan.lowlink[node]);
+                }
+                if tarjan.lowlink[node] != tarjan.index[node] {
+                    continue;
+                }
+                // `node` is the root of a strongly connected component
+                let mut component = Vec::new();
+                while let Some(member) = tarjan.stack.pop() {
+                    tarjan.on_stack.remove(member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                if component.len() > 1 || self.downstream_nodes(node).any(|next| next == node) {
+                    component.reverse();
+                    cycles.push(self.cycle_of(component));
+                }
+            }
+        }
+        cycles
+    }
+
+    fn cycle_of(&self, nodes: Vec<NodeId>) -> GraphCycle {
+        let members: SecondaryMap<NodeId, ()> = nodes.iter().map(|node| (*node, ())).collect();
+        let connections = nodes
+            .iter()
+            .flat_map(|node| self[*node].input_ids())
+            .flat_map(|input| {
+                self.connections(input)
+                    .iter()
+                    .map(move |output| (input, *output))
+            })
+            .filter(|(_, output)| {
+                self.outputs
+                    .get(*output)
+                    .is_some_and(|output| members.contains_key(output.node))
+            })
+            .collect();
+        GraphCycle { nodes, connections }
+    }
+}
+
+/// The state of Tarjan's strongly connected components algorithm, walking
+/// the graph without recursion so huge graphs don't overflow the stack.
+#[derive(Default)]
+struct Tarjan {
+    index: SecondaryMap<NodeId, usize>,
+    lowlink: SecondaryMap<NodeId, usize>,
+    on_stack: SecondaryMap<NodeId, ()>,
+    stack: Vec<NodeId>,
+    frames: Vec<TarjanFrame>,
+}
+
+struct TarjanFrame {
+    node: NodeId,
+    downstream: Vec<NodeId>,
+    visited: usize,
+}
+
+impl Tarjan {
+    fn visit<NodeData, DataType, ValueType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node: NodeId,
+    ) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.lowlink.insert(node, index);
+        self.on_stack.insert(node, ());
+        self.stack.push(node);
+        self.frames.push(TarjanFrame {
+            node,
+            downstream: graph.downstream_nodes(node).collect(),
+            visited: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_node(graph: &mut Graph<(), (), ()>) -> (NodeId, InputId, OutputId) {
+        let node = graph.add_node("node".into(), (), |_, _| {});
+        let input = graph.add_input_param(
+            node,
+            "in".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        let output = graph.add_output_param(node, "out".into(), ());
+        (node, input, output)
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (a, a_in, a_out) = add_node(&mut graph);
+        let (b, b_in, b_out) = add_node(&mut graph);
+        let (c, c_in, c_out) = add_node(&mut graph);
+        let (d, _, _) = add_node(&mut graph);
+        // c <- b <- a
+        graph.add_connection(b_out, c_in);
+        graph.add_connection(a_out, b_in);
+        assert_eq!(graph.topological_order(), Ok(vec![a, d, b, c]));
+
+        // a <- c <- b <- a, reported along the connections
+        graph.add_connection(c_out, a_in);
+        let mut cycle = graph.topological_order().unwrap_err();
+        let start = cycle.nodes.iter().position(|node| *node == a).unwrap();
+        cycle.nodes.rotate_left(start);
+        assert_eq!(cycle.nodes, vec![a, b, c]);
+
+        let mut graph = Graph::<(), (), ()>::new();
+        let (e, e_in, e_out) = add_node(&mut graph);
+        graph.add_connection(e_out, e_in);
+        assert_eq!(
+            graph.topological_order(),
+            Err(CycleError { nodes: vec![e] })
+        );
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (a, a_in, a_out) = add_node(&mut graph);
+        let (b, b_in, b_out) = add_node(&mut graph);
+        let (c, c_in, _) = add_node(&mut graph);
+        let (d, _, d_out) = add_node(&mut graph);
+        let c_in2 = graph.add_input_param(
+            c,
+            "in2".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        // a -> b -> c, d -> c
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(b_out, c_in);
+        graph.add_connection(d_out, c_in2);
+        assert_eq!(graph.dependency_order(c), Ok(vec![a, b, d, c]));
+        assert_eq!(graph.dependency_order(b), Ok(vec![a, b]));
+        assert_eq!(graph.dependency_order(a), Ok(vec![a]));
+
+        graph.add_connection(b_out, a_in);
+        assert!(graph.dependency_order(c).is_err());
+        graph.remove_node(d);
+        assert_eq!(graph.dependency_order(d), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_would_create_cycle() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (_, a_in, a_out) = add_node(&mut graph);
+        let (_, b_in, b_out) = add_node(&mut graph);
+        let (_, c_in, c_out) = add_node(&mut graph);
+        // a -> b -> c
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(b_out, c_in);
+
+        assert!(graph.would_create_cycle(c_out, a_in));
+        assert!(graph.would_create_cycle(b_out, b_in));
+        assert!(!graph.would_create_cycle(a_out, c_in));
+        graph.remove_connection(b_in);
+        assert!(!graph.would_create_cycle(c_out, a_in));
+    }
+
+    #[test]
+    fn test_path_between() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (a, _, a_out) = add_node(&mut graph);
+        let (b, b_in, b_out) = add_node(&mut graph);
+        let (c, c_in, c_out) = add_node(&mut graph);
+        let (d, d_in, _) = add_node(&mut graph);
+        let (e, e_in, _) = add_node(&mut graph);
+        let d_in2 = graph.add_input_param(
+            d,
+            "in2".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        // a -> b -> d, a -> c -> d, and c -> e off the path
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(a_out, c_in);
+        graph.add_connection(b_out, d_in);
+        graph.add_connection(c_out, d_in2);
+        graph.add_connection(c_out, e_in);
+
+        assert_eq!(graph.upstream_closure([d]), vec![d, b, c, a]);
+        let path = graph.path_between(a, d).unwrap();
+        assert_eq!(path.nodes, vec![a, b, c, d]);
+        assert_eq!(path.connections.len(), 4);
+        assert!(!path.connections.contains(&(e_in, c_out)));
+        assert_eq!(graph.path_between(d, a), None);
+        assert_eq!(graph.path_between(b, e), None);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (a, a_in, _) = add_node(&mut graph);
+        let (b, _, _) = add_node(&mut graph);
+        let (c, _, c_out) = add_node(&mut graph);
+        let (d, d_in, _) = add_node(&mut graph);
+        // c -> a and c -> d, b on its own
+        graph.add_connection(c_out, a_in);
+        graph.add_connection(c_out, d_in);
+
+        assert_eq!(graph.connected_components(), vec![vec![a, c, d], vec![b]]);
+        assert_eq!(graph.connected_nodes([d]), vec![a, c, d]);
+        assert_eq!(graph.connected_nodes([b, a]), vec![a, b, c, d]);
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let (a, a_in, a_out) = add_node(&mut graph);
+        let (b, b_in, b_out) = add_node(&mut graph);
+        let (c, c_in, _) = add_node(&mut graph);
+        let (d, d_in, d_out) = add_node(&mut graph);
+        let (e, e_in, _) = add_node(&mut graph);
+        assert!(graph.find_cycles().is_empty());
+
+        // a -> b -> a, and b -> c out of the cycle
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(b_out, a_in);
+        graph.add_connection(b_out, c_in);
+        // d -> d
+        graph.add_connection(d_out, d_in);
+        // e is fed by the cycle of d
+        graph.add_connection(d_out, e_in);
+
+        let cycles = graph.find_cycles();
+        assert_eq!(cycles.len(), 2);
+        let ab = cycles
+            .iter()
+            .find(|cycle| cycle.nodes.contains(&a))
+            .unwrap();
+        assert_eq!(ab.nodes.len(), 2);
+        assert!(ab.nodes.contains(&b));
+        assert_eq!(ab.connections.len(), 2);
+        assert!(ab.connections.contains(&(a_in, b_out)));
+        assert!(ab.connections.contains(&(b_in, a_out)));
+        let dd = cycles
+            .iter()
+            .find(|cycle| cycle.nodes.contains(&d))
+            .unwrap();
+        assert_eq!(dd.nodes, vec![d]);
+        assert_eq!(dd.connections, vec![(d_in, d_out)]);
+        assert!(!cycles
+            .iter()
+            .any(|cycle| cycle.nodes.contains(&c) || cycle.nodes.contains(&e)));
+    }
+}
@@ -9,6 +9,7 @@ use super::*;
 /// `node_data` parameter during `value_widget`. The default value is never
 /// used, so the implementation is not important, but it should be reasonably
 /// cheap to construct.
+#[cfg(feature = "ui")]
 pub trait WidgetValueTrait: Default {
     type Response;
     type UserState;
@@ -52,6 +53,7 @@ pub trait WidgetValueTrait: Default {
 /// This trait must be implemented by the `DataType` generic parameter of the
 /// [`Graph`]. This trait tells the library how to visually expose data types
 /// to the user.
+#[cfg(feature = "ui")]
 pub trait DataTypeTrait<UserState>: PartialEq + Eq {
     /// The associated port color of this datatype
     fn data_type_color(&self, user_state: &mut UserState) -> egui::Color32;
@@ -84,11 +86,26 @@ pub trait DataTypeTrait<UserState>: PartialEq + Eq {
     ///     }
     /// }
     /// ```
-    fn name(&self) -> std::borrow::Cow<str>;
+    fn name(&self) -> std::borrow::Cow<'_, str>;
+
+    /// The content of the tooltip shown when hovering a port of this type,
+    /// below the name of the parameter. Override it to show documentation,
+    /// or the values the application keeps for `param_id` in `user_state`.
+    ///
+    /// Shows the name of the type by default.
+    fn tooltip_ui(
+        &self,
+        ui: &mut egui::Ui,
+        _param_id: AnyParameterId,
+        _user_state: &mut UserState,
+    ) {
+        ui.weak(self.name());
+    }
 }
 
 /// This trait must be implemented for the `NodeData` generic parameter of the
 /// [`Graph`]. This trait allows customizing some aspects of the node drawing.
+#[cfg(feature = "ui")]
 pub trait NodeDataTrait
 where
     Self: Sized,
@@ -158,6 +175,71 @@ where
         None
     }
 
+    /// Set background color on the body of the node, e.g. to tell node
+    /// families apart or to point out errors. Nodes without a title bar are
+    /// entirely filled with it.
+    /// If the return value is None, the default color is set.
+    fn body_color(
+        &self,
+        _ui: &egui::Ui,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Option<egui::Color32> {
+        None
+    }
+
+    /// The sizes the node can be resized to. Nodes returning some get a
+    /// handle in their bottom right corner resizing them, and fill their
+    /// size, which suits large custom UIs like plots or text editors.
+    /// Other nodes shrink to fit their contents, within
+    /// [`GraphNodeWidget::MAX_NODE_SIZE`].
+    ///
+    /// Nodes can't be resized by default.
+    fn size_constraints(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Option<NodeSizeConstraints> {
+        None
+    }
+
+    /// Where to show the title of the node, along with the top bar UI and
+    /// the buttons. Defaults to a title bar at the top.
+    fn title_placement(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> TitlePlacement {
+        TitlePlacement::Top
+    }
+
+    /// Whether the node is disabled or bypassed, so it's drawn faded out to
+    /// tell the inactive parts of the graph apart, see
+    /// [`GraphEditorStyle::disabled`]. Defaults to false.
+    fn is_disabled(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> bool {
+        false
+    }
+
+    /// The text style, size and color of the title and the parameter labels
+    /// of the node, e.g. monospace for code nodes or larger titles for group
+    /// headers. Defaults to the style of the editor.
+    fn text_style(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> NodeTextStyle {
+        NodeTextStyle::default()
+    }
+
     /// Separator to put between elements in the node.
     ///
     /// Invoked between inputs, outputs and bottom UI. Useful for
@@ -176,6 +258,40 @@ where
     ) {
     }
 
+    /// Paints a port of this node, e.g. as a ring when connected, with a
+    /// glyph of its data type inside, or as an image. The editor handles
+    /// the interaction with the port either way.
+    ///
+    /// Defaults to a circle of `port.color`.
+    fn draw_port(
+        &self,
+        painter: &egui::Painter,
+        port: &PortDrawInfo,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) {
+        painter.circle_filled(port.center, port.radius, port.color);
+    }
+
+    /// Draws the value of a [`Probe`] attached to one of the outputs of
+    /// this node, e.g. as a plot or a color swatch.
+    ///
+    /// Defaults to [`ProbeValue::show`], in the color of the data type.
+    fn probe_ui(
+        &self,
+        ui: &mut egui::Ui,
+        output: OutputId,
+        value: &ProbeValue,
+        graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        user_state: &mut Self::UserState,
+    ) where
+        Self::DataType: DataTypeTrait<Self::UserState>,
+    {
+        let color = graph[output].typ.data_type_color(user_state);
+        value.show(ui, color);
+    }
+
     fn can_delete(
         &self,
         _node_id: NodeId,
@@ -251,7 +367,7 @@ pub trait NodeTemplateTrait: Clone {
     /// The return type is Cow<str> to allow returning owned or borrowed values
     /// more flexibly. Refer to the documentation for `DataTypeTrait::name` for
     /// more information
-    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<str>;
+    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<'_, str>;
 
     /// Vec of categories to which the node belongs.
     ///
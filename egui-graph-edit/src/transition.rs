@@ -0,0 +1,161 @@
+use super::*;
+use egui::Pos2;
+
+/// Nodes gliding to the positions set by a layout or an arrange action, see
+/// [`GraphEditorState::layout_transition_duration`].
+///
+/// [`GraphEditorState::node_positions`] always holds where the nodes end
+/// up. The editor only shows them on their way there while drawing.
+#[derive(Clone, Debug, Default)]
+pub struct LayoutTransition {
+    /// Where each moving node started from.
+    pub from: SecondaryMap<NodeId, Pos2>,
+    /// When the nodes started moving, in egui time. Set on the first frame
+    /// drawn after they were moved.
+    pub start: Option<f64>,
+    /// How far along the nodes were on the last frame, from 0 to 1, before
+    /// easing.
+    pub progress: f32,
+    /// The target and shown position of the nodes drawn on their way, while
+    /// the editor is being drawn.
+    pub shown: SecondaryMap<NodeId, (Pos2, Pos2)>,
+}
+
+impl LayoutTransition {
+    pub fn is_running(&self) -> bool {
+        !self.from.is_empty()
+    }
+
+    fn eased_progress(&self) -> f32 {
+        egui::emath::easing::cubic_out(self.progress)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Moves nodes to new positions, animating them from where they are
+    /// shown when [`GraphEditorState::layout_transition_duration`] isn't
+    /// zero. Nodes that were already moving start over from where they are
+    /// shown, so that they don't jump.
+    pub fn move_nodes_animated(&mut self, positions: impl IntoIterator<Item = (NodeId, Pos2)>) {
+        let animate = !self.layout_transition_duration.is_zero();
+        if let Some(history) = &mut self.undo_history {
+            history.unrecorded = true;
+        }
+        let transition = &mut self.layout_transition;
+        let eased = transition.eased_progress();
+        for (node_id, from) in transition.from.iter_mut() {
+            if let Some((_, shown)) = transition.shown.get(node_id) {
+                *from = *shown;
+            } else if let Some(target) = self.node_positions.get(node_id) {
+                *from = from.lerp(*target, eased);
+            }
+        }
+        transition.start = None;
+        transition.progress = 0.0;
+
+        for (node_id, target) in positions {
+            let Some(position) = self.node_positions.get_mut(node_id) else {
+                continue;
+            };
+            let shown = transition.shown.get_mut(node_id);
+            // Moving nodes were rebased above
+            if animate && !transition.from.contains_key(node_id) {
+                let from = shown.as_ref().map_or(*position, |(_, shown)| *shown);
+                transition.from.insert(node_id, from);
+            }
+            match shown {
+                // The node is drawn on its way, and lands on the target at
+                // the end of the frame
+                Some((shown_target, _)) => *shown_target = target,
+                None => *position = target,
+            }
+        }
+    }
+
+    /// Shows the moving nodes on their way for the frame being drawn, until
+    /// [`Self::end_layout_transition_frame`].
+    pub(crate) fn begin_layout_transition_frame(&mut self, time: f64) {
+        let transition = &mut self.layout_transition;
+        if !transition.is_running() {
+            return;
+        }
+        let duration = self.layout_transition_duration.as_secs_f64();
+        let start = *transition.start.get_or_insert(time);
+        transition.progress = if duration > 0.0 {
+            ((time - start) / duration).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        let eased = transition.eased_progress();
+        for (node_id, from) in &transition.from {
+            if let Some(position) = self.node_positions.get_mut(node_id) {
+                let shown = from.lerp(*position, eased);
+                transition.shown.insert(node_id, (*position, shown));
+                *position = shown;
+            }
+        }
+        if transition.progress >= 1.0 {
+            transition.from.clear();
+        }
+    }
+
+    /// Puts the moving nodes back at their target, keeping the moves made
+    /// while drawing, e.g. dragging a node on its way.
+    pub(crate) fn end_layout_transition_frame(&mut self) {
+        let transition = &mut self.layout_transition;
+        for (node_id, (target, shown)) in transition.shown.drain() {
+            if let Some(position) = self.node_positions.get_mut(node_id) {
+                let moved = *position - shown;
+                *position = target + moved;
+                if let Some(from) = transition.from.get_mut(node_id) {
+                    *from += moved;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::pos2;
+    use std::time::Duration;
+
+    #[test]
+    fn test_layout_transition() {
+        let mut state = GraphEditorState::<(), (), (), (), ()> {
+            layout_transition_duration: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let node = state.graph.add_node("node".into(), (), |_, _| {});
+        state.node_positions.insert(node, pos2(0.0, 0.0));
+
+        state.move_nodes_animated([(node, pos2(100.0, 0.0))]);
+        assert_eq!(state.node_positions[node], pos2(100.0, 0.0));
+
+        state.begin_layout_transition_frame(10.0);
+        assert_eq!(state.node_positions[node], pos2(0.0, 0.0));
+        state.end_layout_transition_frame();
+
+        // Dragged while on its way
+        state.begin_layout_transition_frame(10.5);
+        let shown = state.node_positions[node];
+        assert!(shown.x > 50.0 && shown.x < 100.0);
+        state.node_positions[node].y += 10.0;
+        state.end_layout_transition_frame();
+        assert_eq!(state.node_positions[node], pos2(100.0, 10.0));
+
+        // Starts over from where it was shown
+        state.move_nodes_animated([(node, pos2(0.0, 10.0))]);
+        state.begin_layout_transition_frame(11.0);
+        assert_eq!(state.node_positions[node], shown + egui::vec2(0.0, 10.0));
+        state.end_layout_transition_frame();
+
+        state.begin_layout_transition_frame(12.0);
+        state.end_layout_transition_frame();
+        assert!(!state.layout_transition.is_running());
+        assert_eq!(state.node_positions[node], pos2(0.0, 10.0));
+    }
+}
@@ -0,0 +1,219 @@
+use super::*;
+use egui::{Pos2, Vec2};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Where a [`TreeLayout`] puts the dependencies of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum TreeDirection {
+    /// On the side of the inputs of the root: to the left of a root going
+    /// left to right, and to the right of a root going right to left.
+    #[default]
+    Horizontal,
+    /// Above the root.
+    Vertical,
+}
+
+/// Lays out the dependency trees of the given roots: the nodes feeding
+/// each root, the nodes feeding those, and so on, one level per column (or
+/// row). Roots keep their position and their subtrees are centered on them.
+///
+/// A node that feeds several branches goes with the branch closest to a
+/// root, and siblings keep the order they currently have across the level.
+/// Nodes outside the trees don't move. This suits expression-like graphs,
+/// where most nodes feed a single other node. To use the selected nodes as
+/// roots, set [`TreeLayout::roots`] to [`GraphEditorState::selected_nodes`].
+#[derive(Debug, Clone)]
+pub struct TreeLayout {
+    pub roots: Vec<NodeId>,
+    pub direction: TreeDirection,
+    /// The space between neighbouring nodes of the same level.
+    pub sibling_spacing: f32,
+    /// The space between two levels.
+    pub level_spacing: f32,
+}
+
+impl Default for TreeLayout {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            direction: TreeDirection::default(),
+            sibling_spacing: 20.0,
+            level_spacing: 60.0,
+        }
+    }
+}
+
+impl LayoutAlgorithm for TreeLayout {
+    fn layout(&self, input: &LayoutInput) -> LayoutPositions {
+        let vertical = self.direction == TreeDirection::Vertical;
+        // Levels go along the first coordinate, siblings along the second
+        let split = |v: Vec2| if vertical { (v.y, v.x) } else { (v.x, v.y) };
+        let join = |level: f32, cross: f32| {
+            if vertical {
+                Pos2::new(cross, level)
+            } else {
+                Pos2::new(level, cross)
+            }
+        };
+        let size = |node_id: NodeId| {
+            split(
+                input
+                    .sizes
+                    .get(node_id)
+                    .copied()
+                    .unwrap_or(UNMEASURED_NODE_SIZE),
+            )
+        };
+        let cross_center =
+            |node_id: NodeId| split(input.positions[node_id].to_vec2()).1 + size(node_id).1 / 2.0;
+
+        let mut dependencies: SecondaryMap<NodeId, Vec<NodeId>> = SecondaryMap::new();
+        for (from, to) in &input.edges {
+            if input.positions.contains_key(*from) && input.positions.contains_key(*to) {
+                dependencies.entry(*to).unwrap().or_default().push(*from);
+            }
+        }
+
+        // Roots are claimed up front, so that a root feeding another one
+        // still anchors its own tree
+        let mut claimed: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let roots: Vec<NodeId> = self
+            .roots
+            .iter()
+            .copied()
+            .filter(|root| input.positions.contains_key(*root))
+            .filter(|root| claimed.insert(*root, ()).is_none())
+            .collect();
+
+        let mut positions = LayoutPositions::new();
+        for root in roots {
+            // Breadth first, so that shared nodes go to the shallowest branch
+            let mut order = vec![(root, 0)];
+            let mut children: SecondaryMap<NodeId, Vec<NodeId>> = SecondaryMap::new();
+            let mut next = 0;
+            while let Some(&(node_id, depth)) = order.get(next) {
+                next += 1;
+                let mut kids: Vec<NodeId> = dependencies
+                    .get(node_id)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|kid| claimed.insert(*kid, ()).is_none())
+                    .collect();
+                kids.sort_by(|a, b| cross_center(*a).total_cmp(&cross_center(*b)));
+                order.extend(kids.iter().map(|kid| (*kid, depth + 1)));
+                children.insert(node_id, kids);
+            }
+
+            // The room each subtree takes across the levels, leaves first
+            let mut spans: SecondaryMap<NodeId, f32> = SecondaryMap::new();
+            let mut level_sizes: Vec<f32> = Vec::new();
+            for (node_id, depth) in order.iter().rev() {
+                let kids = &children[*node_id];
+                let kids_span = self.row_span(kids, &spans);
+                let (level_size, cross_size) = size(*node_id);
+                spans.insert(*node_id, cross_size.max(kids_span));
+                if level_sizes.len() <= *depth {
+                    level_sizes.resize(*depth + 1, 0.0);
+                }
+                level_sizes[*depth] = level_sizes[*depth].max(level_size);
+            }
+
+            // Whether levels go towards lower coordinates
+            let backwards = vertical
+                || !matches!(
+                    input.orientations.get(root),
+                    Some(NodeOrientation::RightToLeft)
+                );
+            let (root_level, _) = split(input.positions[root].to_vec2());
+            // The edge of each level facing the root
+            let mut faces = Vec::with_capacity(level_sizes.len());
+            let mut face = if backwards {
+                root_level + size(root).0
+            } else {
+                root_level
+            };
+            for level_size in &level_sizes {
+                faces.push(face);
+                let step = level_size + self.level_spacing;
+                face += if backwards { -step } else { step };
+            }
+
+            let mut centers: SecondaryMap<NodeId, f32> = SecondaryMap::new();
+            centers.insert(root, cross_center(root));
+            for (node_id, depth) in &order {
+                let center = centers[*node_id];
+                let (level_size, cross_size) = size(*node_id);
+                let level = if backwards {
+                    faces[*depth] - level_size
+                } else {
+                    faces[*depth]
+                };
+                positions.insert(*node_id, join(level, center - cross_size / 2.0));
+
+                let kids = &children[*node_id];
+                let kids_span = self.row_span(kids, &spans);
+                let mut cursor = center - kids_span / 2.0;
+                for kid in kids {
+                    centers.insert(*kid, cursor + spans[*kid] / 2.0);
+                    cursor += spans[*kid] + self.sibling_spacing;
+                }
+            }
+        }
+        positions
+    }
+}
+
+impl TreeLayout {
+    /// The room taken by the subtrees of `kids` side by side.
+    fn row_span(&self, kids: &[NodeId], spans: &SecondaryMap<NodeId, f32>) -> f32 {
+        kids.iter().map(|kid| spans[*kid]).sum::<f32>()
+            + self.sibling_spacing * kids.len().saturating_sub(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn test_tree_layout() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let [root, a, b, c, other] = ["root", "a", "b", "c", "other"]
+            .map(|label| graph.add_node(label.into(), (), |_, _| {}));
+        let mut input = LayoutInput::default();
+        for (node_id, position) in [
+            (root, pos2(1000.0, 0.0)),
+            (a, pos2(0.0, 0.0)),
+            (b, pos2(10.0, 100.0)),
+            (c, pos2(0.0, 0.0)),
+            (other, pos2(0.0, 0.0)),
+        ] {
+            input.nodes.push(node_id);
+            input.positions.insert(node_id, position);
+            input.sizes.insert(node_id, vec2(100.0, 50.0));
+        }
+        // c feeds both a and b, and stays with a which comes first
+        input.edges = vec![(a, root), (b, root), (c, a), (c, b), (root, other)];
+
+        let mut layout = TreeLayout {
+            roots: vec![root],
+            ..Default::default()
+        };
+        let positions = layout.layout(&input);
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[root], pos2(1000.0, 0.0));
+        assert_eq!(positions[a], pos2(840.0, -35.0));
+        assert_eq!(positions[b], pos2(840.0, 35.0));
+        assert_eq!(positions[c], pos2(680.0, -35.0));
+
+        layout.direction = TreeDirection::Vertical;
+        let positions = layout.layout(&input);
+        assert_eq!(positions[a], pos2(940.0, -110.0));
+        assert_eq!(positions[b], pos2(1060.0, -110.0));
+    }
+}
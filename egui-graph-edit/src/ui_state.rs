@@ -4,13 +4,59 @@ use std::marker::PhantomData;
 #[cfg(feature = "persistence")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Default, Copy, Clone)]
+/// Which connections the editor lets users make, see
+/// [`GraphEditorState::cycle_policy`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum CyclePolicy {
+    /// Any connection between compatible ports.
+    #[default]
+    Allow,
+    /// Keeps the graph acyclic: connections that would close a cycle are
+    /// shown as invalid while dragged, and
+    /// [`NodeResponse::ConnectEventEnded`] responses making them are
+    /// dropped. See [`Graph::would_create_cycle`].
+    Reject,
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "persistence", serde(from = "SavedPanZoom"))]
 pub struct PanZoom {
     pub pan: egui::Vec2,
     pub zoom: f32,
 }
 
+/// Older saves store a zoom of 0, which the editor can't divide by, so
+/// loading resets the values that can't be drawn.
+#[cfg(feature = "persistence")]
+#[derive(Deserialize)]
+struct SavedPanZoom {
+    pan: egui::Vec2,
+    zoom: f32,
+}
+
+#[cfg(feature = "persistence")]
+impl From<SavedPanZoom> for PanZoom {
+    fn from(saved: SavedPanZoom) -> Self {
+        let mut pan_zoom = PanZoom {
+            pan: saved.pan,
+            zoom: saved.zoom,
+        };
+        pan_zoom.reset_invalid();
+        pan_zoom
+    }
+}
+
+impl Default for PanZoom {
+    fn default() -> Self {
+        Self {
+            pan: egui::Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
 /// Orientation of the node
 ///
 /// - [`NodeOrientation::LeftToRight`] - inputs on the left, outputs on the right
@@ -37,7 +83,7 @@ pub struct GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserSta
     pub graph: Graph<NodeData, DataType, ValueType>,
     /// Nodes are drawn in this order. Draw order is important because nodes
     /// that are drawn last are on top.
-    pub node_order: Vec<NodeId>,
+    pub node_order: NodeOrder,
     /// An ongoing connection interaction: The mouse has dragged away from a
     /// port and the user is holding the click
     pub connection_in_progress: Option<(NodeId, AnyParameterId)>,
@@ -50,10 +96,225 @@ pub struct GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserSta
     pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
     /// Orientation of each node
     pub node_orientations: SecondaryMap<NodeId, NodeOrientation>,
+    /// The size of the nodes that were resized, in graph coordinates, see
+    /// [`NodeDataTrait::size_constraints`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_sizes: SecondaryMap<NodeId, egui::Vec2>,
+    /// The nodes drawn as their title bar only, see
+    /// [`GraphEditorState::set_node_collapsed`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub collapsed_nodes: SecondaryMap<NodeId, ()>,
     /// The node finder is used to create new nodes.
     pub node_finder: Option<NodeFinder<NodeTemplate>>,
-    /// The panning of the graph viewport.
+    /// The templates recently created from the node finder, and the pinned
+    /// ones, listed first in the finder.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub template_history: TemplateHistory,
+    /// Move nodes created from the node finder or with
+    /// [`GraphEditorState::add_node_from_template`] to the nearest free spot
+    /// when their position is taken by another node. Enabled by default.
+    #[cfg_attr(feature = "persistence", serde(default = "default_avoid_overlaps"))]
+    pub avoid_overlaps: bool,
+    /// The node being dragged with the pointer, if any.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_drag: Option<NodeDrag>,
+    /// The increment nodes move by when dragged while holding ctrl (cmd on
+    /// macOS).
+    #[cfg_attr(feature = "persistence", serde(default = "default_drag_step"))]
+    pub drag_step: f32,
+    /// Push the nodes that dragged nodes run into out of their way, see
+    /// [`GraphEditorState::push_nodes_aside`]. Disabled by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub push_aside: bool,
+    /// How far pasted and duplicated nodes land from the nodes they were
+    /// copied from, see [`GraphEditorState::paste_fragment`].
+    #[cfg_attr(feature = "persistence", serde(default = "default_paste_offset"))]
+    pub paste_offset: egui::Vec2,
+    /// Where the last fragment was pasted from, and how many times in a row.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub paste_cascade: Option<(egui::Pos2, u32)>,
+    /// The nodes copied by [`GraphEditorState::copy_selection`].
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    pub clipboard: Option<GraphFragment<NodeData, DataType, ValueType>>,
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    pub(crate) clipboard_hooks:
+        Option<EditorClipboardHooks<NodeData, DataType, ValueType, NodeTemplate, UserState>>,
+    /// Where the menu arranging the selected nodes is open, in screen
+    /// coordinates. It opens when right clicking one of several selected
+    /// nodes.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub selection_menu: Option<egui::Pos2>,
+    /// Route connections with horizontal and vertical segments only,
+    /// turning on the vertical lines of a grid with this spacing, in graph
+    /// coordinates. Use the same spacing as a [`GridLayout`] for schematic
+    /// diagrams. Takes precedence over [`Self::connection_style`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub connection_grid: Option<f32>,
+    /// How connections are drawn. Curves by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub connection_style: ConnectionStyle,
+    /// Shows a scaled-down view of the whole graph in a corner of the
+    /// editor, which pans the editor when clicked. Disabled by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub minimap: Option<Minimap>,
+    /// A grid drawn behind the nodes. None by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub background_grid: Option<BackgroundGrid>,
+    /// The keyboard shortcuts deleting, selecting and duplicating nodes.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub keybindings: EditorKeybindings,
+    /// The waypoints connections are routed through, by input, see
+    /// [`ConnectionWaypoints`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub waypoints: SecondaryMap<InputId, SVec<ConnectionWaypoints>>,
+    /// The frames drawn behind the nodes, see [`CommentFrame`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub frames: SlotMap<FrameId, CommentFrame>,
+    /// The frame whose title is being edited, if any.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub frame_renaming: Option<FrameRenaming>,
+    /// The frame being dragged by its title bar, if any.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub frame_drag: Option<FrameDrag>,
+    /// Snap dragged nodes, and nodes created from the node finder, to the
+    /// [`GraphEditorState::background_grid`]. Disabled by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub snap_to_grid: bool,
+    /// The colors and measurements nodes and the editor widgets are drawn
+    /// with. By default, this follows [`GraphEditorState::theme`], see
+    /// [`GraphEditorState::current_style`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub style: Option<GraphEditorStyle>,
+    /// Draws this editor with the dark or light theme, whatever the theme
+    /// of the rest of the application. This picks the default
+    /// [`GraphEditorStyle`], and the egui visuals of the widgets inside the
+    /// nodes and of the editor background. Follows the egui theme when
+    /// unset.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub theme: Option<egui::Theme>,
+    /// The node holding the keyboard focus, e.g. after tabbing to it, as of
+    /// the last frame. It is drawn with [`GraphEditorStyle::focus_outline`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub focused_node: Option<NodeId>,
+    /// The nodes and connections emphasized while the rest of the graph is
+    /// dimmed, see [`GraphEditorState::highlight_path`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub highlighted_path: Option<GraphPath>,
+    /// Dim the nodes and connections unrelated to the selected nodes,
+    /// showing at a glance what they depend on and what they affect.
+    /// Disabled by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub dependency_focus: bool,
+    /// Whether connections can close cycles. Allowed by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub cycle_policy: CyclePolicy,
+    /// Checks the graph for problems when it may have changed, filling in
+    /// [`GraphEditorState::diagnostics`]. See [`GraphEditorState::validate`].
+    // With an explicit default, serde doesn't require the type parameters
+    // to implement `Default`.
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    pub validator:
+        Option<std::sync::Arc<dyn GraphValidator<NodeData, DataType, ValueType> + Send + Sync>>,
+    /// The problems found by the last validation, drawn as badges on the
+    /// nodes and connections they are about. Also useful to list them in a
+    /// problems panel.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub diagnostics: Vec<Diagnostic>,
+    /// The probes showing live values next to outputs. Fill in their
+    /// [`Probe::value`] every frame, e.g. from the results of evaluating
+    /// the graph.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub probes: SecondaryMap<OutputId, Probe>,
+    /// The number of nodes and connections when the graph was last
+    /// validated, to catch changes made from code.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub(crate) validated_counts: Option<(usize, usize)>,
+    /// The edits that can be undone, see [`GraphEditorState::enable_undo`].
+    /// Disabled by default.
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    pub undo_history: Option<UndoHistory<NodeData, DataType, ValueType>>,
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    pub(crate) undo_hooks:
+        Option<EditorUndoHooks<NodeData, DataType, ValueType, NodeTemplate, UserState>>,
+    /// The panning and zoom of the graph viewport.
     pub pan_zoom: PanZoom,
+    /// The zoom levels the scroll wheel and pinch gestures are limited to.
+    #[cfg_attr(feature = "persistence", serde(default = "default_zoom_range"))]
+    pub zoom_range: egui::Rangef,
+    /// Below this zoom level, nodes are drawn as plain boxes showing only
+    /// their title. This skips laying out their contents, which makes
+    /// navigating large graphs much cheaper. Set it to zero to always draw
+    /// nodes in full.
+    #[cfg_attr(feature = "persistence", serde(default = "default_overview_zoom"))]
+    pub overview_zoom: f32,
+    /// Other users editing the same graph, drawn on top of it. This is meant
+    /// to be filled in by the application every frame, so it is never
+    /// persisted.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub remote_participants: Vec<RemoteParticipant>,
+    /// The screen position of each port, as of the last frame. Filled in
+    /// while drawing, and kept around to reuse its allocation.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub port_locations: PortLocations,
+    /// The screen rect of each node, as of the last frame. Filled in while
+    /// drawing, and kept around to reuse its allocation.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_rects: NodeRects,
+    /// The layout of each node when it was last drawn, used to skip laying
+    /// out nodes that are out of view. See [`GraphEditorState::mark_node_dirty`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_layouts: SecondaryMap<NodeId, NodeLayout>,
+    /// How far each node extends, as of the last time it was drawn in full.
+    /// Used for interaction, and to size nodes drawn in overview mode.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_extents: SecondaryMap<NodeId, NodeExtents>,
+    /// The rect of each node in graph coordinates, as of the last time it
+    /// was drawn. Used to find the nodes in an area, e.g. for box selection.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_rect_index: NodeRectIndex,
+    /// Reuse the shapes painted for nodes on previous frames, as long as the
+    /// cursor is away from them and their parameters and connections didn't
+    /// change. This saves laying out mostly static graphs and shaping their text
+    /// while panning around, but changes to node data or input values made
+    /// from code only show up after calling
+    /// [`GraphEditorState::mark_node_dirty`]. Disabled by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub cache_node_shapes: bool,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_shapes: SecondaryMap<NodeId, NodeShapes>,
+    /// Ask each data type for its color once per frame, rather than once for
+    /// every port and connection. Disable this if
+    /// [`DataTypeTrait::data_type_color`] can return different colors for
+    /// equal data types within a frame. Enabled by default.
+    #[cfg_attr(
+        feature = "persistence",
+        serde(default = "default_cache_data_type_colors")
+    )]
+    pub cache_data_type_colors: bool,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub data_type_colors: DataTypeColors,
+    /// Spread node updates over several frames, spending about this long
+    /// drawing nodes every frame. Nodes under the pointer, selected or being
+    /// connected are always updated, then nodes in view, then nodes out of
+    /// view; the others show how they were last drawn until their turn
+    /// comes. Like [`GraphEditorState::cache_node_shapes`], this replays
+    /// the shapes of nodes that didn't change. Ignored on the web. Disabled
+    /// by default.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub frame_budget: Option<std::time::Duration>,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub frame_budget_state: FrameBudgetState,
+    /// How long nodes take to glide to the positions set by
+    /// [`GraphEditorState::apply_layout`] or
+    /// [`GraphEditorState::arrange_selection`]. Set it to zero to move them
+    /// at once.
+    #[cfg_attr(
+        feature = "persistence",
+        serde(default = "default_layout_transition_duration")
+    )]
+    pub layout_transition_duration: std::time::Duration,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub layout_transition: LayoutTransition,
     pub _user_state: PhantomData<fn() -> UserState>,
 }
 
@@ -82,14 +343,129 @@ impl<NodeData, DataType: PartialEq, ValueType, NodeKind, UserState> Default
             ongoing_box_selection: Default::default(),
             node_positions: Default::default(),
             node_orientations: Default::default(),
+            node_sizes: Default::default(),
+            collapsed_nodes: Default::default(),
             node_finder: Default::default(),
+            template_history: Default::default(),
+            avoid_overlaps: default_avoid_overlaps(),
+            node_drag: None,
+            drag_step: default_drag_step(),
+            push_aside: false,
+            paste_offset: default_paste_offset(),
+            paste_cascade: None,
+            clipboard: None,
+            clipboard_hooks: None,
+            selection_menu: None,
+            connection_grid: None,
+            connection_style: ConnectionStyle::Bezier,
+            minimap: None,
+            background_grid: None,
+            keybindings: Default::default(),
+            waypoints: Default::default(),
+            frames: Default::default(),
+            frame_renaming: None,
+            frame_drag: None,
+            snap_to_grid: false,
+            style: None,
+            theme: None,
+            focused_node: None,
+            highlighted_path: None,
+            dependency_focus: false,
+            cycle_policy: CyclePolicy::Allow,
+            validator: None,
+            diagnostics: Vec::new(),
+            validated_counts: None,
+            probes: Default::default(),
+            undo_history: None,
+            undo_hooks: None,
             pan_zoom: Default::default(),
+            zoom_range: default_zoom_range(),
+            overview_zoom: default_overview_zoom(),
+            remote_participants: Default::default(),
+            port_locations: Default::default(),
+            node_rects: Default::default(),
+            node_layouts: Default::default(),
+            node_extents: Default::default(),
+            node_rect_index: Default::default(),
+            cache_node_shapes: false,
+            node_shapes: Default::default(),
+            cache_data_type_colors: default_cache_data_type_colors(),
+            data_type_colors: Default::default(),
+            frame_budget: None,
+            frame_budget_state: Default::default(),
+            layout_transition_duration: default_layout_transition_duration(),
+            layout_transition: Default::default(),
             _user_state: Default::default(),
         }
     }
 }
 
+fn default_zoom_range() -> egui::Rangef {
+    egui::Rangef::new(0.1, 4.0)
+}
+
+fn default_overview_zoom() -> f32 {
+    0.5
+}
+
+fn default_avoid_overlaps() -> bool {
+    true
+}
+
+fn default_drag_step() -> f32 {
+    20.0
+}
+
+fn default_paste_offset() -> egui::Vec2 {
+    egui::vec2(40.0, 40.0)
+}
+
+fn default_cache_data_type_colors() -> bool {
+    true
+}
+
+fn default_layout_transition_duration() -> std::time::Duration {
+    std::time::Duration::from_millis(250)
+}
+
 impl PanZoom {
+    /// The transform from graph coordinates, such as
+    /// [`GraphEditorState::node_positions`], to screen coordinates, for an
+    /// editor drawn in `editor_rect`.
+    pub fn transform(&self, editor_rect: egui::Rect) -> egui::emath::TSTransform {
+        egui::emath::TSTransform::new(self.pan + editor_rect.min.to_vec2(), self.zoom)
+    }
+
+    /// Whether the pan is finite and the zoom within `range`.
+    pub fn is_valid(&self, range: egui::Rangef) -> bool {
+        self.pan.is_finite() && range.contains(self.zoom)
+    }
+
+    /// Resets a pan that isn't finite, and a zoom that isn't finite and
+    /// positive, then clamps the zoom into `range`.
+    pub fn sanitize(&mut self, range: egui::Rangef) {
+        self.reset_invalid();
+        self.zoom = self.zoom.clamp(range.min, range.max);
+    }
+
+    fn reset_invalid(&mut self) {
+        if !self.pan.is_finite() {
+            self.pan = egui::Vec2::ZERO;
+        }
+        if !self.zoom.is_finite() || self.zoom <= 0.0 {
+            self.zoom = 1.0;
+        }
+    }
+
+    /// Multiplies the zoom by `factor`, keeping it within `range`, and pans
+    /// so that the graph stays in place under `anchor`, relative to the top
+    /// left corner of the editor.
+    pub fn zoom_around(&mut self, factor: f32, anchor: egui::Vec2, range: egui::Rangef) {
+        let zoom = (self.zoom * factor).clamp(range.min, range.max);
+        let graph_anchor = (anchor - self.pan) / self.zoom;
+        self.adjust_zoom(zoom - self.zoom, -graph_anchor, range.min, range.max);
+    }
+
     pub fn adjust_zoom(
         &mut self,
         zoom_delta: f32,
@@ -104,3 +480,82 @@ impl PanZoom {
         self.pan += point * zoom_delta;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn test_editor_state_is_send_sync() {
+        // Templates and user state are never stored, so they don't need to be
+        // thread safe for the editor state to be, e.g. to live in a Bevy
+        // resource.
+        assert_send_sync::<GraphEditorState<u32, (), f32, Rc<()>, Rc<()>>>();
+        assert_send_sync::<GraphWorkspace<u32, (), f32, Rc<()>, Rc<()>>>();
+    }
+
+    #[test]
+    fn test_zoom_around() {
+        let editor_rect =
+            egui::Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(800.0, 600.0));
+        let mut pan_zoom = PanZoom {
+            pan: egui::vec2(20.0, 10.0),
+            zoom: 1.0,
+        };
+        let anchor = egui::vec2(300.0, 200.0);
+        let under_anchor = pan_zoom.transform(editor_rect).inverse() * (editor_rect.min + anchor);
+
+        pan_zoom.zoom_around(2.0, anchor, egui::Rangef::new(0.1, 4.0));
+        assert_eq!(pan_zoom.zoom, 2.0);
+        let screen = pan_zoom.transform(editor_rect) * under_anchor;
+        assert!(screen.distance(editor_rect.min + anchor) < 1e-3);
+
+        pan_zoom.zoom_around(10.0, anchor, egui::Rangef::new(0.1, 4.0));
+        assert_eq!(pan_zoom.zoom, 4.0);
+        let screen = pan_zoom.transform(editor_rect) * under_anchor;
+        assert!(screen.distance(editor_rect.min + anchor) < 1e-3);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_serialize_editor_state() {
+        let mut state = GraphEditorState::<u32, (), f32, (), ()>::default();
+        let node = state.graph.add_node("node".into(), 7, |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "x".into(),
+                (),
+                1.5,
+                InputParamKind::ConstantOnly,
+                true,
+            );
+        });
+        state.node_order.push(node);
+        state.node_positions.insert(node, egui::pos2(3.0, 4.0));
+        state
+            .node_orientations
+            .insert(node, NodeOrientation::RightToLeft);
+        state.selected_nodes = vec![node];
+        state.pan_zoom = PanZoom {
+            pan: egui::vec2(20.0, 10.0),
+            zoom: 2.0,
+        };
+
+        let json = serde_json::to_string(&state).unwrap();
+        let loaded: GraphEditorState<u32, (), f32, (), ()> = serde_json::from_str(&json).unwrap();
+        assert!(loaded.validate_integrity().is_ok());
+        assert_eq!(loaded.graph[node].user_data, 7);
+        assert_eq!(*loaded.graph.inputs.values().next().unwrap().value(), 1.5);
+        assert_eq!(loaded.node_positions[node], egui::pos2(3.0, 4.0));
+        assert!(matches!(
+            loaded.node_orientations[node],
+            NodeOrientation::RightToLeft
+        ));
+        assert_eq!(loaded.selected_nodes, vec![node]);
+        assert_eq!(loaded.pan_zoom.pan, egui::vec2(20.0, 10.0));
+        assert_eq!(loaded.pan_zoom.zoom, 2.0);
+    }
+}
@@ -0,0 +1,463 @@
+use super::*;
+
+/// The edits made to an editor that can be undone, see
+/// [`GraphEditorState::enable_undo`]. Like an [`OperationRecorder`], the
+/// history compares the graph with a shadow copy of it, so it catches node
+/// creations, deletions, moves, connections and value changes no matter
+/// their origin. Changes to the parameters of existing nodes or to their
+/// data aren't tracked.
+///
+/// The editor records the changes once the pointer is released and no
+/// widget has the keyboard focus, so dragging a node or typing a value
+/// makes a single edit. It only looks for changes after frames with input
+/// over the editor or node responses, or after nodes were moved by
+/// [`GraphEditorState::move_nodes_animated`], so other edits made from code
+/// are recorded along with the next edit unless
+/// [`GraphEditorState::record_edit`] is called.
+#[derive(Clone)]
+pub struct UndoHistory<NodeData, DataType, ValueType> {
+    undo_stack: Vec<UndoEntry<NodeData, DataType, ValueType>>,
+    redo_stack: Vec<UndoEntry<NodeData, DataType, ValueType>>,
+    nodes: SecondaryMap<NodeId, NodeSnapshot<NodeData, DataType, ValueType>>,
+    connections: SecondaryMap<InputId, SVec<OutputId>>,
+    /// The ids of the elements recreated by undoing or redoing their
+    /// deletion, indexed by the ids the entries refer to them by.
+    mapping: IdMapping,
+    /// Whether the graph may have changed since the last recorded edit, so
+    /// that the editor doesn't compare it with the shadow copy every frame.
+    pub(crate) unrecorded: bool,
+    /// The number of edits that can be undone. Older ones are forgotten.
+    pub capacity: usize,
+    /// Undo and redo with [`EditorKeybindings::undo`] and
+    /// [`EditorKeybindings::redo`] while the pointer is over the editor.
+    /// Enabled by default.
+    pub shortcuts: bool,
+}
+
+/// An edit, and the operations reverting it.
+#[derive(Clone)]
+struct UndoEntry<NodeData, DataType, ValueType> {
+    operations: Vec<GraphOperation<NodeData, DataType, ValueType>>,
+    inverse: Vec<GraphOperation<NodeData, DataType, ValueType>>,
+}
+
+/// Lets the editor record and undo edits without the bounds the history
+/// needs, see [`GraphEditorState::enable_undo`].
+pub(crate) struct UndoHooks<State> {
+    pub record: fn(&mut State),
+    pub undo: fn(&mut State) -> bool,
+    pub redo: fn(&mut State) -> bool,
+}
+
+pub(crate) type EditorUndoHooks<NodeData, DataType, ValueType, NodeTemplate, UserState> =
+    UndoHooks<GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>>;
+
+impl<State> Clone for UndoHooks<State> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<State> Copy for UndoHooks<State> {}
+
+impl<NodeData, DataType, ValueType> UndoHistory<NodeData, DataType, ValueType> {
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone + PartialEq,
+    ValueType: Clone + PartialEq,
+{
+    /// Starts recording the edits made to the graph in
+    /// [`GraphEditorState::undo_history`], keeping up to `capacity` of them.
+    /// The editor records them itself while it is drawn, and handles the
+    /// undo and redo shortcuts.
+    pub fn enable_undo(&mut self, capacity: usize) {
+        self.undo_history = Some(UndoHistory {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            nodes: SecondaryMap::new(),
+            connections: SecondaryMap::new(),
+            mapping: IdMapping::default(),
+            unrecorded: false,
+            capacity,
+            shortcuts: true,
+        });
+        self.undo_hooks = Some(UndoHooks {
+            record: Self::record_edit,
+            undo: Self::undo,
+            redo: Self::redo,
+        });
+        self.clear_undo_history();
+    }
+
+    /// Forgets all edits, e.g. after loading another graph, and takes the
+    /// current graph as the starting point of the next ones.
+    pub fn clear_undo_history(&mut self) {
+        let Some(mut history) = self.undo_history.take() else {
+            return;
+        };
+        history.undo_stack.clear();
+        history.redo_stack.clear();
+        history.mapping = IdMapping::default();
+        self.sync_undo_history(&mut history);
+        self.undo_history = Some(history);
+    }
+
+    /// Records the changes made to the graph since the last edit as a new
+    /// edit, which can be undone. This clears the edits that were undone.
+    /// The editor calls this while it is drawn, call it after editing the
+    /// graph from code to separate the edits.
+    pub fn record_edit(&mut self) {
+        let Some(mut history) = self.undo_history.take() else {
+            return;
+        };
+        history.unrecorded = false;
+        let entry = self.diff_undo_history(&history);
+        if !entry.operations.is_empty() {
+            history.redo_stack.clear();
+            history.undo_stack.push(entry);
+            if history.undo_stack.len() > history.capacity {
+                let excess = history.undo_stack.len() - history.capacity;
+                history.undo_stack.drain(..excess);
+            }
+            self.sync_undo_history(&mut history);
+        }
+        self.undo_history = Some(history);
+    }
+
+    /// Reverts the last edit. Returns false when there was none. Edits not
+    /// recorded yet are recorded first, so they are the ones undone.
+    pub fn undo(&mut self) -> bool {
+        self.record_edit();
+        self.step_undo_history(true)
+    }
+
+    /// Performs again the last edit that was undone. Returns false when
+    /// there was none.
+    pub fn redo(&mut self) -> bool {
+        self.record_edit();
+        self.step_undo_history(false)
+    }
+
+    /// Undoes the last edit when `undo` is set, or redoes the last undone
+    /// edit otherwise. Operations on elements that were removed since, e.g.
+    /// from code, are skipped.
+    fn step_undo_history(&mut self, undo: bool) -> bool {
+        let Some(mut history) = self.undo_history.take() else {
+            return false;
+        };
+        let entry = if undo {
+            history.undo_stack.pop()
+        } else {
+            history.redo_stack.pop()
+        };
+        let Some(entry) = entry else {
+            self.undo_history = Some(history);
+            return false;
+        };
+        let operations = if undo {
+            &entry.inverse
+        } else {
+            &entry.operations
+        };
+        for operation in operations {
+            let previous = match operation {
+                GraphOperation::AddNode(_) => Some(history.mapping.clone()),
+                _ => None,
+            };
+            if self
+                .apply_operation(operation.clone(), &mut history.mapping)
+                .is_err()
+            {
+                continue;
+            }
+            match (operation, previous) {
+                (GraphOperation::AddNode(snapshot), Some(previous)) => {
+                    follow_recreated(&mut history.mapping, &previous, snapshot);
+                }
+                (GraphOperation::SetValue { input, .. }, _) => {
+                    // The inline widget of the input shows the old value
+                    let input = history
+                        .mapping
+                        .inputs
+                        .get(*input)
+                        .copied()
+                        .unwrap_or(*input);
+                    if let Some(param) = self.graph.inputs.get(input) {
+                        self.node_layouts.remove(param.node);
+                        self.node_shapes.remove(param.node);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if undo {
+            history.redo_stack.push(entry);
+        } else {
+            history.undo_stack.push(entry);
+        }
+        self.sync_undo_history(&mut history);
+        self.undo_history = Some(history);
+        true
+    }
+
+    /// The edit turning the shadow copy of the history into the current
+    /// graph, with the operations reverting it.
+    fn diff_undo_history(
+        &self,
+        history: &UndoHistory<NodeData, DataType, ValueType>,
+    ) -> UndoEntry<NodeData, DataType, ValueType> {
+        let graph = &self.graph;
+        let mut operations = Vec::new();
+        let mut inverse = Vec::new();
+
+        // Connections removed along with their nodes are restored after them
+        for (input, outputs) in &history.connections {
+            for output in outputs {
+                if !graph.connections(input).contains(output) {
+                    let (output, input) = (*output, input);
+                    operations.push(GraphOperation::Disconnect { output, input });
+                    inverse.push(GraphOperation::Connect { output, input });
+                }
+            }
+        }
+        for (node, snapshot) in &history.nodes {
+            if !graph.nodes.contains_key(node) {
+                operations.push(GraphOperation::RemoveNode { node });
+                inverse.push(GraphOperation::AddNode(snapshot.clone()));
+            }
+        }
+        for node in graph.nodes.keys() {
+            if !history.nodes.contains_key(node) {
+                operations.push(GraphOperation::AddNode(NodeSnapshot::capture(self, node)));
+                inverse.push(GraphOperation::RemoveNode { node });
+            }
+        }
+        for (input, output) in graph.iter_connections() {
+            let known = history
+                .connections
+                .get(input)
+                .is_some_and(|outputs| outputs.contains(&output));
+            if !known {
+                operations.push(GraphOperation::Connect { output, input });
+                inverse.push(GraphOperation::Disconnect { output, input });
+            }
+        }
+        for (node, snapshot) in &history.nodes {
+            if !graph.nodes.contains_key(node) {
+                continue;
+            }
+            for (_, old) in &snapshot.inputs {
+                let Some(param) = graph.inputs.get(old.id) else {
+                    continue;
+                };
+                if param.value != old.value {
+                    operations.push(GraphOperation::SetValue {
+                        input: old.id,
+                        value: param.value.clone(),
+                    });
+                    inverse.push(GraphOperation::SetValue {
+                        input: old.id,
+                        value: old.value.clone(),
+                    });
+                }
+            }
+            if let Some(position) = self.node_positions.get(node) {
+                if *position != snapshot.position {
+                    operations.push(GraphOperation::MoveNode {
+                        node,
+                        position: *position,
+                    });
+                    inverse.push(GraphOperation::MoveNode {
+                        node,
+                        position: snapshot.position,
+                    });
+                }
+            }
+        }
+
+        // Reverting an edit takes its operations in reverse order
+        inverse.reverse();
+        UndoEntry {
+            operations,
+            inverse,
+        }
+    }
+
+    /// Copies the current graph into the shadow copy of the history.
+    fn sync_undo_history(&self, history: &mut UndoHistory<NodeData, DataType, ValueType>) {
+        history.nodes = self
+            .graph
+            .nodes
+            .keys()
+            .map(|node| (node, NodeSnapshot::capture(self, node)))
+            .collect();
+        history.connections = self.graph.connections.clone();
+    }
+}
+
+/// Points the ids that referred to the node recreated from `snapshot`, by
+/// its id in the snapshot or the id it was recreated with before, to its new
+/// ids.
+fn follow_recreated<NodeData, DataType, ValueType>(
+    mapping: &mut IdMapping,
+    previous: &IdMapping,
+    snapshot: &NodeSnapshot<NodeData, DataType, ValueType>,
+) {
+    fn follow<K: slotmap::Key>(
+        map: &mut SecondaryMap<K, K>,
+        previous: &SecondaryMap<K, K>,
+        old: K,
+    ) {
+        let Some(new) = map.get(old).copied() else {
+            return;
+        };
+        let before = previous.get(old).copied();
+        for id in map.values_mut() {
+            if *id == old || Some(*id) == before {
+                *id = new;
+            }
+        }
+    }
+    follow(&mut mapping.nodes, &previous.nodes, snapshot.id);
+    for (_, input) in &snapshot.inputs {
+        follow(&mut mapping.inputs, &previous.inputs, input.id);
+    }
+    for (_, output) in &snapshot.outputs {
+        follow(&mut mapping.outputs, &previous.outputs, output.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), (), i32, (), ()>;
+
+    fn add_node(state: &mut TestState) -> NodeId {
+        let node_id = state.graph.add_node("node".into(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                0,
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), ());
+        });
+        state.node_positions.insert(node_id, egui::Pos2::ZERO);
+        state
+            .node_orientations
+            .insert(node_id, NodeOrientation::LeftToRight);
+        state.node_order.push(node_id);
+        node_id
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut state = TestState::default();
+        state.enable_undo(10);
+        let a = add_node(&mut state);
+        let b = add_node(&mut state);
+        let a_out = state.graph[a].get_output("out").unwrap();
+        let b_in = state.graph[b].get_input("in").unwrap();
+        state.graph.add_connection(a_out, b_in);
+        state.record_edit();
+
+        state.graph[b_in].value = 42;
+        state.node_positions[a] = egui::pos2(10.0, 20.0);
+        state.record_edit();
+        assert!(state.undo());
+        assert_eq!(state.graph[b_in].value, 0);
+        assert_eq!(state.node_positions[a], egui::Pos2::ZERO);
+        assert!(state.redo());
+        assert_eq!(state.graph[b_in].value, 42);
+        assert!(!state.redo());
+
+        // Deleted nodes come back with new ids, along with their connections
+        state.graph.remove_node(a);
+        state.node_positions.remove(a);
+        state.node_orientations.remove(a);
+        state.node_order.remove(a);
+        assert!(state.undo());
+        assert_eq!(state.graph.nodes.len(), 2);
+        let a = state.graph.nodes.keys().find(|node| *node != b).unwrap();
+        assert_eq!(state.node_positions[a], egui::pos2(10.0, 20.0));
+        assert_eq!(
+            state.graph.connection(b_in),
+            Some(state.graph[a].get_output("out").unwrap())
+        );
+
+        // Older edits refer to the recreated node through its old id
+        assert!(state.undo());
+        assert_eq!(state.node_positions[a], egui::Pos2::ZERO);
+        assert!(state.undo());
+        assert!(state.graph.nodes.is_empty());
+        assert!(!state.undo());
+        assert!(state.redo());
+        assert!(state.redo());
+        assert!(state.redo());
+        assert_eq!(state.graph.nodes.len(), 1);
+        assert!(state.graph.connections.is_empty());
+        assert!(!state.undo_history.as_ref().unwrap().can_redo());
+    }
+
+    #[cfg(feature = "synthetic")]
+    #[test]
+    fn test_undo_layout_transition() {
+        let mut state = SyntheticGraphOptions {
+            shape: SyntheticShape::Chain,
+            nodes: 4,
+            ..Default::default()
+        }
+        .build_editor_state();
+        state.enable_undo(10);
+        let before = state.node_positions.clone();
+        let ctx = egui::Context::default();
+        let frame = |state: &mut SyntheticEditorState, time: f64| {
+            let input = egui::RawInput {
+                time: Some(time),
+                ..Default::default()
+            };
+            crate::synthetic::run_editor_frame(&ctx, state, input);
+        };
+        frame(&mut state, 0.0);
+
+        state.auto_layout();
+        let after = state.node_positions.clone();
+        for step in 1..=20 {
+            frame(&mut state, step as f64 * 0.02);
+        }
+        assert!(!state.layout_transition.is_running());
+        let history = state.undo_history.as_ref().unwrap();
+        assert_eq!(history.undo_stack.len(), 1);
+
+        assert!(state.undo());
+        for (node, position) in &before {
+            assert_eq!(state.node_positions[node], *position);
+        }
+        assert!(state.redo());
+        for (node, position) in &after {
+            assert_eq!(state.node_positions[node], *position);
+        }
+
+        // Frames without input don't look for edits made from code
+        let node = state.node_order.to_vec()[0];
+        state.node_positions[node] += egui::vec2(10.0, 0.0);
+        frame(&mut state, 1.0);
+        assert_eq!(state.undo_history.as_ref().unwrap().undo_stack.len(), 1);
+        state.record_edit();
+        assert_eq!(state.undo_history.as_ref().unwrap().undo_stack.len(), 2);
+    }
+}
@@ -0,0 +1,252 @@
+use super::*;
+
+#[cfg(feature = "ui")]
+use egui::{Painter, Pos2, Rect, Sense, Stroke, Ui};
+
+/// How serious a [`Diagnostic`] is. Errors sort after warnings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// The element of a graph a [`Diagnostic`] is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticTarget {
+    Node(NodeId),
+    Connection { input: InputId, output: OutputId },
+}
+
+/// A problem found by a [`GraphValidator`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub target: DiagnosticTarget,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(target: DiagnosticTarget, message: impl Into<String>) -> Self {
+        Self {
+            target,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(target: DiagnosticTarget, message: impl Into<String>) -> Self {
+        Self {
+            target,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks a graph for problems the application cares about, e.g. required
+/// inputs left unconnected or values out of range. Set one as
+/// [`GraphEditorState::validator`] to have the editor draw badges on the
+/// elements with problems. Closures taking the graph and the list to push
+/// diagnostics to are validators too.
+pub trait GraphValidator<NodeData, DataType, ValueType> {
+    fn validate(
+        &self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        diagnostics: &mut Vec<Diagnostic>,
+    );
+}
+
+impl<F, NodeData, DataType, ValueType> GraphValidator<NodeData, DataType, ValueType> for F
+where
+    F: Fn(&Graph<NodeData, DataType, ValueType>, &mut Vec<Diagnostic>),
+{
+    fn validate(
+        &self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        self(graph, diagnostics)
+    }
+}
+
+/// Reports the connections closing a cycle as errors, see
+/// [`Graph::find_cycles`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CycleValidator;
+
+impl<NodeData, DataType, ValueType> GraphValidator<NodeData, DataType, ValueType>
+    for CycleValidator
+{
+    fn validate(
+        &self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        for cycle in graph.find_cycles() {
+            for (input, output) in cycle.connections {
+                diagnostics.push(Diagnostic::error(
+                    DiagnosticTarget::Connection { input, output },
+                    "This connection is part of a cycle",
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Runs `validator` and replaces [`GraphEditorState::diagnostics`] with
+    /// what it found. The editor does this itself with
+    /// [`GraphEditorState::validator`] when the graph may have changed, call
+    /// this after changing node data or values from code.
+    pub fn validate(
+        &mut self,
+        validator: &(impl GraphValidator<NodeData, DataType, ValueType> + ?Sized),
+    ) {
+        self.diagnostics.clear();
+        validator.validate(&self.graph, &mut self.diagnostics);
+        self.validated_counts = Some((self.graph.nodes.len(), self.graph.connections.len()));
+    }
+
+    /// Validates the graph with [`GraphEditorState::validator`] if there is
+    /// one, unless nothing could have changed the graph since the last time.
+    pub(crate) fn revalidate(&mut self, changed: bool) {
+        let Some(validator) = self.validator.clone() else {
+            return;
+        };
+        let counts = (self.graph.nodes.len(), self.graph.connections.len());
+        if changed || self.validated_counts != Some(counts) {
+            self.validate(&*validator);
+        }
+    }
+
+    /// The diagnostics about `node_id`, most severe first.
+    pub fn node_diagnostics(&self, node_id: NodeId) -> Vec<&Diagnostic> {
+        let mut diagnostics: Vec<&Diagnostic> = self
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.target == DiagnosticTarget::Node(node_id))
+            .collect();
+        diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity));
+        diagnostics
+    }
+}
+
+/// Draws a badge on each node and connection with diagnostics, colored after
+/// the most severe one, listing their messages when hovered.
+#[cfg(feature = "ui")]
+pub(crate) fn draw_diagnostic_badges(
+    ui: &Ui,
+    diagnostics: &[Diagnostic],
+    node_rects: &NodeRects,
+    port_locations: &PortLocations,
+    style: &DiagnosticStyle,
+) {
+    // Each element gets a single badge, however many problems it has
+    let mut badges: Vec<(DiagnosticTarget, Pos2, Severity, String)> = Vec::new();
+    for diagnostic in diagnostics {
+        if let Some(badge) = badges
+            .iter_mut()
+            .find(|(target, ..)| *target == diagnostic.target)
+        {
+            badge.2 = badge.2.max(diagnostic.severity);
+            badge.3.push('\n');
+            badge.3.push_str(&diagnostic.message);
+            continue;
+        }
+        let center = match diagnostic.target {
+            DiagnosticTarget::Node(node_id) => node_rects
+                .get(&node_id)
+                .map(|rect| rect.right_top() + egui::vec2(-style.badge_radius, style.badge_radius)),
+            DiagnosticTarget::Connection { input, output } => port_locations
+                .get(&AnyParameterId::Input(input))
+                .zip(port_locations.get(&AnyParameterId::Output(output)))
+                .map(|(input, output)| input.lerp(*output, 0.5)),
+        };
+        if let Some(center) = center {
+            badges.push((
+                diagnostic.target,
+                center,
+                diagnostic.severity,
+                diagnostic.message.clone(),
+            ));
+        }
+    }
+
+    let painter: &Painter = ui.painter();
+    for (target, center, severity, messages) in badges {
+        let rect = Rect::from_center_size(center, egui::Vec2::splat(style.badge_radius * 2.0));
+        if !ui.clip_rect().intersects(rect) {
+            continue;
+        }
+        painter.circle(
+            center,
+            style.badge_radius,
+            style.color(severity),
+            Stroke::new(1.0, egui::Color32::WHITE),
+        );
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            "!",
+            egui::FontId::proportional(style.badge_radius * 1.5),
+            egui::Color32::WHITE,
+        );
+        ui.interact(rect, ui.id().with(("diagnostic", target)), Sense::hover())
+            .on_hover_text(messages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validators() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let node = graph.add_node("node".into(), (), |_, _| {});
+        let input = graph.add_input_param(
+            node,
+            "in".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        let output = graph.add_output_param(node, "out".into(), ());
+
+        let unconnected = |graph: &Graph<(), (), ()>, diagnostics: &mut Vec<Diagnostic>| {
+            for (input, param) in &graph.inputs {
+                if !graph.connections.contains_key(input) {
+                    diagnostics.push(Diagnostic::warning(
+                        DiagnosticTarget::Node(param.node),
+                        "Unconnected input",
+                    ));
+                }
+            }
+        };
+        let mut diagnostics = Vec::new();
+        unconnected.validate(&graph, &mut diagnostics);
+        CycleValidator.validate(&graph, &mut diagnostics);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::warning(
+                DiagnosticTarget::Node(node),
+                "Unconnected input"
+            )]
+        );
+
+        graph.add_connection(output, input);
+        diagnostics.clear();
+        unconnected.validate(&graph, &mut diagnostics);
+        CycleValidator.validate(&graph, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].target,
+            DiagnosticTarget::Connection { input, output }
+        );
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+}
@@ -0,0 +1,127 @@
+use super::*;
+use egui::Pos2;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The points a connection is routed through, e.g. to go around nodes. The
+/// points are in graph coordinates, in order from the output to the input.
+///
+/// Double clicking a connection adds a waypoint, which can then be dragged
+/// around. Double clicking a waypoint removes it, as does its context menu.
+/// See [`GraphEditorState::waypoints`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ConnectionWaypoints {
+    pub output: OutputId,
+    pub points: Vec<Pos2>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// The waypoints of the connection from `output` to `input`.
+    pub fn connection_waypoints(&self, input: InputId, output: OutputId) -> &[Pos2] {
+        connection_waypoints(&self.waypoints, input, output)
+    }
+
+    /// Adds a waypoint to the connection from `output` to `input`, before
+    /// the existing waypoint at `index`. Indices past the end add it last.
+    pub fn insert_waypoint(&mut self, input: InputId, output: OutputId, index: usize, pos: Pos2) {
+        let Some(entry) = self.waypoints.entry(input) else {
+            return;
+        };
+        let connections = entry.or_default();
+        let points = match connections.iter().position(|c| c.output == output) {
+            Some(index) => &mut connections[index].points,
+            None => {
+                connections.push(ConnectionWaypoints {
+                    output,
+                    points: Vec::new(),
+                });
+                &mut connections.last_mut().unwrap().points
+            }
+        };
+        points.insert(index.min(points.len()), pos);
+    }
+
+    /// Removes the waypoint at `index` from the connection from `output` to
+    /// `input`.
+    pub fn remove_waypoint(
+        &mut self,
+        input: InputId,
+        output: OutputId,
+        index: usize,
+    ) -> Option<Pos2> {
+        let connections = self.waypoints.get_mut(input)?;
+        let points = &mut connections.iter_mut().find(|c| c.output == output)?.points;
+        (index < points.len()).then(|| points.remove(index))
+    }
+
+    /// Forgets the waypoints of connections that no longer exist, or that
+    /// have none left.
+    pub(crate) fn prune_waypoints(&mut self) {
+        let graph = &self.graph;
+        self.waypoints.retain(|input, connections| {
+            let outputs = graph.connections(input);
+            connections.retain(|c| !c.points.is_empty() && outputs.contains(&c.output));
+            !connections.is_empty()
+        });
+    }
+}
+
+/// Like [`GraphEditorState::connection_waypoints`], while the rest of the
+/// editor is borrowed.
+pub(crate) fn connection_waypoints(
+    waypoints: &SecondaryMap<InputId, SVec<ConnectionWaypoints>>,
+    input: InputId,
+    output: OutputId,
+) -> &[Pos2] {
+    waypoints
+        .get(input)
+        .and_then(|connections| connections.iter().find(|c| c.output == output))
+        .map_or(&[], |c| c.points.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::pos2;
+
+    #[test]
+    fn test_waypoints() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let a = state.graph.add_node("a".into(), (), |_, _| {});
+        let b = state.graph.add_node("b".into(), (), |_, _| {});
+        let output = state.graph.add_output_param(a, "out".into(), ());
+        let input = state.graph.add_input_param(
+            b,
+            "in".into(),
+            (),
+            (),
+            InputParamKind::ConnectionOnly,
+            true,
+        );
+        state.graph.add_connection(output, input);
+
+        state.insert_waypoint(input, output, 0, pos2(10.0, 0.0));
+        state.insert_waypoint(input, output, 5, pos2(30.0, 0.0));
+        state.insert_waypoint(input, output, 1, pos2(20.0, 0.0));
+        assert_eq!(
+            state.connection_waypoints(input, output),
+            [pos2(10.0, 0.0), pos2(20.0, 0.0), pos2(30.0, 0.0)]
+        );
+        assert_eq!(
+            state.remove_waypoint(input, output, 0),
+            Some(pos2(10.0, 0.0))
+        );
+        assert_eq!(state.remove_waypoint(input, output, 2), None);
+
+        state.prune_waypoints();
+        assert_eq!(state.waypoints.len(), 1);
+        state.graph.remove_connection(input);
+        state.prune_waypoints();
+        assert!(state.waypoints.is_empty());
+        assert!(state.connection_waypoints(input, output).is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+use super::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// A graph of a [`GraphWorkspace`], shown as a tab.
+#[derive(Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    pub name: String,
+    /// The graph along with its own view: pan, selection, draw order...
+    pub state: GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+}
+
+/// Several graphs edited side by side, e.g. the different pipelines of a
+/// project. Only the active graph is drawn; [`GraphWorkspace::show_tabs`]
+/// switches between them, and nodes can be copied from one graph to another.
+#[derive(Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "persistence",
+    serde(try_from = "SavedWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>")
+)]
+pub struct GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    /// The graphs of the workspace. There is always at least one.
+    pub graphs: Vec<WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState>>,
+    /// Index of the graph currently shown in the editor.
+    pub active: usize,
+    /// The nodes copied by [`GraphWorkspace::copy_selection`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub clipboard: Option<GraphFragment<NodeData, DataType, ValueType>>,
+}
+
+/// A workspace as saved, checked to have a graph when loading since the
+/// editor always shows one.
+#[cfg(feature = "persistence")]
+#[derive(Deserialize)]
+struct SavedWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    graphs: Vec<WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState>>,
+    active: usize,
+}
+
+#[cfg(feature = "persistence")]
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    TryFrom<SavedWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>>
+    for GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    type Error = &'static str;
+
+    fn try_from(
+        saved: SavedWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> Result<Self, Self::Error> {
+        if saved.graphs.is_empty() {
+            return Err("a workspace needs at least one graph");
+        }
+        Ok(Self {
+            graphs: saved.graphs,
+            active: saved.active,
+            clipboard: None,
+        })
+    }
+}
+
+/// Something that happened to the tabs of a [`GraphWorkspace`] while drawing
+/// them.
+pub enum WorkspaceTabEvent<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    /// The tab at this index became the active one.
+    Selected(usize),
+    /// A new, empty graph was added at this index.
+    Added(usize),
+    /// A graph was closed. It is passed along so user code can save it or
+    /// clean up its data.
+    Closed(Box<WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState>>),
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeTemplate, UserState> Default
+    for GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    fn default() -> Self {
+        Self::new("Graph 1", GraphEditorState::default())
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeTemplate, UserState>
+    GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    pub fn new(
+        name: impl Into<String>,
+        state: GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> Self {
+        Self {
+            graphs: vec![WorkspaceGraph {
+                name: name.into(),
+                state,
+            }],
+            active: 0,
+            clipboard: None,
+        }
+    }
+
+    /// Adds a graph after the existing ones and returns its index. The active
+    /// graph doesn't change.
+    pub fn add_graph(
+        &mut self,
+        name: impl Into<String>,
+        state: GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> usize {
+        self.graphs.push(WorkspaceGraph {
+            name: name.into(),
+            state,
+        });
+        self.graphs.len() - 1
+    }
+
+    /// Removes the graph at `index`, unless it is the last one. The active
+    /// graph stays the same when possible.
+    pub fn remove_graph(
+        &mut self,
+        index: usize,
+    ) -> Option<WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState>> {
+        if self.graphs.len() <= 1 || index >= self.graphs.len() {
+            return None;
+        }
+        let removed = self.graphs.remove(index);
+        if self.active > index || self.active == self.graphs.len() {
+            self.active -= 1;
+        }
+        Some(removed)
+    }
+
+    pub fn active_graph(
+        &self,
+    ) -> &WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        &self.graphs[self.active.min(self.graphs.len() - 1)]
+    }
+
+    pub fn active_graph_mut(
+        &mut self,
+    ) -> &mut WorkspaceGraph<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        let index = self.active.min(self.graphs.len() - 1);
+        &mut self.graphs[index]
+    }
+
+    /// The editor state of the active graph. Call `draw_graph_editor` on it to
+    /// show the graph.
+    pub fn active_state(
+        &self,
+    ) -> &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        &self.active_graph().state
+    }
+
+    pub fn active_state_mut(
+        &mut self,
+    ) -> &mut GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+        &mut self.active_graph_mut().state
+    }
+
+    /// Draws a tab strip with one tab per graph, a close button on each tab
+    /// and a button to add a new graph. Clicking a tab makes it active.
+    pub fn show_tabs(
+        &mut self,
+        ui: &mut egui::Ui,
+    ) -> Vec<WorkspaceTabEvent<NodeData, DataType, ValueType, NodeTemplate, UserState>> {
+        let mut events = Vec::new();
+        let mut selected = None;
+        let mut closed = None;
+        let can_close = self.graphs.len() > 1;
+
+        ui.horizontal(|ui| {
+            for (index, graph) in self.graphs.iter().enumerate() {
+                if ui
+                    .selectable_label(index == self.active, &graph.name)
+                    .clicked()
+                {
+                    selected = Some(index);
+                }
+                if can_close && ui.small_button("🗙").on_hover_text("Close").clicked() {
+                    closed = Some(index);
+                }
+                ui.separator();
+            }
+            if ui.small_button("+").on_hover_text("New graph").clicked() {
+                let name = format!("Graph {}", self.graphs.len() + 1);
+                let index = self.add_graph(name, GraphEditorState::default());
+                self.active = index;
+                events.push(WorkspaceTabEvent::Added(index));
+            }
+        });
+
+        if let Some(index) = selected {
+            if index != self.active {
+                self.active = index;
+                events.push(WorkspaceTabEvent::Selected(index));
+            }
+        }
+        if let Some(removed) = closed.and_then(|index| self.remove_graph(index)) {
+            events.push(WorkspaceTabEvent::Closed(Box::new(removed)));
+        }
+
+        events
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: PartialEq + Clone,
+    ValueType: Clone,
+{
+    /// Copies the selected nodes of the active graph into the workspace
+    /// clipboard. Returns false, leaving the clipboard untouched, when
+    /// nothing is selected.
+    pub fn copy_selection(&mut self) -> bool {
+        let state = self.active_state();
+        if state.selected_nodes.is_empty() {
+            return false;
+        }
+        self.clipboard = Some(state.copy_fragment(&state.selected_nodes));
+        true
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState, UserResponse>
+    GraphWorkspace<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<Response = UserResponse> + Clone,
+    UserResponse: UserResponseTrait,
+    DataType: PartialEq + Clone,
+    ValueType: Clone,
+{
+    /// Pastes the clipboard into the active graph, which may be a different
+    /// one than it was copied from, with its top-left node at `position`.
+    /// The pasted nodes become the selection.
+    ///
+    /// Pass the returned responses to the next call to `draw_graph_editor`,
+    /// as with [`GraphEditorState::import_fragment`].
+    pub fn paste(&mut self, position: egui::Pos2) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let Some(fragment) = self.clipboard.clone() else {
+            return Vec::new();
+        };
+        let state = self.active_state_mut();
+        let (mapping, responses) = state.import_fragment(fragment, position.to_vec2());
+        state.selected_nodes = mapping.nodes.values().copied().collect();
+        responses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Data;
+
+    impl NodeDataTrait for Data {
+        type Response = EmptyResponse;
+        type UserState = ();
+        type DataType = ();
+        type ValueType = ();
+
+        fn bottom_ui(
+            &self,
+            _ui: &mut egui::Ui,
+            _node_id: NodeId,
+            _graph: &Graph<Self, (), ()>,
+            _user_state: &mut (),
+        ) -> Vec<NodeResponse<EmptyResponse, Self>> {
+            Vec::new()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct EmptyResponse;
+
+    impl UserResponseTrait for EmptyResponse {}
+
+    #[test]
+    fn test_copy_between_graphs() {
+        let mut workspace = GraphWorkspace::<Data, (), (), (), ()>::default();
+        let state = workspace.active_state_mut();
+        let mut add = |label: &str, pos| {
+            let node = state.graph.add_node(label.into(), Data, |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    (),
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), ());
+            });
+            state.node_order.push(node);
+            state.node_positions.insert(node, pos);
+            state
+                .node_orientations
+                .insert(node, NodeOrientation::LeftToRight);
+            node
+        };
+        let a = add("a", egui::pos2(10.0, 10.0));
+        let b = add("b", egui::pos2(50.0, 30.0));
+        let out = state.graph[a].outputs[0].1;
+        let input = state.graph[b].inputs[0].1;
+        state.graph.add_connection(out, input);
+        state.selected_nodes = vec![a, b];
+
+        assert!(workspace.copy_selection());
+        workspace.active = workspace.add_graph("other", GraphEditorState::default());
+        let responses = workspace.paste(egui::pos2(100.0, 100.0));
+        assert_eq!(responses.len(), 2);
+
+        let pasted = workspace.active_state();
+        assert_eq!(pasted.graph.nodes.len(), 2);
+        assert_eq!(pasted.graph.connections.len(), 1);
+        assert_eq!(pasted.selected_nodes.len(), 2);
+        assert!(pasted.validate_integrity().is_ok());
+        let mut positions: Vec<_> = pasted.node_positions.values().copied().collect();
+        positions.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert_eq!(
+            positions,
+            vec![egui::pos2(100.0, 100.0), egui::pos2(140.0, 120.0)]
+        );
+
+        assert!(workspace.remove_graph(0).is_some());
+        assert_eq!(workspace.active, 0);
+        assert!(workspace.remove_graph(0).is_none());
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_load_workspace() {
+        type Workspace = GraphWorkspace<(), (), (), (), ()>;
+        let mut workspace = Workspace::default();
+        workspace.add_graph("Graph 2", GraphEditorState::default());
+        workspace.active = 1;
+        let json = serde_json::to_string(&workspace).unwrap();
+        let loaded: Workspace = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.graphs.len(), 2);
+        assert_eq!(loaded.active_graph().name, "Graph 2");
+
+        // The editor always shows a graph
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["graphs"] = serde_json::json!([]);
+        let error = serde_json::from_value::<Workspace>(value).err().unwrap();
+        assert!(error.to_string().contains("at least one graph"));
+    }
+}